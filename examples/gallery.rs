@@ -0,0 +1,395 @@
+//! Built-in and example worlds side by side.
+//!
+//! Number keys switch worlds. Worlds that can be painted use Q, W, E and R
+//! to pick the brush, the left mouse button to paint and the right one to
+//! erase. In the Lenia world, C cycles the colormap. The third world is a
+//! glider gun loaded from its RLE pattern. The HUD in the top-right corner
+//! shows the generation and the rates.
+//!
+//! The random worlds start the same on every run. Pass another seed as the
+//! first argument or in `GALLERY_SEED`, e.g. `cargo run --example gallery 7`.
+
+use cells_renderer::{
+    Colormap, ElementaryRule, ElementaryWorld, Grid, LifeLikeWorld, Neighborhood, Pattern, Rng,
+    Rule, prelude::*, util::*,
+};
+
+/// Gosper's glider gun, from LifeWiki.
+const GLIDER_GUN: &str = "\
+#N Gosper glider gun
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+fn soup(width: u32, height: u32, rule: Rule, density: f64, rng: &mut Rng) -> LifeLikeWorld {
+    LifeLikeWorld::new(width, height, rule).with_cells(|_, _| rng.chance(density))
+}
+
+struct Wireworld {
+    cells: Grid<u8>,
+}
+
+impl Wireworld {
+    const EMPTY: u8 = 0;
+    const HEAD: u8 = 1;
+    const TAIL: u8 = 2;
+    const WIRE: u8 = 3;
+
+    /// A loop of wire with an electron running around it.
+    fn new(width: u32, height: u32) -> Self {
+        let mut cells = Grid::new(width, height);
+        let (x0, y0, x1, y1) = (4, 4, width - 5, height - 5);
+        for x in x0..=x1 {
+            cells.set(x, y0, Self::WIRE);
+            cells.set(x, y1, Self::WIRE);
+        }
+        for y in y0..=y1 {
+            cells.set(x0, y, Self::WIRE);
+            cells.set(x1, y, Self::WIRE);
+        }
+        cells.set(x0 + 1, y0, Self::HEAD);
+        cells.set(x0, y0, Self::TAIL);
+        Self { cells }
+    }
+}
+
+impl WorldGrid2d for Wireworld {
+    type Cell = u8;
+
+    fn dims(&self) -> (u32, u32) {
+        self.cells.dims()
+    }
+
+    fn get_cell(&self, x: u32, y: u32) -> Option<&u8> {
+        self.cells.get(x, y)
+    }
+
+    fn set_cell(&mut self, x: u32, y: u32, cell: u8) {
+        self.cells.set(x, y, cell);
+    }
+
+    fn cell_color(&self, cell: &u8) -> [u8; 4] {
+        match *cell {
+            Self::HEAD => [80, 160, 255, 255],
+            Self::TAIL => [255, 80, 40, 255],
+            Self::WIRE => [220, 180, 40, 255],
+            _ => [0, 0, 0, 255],
+        }
+    }
+}
+
+impl WorldTrait for Wireworld {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new(width, height);
+        self.refresh_image(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        self.cells
+            .step_with(|cells, x, y| match *cells.get(x, y).unwrap() {
+                Self::HEAD => Self::TAIL,
+                Self::TAIL => Self::WIRE,
+                Self::WIRE => {
+                    let heads = cells
+                        .neighbors_moore(x, y)
+                        .filter(|&&cell| cell == Self::HEAD)
+                        .count();
+                    if heads == 1 || heads == 2 {
+                        Self::HEAD
+                    } else {
+                        Self::WIRE
+                    }
+                }
+                _ => Self::EMPTY,
+            });
+        for (x, y, cell) in self.cells.changes() {
+            image.set(x, y, self.cell_color(cell));
+        }
+    }
+}
+
+struct Sand {
+    cells: Grid<u8>,
+    tick: u32,
+}
+
+impl Sand {
+    const AIR: u8 = 0;
+    const SAND: u8 = 1;
+    const WALL: u8 = 2;
+
+    fn new(width: u32, height: u32) -> Self {
+        let mut cells = Grid::new(width, height);
+        for x in width / 4..width * 3 / 4 {
+            cells.set(x, height * 2 / 3, Self::WALL);
+        }
+        for y in 0..height / 4 {
+            for x in width * 3 / 8..width * 5 / 8 {
+                cells.set(x, y, Self::SAND);
+            }
+        }
+        Self { cells, tick: 0 }
+    }
+
+    fn try_move(&mut self, from: (u32, u32), to: (i64, i64)) -> bool {
+        let (w, h) = self.cells.dims();
+        if to.0 < 0 || to.1 < 0 || to.0 >= w as i64 || to.1 >= h as i64 {
+            return false;
+        }
+        let to = (to.0 as u32, to.1 as u32);
+        if self.cells.get(to.0, to.1) != Some(&Self::AIR) {
+            return false;
+        }
+        self.cells.set(to.0, to.1, Self::SAND);
+        self.cells.set(from.0, from.1, Self::AIR);
+        true
+    }
+}
+
+impl WorldGrid2d for Sand {
+    type Cell = u8;
+
+    fn dims(&self) -> (u32, u32) {
+        self.cells.dims()
+    }
+
+    fn get_cell(&self, x: u32, y: u32) -> Option<&u8> {
+        self.cells.get(x, y)
+    }
+
+    fn set_cell(&mut self, x: u32, y: u32, cell: u8) {
+        self.cells.set(x, y, cell);
+    }
+
+    fn cell_color(&self, cell: &u8) -> [u8; 4] {
+        match *cell {
+            Self::SAND => [230, 200, 120, 255],
+            Self::WALL => [120, 120, 130, 255],
+            _ => [20, 20, 30, 255],
+        }
+    }
+}
+
+impl WorldTrait for Sand {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new(width, height);
+        self.refresh_image(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        // Sand moves in place from the bottom up, alternating the preferred
+        // diagonal to avoid drifting to one side.
+        self.tick += 1;
+        let side = if self.tick.is_multiple_of(2) { 1 } else { -1 };
+        let (width, height) = self.cells.dims();
+        for y in (0..height).rev() {
+            for x in 0..width {
+                if self.cells.get(x, y) != Some(&Self::SAND) {
+                    continue;
+                }
+                let (xi, yi) = (x as i64, y as i64);
+                let _ = self.try_move((x, y), (xi, yi + 1))
+                    || self.try_move((x, y), (xi + side, yi + 1))
+                    || self.try_move((x, y), (xi - side, yi + 1));
+            }
+        }
+        self.refresh_image(image);
+    }
+}
+
+/// Continuous automaton with a disc kernel and a Gaussian growth function.
+struct Lenia {
+    cells: Grid<f32>,
+    radius: u32,
+    colormap: usize,
+}
+
+impl Lenia {
+    const MU: f32 = 0.15;
+    const SIGMA: f32 = 0.017;
+    const DT: f32 = 0.1;
+    const COLORMAPS: [Colormap; 3] = [Colormap::VIRIDIS, Colormap::MAGMA, Colormap::COOLWARM];
+
+    fn new(width: u32, height: u32, rng: &mut Rng) -> Self {
+        let cells = Grid::from_fn(width, height, |x, y| {
+            let in_patch = (width / 3..width * 2 / 3).contains(&x)
+                && (height / 3..height * 2 / 3).contains(&y);
+            if in_patch { rng.f32() } else { 0.0 }
+        });
+        Self {
+            cells,
+            radius: 6,
+            colormap: 0,
+        }
+    }
+
+    fn growth(u: f32) -> f32 {
+        2.0 * (-((u - Self::MU).powi(2)) / (2.0 * Self::SIGMA.powi(2))).exp() - 1.0
+    }
+
+    /// The image is indexed, so switching colormaps only swaps the palette.
+    fn draw(&self, image: &mut WorldImage) {
+        for (index, &value) in image.indices_mut().iter_mut().zip(self.cells.cells()) {
+            *index = (value * 255.0).round() as u8;
+        }
+    }
+
+    fn set_palette(&self, image: &mut WorldImage) {
+        let colormap = &Self::COLORMAPS[self.colormap];
+        image.set_palette((0..=255).map(|i| colormap.color(i as f32 / 255.0)));
+    }
+}
+
+impl WorldTrait for Lenia {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new_indexed(width, height);
+        self.set_palette(&mut image);
+        self.draw(&mut image);
+        image
+    }
+
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        if event.state.is_pressed() && event.physical_key == KeyCode::KeyC {
+            self.colormap = (self.colormap + 1) % Self::COLORMAPS.len();
+            self.set_palette(image);
+        }
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        let radius = self.radius;
+        self.cells.step_with(|cells, x, y| {
+            let (sum, n) = cells
+                .neighbors_radius(x, y, radius, Neighborhood::Moore)
+                .fold((0.0, 0), |(sum, n), value| (sum + value, n + 1));
+            let u = sum / n as f32;
+            (cells.get(x, y).unwrap() + Self::DT * Self::growth(u)).clamp(0.0, 1.0)
+        });
+        self.draw(image);
+    }
+}
+
+/// Gray-Scott reaction-diffusion, drawn from a float image so the
+/// concentrations are uploaded as they are.
+struct GrayScott {
+    cells: Grid<(f32, f32)>,
+}
+
+impl GrayScott {
+    const FEED: f32 = 0.037;
+    const KILL: f32 = 0.06;
+    const DIFFUSION: (f32, f32) = (0.2, 0.1);
+    const STEPS_PER_UPDATE: u32 = 8;
+
+    fn new(width: u32, height: u32) -> Self {
+        let cells = Grid::from_fn(width, height, |x, y| {
+            let seeded = x.abs_diff(width / 2) < 6 && y.abs_diff(height / 2) < 6;
+            if seeded { (0.5, 0.25) } else { (1.0, 0.0) }
+        });
+        Self { cells }
+    }
+
+    fn draw(&self, image: &mut WorldImage) {
+        for (value, &(_, v)) in image.values_mut().iter_mut().zip(self.cells.cells()) {
+            *value = v;
+        }
+    }
+}
+
+impl WorldTrait for GrayScott {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new_float(width, height);
+        image.set_colormap(&Colormap::MAGMA);
+        image.set_value_range(0.0, 0.4);
+        self.draw(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        for _ in 0..Self::STEPS_PER_UPDATE {
+            self.cells.step_with(|cells, x, y| {
+                let &(u, v) = cells.get(x, y).unwrap();
+                let (sum_u, sum_v) = cells
+                    .neighbors_von_neumann(x, y)
+                    .fold((0.0, 0.0), |(su, sv), &(u, v)| (su + u, sv + v));
+                let (lap_u, lap_v) = (sum_u - 4.0 * u, sum_v - 4.0 * v);
+                let reaction = u * v * v;
+                (
+                    u + Self::DIFFUSION.0 * lap_u - reaction + Self::FEED * (1.0 - u),
+                    v + Self::DIFFUSION.1 * lap_v + reaction - (Self::FEED + Self::KILL) * v,
+                )
+            });
+        }
+        self.draw(image);
+    }
+}
+
+fn main() {
+    let life_palette = [(KeyCode::KeyQ, 0), (KeyCode::KeyW, 1)];
+    let wire_palette = [
+        (KeyCode::KeyQ, Wireworld::EMPTY),
+        (KeyCode::KeyW, Wireworld::WIRE),
+        (KeyCode::KeyE, Wireworld::HEAD),
+        (KeyCode::KeyR, Wireworld::TAIL),
+    ];
+    let sand_palette = [
+        (KeyCode::KeyQ, Sand::AIR),
+        (KeyCode::KeyW, Sand::SAND),
+        (KeyCode::KeyE, Sand::WALL),
+    ];
+
+    // The worlds are seeded before the app exists, so the seed is settled
+    // here and passed on, keeping `AppHandle::seed` in line with it.
+    let seed = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("GALLERY_SEED").ok())
+        .map_or(1, |seed| seed.parse().expect("the seed must be an integer"));
+    let mut rng = Rng::new(seed);
+
+    let gallery = SceneManager::new()
+        .with_scene(
+            soup(96, 96, Rule::LIFE, 0.3, &mut rng)
+                .with_painter_grid(life_palette, Some(1))
+                .with_erase_button(MouseButton::Right, 0),
+        )
+        .with_scene(
+            soup(96, 96, Rule::parse("B2/S/C3").unwrap(), 0.2, &mut rng)
+                .with_painter_grid(life_palette, Some(1))
+                .with_erase_button(MouseButton::Right, 0),
+        )
+        .with_scene(
+            LifeLikeWorld::new(96, 64, Rule::LIFE)
+                .with_pattern(&Pattern::parse_rle(GLIDER_GUN).unwrap())
+                .with_painter_grid(life_palette, Some(1))
+                .with_erase_button(MouseButton::Right, 0),
+        )
+        .with_scene(
+            Wireworld::new(48, 32)
+                .with_painter_grid(wire_palette, Some(Wireworld::WIRE))
+                .with_erase_button(MouseButton::Right, Wireworld::EMPTY),
+        )
+        .with_scene(
+            Sand::new(96, 96)
+                .with_painter_grid(sand_palette, Some(Sand::SAND))
+                .with_erase_button(MouseButton::Right, Sand::AIR),
+        )
+        .with_scene(Lenia::new(64, 64, &mut rng))
+        .with_scene(GrayScott::new(128, 128))
+        .with_scene(ElementaryWorld::new(160, 96, ElementaryRule::RULE_30));
+
+    App::new(
+        AppConfigs::default()
+            .title_stats(Some("{title} - gen {tick}, {ups} UPS {paused}".into()))
+            .seed(Some(seed))
+            .hud(true),
+        gallery,
+    )
+    .run()
+    .unwrap();
+}
@@ -1,4 +1,7 @@
-use cells_renderer::{prelude::*, util::*};
+use cells_renderer::{
+    Grid, HoverHighlight, Overlay, Tutorial, overlay::Space, prelude::*, tutorial::TutorialAction,
+    util::*,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 enum Cell {
@@ -25,102 +28,166 @@ impl Cell {
 }
 
 struct World {
-    width: u32,
-    height: u32,
-    cells: Vec<Cell>,
-    cells_temp: Vec<Cell>,
+    cells: Grid<Cell>,
 }
 
+/// Layer over the cells tinting those that have ever been alive.
+const VISITED_LAYER: usize = 0;
+const VISITED_COLOR: [u8; 4] = [40, 90, 220, 80];
+
 impl World {
     fn new(width: u32, height: u32) -> Self {
-        let cells = vec![Cell::Dead; width as usize * height as usize];
-        let cells_temp = cells.clone();
         Self {
-            width,
-            height,
-            cells,
-            cells_temp,
+            cells: Grid::new(width, height),
         }
     }
+}
+
+impl WorldGrid2d for World {
+    type Cell = Cell;
 
-    fn calc_index(&self, x: u32, y: u32) -> usize {
-        (x + y * self.width) as usize
+    fn dims(&self) -> (u32, u32) {
+        self.cells.dims()
     }
 
-    fn update_image(&self, image: &mut WorldImage) {
-        debug_assert_eq!(image.width(), self.width);
-        debug_assert_eq!(image.height(), self.height);
+    fn get_cell(&self, x: u32, y: u32) -> Option<&Cell> {
+        self.cells.get(x, y)
+    }
 
-        for (src, dst) in self.cells.iter().zip(image.buf_mut().chunks_exact_mut(4)) {
-            dst.copy_from_slice(&src.color());
-        }
+    fn set_cell(&mut self, x: u32, y: u32, cell: Cell) {
+        self.cells.set(x, y, cell);
     }
 
-    fn update_cell(&mut self, x: u32, y: u32, image: &mut WorldImage) {
-        let x0 = (x + self.width - 1) % self.width;
-        let x1 = (x + 1) % self.width;
-        let y0 = (y + self.height - 1) % self.height;
-        let y1 = (y + 1) % self.height;
-
-        let idx = self.calc_index(x, y);
-        let is_alive = self.cells[idx].is_alive();
-        let n_alive = [
-            (x0, y0),
-            (x, y0),
-            (x1, y0),
-            (x0, y),
-            (x1, y),
-            (x0, y1),
-            (x, y1),
-            (x1, y1),
-        ]
-        .iter()
-        .filter(|(x, y)| self.cells[self.calc_index(*x, *y)].is_alive())
-        .count();
-        let is_alive_out = (n_alive == 3) || (is_alive && n_alive == 2);
-        let cell_out = Cell::new(is_alive_out);
-        self.cells_temp[idx] = cell_out;
-        if is_alive_out != is_alive {
-            image
-                .get_mut(x, y)
-                .unwrap()
-                .copy_from_slice(&cell_out.color());
-        }
+    fn cell_color(&self, cell: &Cell) -> [u8; 4] {
+        cell.color()
     }
 }
 
 impl WorldTrait for World {
     fn init_image(&mut self) -> WorldImage {
-        let mut image = WorldImage::new(self.width, self.height);
-        self.update_image(&mut image);
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new(width, height);
+        self.refresh_image(&mut image);
+        image.add_layer(WorldImage::new(width, height));
         image
     }
 
     fn update(&mut self, image: &mut WorldImage) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.update_cell(x, y, image);
+        self.cells.step_with(|cells, x, y| {
+            let is_alive = cells.get(x, y).unwrap().is_alive();
+            let n_alive = cells
+                .neighbors_moore(x, y)
+                .filter(|cell| cell.is_alive())
+                .count();
+            Cell::new((n_alive == 3) || (is_alive && n_alive == 2))
+        });
+        for (x, y, cell) in self.cells.changes() {
+            image.set(x, y, cell.color());
+            if cell.is_alive() {
+                image.layers_mut()[VISITED_LAYER].set(x, y, VISITED_COLOR);
             }
         }
-        std::mem::swap(&mut self.cells, &mut self.cells_temp);
+    }
+
+    fn hud_lines(&self) -> Vec<String> {
+        let alive = self
+            .cells
+            .cells()
+            .iter()
+            .filter(|cell| cell.is_alive())
+            .count();
+        vec![format!("Alive {alive}")]
+    }
+
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        let cell = self.cells.get(x, y)?;
+        let neighbors = self
+            .cells
+            .neighbors_moore(x, y)
+            .filter(|cell| cell.is_alive())
+            .count();
+        Some(format!("{cell:?}\nNeighbors {neighbors}"))
+    }
+
+    /// Outlines the bounding box of the live cells.
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        let alive = self.cells.iter().filter(|(_, _, cell)| cell.is_alive());
+        let Some((min, max)) = alive.fold(None, |bounds, (x, y, _)| match bounds {
+            None => Some(((x, y), (x, y))),
+            Some((min, max)) => Some(((x.min(min.0), y.min(min.1)), (x.max(max.0), y.max(max.1)))),
+        }) else {
+            return;
+        };
+        overlay.set_space(Space::World);
+        overlay.rect_outline(
+            (min.0 as f64, min.1 as f64),
+            (max.0 as f64 + 1.0, max.1 as f64 + 1.0),
+            2.0,
+            Color::rgb(255, 200, 0),
+        );
+    }
+
+    fn cell_actions(&self, _x: u32, _y: u32) -> Vec<String> {
+        vec!["Toggle".into(), "Place glider".into()]
+    }
+
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        match action {
+            "Toggle" => {
+                let cell = Cell::new(!self.cells.get(x, y).unwrap().is_alive());
+                self.cells.set(x, y, cell);
+            }
+            "Place glider" => {
+                for (dx, dy) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+                    if let Some((x, y)) = self.cells.resolve(x as i64 + dx, y as i64 + dy) {
+                        self.cells.set(x, y, Cell::Alive);
+                    }
+                }
+            }
+            _ => return,
+        }
+        self.refresh_image(image);
+    }
+
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        match command {
+            "clear" => self.cells.cells_mut().fill(Cell::Dead),
+            _ => anyhow::bail!("unknown command `{command}`, try `clear`"),
+        }
+        self.refresh_image(image);
+        Ok(())
     }
 }
 
 fn main() {
+    let tutorial = Tutorial::new()
+        .step("Press Space to pause", TutorialAction::Pause)
+        .step(
+            "Click to draw cells",
+            TutorialAction::MouseButton(MouseButton::Left),
+        )
+        .step(
+            "Press Enter to step once",
+            TutorialAction::Key(KeyCode::Enter),
+        )
+        .step("Press Space to resume", TutorialAction::Resume);
+
     App::new(
-        AppConfigs::default(),
-        World::new(32, 32).with_painter(
-            [
-                (KeyCode::Digit0, Cell::Dead),
-                (KeyCode::Digit1, Cell::Alive),
-            ],
-            |world, x, y, cell, image| {
-                let idx = world.calc_index(x, y);
-                world.cells[idx] = cell;
-                image.get_mut(x, y).unwrap().copy_from_slice(&cell.color());
-            },
-            Some(Cell::Alive),
-        ),
+        AppConfigs::default()
+            .tutorial(Some(tutorial))
+            .trails(Some(0.8))
+            .hud(true)
+            .command_palette(true)
+            .hover_highlight(Some(HoverHighlight::Tint(Color::rgba(255, 255, 255, 60)))),
+        World::new(32, 32)
+            .with_painter_grid(
+                [
+                    (KeyCode::Digit0, Cell::Dead),
+                    (KeyCode::Digit1, Cell::Alive),
+                ],
+                Some(Cell::Alive),
+            )
+            .with_confirm_close(true),
     )
     .run()
     .unwrap();
@@ -0,0 +1,90 @@
+struct View {
+    // Maps world space to clip space.
+    scale: vec2<f32>,
+    offset: vec2<f32>,
+    // Window pixels per cell.
+    cell_size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+struct InstanceInput {
+    @location(0) position: vec2<f32>,
+    @location(1) size: f32,
+    @location(2) rotation: f32,
+    @location(3) color: vec4<f32>,
+    @location(4) shape: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position in the agent's square, from -0.5 to 0.5 along each axis.
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) shape: u32,
+};
+
+const MIN_PIXELS: f32 = 2.0;
+
+const CORNERS = array<vec2<f32>, 6>(
+    vec2<f32>(-0.5, -0.5),
+    vec2<f32>(0.5, -0.5),
+    vec2<f32>(-0.5, 0.5),
+    vec2<f32>(-0.5, 0.5),
+    vec2<f32>(0.5, -0.5),
+    vec2<f32>(0.5, 0.5),
+);
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    agent: InstanceInput,
+) -> VertexOutput {
+    let local = CORNERS[vertex_index];
+    let pixels = agent.size * min(view.cell_size.x, view.cell_size.y);
+    let size = agent.size * max(1.0, MIN_PIXELS / max(pixels, 1e-6));
+
+    // Rotating clockwise on screen, since y points down.
+    let c = cos(agent.rotation);
+    let s = sin(agent.rotation);
+    let corner = local * size;
+    let rotated = vec2<f32>(corner.x * c - corner.y * s, corner.x * s + corner.y * c);
+    let world = agent.position + rotated;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(world * view.scale + view.offset, 0.0, 1.0);
+    out.local = local;
+    out.color = agent.color;
+    out.shape = agent.shape;
+    return out;
+}
+
+// Signed distance to the edge of the shape, negative inside.
+fn shape_distance(local: vec2<f32>, shape: u32) -> f32 {
+    switch shape {
+        // Circle
+        case 0u: {
+            return length(local) - 0.5;
+        }
+        // Arrow, with its base on the left edge and its tip in the middle of
+        // the right one.
+        case 2u: {
+            return abs(local.y) - (0.5 - local.x) * 0.5;
+        }
+        // Square
+        default: {
+            return max(abs(local.x), abs(local.y)) - 0.5;
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = shape_distance(in.local, in.shape);
+    let coverage = clamp(0.5 - d / max(fwidth(d), 1e-6), 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
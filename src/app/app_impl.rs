@@ -1,14 +1,45 @@
-use crate::{AppConfigs, MouseEvent, World, WorldImage};
+#[cfg(feature = "dev")]
+use super::shader_reload::{ShaderKind, ShaderWatcher};
+use super::{
+    agents::AgentRenderer,
+    autosave::Autosaver,
+    camera::Camera,
+    command_palette::{Command, CommandPalette, PaletteInput},
+    context_menu::ContextMenu,
+    diff::Diff,
+    globals::{GlobalsBuffer, with_prelude},
+    heatmap::HeatmapRenderer,
+    mipmaps::Mipmaps,
+    overlay::{OverlayRenderer, Shapes},
+    palette::PaletteLookup,
+    profiler::{RateCounter, draw_frame_graph},
+    screenshot::{Offscreen, ScreenshotTarget},
+    staging::StagingUploads,
+    timelapse::TimelapseCapture,
+    trails::Trails,
+    vectors::VectorRenderer,
+    window_state::WindowState,
+};
+use crate::{
+    Agent, AppAction, AppConfigs, AppHandle, CloseAction, Color, DisplayAdjustments, FrameTiming,
+    ImageFormat, ImageVersion, InputFocus, Lod, MouseEvent, MouseEventKind, Overlay, RateStats,
+    TextureFilter, UpdateCtx, VectorField, WatchdogAction, World, WorldImage, WorldTransform,
+    checkpoint::Checkpoints, commands::AppCommand, mouse_event::GestureTracker,
+    tutorial::TutorialAction,
+};
 use anyhow::Context as _;
 use std::{
-    sync::Arc,
+    sync::{Arc, mpsc},
     time::{Duration, Instant},
 };
 use wgpu::util::DeviceExt as _;
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
-    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
-    event_loop::ActiveEventLoop,
+    event::{
+        ElementState, Ime, KeyEvent, MouseButton, MouseScrollDelta, Touch, TouchPhase, WindowEvent,
+    },
+    event_loop::{ActiveEventLoop, ControlFlow},
+    keyboard::ModifiersState,
     window::{Window, WindowId},
 };
 
@@ -29,34 +60,103 @@ pub struct AppImpl<'window, W> {
     // Update cycle
     update_interval: Duration,
     last_update: Instant,
+    frame_interval: Option<Duration>,
+    last_render: Instant,
+    title: String,
+    title_refreshed: Instant,
+    metrics_written: Instant,
+
+    // Simulation clock
+    tick: u64,
+    sim_elapsed: Duration,
 
     // Cursor
-    bounds: WorldTransform,
+    transform: WorldTransform,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    modifiers: ModifiersState,
     cursor_translated: Option<(u32, u32)>,
 
+    // Camera
+    camera: Camera,
+    panning: bool,
+    lod: Option<Lod>,
+
+    // Input
+    focus: InputFocus,
+    /// Buttons held down over the world, which drag from cell to cell.
+    dragging: Vec<MouseButton>,
+    gestures: GestureTracker,
+    /// The finger acting as the left mouse button on touch screens.
+    touch: Option<u64>,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<crate::gamepad::GamepadPoller>,
+    context_menu: Option<ContextMenu>,
+    command_palette: CommandPalette,
+
     // Pause
     paused: bool,
+    countdown: Option<Countdown>,
+
+    // Commands
+    commands: mpsc::Receiver<AppCommand>,
+    /// Set once the window was closed, for the app to drop it.
+    closed: bool,
+    fast_forward: Option<FastForward>,
+    /// Scales and targets of the screenshots taken after the next frame.
+    screenshots: Vec<(u32, ScreenshotTarget)>,
+    #[cfg(feature = "video")]
+    video: Option<crate::video::VideoEncoder>,
+    timelapse: Option<TimelapseCapture>,
 
     // wgpu
-    surface: wgpu::Surface<'window>,
+    instance: wgpu::Instance,
+    /// `None` while the app is suspended, e.g. in the background on mobile.
+    surface: Option<wgpu::Surface<'window>>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface_config: wgpu::SurfaceConfiguration,
 
     // Texture
     should_update_texture: bool,
+    /// Version of the world image when it was last uploaded.
+    uploaded_version: ImageVersion,
+    upload: RowUpload,
+    staging: StagingUploads,
+    mipmaps: Option<Mipmaps>,
+    /// Whether the mip levels of the world texture are older than its first
+    /// level.
+    mips_dirty: bool,
     texture: wgpu::Texture,
-    #[allow(unused)]
     texture_view: wgpu::TextureView,
-    #[allow(unused)]
     texture_sampler: wgpu::Sampler,
+    texture_filter: TextureFilter,
+    display_adjustments: DisplayAdjustments,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
     texture_bind_group: wgpu::BindGroup,
 
     // Rendering
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     indices_len: u32,
     render_pipeline: wgpu::RenderPipeline,
+    palette_lookup: PaletteLookup,
+    layer_render_pipeline: wgpu::RenderPipeline,
+    world_shader_pipeline: Option<wgpu::RenderPipeline>,
+    globals: GlobalsBuffer,
+    #[cfg(feature = "dev")]
+    world_pipeline_layout: wgpu::PipelineLayout,
+    #[cfg(feature = "dev")]
+    shader_watcher: ShaderWatcher,
+    layer_textures: Vec<LayerTexture>,
+    heatmap: HeatmapRenderer,
+    diff_enabled: bool,
+    diff: Diff,
+    diff_texture: Option<LayerTexture>,
+    autosave: Option<Autosaver>,
+    trails: Option<Trails>,
+    world_texture_changed: bool,
 
     // Grid
     grid_enabled: bool,
@@ -65,6 +165,32 @@ pub struct AppImpl<'window, W> {
     grid_index_buffer: wgpu::Buffer,
     grid_indices_len: u32,
     grid_render_pipeline: wgpu::RenderPipeline,
+    #[cfg(feature = "dev")]
+    grid_pipeline_layout: wgpu::PipelineLayout,
+
+    // Agents
+    agent_renderer: AgentRenderer,
+    /// Reused by [`World::draw_agents`] every frame.
+    agents: Vec<Agent>,
+    vector_renderer: VectorRenderer,
+
+    // Overlay
+    overlay: OverlayRenderer,
+    overlay_shapes: Shapes,
+    hud_enabled: bool,
+    /// Lines of [`AppConfigs::hud_counts`] and the image version they were
+    /// counted for.
+    hud_counts: Option<(ImageVersion, Vec<String>)>,
+    inspector_enabled: bool,
+
+    // Profiling
+    frame_graph_enabled: bool,
+    rates: RateCounter,
+    frame_timing: FrameTiming,
+    last_frame_timing: FrameTiming,
+    handle: AppHandle,
+    #[cfg(feature = "frame-capture")]
+    capture_next_frame: bool,
 }
 
 impl<W: World> AppImpl<'_, W> {
@@ -72,36 +198,95 @@ impl<W: World> AppImpl<'_, W> {
     pub async fn new(
         configs: AppConfigs,
         mut world: W,
+        commands: mpsc::Receiver<AppCommand>,
+        handle: AppHandle,
         event_loop: &ActiveEventLoop,
     ) -> anyhow::Result<Self> {
         let world_image = world.init_image();
+        let uploaded_version = world_image.version();
         let world_aspect = world_image.width() as f32 / world_image.height() as f32;
 
-        let update_interval = { Duration::from_secs(1) / configs.updates_per_second };
+        let update_interval = Duration::from_secs(1) / configs.updates_per_second.max(1);
+        let countdown = match configs.start_delay {
+            Some(delay) if !configs.start_paused => Some(Countdown::new(delay)),
+            _ => None,
+        };
+        let paused = configs.start_paused || countdown.is_some();
+        let hud_enabled = configs.hud;
+        let inspector_enabled = configs.inspector;
+        let diff_enabled = configs.diff_view;
+        handle.lock_plot().set_capacity(configs.plot_samples);
+
+        let frame_interval = configs
+            .max_fps
+            .map(|max_fps| Duration::from_secs(1) / max_fps.max(1));
 
         let (window, window_size) = {
-            let window = event_loop.create_window(configs.window_attributes.clone())?;
+            let mut window_attributes = configs.window_attributes.clone();
+            if let Some(path) = &configs.window_state_path {
+                match WindowState::load(path) {
+                    Ok(Some(state)) => {
+                        window_attributes = state.apply(window_attributes, event_loop);
+                    }
+                    Ok(None) => (),
+                    Err(err) => log::warn!("failed to load window state: {err:#}"),
+                }
+            }
+            let window = event_loop.create_window(window_attributes)?;
             let size = window.inner_size();
             (Arc::new(window), size)
         };
 
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends: configs.backends,
             ..Default::default()
         });
 
+        let surface = instance.create_surface(Arc::clone(&window))?;
+
         let adapter = instance
-            .request_adapter(&Default::default())
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: configs.power_preference,
+                force_fallback_adapter: configs.force_fallback_adapter,
+                compatible_surface: Some(&surface),
+            })
             .await
             .context("adapter not found")?;
 
-        let surface = instance.create_surface(Arc::clone(&window))?;
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .find(|f| f.is_srgb())
+            .copied()
+            .unwrap_or(surface_caps.formats[0]);
+        // Without an sRGB format, draw through an sRGB view of the surface so
+        // that colors are encoded the same way.
+        let view_formats = if !surface_format.is_srgb()
+            && surface_format.add_srgb_suffix() != surface_format
+            && adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS)
+        {
+            vec![surface_format.add_srgb_suffix()]
+        } else {
+            if !surface_format.is_srgb() {
+                log::info!("drawing to {surface_format:?} without sRGB encoding");
+            }
+            vec![]
+        };
+        let (sample_count, msaa_features) = msaa_sample_count(
+            &adapter,
+            view_formats.first().copied().unwrap_or(surface_format),
+            configs.sample_count,
+        );
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Main Device"),
-                    required_features: wgpu::Features::empty(),
+                    required_features: msaa_features,
                     ..Default::default()
                 },
                 None,
@@ -109,14 +294,6 @@ impl<W: World> AppImpl<'_, W> {
             .await?;
 
         let surface_config = {
-            let surface_caps = surface.get_capabilities(&adapter);
-            let surface_format = surface_caps
-                .formats
-                .iter()
-                .find(|f| f.is_srgb())
-                .copied()
-                .unwrap_or(surface_caps.formats[0]);
-
             let config = wgpu::SurfaceConfiguration {
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
                 format: surface_format,
@@ -124,15 +301,29 @@ impl<W: World> AppImpl<'_, W> {
                 height: window_size.height,
                 present_mode: surface_caps.present_modes[0],
                 alpha_mode: surface_caps.alpha_modes[0],
-                view_formats: vec![],
+                view_formats,
                 desired_maximum_frame_latency: 2,
             };
             surface.configure(&device, &config);
             config
         };
-
-        let (texture, texture_view, texture_sampler) =
-            world_image.create_texture(&device, &queue, Some("World Main Texture"))?;
+        let msaa_view = create_msaa_view(&device, &surface_config, sample_count);
+
+        let srgb_target = render_format(&surface_config).is_srgb();
+        let (texture, texture_view) = world_image.create_texture_with_mips(
+            &device,
+            &queue,
+            Some("World Main Texture"),
+            srgb_target,
+            world_mip_level_count(&configs, &world_image),
+        )?;
+        let texture_filter = configs.texture_filter;
+        let display_adjustments = configs.display_adjustments;
+        let texture_sampler = WorldImage::create_sampler(
+            &device,
+            texture_filter.filter_mode(),
+            texture.mip_level_count(),
+        );
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("texture_bind_group_layout"),
@@ -155,31 +346,36 @@ impl<W: World> AppImpl<'_, W> {
                     },
                 ],
             });
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("texture_bind_group"),
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture_sampler),
-                },
-            ],
-        });
+        let globals = GlobalsBuffer::new(&device);
+        let mut palette_lookup = PaletteLookup::new(
+            &device,
+            render_format(&surface_config),
+            sample_count,
+            globals.bind_group_layout(),
+        );
+        palette_lookup.upload_palette(&queue, &world_image, configs.palette_cycle, 0);
+        let texture_bind_group = create_texture_bind_group(
+            &device,
+            &texture_bind_group_layout,
+            &palette_lookup,
+            world_image.format(),
+            &texture_view,
+            &texture_sampler,
+        );
 
         let grid_vertices_len = (world_image.width() + world_image.height() + 2) * 4;
         let mut grid_vertices = vec![LineVertex::default(); grid_vertices_len as _];
 
-        let (vertices, bounds) = aspect_adjusted_vertices(
+        let camera = Camera::default();
+        let (vertices, transform) = aspect_adjusted_vertices(
             world_aspect,
+            &camera,
             window_size,
             world_image.width(),
             world_image.height(),
             &mut grid_vertices,
         );
+        world.transform_changed(&transform);
 
         // We use wgpu::IndexFormat::Uint16
         #[rustfmt::skip]
@@ -201,58 +397,59 @@ impl<W: World> AppImpl<'_, W> {
             usage: wgpu::BufferUsages::INDEX,
         });
 
-        let render_pipeline = {
-            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let world_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&texture_bind_group_layout],
+                bind_group_layouts: &[&texture_bind_group_layout, globals.bind_group_layout()],
                 push_constant_ranges: &[],
             });
+
+        // The world image replaces whatever is below it, its layers are
+        // blended over it.
+        let (render_pipeline, layer_render_pipeline) = {
             let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("Main Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("main.wgsl").into()),
+                source: wgpu::ShaderSource::Wgsl(with_prelude(include_str!("main.wgsl")).into()),
             });
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"),
-                    buffers: &[Vertex::desc()],
-                    compilation_options: Default::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: surface_config.format,
-                        blend: Some(wgpu::BlendState {
-                            alpha: wgpu::BlendComponent::REPLACE,
-                            color: wgpu::BlendComponent::REPLACE,
-                        }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                    compilation_options: Default::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
-                cache: None,
-            })
+            let create_pipeline = |label, blend| {
+                create_world_pipeline(
+                    &device,
+                    &world_pipeline_layout,
+                    &shader,
+                    render_format(&surface_config),
+                    label,
+                    blend,
+                    sample_count,
+                )
+            };
+            (
+                create_pipeline("Render Pipeline", wgpu::BlendState::REPLACE),
+                create_pipeline("Layer Render Pipeline", wgpu::BlendState::ALPHA_BLENDING),
+            )
         };
+        let world_shader_pipeline = configs.world_shader.as_deref().and_then(|source| {
+            create_world_shader_pipeline(
+                &device,
+                &world_pipeline_layout,
+                render_format(&surface_config),
+                sample_count,
+                source,
+            )
+            .inspect_err(|err| log::error!("{err:#}; using the built-in shader instead"))
+            .ok()
+        });
+
+        let mipmaps = configs.mipmaps.then(|| Mipmaps::new(&device));
+        let trails = configs.trails.map(|decay| {
+            Trails::new(
+                &device,
+                render_format(&surface_config),
+                sample_count,
+                decay,
+                (world_image.width(), world_image.height()),
+                &texture_bind_group_layout,
+            )
+        });
 
         let grid_indices = grid_indices(world_image.width(), world_image.height());
         let grid_indices_len = grid_indices.len() as u32;
@@ -269,60 +466,39 @@ impl<W: World> AppImpl<'_, W> {
             usage: wgpu::BufferUsages::INDEX,
         });
 
+        let grid_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Grid Render Pipeline Layout"),
+            bind_group_layouts: &[
+                globals.empty_bind_group_layout(),
+                globals.bind_group_layout(),
+            ],
+            push_constant_ranges: &[],
+        });
         let grid_render_pipeline = {
-            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Grid Render Pipeline Layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
             let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("Main Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("grid.wgsl").into()),
+                label: Some("Grid Shader"),
+                source: wgpu::ShaderSource::Wgsl(with_prelude(include_str!("grid.wgsl")).into()),
             });
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Grid Render Pipeline"),
-                layout: Some(&layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"),
-                    buffers: &[LineVertex::desc()],
-                    compilation_options: Default::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: surface_config.format,
-                        blend: Some(wgpu::BlendState {
-                            alpha: wgpu::BlendComponent::REPLACE,
-                            color: wgpu::BlendComponent::REPLACE,
-                        }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                    compilation_options: Default::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
-                cache: None,
-            })
+            create_grid_pipeline(
+                &device,
+                &grid_pipeline_layout,
+                &shader,
+                render_format(&surface_config),
+                sample_count,
+            )
         };
 
-        Ok(Self {
+        #[cfg(feature = "dev")]
+        let shader_watcher = ShaderWatcher::new(&configs);
+
+        let heatmap = HeatmapRenderer::new(&device, render_format(&surface_config), sample_count);
+        let agent_renderer =
+            AgentRenderer::new(&device, render_format(&surface_config), sample_count);
+        let vector_renderer =
+            VectorRenderer::new(&device, render_format(&surface_config), sample_count);
+        let overlay = OverlayRenderer::new(&device, render_format(&surface_config), sample_count);
+
+        let mut app = Self {
             configs,
             world,
             world_image,
@@ -331,64 +507,374 @@ impl<W: World> AppImpl<'_, W> {
             window_size,
             update_interval,
             last_update: Instant::now(),
-            bounds,
+            frame_interval,
+            last_render: Instant::now(),
+            title: String::new(),
+            title_refreshed: Instant::now(),
+            metrics_written: Instant::now(),
+            tick: 0,
+            sim_elapsed: Duration::ZERO,
+            transform,
+            cursor_position: None,
+            modifiers: ModifiersState::empty(),
             cursor_translated: None,
-            paused: false,
-            surface,
+            camera,
+            panning: false,
+            lod: None,
+            focus: InputFocus::World,
+            dragging: Vec::new(),
+            gestures: GestureTracker::default(),
+            touch: None,
+            #[cfg(feature = "gamepad")]
+            gamepads: crate::gamepad::GamepadPoller::new(),
+            context_menu: None,
+            command_palette: CommandPalette::default(),
+            paused,
+            countdown,
+            commands,
+            closed: false,
+            fast_forward: None,
+            screenshots: Vec::new(),
+            #[cfg(feature = "video")]
+            video: None,
+            timelapse: None,
+            instance,
+            surface: Some(surface),
             device,
             queue,
             surface_config,
             should_update_texture: false,
+            uploaded_version,
+            upload: RowUpload::default(),
+            staging: StagingUploads::default(),
+            mipmaps,
+            mips_dirty: true,
             texture,
             texture_view,
             texture_sampler,
+            texture_filter,
+            display_adjustments,
+            texture_bind_group_layout,
             texture_bind_group,
             vertex_buffer,
             index_buffer,
             indices_len,
+            sample_count,
+            msaa_view,
             render_pipeline,
+            palette_lookup,
+            layer_render_pipeline,
+            world_shader_pipeline,
+            globals,
+            #[cfg(feature = "dev")]
+            world_pipeline_layout,
+            #[cfg(feature = "dev")]
+            shader_watcher,
+            layer_textures: Vec::new(),
+            heatmap,
+            diff_enabled,
+            diff: Diff::new(),
+            diff_texture: None,
+            autosave: None,
+            trails,
+            world_texture_changed: true,
             grid_enabled: false,
             grid_vertices,
             grid_vertex_buffer,
             grid_index_buffer,
             grid_indices_len,
             grid_render_pipeline,
-        })
+            #[cfg(feature = "dev")]
+            grid_pipeline_layout,
+            agent_renderer,
+            agents: Vec::new(),
+            vector_renderer,
+            overlay,
+            overlay_shapes: Shapes::new(window_size),
+            hud_enabled,
+            hud_counts: None,
+            inspector_enabled,
+            frame_graph_enabled: false,
+            rates: RateCounter::new(),
+            frame_timing: FrameTiming::default(),
+            last_frame_timing: FrameTiming::default(),
+            handle,
+            #[cfg(feature = "frame-capture")]
+            capture_next_frame: false,
+        };
+
+        app.world.attach(app.handle.clone());
+        app.world.on_start(&mut app.world_image);
+        app.start_autosave();
+        app.start_timelapse();
+        app.update_lod();
+        app.refresh_title();
+        app.should_update_texture = true;
+
+        Ok(app)
     }
 
     #[inline]
-    pub fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        event: WindowEvent,
-    ) -> anyhow::Result<()> {
+    pub fn window_id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    #[inline]
+    pub fn window_event(&mut self, event: WindowEvent) -> anyhow::Result<()> {
         match event {
             WindowEvent::Resized(physical_size) => {
                 self.resize(physical_size);
+                self.request_input_redraw();
             }
-            WindowEvent::CloseRequested => {
-                event_loop.exit();
+            // The world may veto closing, leaving the event unhandled.
+            WindowEvent::CloseRequested if self.world.on_close_requested() == CloseAction::Exit => {
+                self.exit();
             }
+            // Nothing runs while suspended, as there is nothing to show.
+            WindowEvent::RedrawRequested if self.surface.is_none() => (),
             WindowEvent::RedrawRequested => {
                 self.update();
-                self.render().unwrap();
-                self.window.request_redraw();
+                self.render()?;
+                if !self.configs.low_power && self.frame_interval.is_none() {
+                    self.window.request_redraw();
+                }
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 self.keyboard_input(event);
+                self.request_input_redraw();
+            }
+            WindowEvent::Ime(Ime::Commit(text)) => {
+                if self.command_palette.is_open() {
+                    self.command_palette.insert(&text);
+                } else if self.focus == InputFocus::Text {
+                    self.world.text_input(&text, &mut self.world_image);
+                }
+                self.request_input_redraw();
             }
             WindowEvent::MouseInput { state, button, .. } => {
                 self.mouse_input(state, button);
+                self.request_input_redraw();
             }
             WindowEvent::CursorMoved { position, .. } => {
                 self.cursor_moved(position);
+                self.request_input_redraw();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                self.handle.lock_keyboard().set_modifiers(self.modifiers);
+            }
+            WindowEvent::Focused(false) => self.handle.lock_keyboard().release_all(),
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(_, lines),
+                ..
+            } => {
+                self.zoom(1.25_f64.powf(lines as f64));
+                self.request_input_redraw();
+            }
+            // Trackpads scroll in pixels: two fingers pan, and holding Ctrl
+            // zooms, which is also how some platforms report pinching.
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(delta),
+                ..
+            } => {
+                if self.modifiers.control_key() {
+                    self.zoom(1.25_f64.powf(delta.y / PIXELS_PER_LINE));
+                } else {
+                    self.pan((delta.x, delta.y));
+                }
+                self.request_input_redraw();
+            }
+            WindowEvent::PinchGesture { delta, .. } => {
+                self.zoom((1.0 + delta).max(0.01));
+                self.request_input_redraw();
+            }
+            WindowEvent::PanGesture { delta, .. } => {
+                self.pan((delta.x as f64, delta.y as f64));
+                self.request_input_redraw();
+            }
+            WindowEvent::Touch(Touch {
+                phase,
+                location,
+                id,
+                ..
+            }) => {
+                self.touch_input(phase, location, id);
+                self.request_input_redraw();
             }
             _ => (),
         }
         Ok(())
     }
 
+    /// Schedules the next redraw when rendering isn't continuous, either
+    /// because of low-power mode or a frame-rate cap, and returns when the
+    /// event loop should wake up for it.
+    #[inline]
+    pub fn about_to_wait(&mut self) -> ControlFlow {
+        self.process_commands();
+        if self.closed || self.surface.is_none() {
+            return ControlFlow::Wait;
+        }
+        if self.fast_forward.is_some() || self.countdown.is_some() || self.upload.is_pending() {
+            self.window.request_redraw();
+        }
+
+        let continuous = !self.configs.low_power;
+        if continuous && self.frame_interval.is_none() {
+            return ControlFlow::Poll;
+        }
+
+        let mut next_redraw = if continuous {
+            Some(self.last_render)
+        } else if self.paused {
+            None
+        } else {
+            Some(self.last_update + self.update_interval)
+        };
+        if let (Some(next_redraw), Some(frame_interval)) = (&mut next_redraw, self.frame_interval) {
+            *next_redraw = (*next_redraw).max(self.last_render + frame_interval);
+        }
+
+        match next_redraw {
+            Some(next_redraw) => {
+                if Instant::now() >= next_redraw {
+                    self.window.request_redraw();
+                }
+                ControlFlow::WaitUntil(next_redraw)
+            }
+            None => ControlFlow::Wait,
+        }
+    }
+
+    /// Drops the surface when the platform takes it away, e.g. when an
+    /// Android app goes to the background or a laptop's lid is closed.
+    pub fn suspend(&mut self) {
+        self.surface = None;
+        self.handle.lock_keyboard().release_all();
+        self.dragging.clear();
+    }
+
+    /// Recreates the surface dropped by [`AppImpl::suspend`].
+    pub fn resume(&mut self) -> anyhow::Result<()> {
+        if self.surface.is_some() {
+            return Ok(());
+        }
+        let surface = self.instance.create_surface(Arc::clone(&self.window))?;
+        // The window may have been resized meanwhile without an event.
+        let size = self.window.inner_size();
+        if size.width > 0 && size.height > 0 {
+            self.window_size = size;
+            self.surface_config.width = size.width;
+            self.surface_config.height = size.height;
+            self.msaa_view =
+                create_msaa_view(&self.device, &self.surface_config, self.sample_count);
+            self.update_vertices();
+        }
+        surface.configure(&self.device, &self.surface_config);
+        self.surface = Some(surface);
+        // Don't catch up on the updates missed while suspended.
+        self.last_update = Instant::now();
+        self.should_update_texture = true;
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// Saves what is configured to be saved on exit and calls
+    /// [`World::on_exit`], once.
+    pub fn exit(&mut self) {
+        if self.closed {
+            return;
+        }
+        if let Some(path) = &self.configs.window_state_path
+            && let Err(err) = WindowState::capture(&self.window).save(path)
+        {
+            self.report_error(err.context("failed to save window state"));
+        }
+        self.autosave(true);
+        self.export_metrics(true);
+        #[cfg(feature = "video")]
+        self.stop_recording();
+        if let Some(timelapse) = self.timelapse.take()
+            && let Err(err) = timelapse.finish()
+        {
+            self.report_error(err.context("failed to finish timelapse"));
+        }
+        self.world.on_exit();
+        self.closed = true;
+    }
+
+    fn process_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            match command {
+                AppCommand::FastForward(n) => {
+                    let fast_forward = self.fast_forward.get_or_insert_default();
+                    fast_forward.total += n;
+                    fast_forward.remaining += n;
+                }
+                AppCommand::SetFocus(focus) => self.set_focus(focus),
+                AppCommand::Pause => {
+                    self.countdown = None;
+                    self.set_paused(true);
+                }
+                AppCommand::Resume => {
+                    self.countdown = None;
+                    self.set_paused(false);
+                }
+                AppCommand::Step => self.run_update(),
+                AppCommand::SetUps(ups) => {
+                    self.configs.updates_per_second = ups.max(1);
+                    self.update_interval = Duration::from_secs(1) / self.configs.updates_per_second;
+                }
+                AppCommand::Exit => {
+                    self.exit();
+                    return;
+                }
+                AppCommand::Redraw => self.should_update_texture = true,
+                AppCommand::AdvanceTutorial => {
+                    if let Some(tutorial) = &mut self.configs.tutorial {
+                        tutorial.advance();
+                    }
+                }
+                AppCommand::Bind(chord, action) => self.configs.input_map.bind(chord, action),
+                AppCommand::Rebind(action, chord) => self.configs.input_map.rebind(action, chord),
+                AppCommand::WritePixels(pixels) => {
+                    if self.world_image.format() != ImageFormat::Rgba8 {
+                        log::warn!("ignored pixels written to a non-RGBA world image");
+                        continue;
+                    }
+                    for (x, y, color) in pixels {
+                        if let Some(pixel) = self.world_image.pixel_mut(x, y) {
+                            *pixel = color;
+                        }
+                    }
+                }
+                AppCommand::Snapshot(reply) => {
+                    let _ = reply.send(self.world_image.clone());
+                }
+                AppCommand::Screenshot(scale, target) => self.screenshots.push((scale, target)),
+                AppCommand::SetDisplayAdjustments(adjustments) => {
+                    self.display_adjustments = adjustments;
+                    self.window.request_redraw();
+                }
+                AppCommand::SetPaletteCycle(cycle) => self.configs.palette_cycle = cycle,
+            }
+            self.window.request_redraw();
+        }
+    }
+
+    /// Input only triggers a redraw in low-power mode; otherwise the next
+    /// scheduled frame picks it up.
+    fn request_input_redraw(&self) {
+        if self.configs.low_power {
+            self.window.request_redraw();
+        }
+    }
+
     fn resize(&mut self, new_window_size: PhysicalSize<u32>) {
         if new_window_size == self.window_size {
             return;
@@ -401,11 +887,22 @@ impl<W: World> AppImpl<'_, W> {
         // Update state
         self.surface_config.width = new_window_size.width;
         self.surface_config.height = new_window_size.height;
-        self.surface.configure(&self.device, &self.surface_config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_config);
+        }
+        self.msaa_view = create_msaa_view(&self.device, &self.surface_config, self.sample_count);
+
+        self.update_vertices();
+    }
+
+    fn update_vertices(&mut self) {
+        if self.window_size.width == 0 || self.window_size.height == 0 {
+            return;
+        }
 
-        // Update vertex
-        let (vertices, bounds) = aspect_adjusted_vertices(
+        let (vertices, transform) = aspect_adjusted_vertices(
             self.world_aspect,
+            &self.camera,
             self.window_size,
             self.world_image.width(),
             self.world_image.height(),
@@ -419,189 +916,1974 @@ impl<W: World> AppImpl<'_, W> {
             0,
             bytemuck::cast_slice(&self.grid_vertices),
         );
-        self.bounds = bounds;
+        self.transform = transform;
+        self.world.transform_changed(&self.transform);
+        self.update_lod();
     }
 
-    fn update(&mut self) {
-        let now = Instant::now();
-        let dt = now - self.last_update;
-        if dt < self.update_interval {
-            return;
+    fn update_lod(&mut self) {
+        let window_size = (self.window_size.width, self.window_size.height);
+        let lod = Lod::new(&self.transform, window_size);
+        if self.lod.as_ref() != Some(&lod) {
+            self.world.lod_changed(&lod, &mut self.world_image);
+            self.lod = Some(lod);
         }
+    }
 
-        self.last_update = self
-            .last_update
-            .checked_add(self.update_interval)
-            .unwrap_or(now);
+    /// An empty vector field sampling the visible cells in blocks at least
+    /// [`AppConfigs::vector_spacing`] window pixels wide, a power of two
+    /// cells along each axis so that zooming merges and splits blocks.
+    fn vector_field(&self) -> VectorField {
+        let window_size = (self.window_size.width, self.window_size.height);
+        let lod = Lod::new(&self.transform, window_size);
+        let cells = self.configs.vector_spacing as f64 / lod.pixels_per_cell.max(f64::EPSILON);
+        let step = (cells.ceil().clamp(1.0, (1u32 << 31) as f64) as u32).next_power_of_two();
+        VectorField::new(step, lod.visible_x, lod.visible_y)
+    }
 
-        if !self.paused {
-            self.run_update();
-        }
+    /// Zooms around the cursor, or around the window center if the cursor
+    /// is outside the window.
+    fn zoom(&mut self, factor: f64) {
+        let window_size = (
+            self.window_size.width as f64,
+            self.window_size.height as f64,
+        );
+        let anchor = self
+            .cursor_position
+            .map_or((window_size.0 / 2.0, window_size.1 / 2.0), |pos| {
+                (pos.x, pos.y)
+            });
+        self.camera.zoom_at(
+            factor,
+            anchor,
+            fit_size(self.world_aspect, self.window_size),
+            window_size,
+        );
+        self.camera_changed();
+        self.tutorial_action(TutorialAction::Zoom);
     }
 
-    fn run_update(&mut self) {
-        self.world.update(&mut self.world_image);
-        self.should_update_texture = true;
+    fn camera_changed(&mut self) {
+        self.update_vertices();
+        self.translate_cursor();
     }
 
-    fn render(&mut self) -> anyhow::Result<()> {
-        if self.should_update_texture {
-            self.world_image
-                .update_wgpu_texture(&self.texture, &self.queue);
-            self.should_update_texture = false;
+    fn update(&mut self) {
+        self.run_held_actions();
+        #[cfg(feature = "gamepad")]
+        self.poll_gamepads();
+        if self.fast_forward.is_some() {
+            self.run_fast_forward();
+            return;
         }
 
-        let output = self.surface.get_current_texture()?;
-
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-
+        let now = Instant::now();
+        if let Some(countdown) = &self.countdown
+            && now >= countdown.end
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+            self.countdown = None;
+            self.set_paused(false);
+            self.last_update = now;
+        }
+        if self.paused {
+            self.last_update = now;
+            return;
+        }
 
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+        // Run as many steps as are due, so update rates above the frame rate
+        // still advance the world at the configured speed.
+        let mut steps = 0;
+        while now - self.last_update >= self.update_interval {
+            if self
+                .configs
+                .max_updates_per_frame
+                .is_some_and(|max| steps >= max)
+            {
+                // Drop the backlog instead of spiraling further behind.
+                let backlog = (now - self.last_update).as_nanos() / self.update_interval.as_nanos();
+                self.rates
+                    .record_skipped(backlog.try_into().unwrap_or(u32::MAX));
+                self.last_update = now;
+                break;
+            }
+            self.last_update = self
+                .last_update
+                .checked_add(self.update_interval)
+                .unwrap_or(now);
+            self.run_update();
+            steps += 1;
         }
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Grid Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+    }
 
-            render_pass.set_pipeline(&self.grid_render_pipeline);
-            render_pass.set_vertex_buffer(0, self.grid_vertex_buffer.slice(..));
-            render_pass
-                .set_index_buffer(self.grid_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(
-                grid_indices_range(self.grid_indices_len, self.grid_enabled),
-                0,
-                0..1,
-            );
+    /// Runs fast-forward updates for about one frame's worth of time, so the
+    /// window keeps handling events in between.
+    fn run_fast_forward(&mut self) {
+        const CHUNK_BUDGET: Duration = Duration::from_millis(16);
+
+        let start = Instant::now();
+        while let Some(fast_forward) = &mut self.fast_forward {
+            if fast_forward.remaining == 0 {
+                self.fast_forward = None;
+                self.last_update = Instant::now();
+                break;
+            }
+            if start.elapsed() >= CHUNK_BUDGET {
+                break;
+            }
+            fast_forward.remaining -= 1;
+            self.run_update();
         }
+    }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+    fn run_update(&mut self) {
+        span!("update", tick = self.tick);
+        let ctx = UpdateCtx {
+            tick: self.tick,
+            elapsed: self.sim_elapsed,
+            dt: self.update_interval,
+            last_frame: self.last_frame_timing,
+            rates: self.rates.last(),
+        };
 
-        Ok(())
-    }
+        self.handle.metrics().set_tick(self.tick);
+        let start = Instant::now();
+        self.world.update_with_ctx(&ctx, &mut self.world_image);
 
-    fn keyboard_input(&mut self, event: KeyEvent) {
-        use crate::util::is_pressed;
+        self.tick += 1;
+        self.sim_elapsed += ctx.dt;
+        self.rates.record_update();
 
-        if let Some(key) = self.configs.key_play {
-            if is_pressed(&event, key) {
-                self.paused = !self.paused;
-            }
-        }
-        if self.paused {
-            if let Some(key) = self.configs.key_update_once {
-                if is_pressed(&event, key) {
-                    self.run_update();
-                }
+        let elapsed = start.elapsed();
+        self.frame_timing.update += elapsed;
+        self.check_watchdog(elapsed);
+        self.autosave(false);
+        self.export_metrics(false);
+    }
+
+    fn start_autosave(&mut self) {
+        let Some(configs) = &self.configs.autosave else {
+            return;
+        };
+        let resume = configs.resume;
+        let mut autosaver = match Autosaver::new(configs) {
+            Ok(autosaver) => autosaver,
+            Err(err) => {
+                self.report_error(err.context("failed to start autosave"));
+                return;
             }
-        }
-        if let Some(key) = self.configs.key_grid {
-            if is_pressed(&event, key) {
-                self.grid_enabled = !self.grid_enabled;
+        };
+        if resume {
+            match autosaver.resume(&mut self.world, &mut self.world_image) {
+                Ok(Some(tick)) => self.tick = tick,
+                Ok(None) => {}
+                Err(err) => self.report_error(err.context("failed to resume from a checkpoint")),
             }
         }
-
-        self.world.keyboard_input(event, &mut self.world_image);
-        self.should_update_texture = true;
+        self.autosave = Some(autosaver);
     }
 
-    fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
-        self.world.mouse_input(
-            MouseEvent {
-                state,
-                button,
-                pos: self.cursor_translated,
-            },
-            &mut self.world_image,
-        );
-        self.should_update_texture = true;
+    /// Takes a checkpoint when one is due, or unconditionally with `force`.
+    fn autosave(&mut self, force: bool) {
+        let Some(autosaver) = &mut self.autosave else {
+            return;
+        };
+        if !force && !autosaver.is_due(self.tick) {
+            return;
+        }
+        if let Err(err) = autosaver.save(self.tick, &self.world) {
+            self.report_error(err.context("failed to autosave"));
+        }
     }
 
-    fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {
-        let mut pos = self.bounds.translate_position(position);
-
-        // bounds check
+    /// Writes the metrics when due, or unconditionally with `force`.
+    fn export_metrics(&mut self, force: bool) {
+        let Some(export) = &self.configs.metrics else {
+            return;
+        };
+        let due = export
+            .interval
+            .is_some_and(|interval| self.metrics_written.elapsed() >= interval);
+        if !force && !due {
+            return;
+        }
+        self.metrics_written = Instant::now();
+        if let Err(err) = self.handle.metrics().write(&export.path, export.format) {
+            self.report_error(err);
+        }
+    }
 
-        if let Some((x, y)) = pos {
-            if x >= self.world_image.width() || y >= self.world_image.height() {
-                pos = None;
+    #[cfg(feature = "dev")]
+    fn reload_shaders(&mut self) {
+        for (kind, source) in self.shader_watcher.poll() {
+            let name = kind.name();
+            match source.and_then(|source| self.rebuild_pipelines(kind, &source)) {
+                Ok(()) => log::info!("loaded the {name} shader"),
+                Err(err) => {
+                    self.report_error(err.context(format!("failed to load the {name} shader")))
+                }
             }
         }
+    }
 
-        self.cursor_translated = pos;
-
-        self.world
-            .cursor_moved(self.cursor_translated, &mut self.world_image);
+    /// Replaces the pipelines drawn with a shader of `kind`, keeping the old
+    /// ones if `source` is invalid.
+    #[cfg(feature = "dev")]
+    fn rebuild_pipelines(&mut self, kind: ShaderKind, source: &str) -> anyhow::Result<()> {
+        let device = &self.device;
+        let format = render_format(&self.surface_config);
+        let sample_count = self.sample_count;
+        // The world shader adds its own prelude first.
+        let prepended = with_prelude(source);
+        let shader_module = |label| {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(prepended.as_str().into()),
+            })
+        };
+        match kind {
+            ShaderKind::Main => {
+                let layout = &self.world_pipeline_layout;
+                let (render_pipeline, layer_render_pipeline) =
+                    validated(device, "main shader", || {
+                        let shader = shader_module("Main Shader");
+                        (
+                            create_world_pipeline(
+                                device,
+                                layout,
+                                &shader,
+                                format,
+                                "Render Pipeline",
+                                wgpu::BlendState::REPLACE,
+                                sample_count,
+                            ),
+                            create_world_pipeline(
+                                device,
+                                layout,
+                                &shader,
+                                format,
+                                "Layer Render Pipeline",
+                                wgpu::BlendState::ALPHA_BLENDING,
+                                sample_count,
+                            ),
+                        )
+                    })?;
+                self.render_pipeline = render_pipeline;
+                self.layer_render_pipeline = layer_render_pipeline;
+            }
+            ShaderKind::Grid => {
+                self.grid_render_pipeline = validated(device, "grid shader", || {
+                    let shader = shader_module("Grid Shader");
+                    create_grid_pipeline(
+                        device,
+                        &self.grid_pipeline_layout,
+                        &shader,
+                        format,
+                        sample_count,
+                    )
+                })?;
+            }
+            ShaderKind::World => {
+                self.world_shader_pipeline = Some(create_world_shader_pipeline(
+                    device,
+                    &self.world_pipeline_layout,
+                    format,
+                    sample_count,
+                    source,
+                )?);
+            }
+        }
+        Ok(())
+    }
 
-        self.should_update_texture = true; // This is bad
+    fn report_error(&mut self, err: anyhow::Error) {
+        match &mut self.configs.on_error {
+            Some(handler) => handler.call(&err),
+            None => log::error!("{err:#}"),
+        }
     }
-}
 
-#[derive(Debug)]
-struct WorldTransform {
-    min: (f64, f64),
-    _max: (f64, f64),
-    cell_scale: (f64, f64),
-}
+    fn check_watchdog(&mut self, elapsed: Duration) {
+        let Some(budget) = self.configs.update_budget else {
+            return;
+        };
+        if elapsed <= budget {
+            return;
+        }
 
-impl WorldTransform {
-    fn translate_position(&self, pos: PhysicalPosition<f64>) -> Option<(u32, u32)> {
-        fn calc_pos(val: f64, min: f64, scale: f64) -> Option<u32> {
-            let val = val - min;
-            (val >= 0.0).then(|| (val / scale) as _)
+        log::warn!("World::update took {elapsed:?}, exceeding the budget of {budget:?}");
+        if self.configs.watchdog_action == WatchdogAction::Pause {
+            self.set_paused(true);
         }
-        let x = calc_pos(pos.x, self.min.0, self.cell_scale.0)?;
-        let y = calc_pos(pos.y, self.min.1, self.cell_scale.1)?;
-        Some((x, y))
     }
-}
+
+    fn set_paused(&mut self, paused: bool) {
+        if paused == self.paused {
+            return;
+        }
+        self.paused = paused;
+        if paused {
+            self.world.on_pause();
+            self.tutorial_action(TutorialAction::Pause);
+        } else {
+            self.world.on_resume();
+            self.tutorial_action(TutorialAction::Resume);
+        }
+    }
+
+    /// Rebuilds everything sized after the world image when a world has
+    /// resized or replaced its image.
+    fn recreate_world_texture(&mut self) -> anyhow::Result<()> {
+        let (width, height) = (self.world_image.width(), self.world_image.height());
+
+        let (texture, texture_view) = self.world_image.create_texture_with_mips(
+            &self.device,
+            &self.queue,
+            Some("World Main Texture"),
+            self.srgb_target(),
+            world_mip_level_count(&self.configs, &self.world_image),
+        )?;
+        self.texture = texture;
+        self.texture_view = texture_view;
+        self.rebind_world_texture();
+        self.upload = RowUpload::default();
+        self.world_texture_changed = true;
+        self.mips_dirty = true;
+        if let Some(trails) = &mut self.trails {
+            trails.resize(
+                &self.device,
+                (width, height),
+                &self.texture_bind_group_layout,
+            );
+        }
+        self.should_update_texture = false;
+        self.uploaded_version = self.world_image.version();
+
+        self.world_aspect = width as f32 / height as f32;
+        self.grid_vertices = vec![LineVertex::default(); ((width + height + 2) * 4) as _];
+        self.grid_vertex_buffer =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Grid Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&self.grid_vertices),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                });
+        let grid_indices = grid_indices(width, height);
+        self.grid_indices_len = grid_indices.len() as u32;
+        self.grid_index_buffer =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Grid Index Buffer"),
+                    contents: bytemuck::cast_slice(&grid_indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                });
+
+        self.cursor_translated = None;
+        self.update_vertices();
+        Ok(())
+    }
+
+    /// Whether textures are drawn to an sRGB view, which encodes colors from
+    /// linear light.
+    fn srgb_target(&self) -> bool {
+        render_format(&self.surface_config).is_srgb()
+    }
+
+    /// Recreates the sampler and bind group of the world texture, e.g. for a
+    /// new texture filter.
+    fn rebind_world_texture(&mut self) {
+        self.texture_sampler = WorldImage::create_sampler(
+            &self.device,
+            self.texture_filter.filter_mode(),
+            self.texture.mip_level_count(),
+        );
+        self.texture_bind_group = create_texture_bind_group(
+            &self.device,
+            &self.texture_bind_group_layout,
+            &self.palette_lookup,
+            self.world_image.format(),
+            &self.texture_view,
+            &self.texture_sampler,
+        );
+    }
+
+    fn upload_texture(&mut self) -> anyhow::Result<()> {
+        span!("upload");
+        let mut written_rects = None;
+        if self.world_image.version() != self.uploaded_version {
+            written_rects = self.world_image.take_written_rects(self.uploaded_version);
+            self.should_update_texture |= written_rects.is_none();
+        }
+        if self.should_update_texture {
+            self.upload_layers()?;
+        }
+        if self.diff_enabled {
+            // Also after ticks that left the image unchanged, to clear the
+            // previous changes.
+            self.upload_diff()?;
+        }
+        if self.texture.width() != self.world_image.width()
+            || self.texture.height() != self.world_image.height()
+            || self.texture.format() != self.world_image.texture_format(self.srgb_target())
+        {
+            return self.recreate_world_texture();
+        }
+        self.palette_lookup.upload_palette(
+            &self.queue,
+            &self.world_image,
+            self.configs.palette_cycle,
+            self.tick,
+        );
+
+        if let Some(rects) = written_rects
+            && !self.should_update_texture
+        {
+            for rect in rects {
+                self.world_image
+                    .update_wgpu_texture_rect(&self.texture, &self.queue, rect);
+            }
+            self.mips_dirty = true;
+            self.rates.record_upload();
+            self.world_texture_changed = true;
+            self.uploaded_version = self.world_image.version();
+        }
+
+        let Some(max_bytes) = self.configs.max_upload_bytes_per_frame else {
+            if self.should_update_texture {
+                self.upload_rows(0..self.world_image.height());
+                self.mips_dirty = true;
+                self.rates.record_upload();
+                self.world_texture_changed = true;
+                self.should_update_texture = false;
+                self.uploaded_version = self.world_image.version();
+            }
+            return Ok(());
+        };
+
+        if self.should_update_texture {
+            self.upload.mark_dirty();
+            self.should_update_texture = false;
+            self.uploaded_version = self.world_image.version();
+        }
+        let bytes_per_row = self.world_image.width() as u64 * 4;
+        let max_rows = (max_bytes / bytes_per_row).max(1) as u32;
+        if let Some(rows) = self.upload.next_rows(max_rows, self.world_image.height()) {
+            self.upload_rows(rows);
+            self.mips_dirty = true;
+            self.rates.record_upload();
+            self.world_texture_changed = true;
+        }
+        Ok(())
+    }
+
+    fn upload_rows(&mut self, rows: std::ops::Range<u32>) {
+        if !self
+            .staging
+            .upload(&self.device, &self.world_image, &self.texture, rows.clone())
+        {
+            // The GPU is more than a frame behind.
+            self.world_image
+                .update_wgpu_texture_rows(&self.texture, &self.queue, rows);
+        }
+    }
+
+    /// Uploads the cells changed since the last tick when the tick advanced,
+    /// first creating a texture for a new size.
+    fn upload_diff(&mut self) -> anyhow::Result<()> {
+        if !self
+            .diff
+            .update(&self.world_image, self.tick, self.configs.diff_style)
+        {
+            return Ok(());
+        }
+        let layer = self.diff.layer();
+        if let Some(diff_texture) = &self.diff_texture
+            && diff_texture.texture.width() == layer.width()
+            && diff_texture.texture.height() == layer.height()
+        {
+            layer.update_wgpu_texture(&diff_texture.texture, &self.queue);
+            return Ok(());
+        }
+        let (texture, view, sampler) = layer.create_texture(
+            &self.device,
+            &self.queue,
+            Some("Diff Texture"),
+            self.srgb_target(),
+        )?;
+        let bind_group = create_texture_bind_group(
+            &self.device,
+            &self.texture_bind_group_layout,
+            &self.palette_lookup,
+            ImageFormat::Rgba8,
+            &view,
+            &sampler,
+        );
+        self.diff_texture = Some(LayerTexture {
+            texture,
+            bind_group,
+        });
+        Ok(())
+    }
+
+    /// Uploads every layer, first creating textures for new or resized ones.
+    fn upload_layers(&mut self) -> anyhow::Result<()> {
+        let srgb_target = self.srgb_target();
+        let layers = self.world_image.layers();
+        self.layer_textures.truncate(layers.len());
+        for (i, layer) in layers.iter().enumerate() {
+            let current = self.layer_textures.get(i).filter(|layer_texture| {
+                layer_texture.texture.width() == layer.width()
+                    && layer_texture.texture.height() == layer.height()
+                    && layer_texture.texture.format() == layer.texture_format(srgb_target)
+            });
+            if let Some(layer_texture) = current {
+                if layer.format() == ImageFormat::Rgba8 {
+                    layer.update_wgpu_texture(&layer_texture.texture, &self.queue);
+                }
+                continue;
+            }
+
+            let (texture, view, sampler) = layer.create_texture(
+                &self.device,
+                &self.queue,
+                Some("World Layer Texture"),
+                srgb_target,
+            )?;
+            let bind_group = create_texture_bind_group(
+                &self.device,
+                &self.texture_bind_group_layout,
+                &self.palette_lookup,
+                layer.format(),
+                &view,
+                &sampler,
+            );
+            let layer_texture = LayerTexture {
+                texture,
+                bind_group,
+            };
+            match self.layer_textures.get_mut(i) {
+                Some(slot) => *slot = layer_texture,
+                None => self.layer_textures.push(layer_texture),
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self) -> anyhow::Result<()> {
+        span!("frame");
+        #[cfg(feature = "dev")]
+        self.reload_shaders();
+        if self.world.poll_image(&mut self.world_image) {
+            self.should_update_texture = true;
+        }
+        if let Some((width, height)) = self.world.requested_resize() {
+            self.world_image.resize(width, height);
+        }
+
+        #[cfg(feature = "frame-capture")]
+        let capturing = std::mem::take(&mut self.capture_next_frame);
+        #[cfg(feature = "frame-capture")]
+        if capturing {
+            self.device.start_capture();
+        }
+
+        let start = Instant::now();
+        self.upload_texture()?;
+        self.heatmap
+            .prepare(&self.device, &self.queue, self.world_image.heatmap())?;
+        self.frame_timing.upload = start.elapsed();
+
+        let start = Instant::now();
+        let result = self.draw_frame();
+        self.take_screenshots();
+        #[cfg(feature = "video")]
+        self.record_video_frame();
+        self.capture_timelapse();
+        self.frame_timing.render = start.elapsed();
+
+        #[cfg(feature = "frame-capture")]
+        if capturing {
+            self.device.stop_capture();
+            log::info!("captured frame {}", self.tick);
+        }
+        self.last_frame_timing = std::mem::take(&mut self.frame_timing);
+        self.handle.record_frame(self.last_frame_timing);
+        result
+    }
+
+    fn draw_frame(&mut self) -> anyhow::Result<()> {
+        span!("render");
+        if let Some(rates) = self.rates.end_frame() {
+            log::debug!(
+                "{} updates ({} skipped, target {}), {} frames, {} uploads in the last second",
+                rates.updates,
+                rates.skipped_updates,
+                self.configs.updates_per_second,
+                rates.frames,
+                rates.uploads
+            );
+        }
+        self.frame_timing.interval = self.last_render.elapsed();
+        self.last_render = Instant::now();
+        if self.configs.title_stats.is_some()
+            && self.title_refreshed.elapsed() >= TITLE_STATS_INTERVAL
+        {
+            self.refresh_title();
+        }
+
+        if self.window_size.width == 0 || self.window_size.height == 0 {
+            return Ok(());
+        }
+
+        let Some(surface) = &self.surface else {
+            return Ok(());
+        };
+        let output = match surface.get_current_texture() {
+            Ok(output) => output,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                surface.configure(&self.device, &self.surface_config);
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                anyhow::bail!(wgpu::SurfaceError::OutOfMemory);
+            }
+            // Timeout and unknown errors are transient; skip this frame.
+            Err(err) => {
+                self.report_error(err.into());
+                return Ok(());
+            }
+        };
+
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(render_format(&self.surface_config)),
+            ..Default::default()
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        self.globals.update(
+            &self.queue,
+            (self.world_image.width(), self.world_image.height()),
+            self.window_size,
+            self.cursor_translated,
+            self.display_adjustments,
+        );
+        if let Some(mipmaps) = &self.mipmaps
+            && std::mem::take(&mut self.mips_dirty)
+            && self.texture.mip_level_count() > 1
+        {
+            mipmaps.generate(&self.device, &mut encoder, &self.texture);
+        }
+        let world_pipeline = match self.world_image.format() {
+            ImageFormat::Rgba8 => &self.render_pipeline,
+            _ => self.palette_lookup.render_pipeline(),
+        };
+        if let Some(trails) = &mut self.trails
+            && std::mem::take(&mut self.world_texture_changed)
+        {
+            trails.accumulate(
+                &mut encoder,
+                world_pipeline,
+                &self.texture_bind_group,
+                self.globals.unadjusted_bind_group(),
+            );
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    &view,
+                    self.msaa_view.as_ref(),
+                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.draw_world(&mut render_pass, &self.vertex_buffer);
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Grid Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    &view,
+                    self.msaa_view.as_ref(),
+                    wgpu::LoadOp::Load,
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.grid_render_pipeline);
+            render_pass.set_bind_group(0, self.globals.empty_bind_group(), &[]);
+            render_pass.set_bind_group(1, self.globals.bind_group(), &[]);
+            render_pass.set_vertex_buffer(0, self.grid_vertex_buffer.slice(..));
+            render_pass
+                .set_index_buffer(self.grid_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(
+                grid_indices_range(self.grid_indices_len, self.grid_enabled),
+                0,
+                0..1,
+            );
+        }
+
+        self.agents.clear();
+        self.world.draw_agents(&mut self.agents);
+        self.agent_renderer.prepare(
+            &self.device,
+            &self.queue,
+            &self.agents,
+            &self.transform,
+            self.window_size,
+        );
+        if !self.agent_renderer.is_empty() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Agent Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    &view,
+                    self.msaa_view.as_ref(),
+                    wgpu::LoadOp::Load,
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.agent_renderer.draw(&mut render_pass);
+        }
+
+        let mut vector_field = self.vector_field();
+        self.world.draw_vector_field(&mut vector_field);
+        self.vector_renderer.prepare(
+            &self.device,
+            &self.queue,
+            &vector_field,
+            &self.transform,
+            self.window_size,
+        );
+        if !self.vector_renderer.is_empty() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Vector Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    &view,
+                    self.msaa_view.as_ref(),
+                    wgpu::LoadOp::Load,
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.vector_renderer.draw(&mut render_pass);
+        }
+
+        self.overlay_shapes.clear(self.window_size);
+        let mut world_overlay = Overlay::new(self.window_size.into(), self.transform);
+        self.world.draw_overlay(&mut world_overlay);
+        if let Some(highlight) = self.configs.hover_highlight
+            && let Some((x, y)) = self.cursor_translated
+            && !self.panning
+        {
+            highlight.draw(&self.world.hover_cells(x, y), &mut world_overlay);
+        }
+        world_overlay.tessellate(&mut self.overlay_shapes);
+        if self.focus == InputFocus::Text {
+            self.overlay_shapes.frame(3.0, [0.3, 0.6, 1.0, 0.8]);
+        }
+        if self.frame_graph_enabled {
+            draw_frame_graph(&self.handle.lock_frame_stats(), &mut self.overlay_shapes);
+            // Achieved share of the configured update rate.
+            if !self.paused {
+                let target = self.configs.updates_per_second as f32;
+                self.overlay_shapes.progress_bar(
+                    [8.0, 92.0],
+                    [248.0, 98.0],
+                    self.rates.last().updates as f32 / target,
+                );
+            }
+        }
+        if let Some(countdown) = &self.countdown {
+            let w = self.window_size.width as f32;
+            let h = self.window_size.height as f32;
+            self.overlay_shapes.progress_bar(
+                [w * 0.25, h - 32.0],
+                [w * 0.75, h - 20.0],
+                countdown.remaining_fraction(),
+            );
+        }
+        if let Some(fast_forward) = &self.fast_forward {
+            let w = self.window_size.width as f32;
+            let h = self.window_size.height as f32;
+            self.overlay_shapes.progress_bar(
+                [w * 0.25, h - 32.0],
+                [w * 0.75, h - 20.0],
+                fast_forward.progress(),
+            );
+        }
+        {
+            let plot = self.handle.lock_plot();
+            if !plot.is_empty() {
+                plot.draw(&mut self.overlay_shapes, self.window_size);
+            }
+        }
+        if self.hud_enabled {
+            self.draw_hud();
+        }
+        if self.inspector_enabled && self.context_menu.is_none() {
+            self.draw_inspector();
+        }
+        if let Some(menu) = &self.context_menu {
+            menu.draw(&mut self.overlay_shapes);
+        }
+        if self.command_palette.is_open() {
+            self.command_palette
+                .draw(&mut self.overlay_shapes, self.window_size);
+        }
+        if let Some(tutorial) = &self.configs.tutorial
+            && let Some(step) = tutorial.current()
+        {
+            const SCALE: f32 = 2.0;
+            const PADDING: f32 = 6.0;
+
+            let w = self.window_size.width as f32;
+            self.overlay_shapes.progress_bar(
+                [w * 0.25, 12.0],
+                [w * 0.75, 20.0],
+                tutorial.progress(),
+            );
+            let text = format!(
+                "{} ({}/{})",
+                step.prompt,
+                tutorial.position() + 1,
+                tutorial.len()
+            );
+            let [width, height] = Shapes::text_size(&text, SCALE);
+            let min = [(w - width) / 2.0, 20.0 + PADDING * 2.0];
+            self.overlay_shapes.rect(
+                [min[0] - PADDING, min[1] - PADDING],
+                [min[0] + width + PADDING, min[1] + height + PADDING],
+                [0.0, 0.0, 0.0, 0.6],
+            );
+            self.overlay_shapes
+                .text(min, SCALE, &text, [1.0, 1.0, 1.0, 0.9]);
+        }
+        self.overlay
+            .prepare(&self.device, &self.queue, &self.overlay_shapes);
+        if !self.overlay_shapes.is_empty() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Overlay Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    &view,
+                    self.msaa_view.as_ref(),
+                    wgpu::LoadOp::Load,
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.overlay.draw(&mut render_pass);
+        }
+
+        span!("present");
+        self.queue
+            .submit(self.staging.finish().into_iter().chain([encoder.finish()]));
+        self.staging.recall();
+        output.present();
+
+        Ok(())
+    }
+
+    /// Draws the world quad in `vertex_buffer` with its layers, heatmap and
+    /// the diff view.
+    fn draw_world(&self, render_pass: &mut wgpu::RenderPass<'_>, vertex_buffer: &wgpu::Buffer) {
+        // Trails are already in the surface's colors. The world shader
+        // only applies to RGBA colors.
+        let rgba_pipeline = self
+            .world_shader_pipeline
+            .as_ref()
+            .unwrap_or(&self.render_pipeline);
+        match &self.trails {
+            Some(trails) => {
+                render_pass.set_pipeline(rgba_pipeline);
+                render_pass.set_bind_group(0, trails.bind_group(), &[]);
+            }
+            None if self.world_image.format() == ImageFormat::Rgba8 => {
+                render_pass.set_pipeline(rgba_pipeline);
+                render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+            }
+            None => {
+                render_pass.set_pipeline(self.palette_lookup.render_pipeline());
+                render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+            }
+        }
+        render_pass.set_bind_group(1, self.globals.bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+
+        render_pass.set_pipeline(&self.layer_render_pipeline);
+        for (layer, layer_texture) in self.world_image.layers().iter().zip(&self.layer_textures) {
+            if layer.format() == ImageFormat::Rgba8 {
+                render_pass.set_bind_group(0, &layer_texture.bind_group, &[]);
+                render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+            }
+        }
+        self.heatmap.draw(render_pass, self.indices_len);
+        if self.diff_enabled
+            && let Some(diff_texture) = &self.diff_texture
+        {
+            render_pass.set_pipeline(&self.layer_render_pipeline);
+            render_pass.set_bind_group(0, &diff_texture.bind_group, &[]);
+            render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+        }
+    }
+
+    /// Renders the screenshots requested since the last frame.
+    fn take_screenshots(&mut self) {
+        for (scale, target) in std::mem::take(&mut self.screenshots) {
+            let result = self.render_scaled(scale);
+            if let Some(err) = target.finish(result) {
+                self.report_error(err.context("failed to save screenshot"));
+            }
+        }
+    }
+
+    /// Draws the world and the grid, if shown, to a texture `scale` times
+    /// the size of the world image and reads it back.
+    fn render_scaled(&mut self, scale: u32) -> anyhow::Result<WorldImage> {
+        let (world_width, world_height) = (self.world_image.width(), self.world_image.height());
+        let max = self.device.limits().max_texture_dimension_2d;
+        let size = world_width
+            .checked_mul(scale)
+            .zip(world_height.checked_mul(scale))
+            .filter(|&(width, height)| 0 < width && width <= max && 0 < height && height <= max);
+        let Some((width, height)) = size else {
+            anyhow::bail!(
+                "a {scale}x screenshot of {world_width}x{world_height} cells does not fit in \
+                 a texture of at most {max}x{max} pixels"
+            );
+        };
+        let target = Offscreen::new(
+            &self.device,
+            render_format(&self.surface_config),
+            (width, height),
+            self.sample_count,
+        );
+        let vertex_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Screenshot Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices_rectangle([-1.0, 1.0], [1.0, -1.0])),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        let mut grid_vertices = vec![LineVertex::default(); self.grid_vertices.len()];
+        update_grid_vertices(
+            &mut grid_vertices,
+            [-1.0, -1.0],
+            [1.0, 1.0],
+            world_width,
+            world_height,
+            1.0 / width as f32,
+            1.0 / height as f32,
+        );
+        let grid_vertex_buffer =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Screenshot Grid Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&grid_vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+        self.globals.update(
+            &self.queue,
+            (world_width, world_height),
+            PhysicalSize::new(width, height),
+            None,
+            self.display_adjustments,
+        );
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Screenshot Encoder"),
+            });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Screenshot Render Pass"),
+                color_attachments: &[Some(surface_attachment(
+                    target.view(),
+                    target.msaa_view(),
+                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.draw_world(&mut render_pass, &vertex_buffer);
+            // Without the grid, the outline of the world would only frame the
+            // picture.
+            if self.grid_enabled {
+                render_pass.set_pipeline(&self.grid_render_pipeline);
+                render_pass.set_bind_group(0, self.globals.empty_bind_group(), &[]);
+                render_pass.set_bind_group(1, self.globals.bind_group(), &[]);
+                render_pass.set_vertex_buffer(0, grid_vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(self.grid_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.grid_indices_len, 0, 0..1);
+            }
+        }
+        target.read(&self.device, &self.queue, encoder)
+    }
+
+    #[cfg(feature = "video")]
+    fn start_recording(&mut self) {
+        let scale = self.configs.video.scale.max(1);
+        let size = (
+            self.world_image.width().saturating_mul(scale),
+            self.world_image.height().saturating_mul(scale),
+        );
+        match crate::video::VideoEncoder::start(&self.configs.video, size) {
+            Ok(encoder) => self.video = Some(encoder),
+            Err(err) => self.report_error(err.context("failed to start recording")),
+        }
+    }
+
+    #[cfg(feature = "video")]
+    fn stop_recording(&mut self) {
+        let Some(encoder) = self.video.take() else {
+            return;
+        };
+        match encoder.finish() {
+            Ok(path) => log::info!("saved {}", path.display()),
+            Err(err) => self.report_error(err),
+        }
+    }
+
+    /// Adds the world as of this frame to the recording, if a frame is due.
+    /// Stops recording on failure, e.g. when the world image was resized.
+    #[cfg(feature = "video")]
+    fn record_video_frame(&mut self) {
+        if !self
+            .video
+            .as_ref()
+            .is_some_and(crate::video::VideoEncoder::is_due)
+        {
+            return;
+        }
+        let frame = self.render_scaled(self.configs.video.scale.max(1));
+        let Some(encoder) = &mut self.video else {
+            return;
+        };
+        if let Err(err) = frame.and_then(|frame| encoder.push(frame)) {
+            self.video = None;
+            self.report_error(err.context("stopped recording"));
+        }
+    }
+
+    fn start_timelapse(&mut self) {
+        let Some(timelapse) = &self.configs.timelapse else {
+            return;
+        };
+        match TimelapseCapture::new(timelapse) {
+            Ok(capture) => self.timelapse = Some(capture),
+            Err(err) => self.report_error(err.context("failed to start timelapse")),
+        }
+    }
+
+    /// Captures the world if an update due for the timelapse ran since the
+    /// last capture. Stops capturing on failure.
+    fn capture_timelapse(&mut self) {
+        let Some(timelapse) = &self.timelapse else {
+            return;
+        };
+        if !timelapse.is_due(self.tick) {
+            return;
+        }
+        let frame = self.render_scaled(timelapse.scale());
+        let Some(timelapse) = &mut self.timelapse else {
+            return;
+        };
+        if let Err(err) = frame.and_then(|frame| timelapse.capture(self.tick, frame)) {
+            if let Some(timelapse) = self.timelapse.take() {
+                let _ = timelapse.finish();
+            }
+            self.report_error(err.context("stopped timelapse"));
+        }
+    }
+
+    /// Draws the app stats and the world's lines in the top-right corner.
+    fn draw_hud(&mut self) {
+        const SCALE: f32 = 2.0;
+        const MARGIN: f32 = 8.0;
+        const PADDING: f32 = 6.0;
+
+        let rates = self.rates.last();
+        let mut lines = vec![
+            format!("FPS {}", rates.frames),
+            format!("UPS {}/{}", rates.updates, self.configs.updates_per_second),
+            format!("Gen {}", self.tick),
+        ];
+        if self.paused {
+            lines.push("Paused".to_owned());
+        }
+        #[cfg(feature = "video")]
+        if self.video.is_some() {
+            lines.push("Recording".to_owned());
+        }
+        if self.display_adjustments != DisplayAdjustments::NEUTRAL {
+            let DisplayAdjustments {
+                exposure,
+                gamma,
+                contrast,
+            } = self.display_adjustments;
+            lines.push(format!(
+                "EV {exposure:+.1} Gamma {gamma:.2} Contrast {contrast:.2}"
+            ));
+        }
+        if self.configs.hud_counts {
+            let version = self.world_image.version();
+            if self.hud_counts.as_ref().is_none_or(|(v, _)| *v != version) {
+                self.hud_counts = Some((version, count_lines(&self.world_image)));
+            }
+            if let Some((_, counts)) = &self.hud_counts {
+                lines.extend(counts.iter().cloned());
+            }
+        }
+        lines.extend(self.world.hud_lines());
+        let text = lines.join("\n");
+
+        let [width, height] = Shapes::text_size(&text, SCALE);
+        let right = self.window_size.width as f32 - MARGIN;
+        let min = [right - width - PADDING * 2.0, MARGIN];
+        let max = [right, MARGIN + height + PADDING * 2.0];
+        self.overlay_shapes.rect(min, max, [0.0, 0.0, 0.0, 0.6]);
+        self.overlay_shapes.text(
+            [min[0] + PADDING, min[1] + PADDING],
+            SCALE,
+            &text,
+            [1.0, 1.0, 1.0, 0.9],
+        );
+    }
+
+    /// Outlines the hovered cell and shows a tooltip with its coordinates and
+    /// the world's description next to the cursor.
+    fn draw_inspector(&mut self) {
+        const SCALE: f32 = 2.0;
+        const OFFSET: f32 = 16.0;
+        const PADDING: f32 = 6.0;
+
+        let (Some((x, y)), Some(cursor)) = (self.cursor_translated, self.cursor_position) else {
+            return;
+        };
+        let (left, top) = self.transform.cell_to_window((x, y));
+        let (cell_width, cell_height) = self.transform.cell_size();
+        self.overlay_shapes.rect_outline(
+            [left as f32, top as f32],
+            [(left + cell_width) as f32, (top + cell_height) as f32],
+            2.0,
+            [1.0, 1.0, 0.0, 1.0],
+        );
+
+        let mut text = format!("({x}, {y})");
+        if let Some(inspected) = self.world.inspect(x, y) {
+            text.push('\n');
+            text.push_str(&inspected);
+        }
+        let [width, height] = Shapes::text_size(&text, SCALE);
+        let size = [width + PADDING * 2.0, height + PADDING * 2.0];
+        // Below and to the right of the cursor, flipped at the window edges.
+        let window = [
+            self.window_size.width as f32,
+            self.window_size.height as f32,
+        ];
+        let cursor = [cursor.x as f32, cursor.y as f32];
+        let min: [f32; 2] = std::array::from_fn(|i| {
+            if cursor[i] + OFFSET + size[i] <= window[i] {
+                cursor[i] + OFFSET
+            } else {
+                (cursor[i] - OFFSET - size[i]).max(0.0)
+            }
+        });
+        let max = [min[0] + size[0], min[1] + size[1]];
+        self.overlay_shapes.rect(min, max, [0.0, 0.0, 0.0, 0.6]);
+        self.overlay_shapes.text(
+            [min[0] + PADDING, min[1] + PADDING],
+            SCALE,
+            &text,
+            [1.0, 1.0, 1.0, 0.9],
+        );
+    }
+
+    fn set_focus(&mut self, focus: InputFocus) {
+        if focus == self.focus {
+            return;
+        }
+        self.focus = focus;
+        if focus == InputFocus::Text {
+            // Releases are not forwarded while typing.
+            self.handle.lock_keyboard().release_all();
+            self.dragging.clear();
+            self.gestures.cancel();
+        }
+        self.window.set_ime_allowed(focus == InputFocus::Text);
+        self.world.focus_changed(focus);
+    }
+
+    fn keyboard_input(&mut self, event: KeyEvent) {
+        use crate::util::is_pressed;
+        use winit::keyboard::{KeyCode, PhysicalKey};
+
+        if self.command_palette.is_open() {
+            match self.command_palette.key_input(&event) {
+                PaletteInput::Edited => {}
+                PaletteInput::Submit(line) => self.run_command(&line),
+                PaletteInput::Close => self.close_command_palette(),
+            }
+            return;
+        }
+
+        if self.focus == InputFocus::Text {
+            if is_pressed(&event, KeyCode::Escape) {
+                self.set_focus(InputFocus::World);
+            } else if event.state.is_pressed()
+                && let Some(text) = &event.text
+            {
+                self.world.text_input(text, &mut self.world_image);
+            }
+            return;
+        }
+
+        // While the context menu is open, digits pick an action and any
+        // other key closes it.
+        if self.context_menu.is_some() {
+            if event.state.is_pressed() {
+                let index = match event.physical_key {
+                    PhysicalKey::Code(key) => DIGIT_KEYS.iter().position(|&digit| digit == key),
+                    _ => None,
+                };
+                self.close_context_menu(index);
+            }
+            return;
+        }
+
+        self.handle.lock_keyboard().handle_event(&event);
+        let actions: Vec<AppAction> = self
+            .configs
+            .input_map
+            .fired(&event, self.modifiers)
+            .collect();
+        for action in actions {
+            self.run_action(action);
+            if self.closed || matches!(action, AppAction::TextFocus | AppAction::CommandPalette) {
+                return;
+            }
+        }
+        if event.state.is_pressed()
+            && !event.repeat
+            && let PhysicalKey::Code(key) = event.physical_key
+        {
+            self.tutorial_action(TutorialAction::Key(key));
+        }
+
+        self.world.keyboard_input(event, &mut self.world_image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn poll_gamepads(&mut self) {
+        let Some(poller) = &mut self.gamepads else {
+            return;
+        };
+        let (events, gamepads) = poller.poll();
+        self.handle.set_gamepads(gamepads);
+        for event in events {
+            self.world.gamepad_input(event, &mut self.world_image);
+        }
+    }
+
+    /// Runs the actions with [`Trigger::Held`](crate::Trigger::Held) whose
+    /// keys are held, once per frame.
+    fn run_held_actions(&mut self) {
+        if self.focus != InputFocus::World
+            || self.context_menu.is_some()
+            || self.command_palette.is_open()
+        {
+            return;
+        }
+        let keyboard = self.handle.lock_keyboard().clone();
+        let actions: Vec<AppAction> = self.configs.input_map.held(&keyboard).collect();
+        for action in actions {
+            self.run_action(action);
+            if self.closed || matches!(action, AppAction::TextFocus | AppAction::CommandPalette) {
+                return;
+            }
+        }
+    }
+
+    fn run_action(&mut self, action: AppAction) {
+        match action {
+            AppAction::TextFocus => self.set_focus(InputFocus::Text),
+            AppAction::CommandPalette if self.configs.command_palette => {
+                self.open_command_palette()
+            }
+            AppAction::CommandPalette => (),
+            AppAction::Play => {
+                self.set_paused(!self.paused);
+                self.countdown = None;
+            }
+            AppAction::UpdateOnce if self.paused => self.run_update(),
+            AppAction::UpdateOnce => (),
+            AppAction::Grid => self.grid_enabled = !self.grid_enabled,
+            AppAction::FrameGraph => self.frame_graph_enabled = !self.frame_graph_enabled,
+            AppAction::Hud => self.hud_enabled = !self.hud_enabled,
+            AppAction::Inspector => self.inspector_enabled = !self.inspector_enabled,
+            AppAction::DiffView => {
+                self.diff_enabled = !self.diff_enabled;
+                self.diff.reset();
+                self.should_update_texture = true;
+            }
+            AppAction::TextureFilter => {
+                self.texture_filter = match self.texture_filter {
+                    TextureFilter::Nearest => TextureFilter::Linear,
+                    TextureFilter::Linear => TextureFilter::Nearest,
+                };
+                self.rebind_world_texture();
+            }
+            AppAction::ExposureUp => self.display_adjustments.exposure += 0.5,
+            AppAction::ExposureDown => self.display_adjustments.exposure -= 0.5,
+            AppAction::ResetDisplay => {
+                self.display_adjustments = self.configs.display_adjustments;
+            }
+            AppAction::ResetView => {
+                self.camera = Camera::default();
+                self.camera_changed();
+            }
+            AppAction::Quit => {
+                if self.world.on_close_requested() == CloseAction::Exit {
+                    self.exit();
+                }
+            }
+            #[cfg(feature = "frame-capture")]
+            AppAction::Capture => {
+                self.capture_next_frame = true;
+                self.window.request_redraw();
+            }
+            #[cfg(feature = "video")]
+            AppAction::Record => {
+                if self.video.is_some() {
+                    self.stop_recording();
+                } else {
+                    self.start_recording();
+                }
+            }
+        }
+    }
+
+    fn open_command_palette(&mut self) {
+        // Releases are not forwarded while typing.
+        self.handle.lock_keyboard().release_all();
+        self.command_palette.open();
+        self.window.set_ime_allowed(true);
+    }
+
+    fn close_command_palette(&mut self) {
+        self.command_palette.close();
+        self.window.set_ime_allowed(self.focus == InputFocus::Text);
+    }
+
+    /// Runs a line of the command palette, closing it unless it fails.
+    fn run_command(&mut self, line: &str) {
+        match Command::parse(line).and_then(|command| self.execute(command)) {
+            Ok(()) => self.close_command_palette(),
+            Err(err) => self.command_palette.set_error(&err),
+        }
+    }
+
+    fn execute(&mut self, command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Ups(ups) => self.handle.set_ups(ups),
+            Command::Pause => self.handle.pause(),
+            Command::Play => self.handle.resume(),
+            Command::Step(1) => self.run_update(),
+            Command::Step(n) => self.handle.fast_forward(n),
+            Command::Save(path) => Checkpoints::write(&path, self.tick, &self.world)?,
+            Command::Load(path) => {
+                self.tick = Checkpoints::load(&path, &mut self.world, &mut self.world_image)?;
+            }
+            Command::Goto(x, y) => {
+                let (width, height) = (self.world_image.width(), self.world_image.height());
+                anyhow::ensure!(
+                    x < width && y < height,
+                    "({x}, {y}) is outside the {width}x{height} world"
+                );
+                self.camera.center_on((
+                    (x as f64 + 0.5) / width as f64,
+                    (y as f64 + 0.5) / height as f64,
+                ));
+                self.camera_changed();
+            }
+            Command::ResetView => {
+                self.camera = Camera::default();
+                self.camera_changed();
+            }
+            Command::Quit => self.handle.exit(),
+            Command::World(line) => {
+                self.world.command(&line, &mut self.world_image)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        let button = self
+            .configs
+            .mouse_button_map
+            .get(&button)
+            .copied()
+            .unwrap_or(button);
+        let context_button = self.configs.button_context_menu;
+
+        // Clicking while typing only takes focus back to the world.
+        if self.focus == InputFocus::Text {
+            if state.is_pressed() {
+                self.set_focus(InputFocus::World);
+            }
+            return;
+        }
+
+        if state.is_pressed() && self.context_menu.is_some() {
+            let index = self.context_menu.as_ref().and_then(|menu| {
+                let position = self.cursor_position?;
+                menu.item_at((position.x, position.y))
+            });
+            self.close_context_menu(index.filter(|_| button == self.configs.button_menu_select));
+            if Some(button) != context_button {
+                return;
+            }
+        }
+        if state.is_pressed() && Some(button) == context_button && self.open_context_menu() {
+            return;
+        }
+
+        if Some(button) == self.configs.button_pan {
+            self.panning = state.is_pressed();
+        }
+        if state.is_pressed() {
+            self.tutorial_action(TutorialAction::MouseButton(button));
+        }
+
+        self.send_mouse_event(state, button, MouseEventKind::Button);
+        self.dragging.retain(|&b| b != button);
+        if state.is_pressed() && Some(button) != self.configs.button_pan {
+            self.dragging.push(button);
+            if let Some(cell) = self.cursor_translated {
+                self.world
+                    .cursor_drag(cell, cell, button, &mut self.world_image);
+            }
+        }
+
+        if let Some(gestures) = &self.configs.gestures
+            && let Some(position) = self.cursor_position
+            && Some(button) != self.configs.button_pan
+        {
+            let position = (position.x, position.y);
+            if state.is_pressed() {
+                self.gestures.press(button, position);
+            } else {
+                for kind in self.gestures.release(button, position, gestures) {
+                    self.send_mouse_event(state, button, kind);
+                }
+            }
+        }
+    }
+
+    fn send_mouse_event(&mut self, state: ElementState, button: MouseButton, kind: MouseEventKind) {
+        self.world.mouse_input(
+            MouseEvent {
+                state,
+                button,
+                pos: self.cursor_translated,
+                kind,
+            },
+            &mut self.world_image,
+        );
+    }
+
+    /// Treats the first finger down as the left mouse button, so that worlds
+    /// work on touch screens unchanged. Other fingers are ignored.
+    fn touch_input(&mut self, phase: TouchPhase, location: PhysicalPosition<f64>, id: u64) {
+        match phase {
+            TouchPhase::Started if self.touch.is_none() => {
+                self.touch = Some(id);
+                self.cursor_moved(location);
+                self.mouse_input(ElementState::Pressed, MouseButton::Left);
+            }
+            TouchPhase::Moved if self.touch == Some(id) => self.cursor_moved(location),
+            TouchPhase::Ended | TouchPhase::Cancelled if self.touch == Some(id) => {
+                self.touch = None;
+                self.cursor_moved(location);
+                self.mouse_input(ElementState::Released, MouseButton::Left);
+            }
+            _ => (),
+        }
+    }
+
+    fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        let last = self.cursor_position.replace(position);
+        if let Some(menu) = &mut self.context_menu
+            && menu.hover(Some((position.x, position.y)))
+        {
+            self.request_input_redraw();
+        }
+        if self.panning
+            && let Some(last) = last
+        {
+            self.pan((position.x - last.x, position.y - last.y));
+        } else {
+            self.translate_cursor();
+        }
+
+        if let Some(gestures) = &self.configs.gestures
+            && let Some(button) = self.gestures.moved((position.x, position.y), gestures)
+        {
+            self.send_mouse_event(ElementState::Pressed, button, MouseEventKind::DragStart);
+        }
+    }
+
+    /// Moves the view by `delta` window pixels.
+    fn pan(&mut self, delta: (f64, f64)) {
+        self.camera
+            .pan(delta, fit_size(self.world_aspect, self.window_size));
+        self.camera_changed();
+        self.tutorial_action(TutorialAction::Pan);
+    }
+
+    fn translate_cursor(&mut self) {
+        let Some(position) = self.cursor_position else {
+            return;
+        };
+        let pos = self
+            .transform
+            .window_to_cell_precise((position.x, position.y), self.configs.cursor_rounding);
+        let last = std::mem::replace(&mut self.cursor_translated, pos.map(|pos| pos.cell));
+
+        self.world.cursor_moved_precise(pos, &mut self.world_image);
+        if let (Some(from), Some(to)) = (last, self.cursor_translated)
+            && from != to
+        {
+            for &button in &self.dragging {
+                self.world
+                    .cursor_drag(from, to, button, &mut self.world_image);
+            }
+        }
+    }
+
+    fn tutorial_action(&mut self, action: TutorialAction) {
+        if let Some(tutorial) = &mut self.configs.tutorial
+            && tutorial.perform(action)
+        {
+            self.request_input_redraw();
+        }
+    }
+
+    /// Sets the window title, with the stats of `AppConfigs::title_stats`
+    /// if it is set.
+    fn refresh_title(&mut self) {
+        let title = &self.configs.window_attributes.title;
+        let title = match &self.configs.title_stats {
+            Some(format) => {
+                format_title_stats(format, title, self.rates.last(), self.tick, self.paused)
+            }
+            None => title.clone(),
+        };
+        // Setting an unchanged title still repaints the title bar on some
+        // platforms.
+        if title != self.title {
+            self.window.set_title(&title);
+            self.title = title;
+        }
+        self.title_refreshed = Instant::now();
+    }
+
+    /// Opens the context menu of the cell under the cursor. Returns `false`
+    /// if the world offers no actions there.
+    fn open_context_menu(&mut self) -> bool {
+        let (Some(cell), Some(position)) = (self.cursor_translated, self.cursor_position) else {
+            return false;
+        };
+        let actions = self.world.cell_actions(cell.0, cell.1);
+        if actions.is_empty() {
+            return false;
+        }
+        let mut menu = ContextMenu::new(cell, actions, (position.x, position.y), self.window_size);
+        menu.hover(Some((position.x, position.y)));
+        self.context_menu = Some(menu);
+        self.request_input_redraw();
+        true
+    }
+
+    /// Closes the context menu, running the action at `index` if there is
+    /// one.
+    fn close_context_menu(&mut self, index: Option<usize>) {
+        let Some(menu) = self.context_menu.take() else {
+            return;
+        };
+        if let Some(action) = index.and_then(|index| menu.action(index)) {
+            let (x, y) = menu.cell();
+            self.world.cell_action(x, y, action, &mut self.world_image);
+        }
+        self.request_input_redraw();
+    }
+}
+
+/// Scroll distance in pixels treated like one step of a mouse wheel.
+const PIXELS_PER_LINE: f64 = 40.0;
+
+/// Minimum time between title refreshes for `AppConfigs::title_stats`.
+const TITLE_STATS_INTERVAL: Duration = Duration::from_millis(250);
+
+const DIGIT_KEYS: [winit::keyboard::KeyCode; 9] = {
+    use winit::keyboard::KeyCode::*;
+    [
+        Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+    ]
+};
+
+/// Schedules texture uploads in bands of rows, so that a dirty image is
+/// spread over several frames.
+#[derive(Debug, Default)]
+struct RowUpload {
+    next_row: Option<u32>,
+    restart: bool,
+}
+
+impl RowUpload {
+    fn is_pending(&self) -> bool {
+        self.next_row.is_some()
+    }
+
+    fn mark_dirty(&mut self) {
+        if self.next_row.is_some() {
+            // Rows already uploaded in this pass are stale again.
+            self.restart = true;
+        } else {
+            self.next_row = Some(0);
+        }
+    }
+
+    fn next_rows(&mut self, max_rows: u32, height: u32) -> Option<std::ops::Range<u32>> {
+        let start = self.next_row?;
+        let end = (start + max_rows).min(height);
+        self.next_row = if end < height {
+            Some(end)
+        } else if std::mem::take(&mut self.restart) {
+            Some(0)
+        } else {
+            None
+        };
+        Some(start..end)
+    }
+}
+
+#[derive(Debug)]
+struct Countdown {
+    start: Instant,
+    end: Instant,
+}
+
+impl Countdown {
+    fn new(delay: Duration) -> Self {
+        let start = Instant::now();
+        Self {
+            start,
+            end: start + delay,
+        }
+    }
+
+    fn remaining_fraction(&self) -> f32 {
+        let total = (self.end - self.start).as_secs_f32();
+        if total > 0.0 {
+            (self.end - Instant::now()).as_secs_f32() / total
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FastForward {
+    total: u64,
+    remaining: u64,
+}
+
+impl FastForward {
+    fn progress(&self) -> f32 {
+        1.0 - self.remaining as f32 / self.total.max(1) as f32
+    }
+}
+
+/// `requested` if the adapter can multisample `format` that many times,
+/// else 4 if it can, else 1, with the device features the count needs.
+fn msaa_sample_count(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    requested: u32,
+) -> (u32, wgpu::Features) {
+    if requested <= 1 {
+        return (1, wgpu::Features::empty());
+    }
+    // Only 4 samples are guaranteed without adapter-specific features.
+    let specific = wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+    let flags = adapter.get_texture_format_features(format).flags;
+    if requested != 4
+        && adapter.features().contains(specific)
+        && flags.sample_count_supported(requested)
+    {
+        return (requested, specific);
+    }
+    let sample_count = if flags.sample_count_supported(4) {
+        4
+    } else {
+        1
+    };
+    if sample_count != requested {
+        log::warn!("{requested}x MSAA is not supported, using {sample_count}x");
+    }
+    (sample_count, wgpu::Features::empty())
+}
+
+/// The window title from `format`, filling in the `{fps}`, `{ups}`,
+/// `{tick}`, `{paused}` and `{title}` placeholders.
+fn format_title_stats(
+    format: &str,
+    title: &str,
+    rates: RateStats,
+    tick: u64,
+    paused: bool,
+) -> String {
+    // The title goes in last, so that braces in it are left alone.
+    format
+        .replace("{fps}", &rates.frames.to_string())
+        .replace("{ups}", &rates.updates.to_string())
+        .replace("{tick}", &tick.to_string())
+        .replace("{paused}", if paused { "paused" } else { "" })
+        .trim_end()
+        .replace("{title}", title)
+}
+
+/// The format drawn in, an sRGB view of the surface if it has one.
+fn render_format(surface_config: &wgpu::SurfaceConfiguration) -> wgpu::TextureFormat {
+    surface_config
+        .view_formats
+        .first()
+        .copied()
+        .unwrap_or(surface_config.format)
+}
+
+/// Multisampled color target the window is drawn to before being resolved,
+/// if MSAA is on.
+fn create_msaa_view(
+    device: &wgpu::Device,
+    surface_config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("MSAA Texture"),
+        size: wgpu::Extent3d {
+            width: surface_config.width,
+            height: surface_config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: render_format(surface_config),
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
+
+/// Color attachment drawing to `view`, through `msaa_view` and resolving
+/// into `view` when MSAA is on.
+fn surface_attachment<'a>(
+    view: &'a wgpu::TextureView,
+    msaa_view: Option<&'a wgpu::TextureView>,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPassColorAttachment<'a> {
+    let ops = wgpu::Operations {
+        load,
+        store: wgpu::StoreOp::Store,
+    };
+    match msaa_view {
+        Some(msaa_view) => wgpu::RenderPassColorAttachment {
+            view: msaa_view,
+            resolve_target: Some(view),
+            ops,
+        },
+        None => wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops,
+        },
+    }
+}
+
+/// Pipeline drawing the world quad with `shader`, which must take [`Vertex`]
+/// input and the world texture in bind group 0.
+fn create_world_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    label: &str,
+    blend: wgpu::BlendState,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_grid_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Grid Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[LineVertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    alpha: wgpu::BlendComponent::REPLACE,
+                    color: wgpu::BlendComponent::REPLACE,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Runs `f`, turning validation errors it causes into an error instead of
+/// the default panic. Used for shaders supplied at runtime.
+fn validated<T>(device: &wgpu::Device, what: &str, f: impl FnOnce() -> T) -> anyhow::Result<T> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = f();
+    if let Some(err) = futures::executor::block_on(device.pop_error_scope()) {
+        anyhow::bail!("invalid {what}: {err}");
+    }
+    Ok(value)
+}
+
+/// Builds the pipeline of [`AppConfigs::world_shader`] from its source,
+/// without the prelude.
+fn create_world_shader_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    source: &str,
+) -> anyhow::Result<wgpu::RenderPipeline> {
+    let source = with_prelude(&format!("{}\n{source}", include_str!("world_shader.wgsl")));
+    validated(device, "world shader", || {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("World Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        create_world_pipeline(
+            device,
+            layout,
+            &shader,
+            format,
+            "World Shader Render Pipeline",
+            wgpu::BlendState::REPLACE,
+            sample_count,
+        )
+    })
+}
+
+/// GPU side of one of the world image's layers.
+#[derive(Debug)]
+struct LayerTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct Vertex {
+pub(super) struct Vertex {
     position: [f32; 2],
     tex_coords: [f32; 2],
 }
@@ -612,7 +2894,7 @@ impl Vertex {
         1 => Float32x2,
     ];
 
-    fn desc() -> wgpu::VertexBufferLayout<'static> {
+    pub(super) fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
@@ -643,59 +2925,94 @@ impl LineVertex {
     }
 }
 
+/// Binds the world texture for the pipeline drawing images of `format`.
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    palette_lookup: &PaletteLookup,
+    format: ImageFormat,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    if format != ImageFormat::Rgba8 {
+        return palette_lookup.create_bind_group(device, view);
+    }
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("texture_bind_group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+/// Mip levels of the world texture, more than one only for RGBA images with
+/// [`AppConfigs::mipmaps`] on.
+fn world_mip_level_count(configs: &AppConfigs, image: &WorldImage) -> u32 {
+    if configs.mipmaps && image.format() == ImageFormat::Rgba8 {
+        Mipmaps::level_count(image.width(), image.height())
+    } else {
+        1
+    }
+}
+
+/// Size in window pixels of the world quad fitted into the window at zoom 1.
+fn fit_size(world_aspect: f32, window_size: PhysicalSize<u32>) -> (f64, f64) {
+    let window_aspect = window_size.width as f32 / window_size.height as f32;
+    let (x, y) = if window_aspect > world_aspect {
+        (world_aspect / window_aspect, 1.0)
+    } else {
+        (1.0, window_aspect / world_aspect)
+    };
+    // add margin
+    let p = 0.999;
+    (
+        window_size.width as f64 * (x * p) as f64,
+        window_size.height as f64 * (y * p) as f64,
+    )
+}
+
 fn aspect_adjusted_vertices(
     world_aspect: f32,
+    camera: &Camera,
     window_size: PhysicalSize<u32>,
     world_width: u32,
     world_height: u32,
     grid_vertices: &mut [LineVertex],
 ) -> ([Vertex; 4], WorldTransform) {
-    let (x, y) = {
-        let window_aspect = window_size.width as f32 / window_size.height as f32;
-        let (x, y) = if window_aspect > world_aspect {
-            (world_aspect / window_aspect, 1.0)
-        } else {
-            (1.0, window_aspect / world_aspect)
-        };
-        // add margin
-        let p = 0.999;
-        let x = x * p;
-        let y = y * p;
-        (x, y)
-    };
-
-    let vertices = vertices_rectangle([-x, y], [x, -y]);
-
-    // Calculate bounds
     let w = window_size.width as f64;
     let h = window_size.height as f64;
-    let x0 = w * (1.0 - x as f64) / 2.0;
-    let y0 = h * (1.0 - y as f64) / 2.0;
-    let x1 = w - x0;
-    let y1 = h - y0;
-    let w1 = (x1 - x0) / world_width as f64;
-    let h1 = (y1 - y0) / world_height as f64;
-    let bounds = WorldTransform {
-        min: (x0, y0),
-        _max: (x1, y1),
-        cell_scale: (w1, h1),
-    };
+    let (min, max) = camera.apply(fit_size(world_aspect, window_size), (w, h));
+    let transform = WorldTransform::new(min, max, (world_width, world_height));
+
+    let to_clip = |(x, y): (f64, f64)| [(x / w * 2.0 - 1.0) as f32, (1.0 - y / h * 2.0) as f32];
+    let [left, top] = to_clip(min);
+    let [right, bottom] = to_clip(max);
+
+    let vertices = vertices_rectangle([left, top], [right, bottom]);
 
     // Update grid info
     update_grid_vertices(
         grid_vertices,
-        x,
-        y,
+        [left, bottom],
+        [right, top],
         world_width,
         world_height,
         1.0 / window_size.width as f32,
         1.0 / window_size.height as f32,
     );
 
-    (vertices, bounds)
+    (vertices, transform)
 }
 
-fn vertices_rectangle(top_left: [f32; 2], bottom_right: [f32; 2]) -> [Vertex; 4] {
+pub(super) fn vertices_rectangle(top_left: [f32; 2], bottom_right: [f32; 2]) -> [Vertex; 4] {
     let [a, b, c, d] = positions_rectangle(top_left, bottom_right);
 
     [
@@ -760,18 +3077,13 @@ fn positions_rectangle(top_left: [f32; 2], bottom_right: [f32; 2]) -> [[f32; 2];
 
 fn update_grid_vertices(
     grid_vertices: &mut [LineVertex],
-    x: f32,
-    y: f32,
+    [x0, y0]: [f32; 2],
+    [x1, y1]: [f32; 2],
     world_width: u32,
     world_height: u32,
     half_line_width: f32,
     half_line_height: f32,
 ) {
-    let x0 = -x;
-    let y0 = -y;
-    let x1 = x;
-    let y1 = y;
-
     let w = world_width as f32;
     let h = world_height as f32;
 
@@ -829,3 +3141,36 @@ fn grid_indices_range(n_indices: u32, grid_enabled: bool) -> std::ops::Range<u32
         0..24 // 6 * 4
     }
 }
+
+/// Lines of [`AppConfigs::hud_counts`] for `image`.
+fn count_lines(image: &WorldImage) -> Vec<String> {
+    const MAX_LINES: usize = 8;
+
+    match image.format() {
+        ImageFormat::Rgba8 => {
+            let mut counts: Vec<_> = image.color_histogram().into_iter().collect();
+            counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.0.cmp(&b.0.0)));
+            counts
+                .into_iter()
+                .take(MAX_LINES)
+                .map(|(Color([r, g, b, _]), count)| format!("#{r:02x}{g:02x}{b:02x} {count}"))
+                .collect()
+        }
+        ImageFormat::Indexed8 => image
+            .index_histogram()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .take(MAX_LINES)
+            .map(|(index, count)| format!("[{index}] {count}"))
+            .collect(),
+        ImageFormat::R32Float => {
+            let stats = image.value_stats();
+            vec![format!(
+                "Sum {:.3} Mean {:.3}",
+                stats.sum,
+                stats.mean().unwrap_or(0.0)
+            )]
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    World, WorldImage,
+    checkpoint::{Autosave, AutosaveInterval, Checkpoints},
+};
+use std::time::Instant;
+
+/// Takes the checkpoints configured with
+/// [`AppConfigs::autosave`](crate::AppConfigs::autosave).
+#[derive(Debug)]
+pub(crate) struct Autosaver {
+    checkpoints: Checkpoints,
+    interval: AutosaveInterval,
+    last_tick: u64,
+    last_time: Instant,
+}
+
+impl Autosaver {
+    pub fn new(autosave: &Autosave) -> anyhow::Result<Self> {
+        Ok(Self {
+            checkpoints: Checkpoints::new(&autosave.dir, autosave.max_kept)?,
+            interval: autosave.interval,
+            last_tick: 0,
+            last_time: Instant::now(),
+        })
+    }
+
+    /// Restores `world` from the latest checkpoint, returning its tick, or
+    /// `None` when there is nothing to resume.
+    pub fn resume<W: World + ?Sized>(
+        &mut self,
+        world: &mut W,
+        image: &mut WorldImage,
+    ) -> anyhow::Result<Option<u64>> {
+        let Some((_, path)) = self.checkpoints.latest()? else {
+            return Ok(None);
+        };
+        let tick = Checkpoints::load(&path, world, image)?;
+        log::info!("resumed from {}", path.display());
+        self.last_tick = tick;
+        Ok(Some(tick))
+    }
+
+    pub fn is_due(&self, tick: u64) -> bool {
+        match self.interval {
+            AutosaveInterval::Ticks(n) => n > 0 && tick >= self.last_tick + n,
+            AutosaveInterval::Time(interval) => {
+                tick != self.last_tick && self.last_time.elapsed() >= interval
+            }
+        }
+    }
+
+    /// Saves unless the last checkpoint was taken at `tick`.
+    pub fn save<W: World + ?Sized>(&mut self, tick: u64, world: &W) -> anyhow::Result<()> {
+        self.last_time = Instant::now();
+        if tick == self.last_tick {
+            return Ok(());
+        }
+        // Not retried every update if it keeps failing.
+        self.last_tick = tick;
+        let path = self.checkpoints.save(tick, world)?;
+        log::debug!("autosaved {}", path.display());
+        Ok(())
+    }
+}
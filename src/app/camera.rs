@@ -0,0 +1,66 @@
+/// Zoom and pan applied on top of fitting the world into the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Camera {
+    zoom: f64,
+    /// World point shown at the window center, relative to the world size
+    /// (0.0 to 1.0 on each axis), so that it survives world resizes.
+    center: (f64, f64),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            center: (0.5, 0.5),
+        }
+    }
+}
+
+impl Camera {
+    const MIN_ZOOM: f64 = 0.1;
+    const MAX_ZOOM: f64 = 10_000.0;
+
+    /// Returns the top-left and bottom-right corners of the world quad in
+    /// window pixels, given the size of the quad fitted at zoom 1.
+    pub fn apply(&self, fit_size: (f64, f64), window_size: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+        let w = fit_size.0 * self.zoom;
+        let h = fit_size.1 * self.zoom;
+        let x0 = window_size.0 / 2.0 - self.center.0 * w;
+        let y0 = window_size.1 / 2.0 - self.center.1 * h;
+        ((x0, y0), (x0 + w, y0 + h))
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the world point under
+    /// `anchor` (in window pixels) in place.
+    pub fn zoom_at(
+        &mut self,
+        factor: f64,
+        anchor: (f64, f64),
+        fit_size: (f64, f64),
+        window_size: (f64, f64),
+    ) {
+        let (min, max) = self.apply(fit_size, window_size);
+        let u = (anchor.0 - min.0) / (max.0 - min.0);
+        let v = (anchor.1 - min.1) / (max.1 - min.1);
+
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let w = fit_size.0 * self.zoom;
+        let h = fit_size.1 * self.zoom;
+        self.center = (
+            u + (window_size.0 / 2.0 - anchor.0) / w,
+            v + (window_size.1 / 2.0 - anchor.1) / h,
+        );
+    }
+
+    /// Centers the view on a point relative to the world size, keeping the
+    /// zoom.
+    pub fn center_on(&mut self, center: (f64, f64)) {
+        self.center = center;
+    }
+
+    /// Moves the view by a cursor delta in window pixels.
+    pub fn pan(&mut self, delta: (f64, f64), fit_size: (f64, f64)) {
+        self.center.0 -= delta.0 / (fit_size.0 * self.zoom);
+        self.center.1 -= delta.1 / (fit_size.1 * self.zoom);
+    }
+}
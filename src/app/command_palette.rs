@@ -0,0 +1,200 @@
+use super::overlay::Shapes;
+use anyhow::Context as _;
+use std::path::PathBuf;
+use winit::{
+    dpi::PhysicalSize,
+    event::KeyEvent,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// A line typed into the palette, parsed. Lines that are not one of the
+/// app's commands go to [`World::command`](crate::World::command).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Command {
+    Ups(u32),
+    Pause,
+    Play,
+    Step(u64),
+    Save(PathBuf),
+    Load(PathBuf),
+    Goto(u32, u32),
+    ResetView,
+    Quit,
+    World(String),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        let arg = |i: usize, what: &str| {
+            args.get(i)
+                .copied()
+                .with_context(|| format!("`{name}` expects {what}"))
+        };
+        let number = |i: usize, what: &str| {
+            let arg = arg(i, what)?;
+            arg.parse::<u64>()
+                .with_context(|| format!("`{arg}` is not a valid {what}"))
+        };
+
+        Ok(match name {
+            "ups" => Self::Ups(number(0, "an update rate")?.clamp(1, u32::MAX as u64) as u32),
+            "pause" => Self::Pause,
+            "play" => Self::Play,
+            "step" if args.is_empty() => Self::Step(1),
+            "step" => Self::Step(number(0, "a number of updates")?),
+            "save" => Self::Save(arg(0, "a file name")?.into()),
+            "load" => Self::Load(arg(0, "a file name")?.into()),
+            "goto" => Self::Goto(
+                number(0, "x coordinate")?.min(u32::MAX as u64) as u32,
+                number(1, "y coordinate")?.min(u32::MAX as u64) as u32,
+            ),
+            "reset" => Self::ResetView,
+            "quit" => Self::Quit,
+            _ => Self::World(line.trim().to_owned()),
+        })
+    }
+}
+
+/// What a key press did to an open palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PaletteInput {
+    Edited,
+    Submit(String),
+    Close,
+}
+
+/// A line of text input at the bottom of the window, with the previous
+/// lines recalled by the up and down arrows.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandPalette {
+    open: bool,
+    input: String,
+    error: Option<String>,
+    history: Vec<String>,
+    recalled: Option<usize>,
+}
+
+impl CommandPalette {
+    const SCALE: f32 = 2.0;
+    const PADDING: f32 = 6.0;
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.input.clear();
+        self.error = None;
+        self.recalled = None;
+    }
+
+    #[inline]
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Shows `error` under the input until the next edit.
+    pub fn set_error(&mut self, error: &anyhow::Error) {
+        self.error = Some(format!("{error:#}"));
+    }
+
+    /// Appends typed text, without control characters.
+    pub fn insert(&mut self, text: &str) {
+        self.input.extend(text.chars().filter(|c| !c.is_control()));
+        self.error = None;
+    }
+
+    pub fn key_input(&mut self, event: &KeyEvent) -> PaletteInput {
+        if !event.state.is_pressed() {
+            return PaletteInput::Edited;
+        }
+        match event.physical_key {
+            PhysicalKey::Code(KeyCode::Escape) => PaletteInput::Close,
+            PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter) => {
+                let line = std::mem::take(&mut self.input);
+                if line.trim().is_empty() {
+                    return PaletteInput::Close;
+                }
+                if self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.recalled = None;
+                PaletteInput::Submit(line)
+            }
+            PhysicalKey::Code(KeyCode::Backspace) => {
+                self.input.pop();
+                self.error = None;
+                PaletteInput::Edited
+            }
+            PhysicalKey::Code(KeyCode::ArrowUp) => {
+                let index = match self.recalled {
+                    Some(index) => index.saturating_sub(1),
+                    None => self.history.len().saturating_sub(1),
+                };
+                self.recall(Some(index));
+                PaletteInput::Edited
+            }
+            PhysicalKey::Code(KeyCode::ArrowDown) => {
+                let index = self
+                    .recalled
+                    .map(|index| index + 1)
+                    .filter(|&index| index < self.history.len());
+                self.recall(index);
+                PaletteInput::Edited
+            }
+            _ => {
+                if let Some(text) = &event.text {
+                    self.insert(text);
+                }
+                PaletteInput::Edited
+            }
+        }
+    }
+
+    fn recall(&mut self, index: Option<usize>) {
+        let Some(line) = index.and_then(|index| self.history.get(index)) else {
+            self.recalled = None;
+            self.input.clear();
+            return;
+        };
+        self.input = line.clone();
+        self.recalled = index;
+    }
+
+    /// Draws the input across the bottom of the window, with the last error
+    /// above it.
+    pub fn draw(&self, shapes: &mut Shapes, window_size: PhysicalSize<u32>) {
+        let width = window_size.width as f32;
+        let bottom = window_size.height as f32;
+        let line_height = Shapes::text_size("|", Self::SCALE)[1] + Self::PADDING * 2.0;
+
+        let top = bottom - line_height;
+        shapes.rect([0.0, top], [width, bottom], [0.1, 0.1, 0.12, 0.9]);
+        shapes.text(
+            [Self::PADDING, top + Self::PADDING],
+            Self::SCALE,
+            &format!(":{}_", self.input),
+            [1.0, 1.0, 1.0, 0.9],
+        );
+
+        if let Some(error) = &self.error {
+            let top = top - line_height;
+            shapes.rect(
+                [0.0, top],
+                [width, top + line_height],
+                [0.4, 0.05, 0.05, 0.9],
+            );
+            shapes.text(
+                [Self::PADDING, top + Self::PADDING],
+                Self::SCALE,
+                error,
+                [1.0, 0.8, 0.8, 1.0],
+            );
+        }
+    }
+}
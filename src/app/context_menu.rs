@@ -0,0 +1,94 @@
+use super::overlay::Shapes;
+use winit::dpi::PhysicalSize;
+
+/// Actions offered for a right-clicked cell, drawn as a column of labeled
+/// items next to the cursor.
+#[derive(Debug, Clone)]
+pub(crate) struct ContextMenu {
+    cell: (u32, u32),
+    actions: Vec<String>,
+    origin: [f32; 2],
+    /// Wide enough for the longest label.
+    width: f32,
+    hovered: Option<usize>,
+}
+
+impl ContextMenu {
+    const ITEM_WIDTH: f32 = 160.0;
+    const ITEM_HEIGHT: f32 = 22.0;
+    const SCALE: f32 = 2.0;
+    const PADDING: f32 = 6.0;
+
+    /// Opens the menu at `pos`, shifted as needed to fit in the window.
+    pub fn new(
+        cell: (u32, u32),
+        actions: Vec<String>,
+        pos: (f64, f64),
+        window_size: PhysicalSize<u32>,
+    ) -> Self {
+        let width = actions
+            .iter()
+            .map(|action| Shapes::text_size(action, Self::SCALE)[0] + Self::PADDING * 2.0)
+            .fold(Self::ITEM_WIDTH, f32::max);
+        let height = Self::ITEM_HEIGHT * actions.len() as f32;
+        let max_x = (window_size.width as f32 - width).max(0.0);
+        let max_y = (window_size.height as f32 - height).max(0.0);
+        Self {
+            cell,
+            actions,
+            origin: [(pos.0 as f32).min(max_x), (pos.1 as f32).min(max_y)],
+            width,
+            hovered: None,
+        }
+    }
+
+    pub fn cell(&self) -> (u32, u32) {
+        self.cell
+    }
+
+    pub fn action(&self, index: usize) -> Option<&str> {
+        self.actions.get(index).map(String::as_str)
+    }
+
+    /// Updates the hovered item. Returns whether it changed.
+    pub fn hover(&mut self, pos: Option<(f64, f64)>) -> bool {
+        let hovered = pos.and_then(|pos| self.item_at(pos));
+        let changed = hovered != self.hovered;
+        self.hovered = hovered;
+        changed
+    }
+
+    pub fn item_at(&self, (x, y): (f64, f64)) -> Option<usize> {
+        let [x0, y0] = self.origin;
+        let (x, y) = (x as f32 - x0, y as f32 - y0);
+        if !(0.0..self.width).contains(&x) || y < 0.0 {
+            return None;
+        }
+        let index = (y / Self::ITEM_HEIGHT) as usize;
+        (index < self.actions.len()).then_some(index)
+    }
+
+    pub fn draw(&self, shapes: &mut Shapes) {
+        let [x0, y0] = self.origin;
+        let text_offset = (Self::ITEM_HEIGHT - Shapes::text_size("|", Self::SCALE)[1]) / 2.0;
+        for (i, action) in self.actions.iter().enumerate() {
+            let top = y0 + Self::ITEM_HEIGHT * i as f32;
+            let color = if self.hovered == Some(i) {
+                [0.3, 0.6, 1.0, 0.9]
+            } else {
+                [0.1, 0.1, 0.12, 0.9]
+            };
+            shapes.rect(
+                [x0, top + 1.0],
+                [x0 + self.width, top + Self::ITEM_HEIGHT - 1.0],
+                color,
+            );
+            shapes.text(
+                [x0 + Self::PADDING, top + text_offset],
+                Self::SCALE,
+                action,
+                [1.0, 1.0, 1.0, 0.9],
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{Color, DiffStyle, ImageFormat, WorldImage};
+
+/// Cells changed by the last update, for [`AppConfigs::diff_view`].
+///
+/// Drawn as an extra layer over the world: opaque black over unchanged
+/// cells, and transparent or coded colors over changed ones.
+///
+/// [`AppConfigs::diff_view`]: crate::AppConfigs::diff_view
+#[derive(Debug)]
+pub(crate) struct Diff {
+    previous: Option<WorldImage>,
+    layer: WorldImage,
+    tick: Option<u64>,
+}
+
+impl Diff {
+    pub fn new() -> Self {
+        Self {
+            previous: None,
+            layer: WorldImage::new(1, 1),
+            tick: None,
+        }
+    }
+
+    #[inline]
+    pub fn layer(&self) -> &WorldImage {
+        &self.layer
+    }
+
+    /// Forgets the previous image, e.g. when the view is turned off, so that
+    /// stale changes are not shown when it is turned on again.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.tick = None;
+    }
+
+    /// Compares `image` with the one seen at the last different tick.
+    /// Returns whether the layer changed.
+    pub fn update(&mut self, image: &WorldImage, tick: u64, style: DiffStyle) -> bool {
+        if self.tick == Some(tick) {
+            return false;
+        }
+        self.tick = Some(tick);
+
+        let current = match image.format() {
+            ImageFormat::Rgba8 => image.clone(),
+            _ => image.to_rgba(),
+        };
+        let (width, height) = (current.width(), current.height());
+        if (self.layer.width(), self.layer.height()) != (width, height) {
+            self.layer = WorldImage::new(width, height);
+        }
+        match &self.previous {
+            Some(previous) if (previous.width(), previous.height()) == (width, height) => {
+                let changes = previous.pixels().iter().zip(current.pixels());
+                for (cell, (&before, &after)) in self.layer.pixels_mut().iter_mut().zip(changes) {
+                    *cell = mark(before, after, style);
+                }
+            }
+            // Nothing is known to have changed yet.
+            _ => self.layer.fill(Color::BLACK),
+        }
+        self.previous = Some(current);
+        true
+    }
+}
+
+fn mark(before: Color, after: Color, style: DiffStyle) -> Color {
+    if before == after {
+        return Color::BLACK;
+    }
+    match style {
+        DiffStyle::NewColor => Color::TRANSPARENT,
+        DiffStyle::Coded {
+            brighter,
+            darker,
+            other,
+        } => match luma(after).total_cmp(&luma(before)) {
+            std::cmp::Ordering::Greater => brighter,
+            std::cmp::Ordering::Less => darker,
+            std::cmp::Ordering::Equal => other,
+        },
+    }
+}
+
+fn luma(color: Color) -> f32 {
+    let [r, g, b, _] = color.0;
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+}
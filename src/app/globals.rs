@@ -0,0 +1,159 @@
+use crate::DisplayAdjustments;
+use bytemuck::Zeroable as _;
+use std::time::Instant;
+use wgpu::util::DeviceExt as _;
+use winit::dpi::PhysicalSize;
+
+/// Layout of the `Globals` uniform declared in `globals.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Globals {
+    world_size: [f32; 2],
+    window_size: [f32; 2],
+    cursor: [f32; 2],
+    time: f32,
+    frame: u32,
+    exposure: f32,
+    gamma: f32,
+    contrast: f32,
+    _padding: f32,
+}
+
+/// Per-frame values available to the world and grid shaders.
+///
+/// A second copy without display adjustments is bound while drawing the
+/// world into intermediate targets, such as trails, so that adjustments are
+/// applied only once, when drawing to the window.
+#[derive(Debug)]
+pub(crate) struct GlobalsBuffer {
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    buffer: wgpu::Buffer,
+    unadjusted_bind_group: wgpu::BindGroup,
+    unadjusted_buffer: wgpu::Buffer,
+    empty_bind_group_layout: wgpu::BindGroupLayout,
+    empty_bind_group: wgpu::BindGroup,
+    start: Instant,
+    frame: u32,
+}
+
+impl GlobalsBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Globals Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let create = |label| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::bytes_of(&Globals::zeroed()),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Globals Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            (buffer, bind_group)
+        };
+        let (buffer, bind_group) = create("Globals Buffer");
+        let (unadjusted_buffer, unadjusted_bind_group) = create("Unadjusted Globals Buffer");
+
+        let empty_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Empty Bind Group Layout"),
+                entries: &[],
+            });
+        let empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Empty Bind Group"),
+            layout: &empty_bind_group_layout,
+            entries: &[],
+        });
+
+        Self {
+            bind_group_layout,
+            bind_group,
+            buffer,
+            unadjusted_bind_group,
+            unadjusted_buffer,
+            empty_bind_group_layout,
+            empty_bind_group,
+            start: Instant::now(),
+            frame: 0,
+        }
+    }
+
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Group 0 of pipelines that only read the globals, such as the grid's,
+    /// which the prelude puts in group 1.
+    pub fn empty_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.empty_bind_group_layout
+    }
+
+    pub fn empty_bind_group(&self) -> &wgpu::BindGroup {
+        &self.empty_bind_group
+    }
+
+    /// The same values with [`DisplayAdjustments::NEUTRAL`].
+    pub fn unadjusted_bind_group(&self) -> &wgpu::BindGroup {
+        &self.unadjusted_bind_group
+    }
+
+    /// Writes this frame's values and advances the frame index. The cursor
+    /// is `(-1, -1)` when it is not over the world.
+    pub fn update(
+        &mut self,
+        queue: &wgpu::Queue,
+        world_size: (u32, u32),
+        window_size: PhysicalSize<u32>,
+        cursor: Option<(u32, u32)>,
+        adjustments: DisplayAdjustments,
+    ) {
+        let cursor = cursor.map_or([-1.0; 2], |(x, y)| [x as f32, y as f32]);
+        let globals = |adjustments: DisplayAdjustments| Globals {
+            world_size: [world_size.0 as f32, world_size.1 as f32],
+            window_size: [window_size.width as f32, window_size.height as f32],
+            cursor,
+            time: self.start.elapsed().as_secs_f32(),
+            frame: self.frame,
+            exposure: adjustments.exposure,
+            // The field is public, so it may have skipped the assert of
+            // `DisplayAdjustments::gamma`.
+            gamma: adjustments.gamma.max(f32::MIN_POSITIVE),
+            contrast: adjustments.contrast,
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&globals(adjustments)));
+        queue.write_buffer(
+            &self.unadjusted_buffer,
+            0,
+            bytemuck::bytes_of(&globals(DisplayAdjustments::NEUTRAL)),
+        );
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
+
+/// `source` after the `Globals` uniform and the `adjust_display` function
+/// shared by the shaders drawing the world to the window.
+pub(crate) fn with_prelude(source: &str) -> String {
+    format!("{}\n{source}", include_str!("globals.wgsl"))
+}
@@ -0,0 +1,25 @@
+// Prepended to `main.wgsl`, `grid.wgsl`, `lookup.wgsl` and
+// `world_shader.wgsl` when they are loaded, see `globals::with_prelude`.
+
+// Updated every frame. `cursor` is the cell under the mouse, or (-1, -1).
+struct Globals {
+    world_size: vec2<f32>,
+    window_size: vec2<f32>,
+    cursor: vec2<f32>,
+    time: f32,
+    frame: u32,
+    exposure: f32,
+    gamma: f32,
+    contrast: f32,
+}
+@group(1) @binding(0)
+var<uniform> globals: Globals;
+
+// Display adjustments set in the app, applied to a color in linear light:
+// exposure in stops, then gamma, then contrast around mid-gray.
+fn adjust_display(color: vec4<f32>) -> vec4<f32> {
+    var rgb = max(color.rgb * exp2(globals.exposure), vec3<f32>(0.0));
+    rgb = pow(rgb, vec3<f32>(1.0 / globals.gamma));
+    rgb = (rgb - 0.5) * globals.contrast + 0.5;
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}
@@ -0,0 +1,272 @@
+use super::app_impl::Vertex;
+use crate::{Color, Heatmap, ImageFormat, ImageVersion, WorldImage};
+use bytemuck::Zeroable as _;
+use wgpu::util::DeviceExt as _;
+
+/// Layout of the `Params` uniform of `heatmap.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    min: f32,
+    max: f32,
+    opacity: f32,
+    smooth: u32,
+}
+
+impl Params {
+    fn of(heatmap: &Heatmap) -> Self {
+        let (min, max) = heatmap.field().value_range();
+        Self {
+            min,
+            max,
+            opacity: heatmap.opacity,
+            smooth: heatmap.smooth as u32,
+        }
+    }
+}
+
+/// Texture of the field of the image's [`Heatmap`].
+#[derive(Debug)]
+struct FieldTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    version: ImageVersion,
+}
+
+/// Draws the image's [`Heatmap`] over its layers with alpha blending,
+/// looking values up in a palette texture of its own.
+#[derive(Debug)]
+pub(crate) struct HeatmapRenderer {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    palette_texture: wgpu::Texture,
+    palette_view: wgpu::TextureView,
+    uploaded_palette: Vec<Color>,
+    params_buffer: wgpu::Buffer,
+    params: Params,
+    field: Option<FieldTexture>,
+    visible: bool,
+}
+
+impl HeatmapRenderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Heatmap Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Heatmap Render Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Heatmap Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("heatmap.wgsl").into()),
+            });
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Heatmap Render Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let palette_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Heatmap Palette Texture"),
+            size: wgpu::Extent3d {
+                width: WorldImage::PALETTE_LEN as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // Like the palette of the world image.
+            format: if format.is_srgb() {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let palette_view = palette_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let params = Params::zeroed();
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Heatmap Params Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+            palette_texture,
+            palette_view,
+            uploaded_palette: Vec::new(),
+            params_buffer,
+            params,
+            field: None,
+            visible: false,
+        }
+    }
+
+    /// Uploads whatever changed about `heatmap` since the last call, to be
+    /// drawn by [`HeatmapRenderer::draw`].
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        heatmap: Option<&Heatmap>,
+    ) -> anyhow::Result<()> {
+        let Some(heatmap) = heatmap.filter(|heatmap| {
+            heatmap.field().format() == ImageFormat::R32Float && heatmap.opacity > 0.0
+        }) else {
+            self.visible = false;
+            return Ok(());
+        };
+        self.visible = true;
+
+        let params = Params::of(heatmap);
+        if params != self.params {
+            queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+            self.params = params;
+        }
+        self.upload_palette(queue, heatmap.field().palette());
+
+        let field = heatmap.field();
+        match &mut self.field {
+            Some(texture)
+                if texture.texture.width() == field.width()
+                    && texture.texture.height() == field.height() =>
+            {
+                if texture.version != field.version() {
+                    field.update_wgpu_texture(&texture.texture, queue);
+                    texture.version = field.version();
+                }
+            }
+            _ => {
+                let (texture, view, _) =
+                    field.create_texture(device, queue, Some("Heatmap Texture"), false)?;
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Heatmap Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&self.palette_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: self.params_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+                self.field = Some(FieldTexture {
+                    texture,
+                    bind_group,
+                    version: field.version(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws over the world quad, whose vertex and index buffers are bound.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>, indices_len: u32) {
+        let Some(field) = self.field.as_ref().filter(|_| self.visible) else {
+            return;
+        };
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &field.bind_group, &[]);
+        render_pass.draw_indexed(0..indices_len, 0, 0..1);
+    }
+
+    fn upload_palette(&mut self, queue: &wgpu::Queue, palette: &[Color]) {
+        if palette.is_empty() || palette == self.uploaded_palette {
+            return;
+        }
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.palette_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(palette),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(palette.len() as u32 * 4),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d {
+                width: palette.len() as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.uploaded_palette = palette.to_vec();
+    }
+}
@@ -0,0 +1,67 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.tex_coords = model.tex_coords;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_field: texture_2d<f32>;
+@group(0) @binding(1)
+var t_palette: texture_2d<f32>;
+
+// Maps field values onto the palette: `min` to the first entry and `max` to
+// the last.
+struct Params {
+    min: f32,
+    max: f32,
+    opacity: f32,
+    smoothed: u32,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+fn load(texel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(t_field));
+    return textureLoad(t_field, clamp(texel, vec2<i32>(0), size - 1), 0).r;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_field));
+    var value: f32;
+    if params.smoothed != 0u {
+        let pos = in.tex_coords * size - 0.5;
+        let base = floor(pos);
+        let f = pos - base;
+        let texel = vec2<i32>(base);
+        let top = mix(load(texel), load(texel + vec2<i32>(1, 0)), f.x);
+        let bottom = mix(load(texel + vec2<i32>(0, 1)), load(texel + vec2<i32>(1, 1)), f.x);
+        value = mix(top, bottom, f.y);
+    } else {
+        value = load(vec2<i32>(in.tex_coords * size));
+    }
+    // NaN marks cells without data. Compared by bits, since compilers may
+    // assume floats are never NaN.
+    if (bitcast<u32>(value) & 0x7fffffffu) > 0x7f800000u {
+        discard;
+    }
+    let t = (value - params.min) / (params.max - params.min);
+    let index = u32(round(clamp(t, 0.0, 1.0) * 255.0));
+    let color = textureLoad(t_palette, vec2<u32>(index, 0u), 0);
+    return vec4<f32>(color.rgb, color.a * clamp(params.opacity, 0.0, 1.0));
+}
@@ -0,0 +1,53 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.tex_coords = model.tex_coords;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_indices: texture_2d<f32>;
+@group(0) @binding(1)
+var t_palette: texture_2d<f32>;
+
+// Maps texel values onto the palette: `min` to the first entry and `max` to
+// the last. Colors of the `cycle_len` entries from `cycle_first` are then
+// rotated by `cycle_offset` entries.
+struct Transfer {
+    min: f32,
+    max: f32,
+    cycle_first: u32,
+    cycle_len: u32,
+    cycle_offset: u32,
+}
+@group(0) @binding(2)
+var<uniform> transfer: Transfer;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(t_indices);
+    let texel = min(vec2<u32>(in.tex_coords * vec2<f32>(size)), size - 1u);
+    let value = textureLoad(t_indices, texel, 0).r;
+    let t = (value - transfer.min) / (transfer.max - transfer.min);
+    var index = u32(round(clamp(t, 0.0, 1.0) * 255.0));
+    if index >= transfer.cycle_first && index - transfer.cycle_first < transfer.cycle_len {
+        let i = index - transfer.cycle_first;
+        index = transfer.cycle_first
+            + (i + transfer.cycle_len - transfer.cycle_offset) % transfer.cycle_len;
+    }
+    return adjust_display(textureLoad(t_palette, vec2<u32>(index, 0u), 0));
+}
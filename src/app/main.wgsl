@@ -26,5 +26,5 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return adjust_display(textureSample(t_diffuse, s_diffuse, in.tex_coords));
 }
@@ -1,4 +1,9 @@
-use crate::{AppConfigs, World};
+use crate::{
+    AppConfigs, World,
+    commands::{AppCommand, AppHandle},
+    headless::{Bench, BenchReport},
+};
+use std::sync::mpsc;
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
@@ -6,36 +11,83 @@ use winit::{
     window::WindowId,
 };
 
+mod agents;
 mod app_impl;
+mod autosave;
+mod camera;
+mod command_palette;
+mod context_menu;
+mod diff;
+mod globals;
+mod heatmap;
+mod mipmaps;
 use app_impl::AppImpl;
 
+pub(crate) mod overlay;
+mod palette;
+mod profiler;
+pub(crate) mod screenshot;
+#[cfg(feature = "dev")]
+mod shader_reload;
+mod staging;
+mod timelapse;
+mod trails;
+mod vectors;
+mod window_state;
+
+/// Runs worlds in windows.
+///
+/// An app opens one window for the world passed to [`App::new`], and one
+/// for each world added with [`App::add_window`]. Every window has its own
+/// configs, surface and [`AppHandle`], and closing it stops only its world;
+/// the app exits once all windows are closed.
 pub struct App<'window, W> {
+    windows: Vec<AppWindow<'window, W>>,
+    error: Option<anyhow::Error>,
+}
+
+struct AppWindow<'window, W> {
     state: AppState<'window, W>,
+    commands: AppHandle,
 }
 
+impl<W> AppWindow<'_, W> {
+    fn new(configs: AppConfigs, world: W) -> Self {
+        let (commands, receiver) = AppHandle::channel(configs.seed);
+        Self {
+            state: AppState::Ready(Some((configs, world, receiver))),
+            commands,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
 enum AppState<'window, W> {
-    Ready(Option<(AppConfigs, W)>),
+    Ready(Option<(AppConfigs, W, mpsc::Receiver<AppCommand>)>),
     Running(AppImpl<'window, W>),
 }
 
 impl<'window, W> AppState<'window, W> {
-    fn init<F>(&mut self, initializer: F)
+    fn init<F>(&mut self, initializer: F) -> anyhow::Result<()>
     where
-        F: FnOnce(AppConfigs, W) -> AppImpl<'window, W>,
+        F: FnOnce(AppConfigs, W, mpsc::Receiver<AppCommand>) -> anyhow::Result<AppImpl<'window, W>>,
     {
         let Self::Ready(data) = self else {
-            panic!("AppState::init called on AppState::Running");
+            return Ok(());
         };
-        let (configs, world) = data.take().unwrap();
+        let (configs, world, commands) = data
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("app failed to initialize earlier"))?;
 
-        let app = initializer(configs, world);
+        let app = initializer(configs, world, commands)?;
         *self = Self::Running(app);
+        Ok(())
     }
 
-    fn unwrap_running_mut(&mut self) -> &mut AppImpl<'window, W> {
+    fn running_mut(&mut self) -> Option<&mut AppImpl<'window, W>> {
         match self {
-            Self::Running(app) => app,
-            _ => panic!("unwrap_running_mut called on AppState::Ready"),
+            Self::Running(app) => Some(app),
+            _ => None,
         }
     }
 }
@@ -44,25 +96,160 @@ impl<W: World> App<'_, W> {
     #[inline]
     pub fn new(configs: AppConfigs, world: W) -> Self {
         Self {
-            state: AppState::Ready(Some((configs, world))),
+            windows: vec![AppWindow::new(configs, world)],
+            error: None,
         }
     }
 
+    /// Opens another window for `world` when the app runs, e.g. a control
+    /// panel next to the simulation. Returns a handle for controlling it.
+    ///
+    /// Worlds of different types can share an app as `Box<dyn World>`, and
+    /// [`util::Mirror`](crate::util::Mirror) shows the world of another
+    /// window, e.g. on a second monitor.
+    ///
+    /// ```no_run
+    /// use cells_renderer::{
+    ///     App, AppConfigs, LifeLikeWorld, Rule, World, util::Mirror, winit::WindowAttributes,
+    /// };
+    ///
+    /// let life = LifeLikeWorld::new(160, 90, Rule::LIFE);
+    /// let mut app = App::new(AppConfigs::default(), Box::new(life) as Box<dyn World>);
+    /// let mirror = Mirror::new(app.commands());
+    /// let attributes = WindowAttributes::default().with_title("Mirror");
+    /// app.add_window(
+    ///     AppConfigs::default().window_attributes(attributes),
+    ///     Box::new(mirror),
+    /// );
+    /// app.run().unwrap();
+    /// ```
+    pub fn add_window(&mut self, configs: AppConfigs, world: W) -> AppHandle {
+        let window = AppWindow::new(configs, world);
+        let commands = window.commands.clone();
+        self.windows.push(window);
+        commands
+    }
+
+    /// Returns a handle for controlling the first window once it runs.
     #[inline]
-    pub fn run(mut self) -> anyhow::Result<()> {
-        let event_loop = EventLoop::new()?;
+    pub fn commands(&self) -> AppHandle {
+        self.windows[0].commands.clone()
+    }
+
+    /// Calls `f` with a handle to the app, e.g. to move it to a controlling
+    /// thread, then runs the app. See [`App::run`].
+    #[inline]
+    pub fn run_with_handle<F>(self, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(AppHandle),
+    {
+        f(self.commands());
+        self.run()
+    }
+
+    /// Runs the event loop until all windows are closed.
+    ///
+    /// Returns the first fatal error, such as a failure to initialize the GPU.
+    #[inline]
+    pub fn run(self) -> anyhow::Result<()> {
+        self.run_on(EventLoop::new()?)
+    }
+
+    /// Runs the app in the activity passed to `android_main`, in a `cdylib`
+    /// packaged with the `android` feature on. The window is created once
+    /// the activity resumes, and touches act as the left mouse button.
+    ///
+    /// ```ignore
+    /// use cells_renderer::{App, AppConfigs, LifeLikeWorld, Rule, winit::AndroidApp};
+    ///
+    /// #[unsafe(no_mangle)]
+    /// fn android_main(android_app: AndroidApp) {
+    ///     let world = LifeLikeWorld::new(90, 160, Rule::LIFE);
+    ///     App::new(AppConfigs::default(), world)
+    ///         .run_android(android_app)
+    ///         .unwrap();
+    /// }
+    /// ```
+    #[cfg(all(feature = "android", target_os = "android"))]
+    pub fn run_android(self, android_app: crate::winit::AndroidApp) -> anyhow::Result<()> {
+        use winit::platform::android::EventLoopBuilderExtAndroid as _;
+
+        let event_loop = EventLoop::builder().with_android_app(android_app).build()?;
+        self.run_on(event_loop)
+    }
+
+    fn run_on(mut self, event_loop: EventLoop<()>) -> anyhow::Result<()> {
         event_loop.set_control_flow(ControlFlow::Poll);
+        for window in &self.windows {
+            window.commands.set_waker(event_loop.create_proxy());
+        }
         event_loop.run_app(&mut self)?;
-        Ok(())
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs `generations` updates of `world` without a window and reports
+    /// how fast they went. See [`Bench`] for more options.
+    #[inline]
+    pub fn bench(world: W, generations: u64) -> BenchReport {
+        Bench::new(world, generations).run()
+    }
+
+    fn fail(&mut self, event_loop: &ActiveEventLoop, err: anyhow::Error) {
+        self.error.get_or_insert(err);
+        for window in &mut self.windows {
+            if let Some(app) = window.state.running_mut() {
+                app.exit();
+            }
+        }
+        event_loop.exit();
+    }
+
+    /// Drops the windows that were closed, and exits once none are left.
+    fn close_windows(&mut self, event_loop: &ActiveEventLoop) {
+        self.windows.retain_mut(|window| {
+            !window
+                .state
+                .running_mut()
+                .is_some_and(|app| app.is_closed())
+        });
+        if self.windows.is_empty() {
+            event_loop.exit();
+        }
     }
 }
 
 impl<W: World> ApplicationHandler for App<'_, W> {
     #[inline]
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        self.state.init(|configs, world| {
-            futures::executor::block_on(AppImpl::new(configs, world, event_loop)).unwrap()
-        });
+        for window in &mut self.windows {
+            let handle = window.commands.clone();
+            let result = window.state.init(|configs, world, commands| {
+                futures::executor::block_on(AppImpl::new(
+                    configs, world, commands, handle, event_loop,
+                ))
+            });
+            // Windows started earlier get their surfaces back.
+            let result = result.and_then(|()| match window.state.running_mut() {
+                Some(app) => app.resume(),
+                None => Ok(()),
+            });
+            if let Err(err) = result {
+                self.fail(event_loop, err);
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        for window in &mut self.windows {
+            if let Some(app) = window.state.running_mut() {
+                app.suspend();
+            }
+        }
     }
 
     #[inline]
@@ -72,9 +259,39 @@ impl<W: World> ApplicationHandler for App<'_, W> {
         window_id: WindowId,
         event: WindowEvent,
     ) {
-        self.state
-            .unwrap_running_mut()
-            .window_event(event_loop, window_id, event)
-            .unwrap();
+        let Some(app) = self
+            .windows
+            .iter_mut()
+            .filter_map(|window| window.state.running_mut())
+            .find(|app| app.window_id() == window_id)
+        else {
+            return;
+        };
+        if let Err(err) = app.window_event(event) {
+            self.fail(event_loop, err);
+            return;
+        }
+        self.close_windows(event_loop);
+    }
+
+    #[inline]
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Wake up for whichever window needs it first.
+        let control_flow = self
+            .windows
+            .iter_mut()
+            .filter_map(|window| window.state.running_mut())
+            .map(|app| app.about_to_wait())
+            .fold(ControlFlow::Wait, |a, b| match (a, b) {
+                (ControlFlow::Poll, _) | (_, ControlFlow::Poll) => ControlFlow::Poll,
+                (ControlFlow::WaitUntil(a), ControlFlow::WaitUntil(b)) => {
+                    ControlFlow::WaitUntil(a.min(b))
+                }
+                (ControlFlow::WaitUntil(t), ControlFlow::Wait)
+                | (ControlFlow::Wait, ControlFlow::WaitUntil(t)) => ControlFlow::WaitUntil(t),
+                (ControlFlow::Wait, ControlFlow::Wait) => ControlFlow::Wait,
+            });
+        event_loop.set_control_flow(control_flow);
+        self.close_windows(event_loop);
     }
 }
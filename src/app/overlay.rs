@@ -0,0 +1,297 @@
+use crate::font;
+use winit::dpi::PhysicalSize;
+
+/// Colored shapes in window pixel coordinates, drawn above the world and grid.
+#[derive(Debug)]
+pub(crate) struct Shapes {
+    window_size: PhysicalSize<u32>,
+    vertices: Vec<ColorVertex>,
+}
+
+impl Shapes {
+    pub fn new(window_size: PhysicalSize<u32>) -> Self {
+        Self {
+            window_size,
+            vertices: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self, window_size: PhysicalSize<u32>) {
+        self.window_size = window_size;
+        self.vertices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn vertices(&self) -> &[ColorVertex] {
+        &self.vertices
+    }
+
+    pub fn rect(&mut self, min: [f32; 2], max: [f32; 2], color: [f32; 4]) {
+        let [x0, y0] = self.to_clip(min);
+        let [x1, y1] = self.to_clip(max);
+
+        let vertex = |x, y| ColorVertex {
+            position: [x, y],
+            color,
+        };
+        self.vertices.extend([
+            vertex(x0, y1),
+            vertex(x1, y1),
+            vertex(x0, y0),
+            vertex(x0, y0),
+            vertex(x1, y1),
+            vertex(x1, y0),
+        ]);
+    }
+
+    pub fn triangle(&mut self, a: [f32; 2], b: [f32; 2], c: [f32; 2], color: [f32; 4]) {
+        for position in [a, b, c] {
+            self.vertices.push(ColorVertex {
+                position: self.to_clip(position),
+                color,
+            });
+        }
+    }
+
+    /// Draws a line `width` pixels thick, without caps.
+    pub fn line(&mut self, from: [f32; 2], to: [f32; 2], width: f32, color: [f32; 4]) {
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let len = dx.hypot(dy);
+        if len == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / len * width / 2.0, dx / len * width / 2.0);
+        let a = [from[0] + nx, from[1] + ny];
+        let b = [from[0] - nx, from[1] - ny];
+        let c = [to[0] + nx, to[1] + ny];
+        let d = [to[0] - nx, to[1] - ny];
+        self.triangle(a, b, c, color);
+        self.triangle(c, b, d, color);
+    }
+
+    /// Draws the outline of a rectangle, `width` pixels thick on its inside.
+    pub fn rect_outline(&mut self, min: [f32; 2], max: [f32; 2], width: f32, color: [f32; 4]) {
+        let width = width
+            .min((max[0] - min[0]) / 2.0)
+            .min((max[1] - min[1]) / 2.0);
+        self.rect(min, [max[0], min[1] + width], color);
+        self.rect([min[0], max[1] - width], max, color);
+        self.rect(
+            [min[0], min[1] + width],
+            [min[0] + width, max[1] - width],
+            color,
+        );
+        self.rect(
+            [max[0] - width, min[1] + width],
+            [max[0], max[1] - width],
+            color,
+        );
+    }
+
+    /// Fills an ellipse with the given radii along the axes.
+    pub fn ellipse(&mut self, center: [f32; 2], radii: [f32; 2], color: [f32; 4]) {
+        let points = ellipse_points(center, radii);
+        for pair in points.windows(2) {
+            self.triangle(center, pair[0], pair[1], color);
+        }
+    }
+
+    /// Draws the outline of an ellipse, `width` pixels thick on its inside.
+    pub fn ellipse_outline(
+        &mut self,
+        center: [f32; 2],
+        radii: [f32; 2],
+        width: f32,
+        color: [f32; 4],
+    ) {
+        let inner = [(radii[0] - width).max(0.0), (radii[1] - width).max(0.0)];
+        let outer = ellipse_points(center, radii);
+        let inner = ellipse_points(center, inner);
+        for i in 0..outer.len() - 1 {
+            self.triangle(outer[i], inner[i], outer[i + 1], color);
+            self.triangle(outer[i + 1], inner[i], inner[i + 1], color);
+        }
+    }
+
+    /// Draws a border of the given width along the window edges.
+    pub fn frame(&mut self, width: f32, color: [f32; 4]) {
+        let w = self.window_size.width as f32;
+        let h = self.window_size.height as f32;
+        self.rect([0.0, 0.0], [w, width], color);
+        self.rect([0.0, h - width], [w, h], color);
+        self.rect([0.0, width], [width, h - width], color);
+        self.rect([w - width, width], [w, h - width], color);
+    }
+
+    /// Draws a horizontal bar filled up to `progress` (0.0 to 1.0).
+    pub fn progress_bar(&mut self, min: [f32; 2], max: [f32; 2], progress: f32) {
+        let fill = min[0] + (max[0] - min[0]) * progress.clamp(0.0, 1.0);
+        self.rect(min, max, [0.0, 0.0, 0.0, 0.6]);
+        self.rect(min, [fill, max[1]], [0.3, 0.6, 1.0, 0.9]);
+    }
+
+    /// Draws `text` in the built-in bitmap font with its top-left corner at
+    /// `pos`, each font pixel `scale` window pixels wide.
+    pub fn text(&mut self, pos: [f32; 2], scale: f32, text: &str, color: [f32; 4]) {
+        for (x, y) in font::pixels(text) {
+            let min = [pos[0] + x as f32 * scale, pos[1] + y as f32 * scale];
+            self.rect(min, [min[0] + scale, min[1] + scale], color);
+        }
+    }
+
+    /// Size of `text` as drawn by [`Shapes::text`].
+    pub fn text_size(text: &str, scale: f32) -> [f32; 2] {
+        let (width, height) = font::size(text);
+        [width as f32 * scale, height as f32 * scale]
+    }
+
+    fn to_clip(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let w = self.window_size.width.max(1) as f32;
+        let h = self.window_size.height.max(1) as f32;
+        [x / w * 2.0 - 1.0, 1.0 - y / h * 2.0]
+    }
+}
+
+/// Points around an ellipse, the first repeated at the end. Larger ellipses
+/// get more segments.
+fn ellipse_points(center: [f32; 2], radii: [f32; 2]) -> Vec<[f32; 2]> {
+    let segments = (radii[0].max(radii[1]) / 2.0).clamp(12.0, 96.0) as usize;
+    (0..=segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            [
+                center[0] + radii[0] * angle.cos(),
+                center[1] + radii[1] * angle.sin(),
+            ]
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub(crate) struct OverlayRenderer {
+    render_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    vertices_len: u32,
+}
+
+impl OverlayRenderer {
+    const INITIAL_CAPACITY: usize = 1024;
+
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let render_pipeline = {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Overlay Render Pipeline Layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Overlay Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("overlay.wgsl").into()),
+            });
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Overlay Render Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[ColorVertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let vertex_buffer = Self::create_vertex_buffer(device, Self::INITIAL_CAPACITY);
+
+        Self {
+            render_pipeline,
+            vertex_buffer,
+            vertex_capacity: Self::INITIAL_CAPACITY,
+            vertices_len: 0,
+        }
+    }
+
+    /// Uploads the shapes to be drawn by the next [`OverlayRenderer::draw`].
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, shapes: &Shapes) {
+        let vertices = shapes.vertices();
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        }
+        self.vertices_len = vertices.len() as u32;
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        if self.vertices_len == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertices_len, 0..1);
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Overlay Vertex Buffer"),
+            size: (capacity * std::mem::size_of::<ColorVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, Default)]
+pub(crate) struct ColorVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl ColorVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x4,
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use super::{app_impl::Vertex, globals::with_prelude};
+use crate::{Color, ImageFormat, PaletteCycle, WorldImage};
+use wgpu::util::DeviceExt as _;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Transfer {
+    min: f32,
+    max: f32,
+    cycle_first: u32,
+    cycle_len: u32,
+    cycle_offset: u32,
+    _padding: [u32; 3],
+}
+
+impl Transfer {
+    const IDENTITY: Self = Self {
+        min: 0.0,
+        max: 1.0,
+        cycle_first: 0,
+        cycle_len: 1,
+        cycle_offset: 0,
+        _padding: [0; 3],
+    };
+
+    fn of(image: &WorldImage, cycle: Option<PaletteCycle>, tick: u64) -> Self {
+        // Indexed texels are normalized to `0.0..=1.0` by the texture format.
+        let (min, max) = match image.format() {
+            ImageFormat::R32Float => image.value_range(),
+            _ => (0.0, 1.0),
+        };
+        let (cycle_first, cycle_len, cycle_offset) = cycle.map_or((0, 1, 0), |cycle| {
+            (cycle.first as u32, cycle.entry_count(), cycle.offset(tick))
+        });
+        Self {
+            min,
+            max,
+            cycle_first,
+            cycle_len,
+            cycle_offset,
+            ..Self::IDENTITY
+        }
+    }
+}
+
+/// Draws single-channel world images by looking each texel up in a
+/// 256-color palette texture, after mapping it through the image's value
+/// range and rotating it by the app's palette cycle.
+#[derive(Debug)]
+pub(crate) struct PaletteLookup {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    palette_texture: wgpu::Texture,
+    palette_view: wgpu::TextureView,
+    uploaded: Vec<Color>,
+    transfer_buffer: wgpu::Buffer,
+    transfer: Transfer,
+}
+
+impl PaletteLookup {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Palette Lookup Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Palette Lookup Render Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout, globals_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Palette Lookup Shader"),
+                source: wgpu::ShaderSource::Wgsl(with_prelude(include_str!("lookup.wgsl")).into()),
+            });
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Palette Lookup Render Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState {
+                            alpha: wgpu::BlendComponent::REPLACE,
+                            color: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let palette_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Palette Texture"),
+            size: wgpu::Extent3d {
+                width: WorldImage::PALETTE_LEN as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // Palette colors are sRGB, passed through as they are to targets
+            // without sRGB encoding.
+            format: if format.is_srgb() {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            },
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let palette_view = palette_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let transfer = Transfer::IDENTITY;
+        let transfer_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Transfer Buffer"),
+            contents: bytemuck::bytes_of(&transfer),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+            palette_texture,
+            palette_view,
+            uploaded: Vec::new(),
+            transfer_buffer,
+            transfer,
+        }
+    }
+
+    pub fn render_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.render_pipeline
+    }
+
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Palette Lookup Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&self.palette_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.transfer_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Uploads the image's palette and value range, and the rotation of
+    /// `cycle` after `tick` updates, if they differ from the last ones
+    /// uploaded.
+    pub fn upload_palette(
+        &mut self,
+        queue: &wgpu::Queue,
+        image: &WorldImage,
+        cycle: Option<PaletteCycle>,
+        tick: u64,
+    ) {
+        let transfer = Transfer::of(image, cycle, tick);
+        if transfer != self.transfer {
+            queue.write_buffer(&self.transfer_buffer, 0, bytemuck::bytes_of(&transfer));
+            self.transfer = transfer;
+        }
+
+        let palette = image.palette();
+        if palette.is_empty() || palette == self.uploaded {
+            return;
+        }
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.palette_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(palette),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(palette.len() as u32 * 4),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d {
+                width: palette.len() as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.uploaded = palette.to_vec();
+    }
+}
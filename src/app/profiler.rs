@@ -0,0 +1,104 @@
+use super::overlay::Shapes;
+use crate::{FrameStats, RateStats};
+use std::time::{Duration, Instant};
+
+/// Draws a bar graph of the recorded frames: the interval between frames
+/// behind the time spent in updates, uploads and rendering, stacked.
+pub(crate) fn draw_frame_graph(stats: &FrameStats, shapes: &mut Shapes) {
+    const ORIGIN: [f32; 2] = [8.0, 8.0];
+    const BAR_WIDTH: f32 = 2.0;
+    const HEIGHT: f32 = 80.0;
+    const TARGET: Duration = Duration::from_micros(16_667);
+
+    let width = stats.capacity() as f32 * BAR_WIDTH;
+    let [x0, y0] = ORIGIN;
+    let y1 = y0 + HEIGHT;
+    let bar_height =
+        |d: Duration| (d.as_secs_f32() / (TARGET.as_secs_f32() * 2.0)).min(1.0) * HEIGHT;
+
+    shapes.rect([x0, y0], [x0 + width, y1], [0.0, 0.0, 0.0, 0.6]);
+
+    for (i, timing) in stats.iter().enumerate() {
+        let x = x0 + i as f32 * BAR_WIDTH;
+        let interval_color = if timing.interval > TARGET * 2 {
+            [0.9, 0.2, 0.2, 0.9]
+        } else if timing.interval > TARGET {
+            [0.9, 0.8, 0.2, 0.9]
+        } else {
+            [0.2, 0.9, 0.3, 0.9]
+        };
+        shapes.rect(
+            [x, y1 - bar_height(timing.interval)],
+            [x + BAR_WIDTH, y1],
+            interval_color,
+        );
+
+        let mut y = y1;
+        for (duration, color) in [
+            (timing.update, [0.9, 0.5, 0.1, 0.9]),
+            (timing.upload, [0.3, 0.6, 1.0, 0.9]),
+            (timing.render, [0.7, 0.3, 0.9, 0.9]),
+        ] {
+            let top = (y - bar_height(duration)).max(y0);
+            shapes.rect([x, top], [x + BAR_WIDTH, y], color);
+            y = top;
+        }
+    }
+
+    let target_y = y1 - bar_height(TARGET);
+    shapes.rect(
+        [x0, target_y],
+        [x0 + width, target_y + 1.0],
+        [1.0, 1.0, 1.0, 0.5],
+    );
+}
+
+/// Counts updates, frames and uploads over one-second windows.
+#[derive(Debug)]
+pub(crate) struct RateCounter {
+    window_start: Instant,
+    current: RateStats,
+    last: RateStats,
+}
+
+impl RateCounter {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            current: RateStats::default(),
+            last: RateStats::default(),
+        }
+    }
+
+    /// Counts of the last complete window.
+    pub fn last(&self) -> RateStats {
+        self.last
+    }
+
+    pub fn record_update(&mut self) {
+        self.current.updates += 1;
+    }
+
+    pub fn record_skipped(&mut self, n: u32) {
+        self.current.skipped_updates += n;
+    }
+
+    pub fn record_upload(&mut self) {
+        self.current.uploads += 1;
+    }
+
+    /// Counts a frame, closing the window once it is a second old. Returns
+    /// the closed window's counts.
+    pub fn end_frame(&mut self) -> Option<RateStats> {
+        self.current.frames += 1;
+        let now = Instant::now();
+        if now - self.window_start < Self::WINDOW {
+            return None;
+        }
+        self.window_start = now;
+        self.last = std::mem::take(&mut self.current);
+        Some(self.last)
+    }
+}
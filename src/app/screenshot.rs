@@ -0,0 +1,158 @@
+use crate::WorldImage;
+use std::sync::mpsc;
+
+/// Where a screenshot requested with [`AppHandle`] goes.
+///
+/// [`AppHandle`]: crate::AppHandle
+#[derive(Debug)]
+pub(crate) enum ScreenshotTarget {
+    Reply(mpsc::Sender<anyhow::Result<WorldImage>>),
+    #[cfg(feature = "png")]
+    Save(std::path::PathBuf),
+}
+
+impl ScreenshotTarget {
+    /// Hands the screenshot over. Returns errors no one else will see.
+    pub fn finish(self, result: anyhow::Result<WorldImage>) -> Option<anyhow::Error> {
+        match self {
+            Self::Reply(reply) => {
+                let _ = reply.send(result);
+                None
+            }
+            #[cfg(feature = "png")]
+            Self::Save(path) => result.and_then(|image| image.save_png(path)).err(),
+        }
+    }
+}
+
+/// Texture a screenshot is drawn to instead of the window, through a
+/// multisampled texture when MSAA is on, since it shares the pipelines
+/// drawing to the window.
+#[derive(Debug)]
+pub(crate) struct Offscreen {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    msaa_view: Option<wgpu::TextureView>,
+}
+
+impl Offscreen {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+        sample_count: u32,
+    ) -> Self {
+        let create = |label, sample_count, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let texture = create(
+            "Screenshot Texture",
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let msaa_view = (sample_count > 1).then(|| {
+            create(
+                "Screenshot MSAA Texture",
+                sample_count,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+            .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        Self {
+            texture,
+            view,
+            msaa_view,
+        }
+    }
+
+    #[inline]
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    #[inline]
+    pub fn msaa_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa_view.as_ref()
+    }
+
+    /// Submits `encoder`, which draws to the texture, and waits for the GPU
+    /// to copy the result back.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut encoder: wgpu::CommandEncoder,
+    ) -> anyhow::Result<WorldImage> {
+        let format = self.texture.format();
+        let bgra = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => anyhow::bail!("screenshots of {format:?} surfaces are not supported"),
+        };
+        let (width, height) = (self.texture.width(), self.texture.height());
+        let row_bytes = width * 4;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: padded_row_bytes as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        let _ = device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut image = WorldImage::new(width, height);
+        {
+            let data = buffer.slice(..).get_mapped_range();
+            for (src, dst) in data
+                .chunks_exact(padded_row_bytes as usize)
+                .zip(image.buf_mut().chunks_exact_mut(row_bytes as usize))
+            {
+                dst.copy_from_slice(&src[..row_bytes as usize]);
+            }
+        }
+        buffer.unmap();
+        // Windows show the alpha channel as opaque.
+        for pixel in image.buf_mut().chunks_exact_mut(4) {
+            if bgra {
+                pixel.swap(0, 2);
+            }
+            pixel[3] = u8::MAX;
+        }
+        Ok(image)
+    }
+}
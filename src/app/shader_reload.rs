@@ -0,0 +1,97 @@
+use crate::AppConfigs;
+use anyhow::Context as _;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Which pipelines a watched shader file rebuilds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShaderKind {
+    Main,
+    Grid,
+    World,
+}
+
+impl ShaderKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Main => "main",
+            Self::Grid => "grid",
+            Self::World => "world",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Watched {
+    kind: ShaderKind,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// Polls the shader files of [`AppConfigs`] for changes.
+#[derive(Debug)]
+pub(crate) struct ShaderWatcher {
+    shaders: Vec<Watched>,
+    last_poll: Option<Instant>,
+}
+
+impl ShaderWatcher {
+    pub fn new(configs: &AppConfigs) -> Self {
+        let paths = [
+            (ShaderKind::Main, &configs.main_shader_path),
+            (ShaderKind::Grid, &configs.grid_shader_path),
+            (ShaderKind::World, &configs.world_shader_path),
+        ];
+        let shaders = paths
+            .into_iter()
+            .filter_map(|(kind, path)| {
+                Some(Watched {
+                    kind,
+                    path: path.clone()?,
+                    modified: None,
+                })
+            })
+            .collect();
+        Self {
+            shaders,
+            last_poll: None,
+        }
+    }
+
+    /// Sources of the shaders whose files changed since the last poll. Every
+    /// file counts as changed on the first poll, so that it replaces the
+    /// built-in shader right away.
+    pub fn poll(&mut self) -> Vec<(ShaderKind, anyhow::Result<String>)> {
+        if self.shaders.is_empty()
+            || self
+                .last_poll
+                .is_some_and(|last_poll| last_poll.elapsed() < POLL_INTERVAL)
+        {
+            return Vec::new();
+        }
+        let first = self.last_poll.is_none();
+        self.last_poll = Some(Instant::now());
+
+        let mut changed = Vec::new();
+        for shader in &mut self.shaders {
+            // Missing files compare equal too, so they are reported once.
+            let modified = modified(&shader.path);
+            if !first && modified == shader.modified {
+                continue;
+            }
+            shader.modified = modified;
+            let source = std::fs::read_to_string(&shader.path)
+                .with_context(|| format!("failed to read {}", shader.path.display()));
+            changed.push((shader.kind, source));
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|meta| meta.modified()).ok()
+}
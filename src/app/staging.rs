@@ -0,0 +1,160 @@
+use crate::WorldImage;
+use std::{
+    ops::Range,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Uploads the world image through persistent staging buffers, copied into
+/// the texture by a command buffer submitted with the frame.
+///
+/// Two buffers sized for the image alternate: one is written while the other
+/// may still be in flight, and a buffer is mapped again once the GPU is done
+/// with it. `queue.write_texture` instead allocates fresh staging memory for
+/// every upload.
+#[derive(Debug, Default)]
+pub(crate) struct StagingUploads {
+    buffers: Vec<StagingBuffer>,
+    padded_bytes_per_row: u32,
+    encoder: Option<wgpu::CommandEncoder>,
+    /// Indices of the buffers recorded into `encoder`.
+    in_flight: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct StagingBuffer {
+    buffer: wgpu::Buffer,
+    mapped: Arc<AtomicBool>,
+}
+
+impl StagingUploads {
+    const BUFFERS: usize = 2;
+
+    /// Records a copy of `rows` of `image` into `texture`. Returns `false`
+    /// without recording anything when both buffers are still in use, and
+    /// the caller should upload the rows some other way.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        image: &WorldImage,
+        texture: &wgpu::Texture,
+        rows: Range<u32>,
+    ) -> bool {
+        let rows = rows.start.min(image.height())..rows.end.min(image.height());
+        if rows.is_empty() {
+            return true;
+        }
+        let bytes_per_row = image.format().bytes_per_pixel() as u32 * image.width();
+        let padded_bytes_per_row =
+            bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        if padded_bytes_per_row != self.padded_bytes_per_row
+            || self.buffers.first().is_some_and(|staging| {
+                staging.buffer.size() != padded_bytes_per_row as u64 * image.height() as u64
+            })
+        {
+            // Buffers in flight are kept alive by their command buffers.
+            self.buffers.clear();
+            self.in_flight.clear();
+            self.padded_bytes_per_row = padded_bytes_per_row;
+        }
+
+        // Runs the callbacks of finished mappings.
+        let _ = device.poll(wgpu::Maintain::Poll);
+        // Buffers in flight stay unmapped until recalled.
+        let index = match self
+            .buffers
+            .iter()
+            .position(|staging| staging.mapped.load(Ordering::Acquire))
+        {
+            Some(index) => index,
+            None if self.buffers.len() < Self::BUFFERS => {
+                self.buffers.push(StagingBuffer {
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("World Staging Buffer"),
+                        size: padded_bytes_per_row as u64 * image.height() as u64,
+                        usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: true,
+                    }),
+                    mapped: Arc::new(AtomicBool::new(true)),
+                });
+                self.buffers.len() - 1
+            }
+            None => return false,
+        };
+
+        let staging = &self.buffers[index];
+        let len = padded_bytes_per_row as u64 * rows.len() as u64;
+        {
+            let mut mapped = staging.buffer.slice(..len).get_mapped_range_mut();
+            let buf = image.buf();
+            for (i, row) in rows.clone().enumerate() {
+                let src = row as usize * bytes_per_row as usize;
+                let dst = i * padded_bytes_per_row as usize;
+                mapped[dst..dst + bytes_per_row as usize]
+                    .copy_from_slice(&buf[src..src + bytes_per_row as usize]);
+            }
+        }
+        staging.buffer.unmap();
+        staging.mapped.store(false, Ordering::Release);
+
+        let encoder = self.encoder.get_or_insert_with(|| {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Upload Encoder"),
+            })
+        });
+        encoder.copy_buffer_to_texture(
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(rows.len() as u32),
+                },
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: rows.start,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::Extent3d {
+                width: image.width(),
+                height: rows.len() as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.in_flight.push(index);
+        true
+    }
+
+    /// The copies recorded since the last call, to be submitted before the
+    /// frame that samples the texture.
+    pub fn finish(&mut self) -> Option<wgpu::CommandBuffer> {
+        self.encoder.take().map(|encoder| encoder.finish())
+    }
+
+    /// Maps the buffers used by the submitted copies again, making them
+    /// available once the GPU has finished with them.
+    pub fn recall(&mut self) {
+        for index in self.in_flight.drain(..) {
+            let Some(staging) = self.buffers.get(index) else {
+                continue;
+            };
+            let mapped = Arc::clone(&staging.mapped);
+            staging
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Write, move |result| {
+                    if result.is_ok() {
+                        mapped.store(true, Ordering::Release);
+                    }
+                });
+        }
+    }
+}
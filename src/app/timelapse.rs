@@ -0,0 +1,88 @@
+use crate::{
+    WorldImage,
+    timelapse::{Timelapse, TimelapseOutput},
+};
+#[cfg(feature = "png")]
+use anyhow::Context as _;
+
+/// Takes the captures configured with
+/// [`AppConfigs::timelapse`](crate::AppConfigs::timelapse).
+#[derive(Debug)]
+pub(crate) struct TimelapseCapture {
+    every: u64,
+    output: TimelapseOutput,
+    /// Tick of the last capture.
+    last_tick: Option<u64>,
+    #[cfg(feature = "video")]
+    encoder: Option<crate::video::VideoEncoder>,
+}
+
+impl TimelapseCapture {
+    pub fn new(timelapse: &Timelapse) -> anyhow::Result<Self> {
+        #[cfg(feature = "png")]
+        if let TimelapseOutput::Png { dir, .. } = &timelapse.output {
+            std::fs::create_dir_all(dir).with_context(|| {
+                format!("failed to create timelapse directory {}", dir.display())
+            })?;
+        }
+        Ok(Self {
+            every: timelapse.every.max(1),
+            output: timelapse.output.clone(),
+            last_tick: None,
+            #[cfg(feature = "video")]
+            encoder: None,
+        })
+    }
+
+    /// Whether an `every`th update ran since the last capture.
+    pub fn is_due(&self, tick: u64) -> bool {
+        self.last_tick
+            .is_none_or(|last_tick| last_tick / self.every != tick / self.every)
+    }
+
+    /// Pixels per cell of the frames.
+    pub fn scale(&self) -> u32 {
+        let scale = match &self.output {
+            #[cfg(feature = "png")]
+            TimelapseOutput::Png { scale, .. } => *scale,
+            #[cfg(feature = "video")]
+            TimelapseOutput::Video(export) => export.scale,
+            TimelapseOutput::Channel { scale, .. } => *scale,
+        };
+        scale.max(1)
+    }
+
+    pub fn capture(&mut self, tick: u64, frame: WorldImage) -> anyhow::Result<()> {
+        self.last_tick = Some(tick);
+        match &self.output {
+            #[cfg(feature = "png")]
+            TimelapseOutput::Png { dir, .. } => {
+                frame.save_png(dir.join(format!("frame-{tick:012}.png")))
+            }
+            #[cfg(feature = "video")]
+            TimelapseOutput::Video(export) => {
+                let encoder = match &mut self.encoder {
+                    Some(encoder) => encoder,
+                    None => self.encoder.insert(crate::video::VideoEncoder::start(
+                        export,
+                        (frame.width(), frame.height()),
+                    )?),
+                };
+                encoder.push(frame)
+            }
+            TimelapseOutput::Channel { sender, .. } => sender
+                .send((tick, frame))
+                .map_err(|_| anyhow::anyhow!("the timelapse receiver was dropped")),
+        }
+    }
+
+    /// Finishes the video, if any.
+    pub fn finish(self) -> anyhow::Result<()> {
+        #[cfg(feature = "video")]
+        if let Some(encoder) = self.encoder {
+            let path = encoder.finish()?;
+            log::info!("saved {}", path.display());
+        }
+        Ok(())
+    }
+}
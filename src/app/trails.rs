@@ -0,0 +1,361 @@
+use super::app_impl::{Vertex, vertices_rectangle};
+use wgpu::util::DeviceExt as _;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Fade {
+    decay: f32,
+    _padding: [f32; 3],
+}
+
+/// Accumulates the world over updates for [`AppConfigs::trails`].
+///
+/// Each step draws the world into one of two targets the size of the world
+/// image, then the other target, holding the previous result, faded and
+/// combined with a per-channel maximum. The targets swap roles every step.
+///
+/// With MSAA, steps draw to a multisampled texture resolved into the target,
+/// since they share the world pipelines drawing to the window.
+///
+/// [`AppConfigs::trails`]: crate::AppConfigs::trails
+#[derive(Debug)]
+pub(crate) struct Trails {
+    fade_bind_group_layout: wgpu::BindGroupLayout,
+    fade_pipeline: wgpu::RenderPipeline,
+    fade_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
+    targets: [Target; 2],
+    current: usize,
+}
+
+#[derive(Debug)]
+struct Target {
+    view: wgpu::TextureView,
+    /// Binds the target as the previous result for the fade.
+    fade_bind_group: wgpu::BindGroup,
+    /// Binds the target for the main pipeline.
+    bind_group: wgpu::BindGroup,
+}
+
+impl Trails {
+    const INDICES: [u16; 6] = [0, 1, 2, 2, 1, 3];
+
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        decay: f32,
+        size: (u32, u32),
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let fade_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Trails Fade Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let fade_pipeline = {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Trails Fade Render Pipeline Layout"),
+                bind_group_layouts: &[&fade_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Trails Fade Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("trails.wgsl").into()),
+            });
+            let max = wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Max,
+            };
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Trails Fade Render Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState {
+                            alpha: max,
+                            color: max,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let fade_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Trails Fade Buffer"),
+            contents: bytemuck::bytes_of(&Fade {
+                decay: decay.clamp(0.0, 1.0),
+                _padding: [0.0; 3],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Trails Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices_rectangle([-1.0, 1.0], [1.0, -1.0])),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Trails Index Buffer"),
+            contents: bytemuck::cast_slice(&Self::INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let create_target = |label| {
+            create_target(
+                device,
+                label,
+                format,
+                size,
+                &fade_bind_group_layout,
+                &fade_buffer,
+                texture_bind_group_layout,
+                &sampler,
+            )
+        };
+        let targets = [
+            create_target("Trails Target A"),
+            create_target("Trails Target B"),
+        ];
+        let msaa_view = create_msaa_view(device, format, sample_count, size);
+
+        Self {
+            fade_bind_group_layout,
+            fade_pipeline,
+            fade_buffer,
+            sampler,
+            vertex_buffer,
+            index_buffer,
+            format,
+            sample_count,
+            msaa_view,
+            targets,
+            current: 0,
+        }
+    }
+
+    /// Recreates the targets for a world image of another size, clearing the
+    /// trails.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        size: (u32, u32),
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        let create_target = |label| {
+            create_target(
+                device,
+                label,
+                self.format,
+                size,
+                &self.fade_bind_group_layout,
+                &self.fade_buffer,
+                texture_bind_group_layout,
+                &self.sampler,
+            )
+        };
+        self.targets = [
+            create_target("Trails Target A"),
+            create_target("Trails Target B"),
+        ];
+        self.msaa_view = create_msaa_view(device, self.format, self.sample_count, size);
+        self.current = 0;
+    }
+
+    /// Draws the world over the faded previous result.
+    pub fn accumulate(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world_pipeline: &wgpu::RenderPipeline,
+        world_bind_group: &wgpu::BindGroup,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        let next = 1 - self.current;
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Trails Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_view.as_ref().unwrap_or(&self.targets[next].view),
+                resolve_target: self.msaa_view.as_ref().map(|_| &self.targets[next].view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let indices_len = Self::INDICES.len() as u32;
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.set_pipeline(world_pipeline);
+        render_pass.set_bind_group(0, world_bind_group, &[]);
+        render_pass.set_bind_group(1, globals_bind_group, &[]);
+        render_pass.draw_indexed(0..indices_len, 0, 0..1);
+        render_pass.set_pipeline(&self.fade_pipeline);
+        render_pass.set_bind_group(0, &self.targets[self.current].fade_bind_group, &[]);
+        render_pass.draw_indexed(0..indices_len, 0, 0..1);
+
+        self.current = next;
+    }
+
+    /// Binds the latest result in place of the world texture.
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.targets[self.current].bind_group
+    }
+}
+
+fn create_msaa_view(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    (width, height): (u32, u32),
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Trails MSAA Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_target(
+    device: &wgpu::Device,
+    label: &str,
+    format: wgpu::TextureFormat,
+    (width, height): (u32, u32),
+    fade_bind_group_layout: &wgpu::BindGroupLayout,
+    fade_buffer: &wgpu::Buffer,
+    texture_bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+) -> Target {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let fade_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Trails Fade Bind Group"),
+        layout: fade_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: fade_buffer.as_entire_binding(),
+            },
+        ],
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Trails Bind Group"),
+        layout: texture_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+
+    Target {
+        view,
+        fade_bind_group,
+        bind_group,
+    }
+}
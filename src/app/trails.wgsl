@@ -0,0 +1,37 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.tex_coords = model.tex_coords;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_previous: texture_2d<f32>;
+
+struct Fade {
+    decay: f32,
+}
+@group(0) @binding(1)
+var<uniform> fade: Fade;
+
+// The previous result has the same size as the target, so each fragment
+// reads the texel under it.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = vec2<u32>(in.clip_position.xy);
+    return textureLoad(t_previous, texel, 0) * fade.decay;
+}
@@ -0,0 +1,220 @@
+use crate::{
+    WorldTransform,
+    overlay::{Arrow, VectorField, linear},
+};
+use bytemuck::Zeroable as _;
+use wgpu::util::DeviceExt as _;
+use winit::dpi::PhysicalSize;
+
+/// Layout of the `View` uniform of `vectors.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct View {
+    min: [f32; 2],
+    cell_size: [f32; 2],
+    window_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+/// Draws the arrows of
+/// [`World::draw_vector_field`](crate::World::draw_vector_field) as one
+/// instance each, built from a shaft and a head in the vertex shader.
+#[derive(Debug)]
+pub(crate) struct VectorRenderer {
+    render_pipeline: wgpu::RenderPipeline,
+    view_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    instances: Vec<ArrowInstance>,
+}
+
+impl VectorRenderer {
+    const INITIAL_CAPACITY: usize = 256;
+
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Vector Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vector View Buffer"),
+            contents: bytemuck::bytes_of(&View::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Vector Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        let render_pipeline = {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Vector Render Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Vector Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("vectors.wgsl").into()),
+            });
+
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Vector Render Pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[ArrowInstance::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        Self {
+            render_pipeline,
+            view_buffer,
+            bind_group,
+            instance_buffer: Self::create_instance_buffer(device, Self::INITIAL_CAPACITY),
+            instance_capacity: Self::INITIAL_CAPACITY,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Uploads the arrows to be drawn by the next [`VectorRenderer::draw`],
+    /// placed by `transform` in a window of `window_size`.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        field: &VectorField,
+        transform: &WorldTransform,
+        window_size: PhysicalSize<u32>,
+    ) {
+        self.instances.clear();
+        self.instances
+            .extend(field.arrows.iter().map(ArrowInstance::new));
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instances.len() > self.instance_capacity {
+            self.instance_capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&self.instances),
+        );
+
+        let (min, cell_size) = (transform.min(), transform.cell_size());
+        let view = View {
+            min: [min.0 as f32, min.1 as f32],
+            cell_size: [cell_size.0 as f32, cell_size.1 as f32],
+            window_size: [
+                window_size.width.max(1) as f32,
+                window_size.height.max(1) as f32,
+            ],
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&view));
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        if self.instances.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..9, 0..self.instances.len() as u32);
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Vector Instance Buffer"),
+            size: (capacity * std::mem::size_of::<ArrowInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, Default)]
+struct ArrowInstance {
+    center: [f32; 2],
+    vector: [f32; 2],
+    color: [f32; 4],
+    width: f32,
+}
+
+impl ArrowInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32x4,
+        3 => Float32,
+    ];
+
+    fn new(arrow: &Arrow) -> Self {
+        Self {
+            center: [arrow.center.0, arrow.center.1],
+            vector: [arrow.vector.0, arrow.vector.1],
+            color: linear(arrow.color),
+            width: arrow.width,
+        }
+    }
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
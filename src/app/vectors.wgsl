@@ -0,0 +1,67 @@
+struct View {
+    // Window pixels of the world's top-left corner and of one cell.
+    min: vec2<f32>,
+    cell_size: vec2<f32>,
+    window_size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+struct InstanceInput {
+    @location(0) center: vec2<f32>,
+    @location(1) vector: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) width: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+fn to_pixels(world: vec2<f32>) -> vec2<f32> {
+    return view.min + world * view.cell_size;
+}
+
+// Six vertices for the shaft followed by three for the head.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    arrow: InstanceInput,
+) -> VertexOutput {
+    let start = to_pixels(arrow.center - arrow.vector * 0.5);
+    let end = to_pixels(arrow.center + arrow.vector * 0.5);
+    let len = length(end - start);
+    let dir = (end - start) / max(len, 1e-6);
+    let normal = vec2<f32>(-dir.y, dir.x);
+
+    let head_len = min(len * 0.4, arrow.width * 3.0 + 4.0);
+    let base = end - dir * head_len;
+    let shaft = normal * arrow.width * 0.5;
+    let head = normal * max(head_len * 0.5, arrow.width);
+
+    var points = array<vec2<f32>, 9>(
+        start + shaft,
+        start - shaft,
+        base + shaft,
+        base + shaft,
+        start - shaft,
+        base - shaft,
+        end,
+        base + head,
+        base - head,
+    );
+    let pixel = points[vertex_index];
+
+    var out: VertexOutput;
+    let clip = pixel / view.window_size * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(clip.x, -clip.y, 0.0, 1.0);
+    out.color = arrow.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
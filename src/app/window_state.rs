@@ -0,0 +1,104 @@
+use std::{fs, io, path::Path};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::ActiveEventLoop,
+    window::{Fullscreen, Window, WindowAttributes},
+};
+
+/// Window geometry saved between runs as `key=value` lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct WindowState {
+    size: Option<(u32, u32)>,
+    position: Option<(i32, i32)>,
+    monitor: Option<String>,
+    maximized: bool,
+    fullscreen: bool,
+}
+
+impl WindowState {
+    pub fn capture(window: &Window) -> Self {
+        let size = window.inner_size();
+        Self {
+            size: Some((size.width, size.height)),
+            position: window.outer_position().ok().map(|pos| (pos.x, pos.y)),
+            monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+            maximized: window.is_maximized(),
+            fullscreen: window.fullscreen().is_some(),
+        }
+    }
+
+    /// Reads a saved state, returning `None` if there is none yet.
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut state = Self::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "size" => state.size = parse_pair(value),
+                "position" => state.position = parse_pair(value),
+                "monitor" => state.monitor = Some(value.to_owned()),
+                "maximized" => state.maximized = value == "true",
+                "fullscreen" => state.fullscreen = value == "true",
+                _ => (),
+            }
+        }
+        Ok(Some(state))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut text = String::new();
+        if let Some((w, h)) = self.size {
+            text += &format!("size={w},{h}\n");
+        }
+        if let Some((x, y)) = self.position {
+            text += &format!("position={x},{y}\n");
+        }
+        if let Some(monitor) = &self.monitor {
+            text += &format!("monitor={monitor}\n");
+        }
+        text += &format!("maximized={}\n", self.maximized);
+        text += &format!("fullscreen={}\n", self.fullscreen);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn apply(
+        &self,
+        mut attributes: WindowAttributes,
+        event_loop: &ActiveEventLoop,
+    ) -> WindowAttributes {
+        if let Some((w, h)) = self.size {
+            attributes = attributes.with_inner_size(PhysicalSize::new(w, h));
+        }
+        if let Some((x, y)) = self.position {
+            attributes = attributes.with_position(PhysicalPosition::new(x, y));
+        }
+        attributes = attributes.with_maximized(self.maximized);
+        if self.fullscreen {
+            let monitor = self.monitor.as_ref().and_then(|name| {
+                event_loop
+                    .available_monitors()
+                    .find(|monitor| monitor.name().as_ref() == Some(name))
+            });
+            attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)));
+        }
+        attributes
+    }
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str) -> Option<(T, T)> {
+    let (a, b) = value.split_once(',')?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
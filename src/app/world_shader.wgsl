@@ -0,0 +1,28 @@
+// Prelude of `AppConfigs::world_shader`. The user's source is appended and
+// must define `fs_main`.
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.tex_coords = model.tex_coords;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_world: texture_2d<f32>;
+@group(0) @binding(1)
+var s_world: sampler;
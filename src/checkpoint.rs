@@ -0,0 +1,190 @@
+use crate::{World, WorldImage};
+use anyhow::Context as _;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How often [`Autosave`] takes a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutosaveInterval {
+    /// After every `n` updates.
+    Ticks(u64),
+    /// After the first update once this much wall-clock time has passed.
+    Time(Duration),
+}
+
+/// Periodic checkpoints of a running [`App`](crate::App), configured with
+/// [`AppConfigs::autosave`](crate::AppConfigs::autosave), so that long runs
+/// survive crashes and can be resumed.
+///
+/// Checkpoints are written with [`Checkpoints`], also when the app exits.
+/// Failing to write one is reported to the error handler without stopping
+/// the app. Worlds must implement [`World::save_state`] and
+/// [`World::load_state`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Autosave {
+    pub dir: PathBuf,
+    pub interval: AutosaveInterval,
+    pub max_kept: usize,
+    pub resume: bool,
+}
+
+impl Autosave {
+    /// Saves to `dir` every minute, keeping 5 checkpoints, without resuming.
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            interval: AutosaveInterval::Time(Duration::from_secs(60)),
+            max_kept: 5,
+            resume: false,
+        }
+    }
+
+    #[inline]
+    pub fn interval(self, interval: AutosaveInterval) -> Self {
+        Self { interval, ..self }
+    }
+
+    /// Number of checkpoints kept on disk. Zero keeps every checkpoint.
+    #[inline]
+    pub fn max_kept(self, max_kept: usize) -> Self {
+        Self { max_kept, ..self }
+    }
+
+    /// Whether to start from the latest checkpoint in the directory, if
+    /// there is one, continuing its tick count.
+    #[inline]
+    pub fn resume(self, resume: bool) -> Self {
+        Self { resume, ..self }
+    }
+}
+
+/// A directory of rotated world checkpoints.
+///
+/// Each checkpoint is stored as `checkpoint-<tick>.bin` and holds the tick
+/// it was taken at together with the bytes returned by [`World::save_state`].
+#[derive(Debug, Clone)]
+pub struct Checkpoints {
+    dir: PathBuf,
+    max_kept: usize,
+}
+
+impl Checkpoints {
+    const MAGIC: &[u8; 4] = b"CRCK";
+    const PREFIX: &str = "checkpoint-";
+    const EXTENSION: &str = "bin";
+
+    /// Opens (creating if necessary) a checkpoint directory keeping at most
+    /// `max_kept` files. A `max_kept` of zero keeps every checkpoint.
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>, max_kept: usize) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create checkpoint directory {}", dir.display()))?;
+        Ok(Self { dir, max_kept })
+    }
+
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    #[inline]
+    pub fn max_kept(&self) -> usize {
+        self.max_kept
+    }
+
+    /// Writes a checkpoint of `world` taken at `tick`, then removes the oldest
+    /// checkpoints beyond the rotation limit.
+    pub fn save<W: World + ?Sized>(&self, tick: u64, world: &W) -> anyhow::Result<PathBuf> {
+        let path = self.path_for(tick);
+        Self::write(&path, tick, world)?;
+        self.rotate()?;
+        Ok(path)
+    }
+
+    /// Writes a single checkpoint of `world` taken at `tick` to `path`, in
+    /// the format read by [`Checkpoints::load`].
+    pub fn write<W: World + ?Sized>(path: &Path, tick: u64, world: &W) -> anyhow::Result<()> {
+        let state = world
+            .save_state()
+            .context("this world does not support saving states")?;
+
+        let mut data = Vec::with_capacity(Self::MAGIC.len() + 8 + state.len());
+        data.extend_from_slice(Self::MAGIC);
+        data.extend_from_slice(&tick.to_le_bytes());
+        data.extend_from_slice(&state);
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, &data).with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Restores `world` from the checkpoint at `path`, returning its tick.
+    pub fn load<W: World + ?Sized>(
+        path: &Path,
+        world: &mut W,
+        image: &mut WorldImage,
+    ) -> anyhow::Result<u64> {
+        let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        let header_len = Self::MAGIC.len() + 8;
+        anyhow::ensure!(
+            data.len() >= header_len && data.starts_with(Self::MAGIC),
+            "{} is not a checkpoint file",
+            path.display()
+        );
+        let tick = u64::from_le_bytes(data[Self::MAGIC.len()..header_len].try_into().unwrap());
+
+        world.load_state(&data[header_len..], image)?;
+        Ok(tick)
+    }
+
+    /// Returns the tick and path of the most recent checkpoint, if any.
+    pub fn latest(&self) -> anyhow::Result<Option<(u64, PathBuf)>> {
+        Ok(self.list()?.pop())
+    }
+
+    /// Lists checkpoints in this directory, oldest first.
+    pub fn list(&self) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+        let mut checkpoints = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != Self::EXTENSION) {
+                continue;
+            }
+            let tick = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(Self::PREFIX))
+                .and_then(|tick| tick.parse().ok());
+            if let Some(tick) = tick {
+                checkpoints.push((tick, path));
+            }
+        }
+        checkpoints.sort_unstable();
+        Ok(checkpoints)
+    }
+
+    fn rotate(&self) -> anyhow::Result<()> {
+        if self.max_kept == 0 {
+            return Ok(());
+        }
+        let checkpoints = self.list()?;
+        let excess = checkpoints.len().saturating_sub(self.max_kept);
+        for (_, path) in &checkpoints[..excess] {
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn path_for(&self, tick: u64) -> PathBuf {
+        self.dir
+            .join(format!("{}{tick:020}.{}", Self::PREFIX, Self::EXTENSION))
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{Context as _, ensure};
+use std::fmt;
+
+/// An sRGB color with straight alpha, laid out as the four bytes of a
+/// [`WorldImage`](crate::WorldImage) pixel.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color(pub [u8; 4]);
+
+impl Color {
+    pub const TRANSPARENT: Self = Self([0, 0, 0, 0]);
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+    pub const RED: Self = Self::rgb(255, 0, 0);
+    pub const GREEN: Self = Self::rgb(0, 255, 0);
+    pub const BLUE: Self = Self::rgb(0, 0, 255);
+
+    #[inline]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self([r, g, b, 255])
+    }
+
+    #[inline]
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self([r, g, b, a])
+    }
+
+    /// An opaque color from `0xRRGGBB`.
+    #[inline]
+    pub const fn hex(rgb: u32) -> Self {
+        Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// Parses `RRGGBB` or `RRGGBBAA`, with or without a leading `#`.
+    pub fn from_hex(hex: &str) -> anyhow::Result<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        ensure!(
+            digits.len() == 6 || digits.len() == 8,
+            "invalid color {hex:?}: expected 6 or 8 hex digits"
+        );
+        let value =
+            u32::from_str_radix(digits, 16).with_context(|| format!("invalid color {hex:?}"))?;
+        Ok(if digits.len() == 6 {
+            Self::hex(value)
+        } else {
+            Self(value.to_be_bytes())
+        })
+    }
+
+    /// An opaque color from hue in degrees, and saturation and value from
+    /// 0.0 to 1.0.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let (s, v) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let chroma = v * s;
+        Self::from_hue_chroma(hue, chroma, v - chroma)
+    }
+
+    /// An opaque color from hue in degrees, and saturation and lightness
+    /// from 0.0 to 1.0.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Self::from_hue_chroma(hue, chroma, l - chroma / 2.0)
+    }
+
+    fn from_hue_chroma(hue: f32, chroma: f32, min: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + min) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::rgb(channel(r), channel(g), channel(b))
+    }
+
+    #[inline]
+    pub const fn r(self) -> u8 {
+        self.0[0]
+    }
+
+    #[inline]
+    pub const fn g(self) -> u8 {
+        self.0[1]
+    }
+
+    #[inline]
+    pub const fn b(self) -> u8 {
+        self.0[2]
+    }
+
+    #[inline]
+    pub const fn a(self) -> u8 {
+        self.0[3]
+    }
+
+    #[inline]
+    pub const fn with_alpha(self, a: u8) -> Self {
+        let [r, g, b, _] = self.0;
+        Self([r, g, b, a])
+    }
+
+    /// Interpolates each channel linearly, from `self` at `t = 0.0` to
+    /// `other` at `t = 1.0`.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mut channels = [0; 4];
+        for (i, channel) in channels.iter_mut().enumerate() {
+            let (from, to) = (self.0[i] as f32, other.0[i] as f32);
+            *channel = (from + (to - from) * t).round() as u8;
+        }
+        Self(channels)
+    }
+
+    /// The pixel as one native-endian `u32`, as in
+    /// [`WorldImage::packed`](crate::WorldImage::packed).
+    #[inline]
+    pub const fn to_u32(self) -> u32 {
+        u32::from_ne_bytes(self.0)
+    }
+
+    #[inline]
+    pub const fn from_u32(packed: u32) -> Self {
+        Self(packed.to_ne_bytes())
+    }
+}
+
+impl fmt::Debug for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        write!(f, "Color(#{r:02x}{g:02x}{b:02x}{a:02x})")
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    #[inline]
+    fn from(rgba: [u8; 4]) -> Self {
+        Self(rgba)
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    #[inline]
+    fn from(color: Color) -> Self {
+        color.0
+    }
+}
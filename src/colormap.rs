@@ -0,0 +1,114 @@
+//! Mapping scalar values to colors, for visualizing continuous fields.
+//!
+//! ```
+//! use cells_renderer::{Color, colormap::Colormap};
+//!
+//! assert_eq!(Colormap::GRAYSCALE.map(1.0), [255, 255, 255, 255]);
+//! let fire = Colormap::gradient([Color::BLACK, Color::RED, Color::hex(0xffff00)]);
+//! assert_eq!(fire.map(0.5), Color::RED.0);
+//! ```
+
+use crate::Color;
+use std::borrow::Cow;
+
+/// A piecewise linear gradient over `0.0..=1.0`, with evenly spaced stops.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Colormap {
+    stops: Cow<'static, [Color]>,
+}
+
+impl Colormap {
+    /// Black to white.
+    pub const GRAYSCALE: Self = Self::from_static(&[Color::BLACK, Color::WHITE]);
+
+    /// Perceptually uniform dark blue through green to yellow.
+    pub const VIRIDIS: Self = Self::from_static(&[
+        Color::hex(0x440154),
+        Color::hex(0x482878),
+        Color::hex(0x3e4989),
+        Color::hex(0x31688e),
+        Color::hex(0x26828e),
+        Color::hex(0x1f9e89),
+        Color::hex(0x35b779),
+        Color::hex(0x6ece58),
+        Color::hex(0xb5de2b),
+        Color::hex(0xfde725),
+    ]);
+
+    /// Perceptually uniform black through purple and orange to pale yellow.
+    pub const MAGMA: Self = Self::from_static(&[
+        Color::hex(0x000004),
+        Color::hex(0x180f3d),
+        Color::hex(0x440f76),
+        Color::hex(0x721f81),
+        Color::hex(0x9e2f7f),
+        Color::hex(0xcd4071),
+        Color::hex(0xf1605d),
+        Color::hex(0xfd9668),
+        Color::hex(0xfeca8d),
+        Color::hex(0xfcfdbf),
+    ]);
+
+    /// Blue through white to red, for signed values mapped around 0.5.
+    pub const COOLWARM: Self = Self::from_static(&[
+        Color::hex(0x3b4cc0),
+        Color::hex(0xdddddd),
+        Color::hex(0xb40426),
+    ]);
+
+    const fn from_static(stops: &'static [Color]) -> Self {
+        Self {
+            stops: Cow::Borrowed(stops),
+        }
+    }
+
+    /// A gradient through `stops`, evenly spaced from 0.0 to 1.0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stops` is empty.
+    pub fn gradient(stops: impl IntoIterator<Item = Color>) -> Self {
+        let stops: Vec<Color> = stops.into_iter().collect();
+        assert!(!stops.is_empty(), "a colormap needs at least one color");
+        Self {
+            stops: Cow::Owned(stops),
+        }
+    }
+
+    #[inline]
+    pub fn stops(&self) -> &[Color] {
+        &self.stops
+    }
+
+    /// The color at `t`, clamped to `0.0..=1.0`. NaN maps to the first stop.
+    #[inline]
+    pub fn map(&self, t: f32) -> [u8; 4] {
+        self.color(t).0
+    }
+
+    /// Like [`Colormap::map`], returning a [`Color`].
+    pub fn color(&self, t: f32) -> Color {
+        let last = self.stops.len() - 1;
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let pos = t * last as f32;
+        let i = (pos as usize).min(last.saturating_sub(1));
+        match self.stops.get(i + 1) {
+            Some(&next) => self.stops[i].lerp(next, pos - i as f32),
+            None => self.stops[i],
+        }
+    }
+
+    /// Like [`Colormap::map`], first scaling `value` from `min..=max` to
+    /// `0.0..=1.0`.
+    #[inline]
+    pub fn map_range(&self, value: f32, min: f32, max: f32) -> [u8; 4] {
+        self.map((value - min) / (max - min))
+    }
+}
+
+impl Default for Colormap {
+    #[inline]
+    fn default() -> Self {
+        Self::VIRIDIS
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    AppAction, Color, DisplayAdjustments, FrameStats, FrameTiming, InputFocus, KeyChord,
+    KeyboardState, Metrics, PaletteCycle, Plot, Rng, WorldImage, app::screenshot::ScreenshotTarget,
+};
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use winit::event_loop::EventLoopProxy;
+
+#[derive(Debug)]
+pub(crate) enum AppCommand {
+    FastForward(u64),
+    SetFocus(InputFocus),
+    Pause,
+    Resume,
+    Step,
+    SetUps(u32),
+    Exit,
+    Redraw,
+    AdvanceTutorial,
+    Bind(KeyChord, AppAction),
+    Rebind(AppAction, Option<KeyChord>),
+    WritePixels(Vec<(u32, u32, Color)>),
+    SetDisplayAdjustments(DisplayAdjustments),
+    SetPaletteCycle(Option<PaletteCycle>),
+    Snapshot(mpsc::Sender<WorldImage>),
+    Screenshot(u32, ScreenshotTarget),
+}
+
+/// Former name of [`AppHandle`].
+#[deprecated(note = "renamed to `AppHandle`")]
+pub type AppCommands = AppHandle;
+
+/// Cloneable handle for controlling a running [`App`](crate::App), possibly
+/// from another thread.
+///
+/// Commands sent after the app has exited are ignored.
+#[derive(Debug, Clone)]
+pub struct AppHandle {
+    sender: mpsc::Sender<AppCommand>,
+    waker: Arc<OnceLock<EventLoopProxy<()>>>,
+    frame_stats: Arc<Mutex<FrameStats>>,
+    keyboard: Arc<Mutex<KeyboardState>>,
+    plot: Arc<Mutex<Plot>>,
+    metrics: Metrics,
+    seed: u64,
+    #[cfg(feature = "gamepad")]
+    gamepads: Arc<Mutex<crate::gamepad::Gamepads>>,
+}
+
+impl AppHandle {
+    /// Commands of a run seeded with `seed`, or from the clock for `None`.
+    pub(crate) fn channel(seed: Option<u64>) -> (Self, mpsc::Receiver<AppCommand>) {
+        let seed = seed.unwrap_or_else(|| {
+            let seed = Rng::time_seed();
+            log::info!("seeded from the clock with {seed}");
+            seed
+        });
+        let (sender, receiver) = mpsc::channel();
+        let commands = Self {
+            sender,
+            waker: Arc::default(),
+            frame_stats: Arc::new(Mutex::new(FrameStats::new(120))),
+            keyboard: Arc::default(),
+            plot: Arc::new(Mutex::new(Plot::new(300))),
+            metrics: Metrics::new(),
+            seed,
+            #[cfg(feature = "gamepad")]
+            gamepads: Arc::default(),
+        };
+        (commands, receiver)
+    }
+
+    pub(crate) fn set_waker(&self, waker: EventLoopProxy<()>) {
+        let _ = self.waker.set(waker);
+    }
+
+    /// Runs `n` updates as fast as possible without rendering them, then
+    /// resumes normal playback. The window stays responsive and shows the
+    /// progress meanwhile.
+    #[inline]
+    pub fn fast_forward(&self, n: u64) {
+        self.send(AppCommand::FastForward(n));
+    }
+
+    #[inline]
+    pub fn pause(&self) {
+        self.send(AppCommand::Pause);
+    }
+
+    #[inline]
+    pub fn resume(&self) {
+        self.send(AppCommand::Resume);
+    }
+
+    /// Runs a single update, whether or not the app is paused.
+    #[inline]
+    pub fn step(&self) {
+        self.send(AppCommand::Step);
+    }
+
+    /// Changes `updates_per_second`. Zero is treated as one.
+    #[inline]
+    pub fn set_ups(&self, updates_per_second: u32) {
+        self.send(AppCommand::SetUps(updates_per_second));
+    }
+
+    /// Closes the window as if the user had closed it. The app stops once
+    /// all its windows are closed.
+    #[inline]
+    pub fn exit(&self) {
+        self.send(AppCommand::Exit);
+    }
+
+    /// Redraws the window and re-uploads the world image, e.g. after a world
+    /// changed it from another thread.
+    #[inline]
+    pub fn request_redraw(&self) {
+        self.send(AppCommand::Redraw);
+    }
+
+    /// Completes the current step of the running
+    /// [`Tutorial`](crate::Tutorial), typically one expecting
+    /// [`TutorialAction::Custom`](crate::tutorial::TutorialAction::Custom).
+    #[inline]
+    pub fn advance_tutorial(&self) {
+        self.send(AppCommand::AdvanceTutorial);
+    }
+
+    #[inline]
+    pub fn set_focus(&self, focus: InputFocus) {
+        self.send(AppCommand::SetFocus(focus));
+    }
+
+    /// Adds a key binding for `action`, keeping the others. See
+    /// [`AppConfigs::input_map`](crate::AppConfigs::input_map).
+    #[inline]
+    pub fn bind(&self, chord: impl Into<KeyChord>, action: AppAction) {
+        self.send(AppCommand::Bind(chord.into(), action));
+    }
+
+    /// Replaces the key bindings of `action` with `chord`, or removes them
+    /// for `None`, e.g. from a settings screen.
+    #[inline]
+    pub fn rebind(&self, action: AppAction, chord: Option<KeyChord>) {
+        self.send(AppCommand::Rebind(action, chord));
+    }
+
+    /// Sets pixels of an RGBA world image as if the world had drawn them, and
+    /// uploads them. Pixels outside the image are ignored. The world may
+    /// overwrite them with its next update.
+    #[inline]
+    pub fn write_pixels(&self, pixels: impl IntoIterator<Item = (u32, u32, Color)>) {
+        self.send(AppCommand::WritePixels(pixels.into_iter().collect()));
+    }
+
+    /// Replaces the
+    /// [`display_adjustments`](crate::AppConfigs::display_adjustments) until
+    /// they are changed again, also by keys. The world image is unaffected.
+    #[inline]
+    pub fn set_display_adjustments(&self, adjustments: DisplayAdjustments) {
+        self.send(AppCommand::SetDisplayAdjustments(adjustments));
+    }
+
+    /// Replaces the [`palette_cycle`](crate::AppConfigs::palette_cycle), or
+    /// stops cycling with `None`.
+    #[inline]
+    pub fn set_palette_cycle(&self, cycle: Option<PaletteCycle>) {
+        self.send(AppCommand::SetPaletteCycle(cycle));
+    }
+
+    /// Asks for a copy of the world image, with its layers, as of the next
+    /// frame. Waiting for it on the thread running the app never returns.
+    #[inline]
+    pub fn request_snapshot(&self) -> mpsc::Receiver<WorldImage> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(AppCommand::Snapshot(sender));
+        receiver
+    }
+
+    /// Asks for the world drawn `scale` times the size of its image as of
+    /// the next frame, e.g. for print-quality pictures of small worlds
+    /// whatever the size of the window. Includes the layers, the diff view,
+    /// the trails, the world shader and the grid if shown, but not the
+    /// overlays, zoom or panning.
+    ///
+    /// Fails if the result would be larger than the GPU's largest texture.
+    /// Waiting for it on the thread running the app never returns.
+    #[inline]
+    pub fn request_screenshot_scaled(
+        &self,
+        scale: u32,
+    ) -> mpsc::Receiver<anyhow::Result<WorldImage>> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(AppCommand::Screenshot(
+            scale,
+            ScreenshotTarget::Reply(sender),
+        ));
+        receiver
+    }
+
+    /// Like [`request_screenshot_scaled`](Self::request_screenshot_scaled),
+    /// saving the screenshot as a PNG file instead, e.g. from
+    /// [`World::update`](crate::World::update). Failures are reported through
+    /// [`AppConfigs::on_error`](crate::AppConfigs::on_error).
+    #[cfg(feature = "png")]
+    #[inline]
+    pub fn screenshot_scaled(&self, scale: u32, path: impl Into<std::path::PathBuf>) {
+        self.send(AppCommand::Screenshot(
+            scale,
+            ScreenshotTarget::Save(path.into()),
+        ));
+    }
+
+    /// A copy of the timings of the last 120 rendered frames, e.g. to find
+    /// out whether the world's updates or the texture uploads take longest.
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.lock_frame_stats().clone()
+    }
+
+    pub(crate) fn record_frame(&self, timing: FrameTiming) {
+        self.lock_frame_stats().push(timing);
+    }
+
+    pub(crate) fn lock_frame_stats(&self) -> std::sync::MutexGuard<'_, FrameStats> {
+        self.frame_stats
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// A copy of the keys held down right now, e.g. to move something in
+    /// [`World::update`](crate::World::update) for as long as a key is held
+    /// rather than once per key event.
+    #[inline]
+    pub fn keyboard(&self) -> KeyboardState {
+        self.lock_keyboard().clone()
+    }
+
+    pub(crate) fn lock_keyboard(&self) -> std::sync::MutexGuard<'_, KeyboardState> {
+        self.keyboard.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Appends `value` to the series `name` of the [`Plot`] in the
+    /// bottom-left corner of the window, creating it if needed, e.g. once
+    /// per series from [`World::update`](crate::World::update).
+    pub fn plot(&self, name: &str, value: f64) {
+        self.lock_plot().push(name, value);
+    }
+
+    /// Sets the color of the series `name`, creating it if needed.
+    pub fn plot_color(&self, name: &str, color: impl Into<Color>) {
+        self.lock_plot().set_color(name, color.into());
+    }
+
+    /// Removes all series, hiding the chart.
+    #[inline]
+    pub fn clear_plot(&self) {
+        self.lock_plot().clear();
+    }
+
+    /// A copy of the series pushed with [`plot`](Self::plot), e.g. to save
+    /// them on exit.
+    #[inline]
+    pub fn plot_data(&self) -> Plot {
+        self.lock_plot().clone()
+    }
+
+    pub(crate) fn lock_plot(&self) -> std::sync::MutexGuard<'_, Plot> {
+        self.plot.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Seed of the run, [`AppConfigs::seed`](crate::AppConfigs::seed) if set,
+    /// e.g. for `Rng::new(handle.seed())` in
+    /// [`World::attach`](crate::World::attach).
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The table that worlds record values into each tick, written as
+    /// configured with [`AppConfigs::metrics`](crate::AppConfigs::metrics).
+    /// Clone it to keep it around.
+    #[inline]
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// A copy of the state of the connected gamepads as of this frame.
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    pub fn gamepads(&self) -> crate::gamepad::Gamepads {
+        self.gamepads
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn set_gamepads(&self, gamepads: crate::gamepad::Gamepads) {
+        *self.gamepads.lock().unwrap_or_else(|err| err.into_inner()) = gamepads;
+    }
+
+    fn send(&self, command: AppCommand) {
+        if self.sender.send(command).is_ok()
+            && let Some(waker) = self.waker.get()
+        {
+            let _ = waker.send_event(());
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Settings of [`AppConfigs`] read from TOML or JSON files, with the
+//! `config-file` feature on, so that users of a binary can change key
+//! bindings and window settings without recompiling.
+//!
+//! ```toml
+//! title = "Life"
+//! window_size = [1280, 720]
+//! updates_per_second = 30
+//! start_paused = true
+//! hud = true
+//!
+//! [keys]
+//! Play = ["Space", "P"]
+//! ResetView = ["Home", "Ctrl+R"]
+//! Quit = ["Escape"]
+//! Grid = []
+//!
+//! [triggers]
+//! UpdateOnce = "Held"
+//! ```
+//!
+//! Every setting is optional and keeps the value of the [`AppConfigs`] it
+//! is applied to when missing. Key chords are parsed as described in
+//! [`KeyChord`]'s `FromStr` implementation.
+
+use crate::{
+    AppAction, AppConfigs, DisplayAdjustments, KeyChord, TextureFilter, Trigger, winit::MouseButton,
+};
+use anyhow::Context as _;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use winit::dpi::LogicalSize;
+
+/// The settings of a configuration file. See the [module](self) docs.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub title: Option<String>,
+    /// Inner size of the window in logical pixels.
+    pub window_size: Option<[f64; 2]>,
+    pub window_state_path: Option<PathBuf>,
+    pub updates_per_second: Option<u32>,
+    pub start_paused: Option<bool>,
+    pub max_updates_per_frame: Option<u32>,
+    pub max_fps: Option<u32>,
+    pub low_power: Option<bool>,
+    pub trails: Option<f32>,
+    pub seed: Option<u64>,
+    pub sample_count: Option<u32>,
+    pub mipmaps: Option<bool>,
+    pub texture_filter: Option<TextureFilter>,
+    pub display_adjustments: Option<DisplayAdjustments>,
+    pub hud: Option<bool>,
+    pub hud_counts: Option<bool>,
+    pub inspector: Option<bool>,
+    pub diff_view: Option<bool>,
+    pub title_stats: Option<String>,
+    /// Chords replacing all bindings of each listed action. An empty list
+    /// unbinds the action.
+    pub keys: HashMap<AppAction, Vec<KeyChord>>,
+    pub triggers: HashMap<AppAction, Trigger>,
+    pub button_pan: Option<MouseButton>,
+    pub button_context_menu: Option<MouseButton>,
+    pub button_menu_select: Option<MouseButton>,
+}
+
+impl ConfigFile {
+    /// Reads a JSON file if the extension of `path` is `json`, otherwise a
+    /// TOML file.
+    pub fn from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&source).map_err(anyhow::Error::from)
+        } else {
+            toml::from_str(&source).map_err(anyhow::Error::from)
+        };
+        parsed
+            .and_then(|file: Self| file.validate().map(|()| file))
+            .with_context(|| format!("invalid configuration file {}", path.display()))
+    }
+
+    /// Fails on settings the app cannot run with.
+    fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.updates_per_second != Some(0),
+            "updates_per_second must be at least 1"
+        );
+        anyhow::ensure!(self.max_fps != Some(0), "max_fps must be at least 1");
+        Ok(())
+    }
+
+    /// Overrides the settings of `configs` present in this file.
+    pub fn apply(self, mut configs: AppConfigs) -> AppConfigs {
+        macro_rules! set {
+            ($($field:ident),*) => {
+                $(if let Some(value) = self.$field {
+                    configs.$field = value.into();
+                })*
+            };
+        }
+        set!(
+            window_state_path,
+            updates_per_second,
+            start_paused,
+            max_updates_per_frame,
+            max_fps,
+            low_power,
+            trails,
+            seed,
+            sample_count,
+            mipmaps,
+            texture_filter,
+            display_adjustments,
+            hud,
+            hud_counts,
+            inspector,
+            diff_view,
+            title_stats,
+            button_pan,
+            button_context_menu,
+            button_menu_select
+        );
+
+        if let Some(title) = self.title {
+            configs.window_attributes.title = title;
+        }
+        if let Some([width, height]) = self.window_size {
+            configs.window_attributes.inner_size = Some(LogicalSize::new(width, height).into());
+        }
+        for (action, chords) in self.keys {
+            configs.input_map.unbind_action(action);
+            for chord in chords {
+                configs.input_map.bind(chord, action);
+            }
+        }
+        for (action, trigger) in self.triggers {
+            configs.input_map.set_trigger(action, trigger);
+        }
+        configs
+    }
+}
+
+impl<'de> Deserialize<'de> for AppConfigs {
+    /// Applies a [`ConfigFile`] to the default configs.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = ConfigFile::deserialize(deserializer)?;
+        file.validate().map_err(serde::de::Error::custom)?;
+        Ok(file.apply(AppConfigs::default()))
+    }
+}
+
+impl AppConfigs {
+    /// Environment variable read by [`AppConfigs::from_env`].
+    pub const CONFIG_ENV: &str = "CELLS_RENDERER_CONFIG";
+
+    /// The default configs with the settings of a TOML or JSON file. See
+    /// [`ConfigFile::from_path`].
+    #[inline]
+    pub fn from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(ConfigFile::from_path(path)?.apply(Self::default()))
+    }
+
+    /// Like [`AppConfigs::from_path`] with the file named by the
+    /// `CELLS_RENDERER_CONFIG` environment variable, or the default configs
+    /// when it is not set.
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var_os(Self::CONFIG_ENV) {
+            Some(path) => Self::from_path(PathBuf::from(path)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Overrides these configs with the settings of a TOML or JSON file, so
+    /// that a binary can set its own defaults in code first.
+    #[inline]
+    pub fn merge_file(self, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(ConfigFile::from_path(path)?.apply(self))
+    }
+}
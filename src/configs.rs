@@ -1,12 +1,68 @@
-use crate::winit::{KeyCode, WindowAttributes};
+use crate::{
+    Color, CursorRounding, Gestures, HoverHighlight, Tutorial,
+    checkpoint::Autosave,
+    input::{AppAction, InputMap, KeyChord, Trigger},
+    metrics::MetricsExport,
+    timelapse::Timelapse,
+    wgpu::{Backends, PowerPreference},
+    winit::{KeyCode, MouseButton, WindowAttributes},
+};
+use std::{collections::HashMap, fmt, path::PathBuf, time::Duration};
 
 #[derive(Debug)]
 pub struct AppConfigs {
     pub window_attributes: WindowAttributes,
+    pub window_state_path: Option<PathBuf>,
     pub updates_per_second: u32,
-    pub key_play: Option<KeyCode>,
-    pub key_update_once: Option<KeyCode>,
-    pub key_grid: Option<KeyCode>,
+    pub start_paused: bool,
+    pub start_delay: Option<Duration>,
+    pub seed: Option<u64>,
+    pub max_updates_per_frame: Option<u32>,
+    pub max_fps: Option<u32>,
+    pub max_upload_bytes_per_frame: Option<u64>,
+    pub low_power: bool,
+    pub cursor_rounding: CursorRounding,
+    pub trails: Option<f32>,
+    pub world_shader: Option<String>,
+    pub sample_count: u32,
+    pub mipmaps: bool,
+    pub texture_filter: TextureFilter,
+    pub display_adjustments: DisplayAdjustments,
+    pub palette_cycle: Option<PaletteCycle>,
+    pub hud: bool,
+    pub hud_counts: bool,
+    pub plot_samples: usize,
+    pub inspector: bool,
+    pub hover_highlight: Option<HoverHighlight>,
+    pub vector_spacing: f32,
+    pub diff_view: bool,
+    pub diff_style: DiffStyle,
+    pub autosave: Option<Autosave>,
+    pub metrics: Option<MetricsExport>,
+    #[cfg(feature = "video")]
+    pub video: crate::video::VideoExport,
+    pub timelapse: Option<Timelapse>,
+    pub command_palette: bool,
+    pub title_stats: Option<String>,
+    #[cfg(feature = "dev")]
+    pub main_shader_path: Option<PathBuf>,
+    #[cfg(feature = "dev")]
+    pub grid_shader_path: Option<PathBuf>,
+    #[cfg(feature = "dev")]
+    pub world_shader_path: Option<PathBuf>,
+    pub input_map: InputMap<AppAction>,
+    pub button_pan: Option<MouseButton>,
+    pub button_context_menu: Option<MouseButton>,
+    pub button_menu_select: MouseButton,
+    pub mouse_button_map: HashMap<MouseButton, MouseButton>,
+    pub gestures: Option<Gestures>,
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    pub force_fallback_adapter: bool,
+    pub update_budget: Option<Duration>,
+    pub watchdog_action: WatchdogAction,
+    pub on_error: Option<ErrorHandler>,
+    pub tutorial: Option<Tutorial>,
 }
 
 impl Default for AppConfigs {
@@ -14,10 +70,57 @@ impl Default for AppConfigs {
     fn default() -> Self {
         Self {
             window_attributes: WindowAttributes::default(),
+            window_state_path: None,
             updates_per_second: 60,
-            key_play: Some(KeyCode::Space),
-            key_update_once: Some(KeyCode::Enter),
-            key_grid: Some(KeyCode::KeyG),
+            start_paused: false,
+            start_delay: None,
+            seed: None,
+            max_updates_per_frame: Some(32),
+            max_fps: None,
+            max_upload_bytes_per_frame: None,
+            low_power: false,
+            cursor_rounding: CursorRounding::default(),
+            trails: None,
+            world_shader: None,
+            sample_count: 1,
+            mipmaps: false,
+            texture_filter: TextureFilter::default(),
+            display_adjustments: DisplayAdjustments::NEUTRAL,
+            palette_cycle: None,
+            hud: false,
+            hud_counts: false,
+            plot_samples: 300,
+            inspector: false,
+            hover_highlight: None,
+            vector_spacing: 16.0,
+            diff_view: false,
+            diff_style: DiffStyle::default(),
+            autosave: None,
+            metrics: None,
+            #[cfg(feature = "video")]
+            video: crate::video::VideoExport::default(),
+            timelapse: None,
+            command_palette: false,
+            title_stats: None,
+            #[cfg(feature = "dev")]
+            main_shader_path: None,
+            #[cfg(feature = "dev")]
+            grid_shader_path: None,
+            #[cfg(feature = "dev")]
+            world_shader_path: None,
+            input_map: InputMap::app_defaults(),
+            button_pan: Some(MouseButton::Middle),
+            button_context_menu: Some(MouseButton::Right),
+            button_menu_select: MouseButton::Left,
+            mouse_button_map: HashMap::new(),
+            gestures: None,
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::default(),
+            force_fallback_adapter: false,
+            update_budget: None,
+            watchdog_action: WatchdogAction::default(),
+            on_error: None,
+            tutorial: None,
         }
     }
 }
@@ -36,6 +139,17 @@ impl AppConfigs {
         }
     }
 
+    /// File where the window size, position, monitor and fullscreen state
+    /// are saved on exit and restored on the next launch.
+    #[inline]
+    pub fn window_state_path(self, window_state_path: Option<PathBuf>) -> Self {
+        Self {
+            window_state_path,
+            ..self
+        }
+    }
+
+    /// Zero is treated as one.
     #[inline]
     pub fn updates_per_second(self, updates_per_second: u32) -> Self {
         Self {
@@ -44,21 +158,839 @@ impl AppConfigs {
         }
     }
 
+    /// Opens the window paused. Takes precedence over `start_delay`.
+    #[inline]
+    pub fn start_paused(self, start_paused: bool) -> Self {
+        Self {
+            start_paused,
+            ..self
+        }
+    }
+
+    /// Keeps the app paused for this long after the window opens, showing a
+    /// countdown bar, then starts playback.
+    #[inline]
+    pub fn start_delay(self, start_delay: Option<Duration>) -> Self {
+        Self {
+            start_delay,
+            ..self
+        }
+    }
+
+    /// Seed of the run, from [`AppHandle::seed`](crate::AppHandle::seed),
+    /// so that worlds seeding their [`Rng`](crate::Rng) from it produce the
+    /// same boards run after run. Defaults to `None`, seeding from the clock
+    /// and logging the seed so that a run can be repeated.
+    #[inline]
+    pub fn seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Caps how many updates may run in a single frame to catch up with
+    /// `updates_per_second`. When the cap is hit, the remaining backlog is
+    /// dropped so that slow updates can't make the app fall further behind.
+    ///
+    /// Very high update rates may need a larger cap, or `None` to disable it.
+    #[inline]
+    pub fn max_updates_per_frame(self, max_updates_per_frame: Option<u32>) -> Self {
+        Self {
+            max_updates_per_frame,
+            ..self
+        }
+    }
+
+    /// Caps the number of presented frames per second, independently of
+    /// `updates_per_second`. Several updates may run per frame. `Some(0)` is
+    /// treated as `Some(1)`.
+    #[inline]
+    pub fn max_fps(self, max_fps: Option<u32>) -> Self {
+        Self { max_fps, ..self }
+    }
+
+    /// Caps the bytes of world image uploaded to the GPU per frame. Changes
+    /// to large images are then uploaded in bands of rows over several
+    /// frames, trading some visual latency for steady frame times.
+    #[inline]
+    pub fn max_upload_bytes_per_frame(self, max_upload_bytes_per_frame: Option<u64>) -> Self {
+        Self {
+            max_upload_bytes_per_frame,
+            ..self
+        }
+    }
+
+    /// Redraws only when the world updates, input arrives or the window is
+    /// resized, sleeping in between instead of polling.
+    #[inline]
+    pub fn low_power(self, low_power: bool) -> Self {
+        Self { low_power, ..self }
+    }
+
+    #[inline]
+    pub fn cursor_rounding(self, cursor_rounding: CursorRounding) -> Self {
+        Self {
+            cursor_rounding,
+            ..self
+        }
+    }
+
+    /// Leaves fading trails behind moving cells. On every change of the
+    /// world image, the previous result is multiplied by this decay factor in
+    /// `0.0..=1.0` and each channel keeps the larger of it and the new image,
+    /// so trails show best with bright cells on a dark background.
+    #[inline]
+    pub fn trails(self, trails: Option<f32>) -> Self {
+        Self { trails, ..self }
+    }
+
+    /// WGSL source drawing the world in place of the built-in shader, e.g.
+    /// for CRT or false-color effects.
+    ///
+    /// The source is appended to a prelude declaring `VertexOutput` with the
+    /// texture coordinates in `tex_coords`, the vertex entry point, and:
+    ///
+    /// - `t_world: texture_2d<f32>` and `s_world: sampler`, the world image,
+    /// - `globals.time: f32`, seconds since the app started,
+    /// - `globals.frame: u32`, the index of the frame,
+    /// - `globals.world_size: vec2<f32>`, the world image size in cells,
+    /// - `globals.window_size: vec2<f32>`, the window size in pixels,
+    /// - `globals.cursor: vec2<f32>`, the cell under the mouse, or `(-1, -1)`,
+    /// - `adjust_display(color: vec4<f32>) -> vec4<f32>`, applying the
+    ///   [`display_adjustments`](Self::display_adjustments).
+    ///
+    /// It must define the fragment entry point:
+    ///
+    /// ```wgsl
+    /// @fragment
+    /// fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    ///     let color = textureSample(t_world, s_world, in.tex_coords);
+    ///     return adjust_display(vec4<f32>(1.0 - color.rgb, color.a));
+    /// }
+    /// ```
+    ///
+    /// Invalid shaders are logged and the built-in one is used instead.
+    /// Indexed and float images are drawn through their palette and ignore
+    /// the world shader unless [`trails`](Self::trails) are on.
+    #[inline]
+    pub fn world_shader(self, world_shader: Option<String>) -> Self {
+        Self {
+            world_shader,
+            ..self
+        }
+    }
+
+    /// Samples per pixel for multisample anti-aliasing of the world's edges,
+    /// grid lines and overlays, e.g. 4. Defaults to 1, no MSAA. Counts the
+    /// adapter does not support fall back to 4, or 1.
+    #[inline]
+    pub fn sample_count(self, sample_count: u32) -> Self {
+        Self {
+            sample_count,
+            ..self
+        }
+    }
+
+    /// Generates mip levels for RGBA world images after every upload, so that
+    /// zoomed-out views of worlds much larger than the window average their
+    /// cells instead of aliasing. Zoomed-in cells stay crisp. Defaults to
+    /// `false`, as each upload then takes a few extra render passes.
+    #[inline]
+    pub fn mipmaps(self, mipmaps: bool) -> Self {
+        Self { mipmaps, ..self }
+    }
+
+    /// How RGBA world images are sampled between cells. Defaults to
+    /// [`TextureFilter::Nearest`]; switched at runtime with
+    /// [`key_texture_filter`](Self::key_texture_filter).
+    #[inline]
+    pub fn texture_filter(self, texture_filter: TextureFilter) -> Self {
+        Self {
+            texture_filter,
+            ..self
+        }
+    }
+
+    /// Brightens, darkens or stretches the displayed colors without changing
+    /// the world image. Defaults to [`DisplayAdjustments::NEUTRAL`]; the
+    /// exposure is changed at runtime with
+    /// [`key_exposure_up`](Self::key_exposure_up) and
+    /// [`key_exposure_down`](Self::key_exposure_down), and everything with
+    /// [`AppHandle::set_display_adjustments`].
+    ///
+    /// [`AppHandle::set_display_adjustments`]: crate::AppHandle::set_display_adjustments
+    #[inline]
+    pub fn display_adjustments(self, display_adjustments: DisplayAdjustments) -> Self {
+        Self {
+            display_adjustments,
+            ..self
+        }
+    }
+
+    /// Rotates a range of palette entries of indexed and float images as the
+    /// world updates, the classic color cycling effect for e.g. water or
+    /// fire. Only the displayed colors move: the image and its palette stay
+    /// the same, and nothing is uploaded but a uniform. Defaults to `None`;
+    /// changed at runtime with [`AppHandle::set_palette_cycle`].
+    ///
+    /// [`AppHandle::set_palette_cycle`]: crate::AppHandle::set_palette_cycle
+    #[inline]
+    pub fn palette_cycle(self, palette_cycle: Option<PaletteCycle>) -> Self {
+        Self {
+            palette_cycle,
+            ..self
+        }
+    }
+
+    /// Shows a heads-up display in the top-right corner of the window with
+    /// the frame and update rates, the generation count, whether the app is
+    /// paused, and the lines of [`World::hud_lines`](crate::World::hud_lines).
+    /// Defaults to `false`; toggled with [`key_hud`](Self::key_hud).
+    #[inline]
+    pub fn hud(self, hud: bool) -> Self {
+        Self { hud, ..self }
+    }
+
+    /// Adds the population of the world image to the [`hud`](Self::hud),
+    /// counted again whenever the image changes: the eight most common
+    /// colors of RGBA images, the first eight palette indices in use of
+    /// indexed images, and the sum and mean of float images. Layers are not
+    /// counted. Defaults to `false`.
+    #[inline]
+    pub fn hud_counts(self, hud_counts: bool) -> Self {
+        Self { hud_counts, ..self }
+    }
+
+    /// Number of samples kept per series of the [`Plot`](crate::Plot) fed
+    /// with [`AppHandle::plot`](crate::AppHandle::plot), at least two.
+    /// Defaults to `300`.
+    #[inline]
+    pub fn plot_samples(self, plot_samples: usize) -> Self {
+        Self {
+            plot_samples,
+            ..self
+        }
+    }
+
+    /// Outlines the cell under the cursor and shows its coordinates next to
+    /// the cursor, followed by [`World::inspect`](crate::World::inspect).
+    /// Defaults to `false`; toggled with [`AppAction::Inspector`], `F4` by
+    /// default.
+    #[inline]
+    pub fn inspector(self, inspector: bool) -> Self {
+        Self { inspector, ..self }
+    }
+
+    /// Marks the cells of [`World::hover_cells`](crate::World::hover_cells)
+    /// under the cursor, above the world without changing its image.
+    /// Defaults to `None`.
+    #[inline]
+    pub fn hover_highlight(self, hover_highlight: Option<HoverHighlight>) -> Self {
+        Self {
+            hover_highlight,
+            ..self
+        }
+    }
+
+    /// Minimum distance in window pixels between the arrows of
+    /// [`World::draw_vector_field`](crate::World::draw_vector_field). When
+    /// zoomed out, arrows are drawn for blocks of cells rather than single
+    /// cells to keep them this far apart. Defaults to `16.0`.
+    #[inline]
+    pub fn vector_spacing(self, vector_spacing: f32) -> Self {
+        Self {
+            vector_spacing,
+            ..self
+        }
+    }
+
+    /// Shows only the cells that changed in the last update, compared with
+    /// the image of the update before, to spot activity on mostly static
+    /// boards. Defaults to `false`; toggled with [`AppAction::DiffView`],
+    /// `F5` by default.
+    #[inline]
+    pub fn diff_view(self, diff_view: bool) -> Self {
+        Self { diff_view, ..self }
+    }
+
+    /// Defaults to [`DiffStyle::NewColor`].
+    #[inline]
+    pub fn diff_style(self, diff_style: DiffStyle) -> Self {
+        Self { diff_style, ..self }
+    }
+
+    /// Takes checkpoints of the world while the app runs. Defaults to
+    /// `None`.
+    #[inline]
+    pub fn autosave(self, autosave: Option<Autosave>) -> Self {
+        Self { autosave, ..self }
+    }
+
+    /// Writes the [`Metrics`](crate::Metrics) recorded by the world to a
+    /// file while the app runs and when it exits. Defaults to `None`.
+    #[inline]
+    pub fn metrics(self, metrics: Option<MetricsExport>) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Where and how [`AppAction::Record`] records videos. Defaults to MP4
+    /// files in `videos`, see [`VideoExport::new`].
+    ///
+    /// [`VideoExport::new`]: crate::video::VideoExport::new
+    #[cfg(feature = "video")]
+    #[inline]
+    pub fn video(self, video: crate::video::VideoExport) -> Self {
+        Self { video, ..self }
+    }
+
+    /// Captures a frame every so many updates while the app runs. Defaults
+    /// to `None`.
+    #[inline]
+    pub fn timelapse(self, timelapse: Option<Timelapse>) -> Self {
+        Self { timelapse, ..self }
+    }
+
+    /// Lets [`AppAction::CommandPalette`], `:` or `Ctrl+P` by default, open a
+    /// line of text input at the bottom of the window. Enter runs the line,
+    /// Escape closes the palette and the arrow keys recall earlier lines.
+    ///
+    /// The app runs these commands itself:
+    ///
+    /// - `ups <n>`, sets the update rate,
+    /// - `pause` and `play`,
+    /// - `step [n]`, runs one update or fast-forwards by `n`,
+    /// - `save <file>` and `load <file>`, a checkpoint of the world,
+    /// - `goto <x> <y>`, centers the view on a cell,
+    /// - `reset`, undoes zooming and panning,
+    /// - `quit`.
+    ///
+    /// Other lines go to [`World::command`](crate::World::command). Defaults
+    /// to `false`.
+    #[inline]
+    pub fn command_palette(self, command_palette: bool) -> Self {
+        Self {
+            command_palette,
+            ..self
+        }
+    }
+
+    /// Keeps live stats in the window title, formatted from this string with
+    /// the placeholders:
+    ///
+    /// - `{title}`, the title from `window_attributes`,
+    /// - `{fps}`, frames rendered in the last second,
+    /// - `{ups}`, updates run in the last second,
+    /// - `{tick}`, updates run since the start,
+    /// - `{paused}`, `paused` while paused, otherwise nothing.
+    ///
+    /// For example `"{title} - gen {tick}, {ups} UPS {paused}"`. The title is
+    /// refreshed at most four times per second.
+    #[inline]
+    pub fn title_stats(self, title_stats: Option<String>) -> Self {
+        Self {
+            title_stats,
+            ..self
+        }
+    }
+
+    /// Loads the shader drawing the world from this file instead of the
+    /// built-in `main.wgsl`, and rebuilds its pipelines whenever the file
+    /// changes. Start from a copy of `src/app/main.wgsl`; the bindings must
+    /// stay the same. Like the built-in shader, the file is loaded after
+    /// `src/app/globals.wgsl`, which declares `globals` and `adjust_display`.
+    ///
+    /// Shaders that fail to load or compile are reported through
+    /// [`on_error`](Self::on_error) and the previous ones are kept.
+    #[cfg(feature = "dev")]
+    #[inline]
+    pub fn main_shader_path(self, main_shader_path: Option<PathBuf>) -> Self {
+        Self {
+            main_shader_path,
+            ..self
+        }
+    }
+
+    /// Like [`main_shader_path`](Self::main_shader_path), for the grid
+    /// lines drawn by `src/app/grid.wgsl`.
+    #[cfg(feature = "dev")]
+    #[inline]
+    pub fn grid_shader_path(self, grid_shader_path: Option<PathBuf>) -> Self {
+        Self {
+            grid_shader_path,
+            ..self
+        }
+    }
+
+    /// Like [`main_shader_path`](Self::main_shader_path), for a
+    /// [`world_shader`](Self::world_shader) kept in a file. Takes its place
+    /// once loaded.
+    #[cfg(feature = "dev")]
+    #[inline]
+    pub fn world_shader_path(self, world_shader_path: Option<PathBuf>) -> Self {
+        Self {
+            world_shader_path,
+            ..self
+        }
+    }
+
+    /// Key bindings of the app's actions, replacing all of them. Defaults to
+    /// [`InputMap::app_defaults`].
+    ///
+    /// The `key_*` methods below replace the bindings of a single action
+    /// with one key, or none.
+    #[inline]
+    pub fn input_map(self, input_map: InputMap<AppAction>) -> Self {
+        Self { input_map, ..self }
+    }
+
+    /// Adds a binding for `action`, keeping the others, e.g. `Ctrl+R` for
+    /// [`AppAction::ResetView`] next to `Home`.
+    #[inline]
+    pub fn bind(mut self, chord: impl Into<KeyChord>, action: AppAction) -> Self {
+        self.input_map.bind(chord, action);
+        self
+    }
+
+    /// Sets when `action` fires, e.g. [`Trigger::Held`] to step once every
+    /// frame while the key of [`AppAction::UpdateOnce`] is held.
+    #[inline]
+    pub fn trigger(mut self, action: AppAction, trigger: Trigger) -> Self {
+        self.input_map.set_trigger(action, trigger);
+        self
+    }
+
+    #[inline]
+    pub fn key_play(mut self, key_play: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Play, key_play.map(KeyChord::new));
+        self
+    }
+
+    #[inline]
+    pub fn key_update_once(mut self, key_update_once: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::UpdateOnce, key_update_once.map(KeyChord::new));
+        self
+    }
+
+    #[inline]
+    pub fn key_grid(mut self, key_grid: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Grid, key_grid.map(KeyChord::new));
+        self
+    }
+
+    /// Key that toggles a graph of the [`FrameStats`](crate::FrameStats):
+    /// the interval between frames in green, yellow or red, overlaid with
+    /// update, upload and render times stacked in orange, blue and purple.
+    /// A bar below it shows how much of `updates_per_second` was achieved in
+    /// the last second.
+    #[inline]
+    pub fn key_frame_graph(mut self, key_frame_graph: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::FrameGraph, key_frame_graph.map(KeyChord::new));
+        self
+    }
+
+    /// Key that toggles the [`hud`](Self::hud).
+    #[inline]
+    pub fn key_hud(mut self, key_hud: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Hud, key_hud.map(KeyChord::new));
+        self
+    }
+
+    /// Key that moves keyboard focus to text input. See [`InputFocus`](crate::InputFocus).
+    #[inline]
+    pub fn key_text_focus(mut self, key_text_focus: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::TextFocus, key_text_focus.map(KeyChord::new));
+        self
+    }
+
+    /// Key that switches between nearest and linear
+    /// [`texture_filter`](Self::texture_filter)s. Defaults to `F6`.
+    #[inline]
+    pub fn key_texture_filter(mut self, key_texture_filter: Option<KeyCode>) -> Self {
+        self.input_map.rebind(
+            AppAction::TextureFilter,
+            key_texture_filter.map(KeyChord::new),
+        );
+        self
+    }
+
+    /// Key that raises the exposure of the
+    /// [`display_adjustments`](Self::display_adjustments) by half a stop.
+    /// Defaults to `F8`.
+    #[inline]
+    pub fn key_exposure_up(mut self, key_exposure_up: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::ExposureUp, key_exposure_up.map(KeyChord::new));
+        self
+    }
+
+    /// Key that lowers the exposure by half a stop. Defaults to `F7`.
+    #[inline]
+    pub fn key_exposure_down(mut self, key_exposure_down: Option<KeyCode>) -> Self {
+        self.input_map.rebind(
+            AppAction::ExposureDown,
+            key_exposure_down.map(KeyChord::new),
+        );
+        self
+    }
+
+    /// Key that restores the configured
+    /// [`display_adjustments`](Self::display_adjustments). Defaults to none.
+    #[inline]
+    pub fn key_reset_display(mut self, key_reset_display: Option<KeyCode>) -> Self {
+        self.input_map.rebind(
+            AppAction::ResetDisplay,
+            key_reset_display.map(KeyChord::new),
+        );
+        self
+    }
+
+    /// Key that undoes zooming and panning. The view is zoomed with the mouse
+    /// wheel or by pinching, and panned by dragging with the pan button or
+    /// scrolling with two fingers on a trackpad.
+    #[inline]
+    pub fn key_reset_view(mut self, key_reset_view: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::ResetView, key_reset_view.map(KeyChord::new));
+        self
+    }
+
+    /// Key that closes the window, e.g. `Escape`, as if its close button
+    /// was clicked. Defaults to none.
+    #[inline]
+    pub fn key_quit(mut self, key_quit: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Quit, key_quit.map(KeyChord::new));
+        self
+    }
+
+    /// Key that captures the next frame with the graphics debugger the app
+    /// was launched from, such as RenderDoc or Xcode. Does nothing without
+    /// one attached.
+    #[cfg(feature = "frame-capture")]
+    #[inline]
+    pub fn key_capture(mut self, key_capture: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Capture, key_capture.map(KeyChord::new));
+        self
+    }
+
+    /// Key that starts and stops recording a [`video`](Self::video).
+    /// Defaults to `F9`.
+    #[cfg(feature = "video")]
+    #[inline]
+    pub fn key_record(mut self, key_record: Option<KeyCode>) -> Self {
+        self.input_map
+            .rebind(AppAction::Record, key_record.map(KeyChord::new));
+        self
+    }
+
+    /// Mouse button that pans the view while held.
+    #[inline]
+    pub fn button_pan(self, button_pan: Option<MouseButton>) -> Self {
+        Self { button_pan, ..self }
+    }
+
+    /// Mouse button that opens the menu of [`World::cell_actions`](crate::World::cell_actions).
+    #[inline]
+    pub fn button_context_menu(self, button_context_menu: Option<MouseButton>) -> Self {
+        Self {
+            button_context_menu,
+            ..self
+        }
+    }
+
+    /// Mouse button that runs the clicked action of the context menu. Other
+    /// buttons close the menu without running one. Defaults to `Left`.
     #[inline]
-    pub fn key_play(self, key_play: Option<KeyCode>) -> Self {
-        Self { key_play, ..self }
+    pub fn button_menu_select(self, button_menu_select: MouseButton) -> Self {
+        Self {
+            button_menu_select,
+            ..self
+        }
+    }
+
+    /// Treats presses of `from` as presses of `to`, everywhere including
+    /// [`World::mouse_input`](crate::World::mouse_input), `button_pan`,
+    /// `button_context_menu` and `button_menu_select`. Mapping e.g. `Left`
+    /// to `Right` and `Right` to `Left` swaps them for left-handed use.
+    #[inline]
+    pub fn map_mouse_button(mut self, from: MouseButton, to: MouseButton) -> Self {
+        self.mouse_button_map.insert(from, to);
+        self
     }
 
+    /// Also sends clicks, double clicks and the start and end of drags to
+    /// [`World::mouse_input`](crate::World::mouse_input), detected with
+    /// these thresholds. Off by default. See
+    /// [`MouseEventKind`](crate::MouseEventKind).
     #[inline]
-    pub fn key_update_once(self, key_update_once: Option<KeyCode>) -> Self {
+    pub fn gestures(self, gestures: Option<Gestures>) -> Self {
+        Self { gestures, ..self }
+    }
+
+    #[inline]
+    pub fn backends(self, backends: Backends) -> Self {
+        Self { backends, ..self }
+    }
+
+    #[inline]
+    pub fn power_preference(self, power_preference: PowerPreference) -> Self {
         Self {
-            key_update_once,
+            power_preference,
             ..self
         }
     }
 
     #[inline]
-    pub fn key_grid(self, key_grid: Option<KeyCode>) -> Self {
-        Self { key_grid, ..self }
+    pub fn force_fallback_adapter(self, force_fallback_adapter: bool) -> Self {
+        Self {
+            force_fallback_adapter,
+            ..self
+        }
     }
+
+    /// Time a single `World::update` may take before the watchdog triggers.
+    #[inline]
+    pub fn update_budget(self, update_budget: Option<Duration>) -> Self {
+        Self {
+            update_budget,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn watchdog_action(self, watchdog_action: WatchdogAction) -> Self {
+        Self {
+            watchdog_action,
+            ..self
+        }
+    }
+
+    /// Sets a callback for recoverable errors, such as a frame that could not
+    /// be presented. Without one, they are logged.
+    #[inline]
+    pub fn on_error<F>(self, on_error: F) -> Self
+    where
+        F: FnMut(&anyhow::Error) + Send + 'static,
+    {
+        Self {
+            on_error: Some(ErrorHandler::new(on_error)),
+            ..self
+        }
+    }
+
+    /// Walks the user through the given steps after the window opens. The
+    /// current prompt is shown at the top of the window until the tutorial is
+    /// done.
+    #[inline]
+    pub fn tutorial(self, tutorial: Option<Tutorial>) -> Self {
+        Self { tutorial, ..self }
+    }
+}
+
+/// Callback receiving recoverable errors. See [`AppConfigs::on_error`].
+pub struct ErrorHandler(Box<dyn FnMut(&anyhow::Error) + Send>);
+
+impl ErrorHandler {
+    #[inline]
+    pub fn new<F>(on_error: F) -> Self
+    where
+        F: FnMut(&anyhow::Error) + Send + 'static,
+    {
+        Self(Box::new(on_error))
+    }
+
+    #[inline]
+    pub(crate) fn call(&mut self, err: &anyhow::Error) {
+        (self.0)(err)
+    }
+}
+
+impl fmt::Debug for ErrorHandler {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorHandler(..)")
+    }
+}
+
+/// How [`AppConfigs::diff_view`] shows the cells changed by the last update.
+/// Unchanged cells are always black.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DiffStyle {
+    /// Changed cells in their new color.
+    #[default]
+    NewColor,
+    /// Changed cells in one color by the kind of change.
+    Coded {
+        /// Cells that became brighter, e.g. cells that were born.
+        brighter: Color,
+        /// Cells that became darker, e.g. cells that died.
+        darker: Color,
+        /// Cells that changed color at the same brightness.
+        other: Color,
+    },
+}
+
+/// How the world texture is sampled, see [`AppConfigs::texture_filter`].
+///
+/// Indexed and float images are always drawn with crisp cells, as their
+/// palette lookup does not blend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub enum TextureFilter {
+    /// Every pixel takes the color of the cell under it, drawing zoomed-in
+    /// cells as sharp squares.
+    #[default]
+    Nearest,
+    /// Colors are blended between the centers of neighboring cells, smoothing
+    /// e.g. continuous fields and images.
+    Linear,
+}
+
+impl TextureFilter {
+    pub(crate) fn filter_mode(self) -> wgpu::FilterMode {
+        match self {
+            Self::Nearest => wgpu::FilterMode::Nearest,
+            Self::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// Display controls applied when drawing the world, see
+/// [`AppConfigs::display_adjustments`].
+///
+/// Colors are adjusted in linear light: first scaled by the exposure, then
+/// raised to the power of `1 / gamma`, then stretched by the contrast around
+/// mid-gray. Custom [`world_shader`](AppConfigs::world_shader)s apply them by
+/// calling `adjust_display(color)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DisplayAdjustments {
+    /// Stops of brightening, or darkening when negative. Each stop doubles
+    /// the intensity.
+    pub exposure: f32,
+    /// Values above 1 brighten dark colors more than bright ones. Must be
+    /// positive; smaller values are drawn as the smallest positive `f32`.
+    pub gamma: f32,
+    /// Values above 1 push colors away from mid-gray, values below 1 towards
+    /// it.
+    pub contrast: f32,
+}
+
+impl Default for DisplayAdjustments {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+impl DisplayAdjustments {
+    /// Shows colors unchanged.
+    pub const NEUTRAL: Self = Self {
+        exposure: 0.0,
+        gamma: 1.0,
+        contrast: 1.0,
+    };
+
+    #[inline]
+    pub fn exposure(self, exposure: f32) -> Self {
+        Self { exposure, ..self }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `gamma` is not positive.
+    #[inline]
+    pub fn gamma(self, gamma: f32) -> Self {
+        assert!(gamma > 0.0, "gamma must be positive, got {gamma}");
+        Self { gamma, ..self }
+    }
+
+    #[inline]
+    pub fn contrast(self, contrast: f32) -> Self {
+        Self { contrast, ..self }
+    }
+}
+
+/// Color cycling of palette entries, see [`AppConfigs::palette_cycle`].
+///
+/// Every `ticks_per_step` updates, each color of the range moves to the next
+/// entry, and the last one wraps around to the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaletteCycle {
+    /// First entry of the range.
+    pub first: u8,
+    /// Last entry of the range, included.
+    pub last: u8,
+    /// Updates between steps of the rotation.
+    pub ticks_per_step: u32,
+    /// Moves colors towards lower entries instead.
+    pub reverse: bool,
+}
+
+impl PaletteCycle {
+    /// Rotates the entries `first..=last` by one step per update.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `first` is greater than `last`.
+    #[inline]
+    pub fn new(first: u8, last: u8) -> Self {
+        assert!(
+            first <= last,
+            "first palette entry {first} is after the last {last}"
+        );
+        Self {
+            first,
+            last,
+            ticks_per_step: 1,
+            reverse: false,
+        }
+    }
+
+    /// Sets the updates between steps, at least 1.
+    #[inline]
+    pub fn every(self, ticks_per_step: u32) -> Self {
+        Self {
+            ticks_per_step: ticks_per_step.max(1),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn reverse(self, reverse: bool) -> Self {
+        Self { reverse, ..self }
+    }
+
+    /// Number of entries in the range.
+    #[inline]
+    pub fn entry_count(&self) -> u32 {
+        self.last as u32 - self.first as u32 + 1
+    }
+
+    /// How many entries the colors have moved by after `tick` updates, in
+    /// `0..self.entry_count()`.
+    pub fn offset(&self, tick: u64) -> u32 {
+        let len = self.entry_count() as u64;
+        let steps = (tick / self.ticks_per_step.max(1) as u64 % len) as u32;
+        if self.reverse {
+            (self.entry_count() - steps) % self.entry_count()
+        } else {
+            steps
+        }
+    }
+}
+
+/// What to do when `World::update` exceeds [`AppConfigs::update_budget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WatchdogAction {
+    /// Log a warning.
+    #[default]
+    Warn,
+    /// Log a warning and pause the simulation.
+    Pause,
 }
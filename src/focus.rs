@@ -0,0 +1,11 @@
+/// Which part of the app receives keyboard input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InputFocus {
+    /// Keys go to the app's hotkeys and to [`World::keyboard_input`](crate::World::keyboard_input).
+    #[default]
+    World,
+    /// Typed text goes to [`World::text_input`](crate::World::text_input);
+    /// hotkeys and `keyboard_input` are suppressed. Escape returns focus to
+    /// the world.
+    Text,
+}
@@ -0,0 +1,204 @@
+//! Gamepad input polled with gilrs, with the `gamepad` feature on.
+//!
+//! Worlds get discrete button presses through
+//! [`World::gamepad_input`](crate::World::gamepad_input) and poll sticks and
+//! held buttons through [`AppHandle::gamepads`](crate::AppHandle::gamepads),
+//! both refreshed once per frame.
+
+pub use gilrs::{Axis, Button, GamepadId};
+use std::collections::{HashMap, HashSet};
+
+const BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+const AXES: [Axis; 8] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+];
+
+/// A change of a gamepad, passed to
+/// [`World::gamepad_input`](crate::World::gamepad_input).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    ButtonPressed(GamepadId, Button),
+    ButtonReleased(GamepadId, Button),
+}
+
+/// Buttons and axes of a connected gamepad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadState {
+    id: GamepadId,
+    name: String,
+    pressed: HashSet<Button>,
+    buttons: HashMap<Button, f32>,
+    axes: HashMap<Axis, f32>,
+}
+
+impl GamepadState {
+    #[inline]
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// How far `button` is pressed from 0 to 1, for analog triggers.
+    #[inline]
+    pub fn button_value(&self, button: Button) -> f32 {
+        self.buttons.get(&button).copied().unwrap_or(0.0)
+    }
+
+    /// The value of `axis` from -1 to 1, zero inside the dead zone.
+    #[inline]
+    pub fn axis(&self, axis: Axis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// `(x, y)` of the left stick, with `y` positive upwards.
+    #[inline]
+    pub fn left_stick(&self) -> (f32, f32) {
+        (self.axis(Axis::LeftStickX), self.axis(Axis::LeftStickY))
+    }
+
+    /// `(x, y)` of the right stick, with `y` positive upwards.
+    #[inline]
+    pub fn right_stick(&self) -> (f32, f32) {
+        (self.axis(Axis::RightStickX), self.axis(Axis::RightStickY))
+    }
+
+    fn new(gamepad: gilrs::Gamepad<'_>) -> Self {
+        Self {
+            id: gamepad.id(),
+            name: gamepad.name().to_owned(),
+            pressed: BUTTONS
+                .into_iter()
+                .filter(|&button| gamepad.is_pressed(button))
+                .collect(),
+            buttons: BUTTONS
+                .into_iter()
+                .filter_map(|button| Some((button, gamepad.button_data(button)?.value())))
+                .collect(),
+            axes: AXES
+                .into_iter()
+                .map(|axis| (axis, gamepad.value(axis)))
+                .collect(),
+        }
+    }
+}
+
+/// The connected gamepads, in the order they were connected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gamepads {
+    gamepads: Vec<GamepadState>,
+}
+
+impl Gamepads {
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &GamepadState> {
+        self.gamepads.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.gamepads.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.iter().find(|gamepad| gamepad.id == id)
+    }
+
+    /// The first connected gamepad, for worlds driven by a single player.
+    #[inline]
+    pub fn first(&self) -> Option<&GamepadState> {
+        self.gamepads.first()
+    }
+}
+
+/// Owns the gilrs context of the app.
+#[derive(Debug)]
+pub(crate) struct GamepadPoller {
+    gilrs: gilrs::Gilrs,
+}
+
+impl GamepadPoller {
+    /// Logs a warning and returns `None` when gamepads are unavailable, as
+    /// the app works without them.
+    pub(crate) fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs }),
+            Err(err) => {
+                log::warn!("Gamepads are unavailable: {err}");
+                None
+            }
+        }
+    }
+
+    /// Drains the pending events and returns them with the resulting state.
+    pub(crate) fn poll(&mut self) -> (Vec<GamepadEvent>, Gamepads) {
+        use gilrs::EventType;
+
+        let mut events = Vec::new();
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            events.extend(match event {
+                EventType::Connected => Some(GamepadEvent::Connected(id)),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected(id)),
+                EventType::ButtonPressed(button, _) => {
+                    Some(GamepadEvent::ButtonPressed(id, button))
+                }
+                EventType::ButtonReleased(button, _) => {
+                    Some(GamepadEvent::ButtonReleased(id, button))
+                }
+                _ => None,
+            });
+        }
+        let gamepads = Gamepads {
+            gamepads: self
+                .gilrs
+                .gamepads()
+                .map(|(_, gamepad)| GamepadState::new(gamepad))
+                .collect(),
+        };
+        (events, gamepads)
+    }
+}
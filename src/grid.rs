@@ -0,0 +1,364 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// How coordinates outside a [`Grid`] are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    /// Opposite edges are connected, making the grid a torus.
+    #[default]
+    Wrap,
+    /// Coordinates are clamped to the nearest edge cell.
+    Clamp,
+    /// Everything outside the grid is empty: out-of-range lookups return
+    /// `None` and neighbor iterators skip those cells.
+    Dead,
+}
+
+/// Shape of the cells considered neighbors by [`Grid::neighbors_radius`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighborhood {
+    /// The square of cells within the radius along both axes.
+    #[default]
+    Moore,
+    /// The diamond of cells within the radius in Manhattan distance.
+    VonNeumann,
+}
+
+/// Row-major 2D storage with a back buffer for generation-based updates.
+///
+/// With the `serde` feature on, grids serialize their size, boundary and
+/// current cells, but not the previous generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "GridData<T>")
+)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    cells: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    back: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GridData<T> {
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    cells: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<GridData<T>> for Grid<T> {
+    type Error = anyhow::Error;
+
+    fn try_from(data: GridData<T>) -> Result<Self, Self::Error> {
+        anyhow::ensure!(data.width > 0 && data.height > 0, "empty grid");
+        anyhow::ensure!(
+            data.cells.len() == data.width as usize * data.height as usize,
+            "expected {} cells for a {}x{} grid, found {}",
+            data.width as usize * data.height as usize,
+            data.width,
+            data.height,
+            data.cells.len()
+        );
+        Ok(Self {
+            width: data.width,
+            height: data.height,
+            boundary: data.boundary,
+            cells: data.cells,
+            back: Vec::new(),
+        })
+    }
+}
+
+impl<T: Clone + Default> Grid<T> {
+    #[inline]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::filled(width, height, T::default())
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: u32, height: u32, value: T) -> Self {
+        assert!(width > 0 && height > 0);
+        Self {
+            width,
+            height,
+            boundary: Boundary::default(),
+            cells: vec![value; width as usize * height as usize],
+            back: Vec::new(),
+        }
+    }
+
+    pub fn from_fn<F>(width: u32, height: u32, mut f: F) -> Self
+    where
+        F: FnMut(u32, u32) -> T,
+    {
+        assert!(width > 0 && height > 0);
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            boundary: Boundary::default(),
+            cells,
+            back: Vec::new(),
+        }
+    }
+
+    /// Computes the next generation of every cell from the current one, then
+    /// makes it current. `f` sees the grid as it was before the step.
+    pub fn step_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Self, u32, u32) -> T,
+    {
+        let mut back = std::mem::take(&mut self.back);
+        if back.len() != self.cells.len() {
+            back = self.cells.clone();
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                back[self.index(x, y)] = f(self, x, y);
+            }
+        }
+        self.back = std::mem::replace(&mut self.cells, back);
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Clone + Send + Sync> Grid<T> {
+    /// Parallel [`Grid::step_with`], computing bands of rows on the rayon
+    /// thread pool.
+    pub fn par_step<F>(&mut self, f: F)
+    where
+        F: Fn(&Self, u32, u32) -> T + Sync,
+    {
+        use rayon::prelude::*;
+
+        const BAND_ROWS: usize = 16;
+
+        let mut back = std::mem::take(&mut self.back);
+        if back.len() != self.cells.len() {
+            back = self.cells.clone();
+        }
+        let width = self.width as usize;
+        let this = &*self;
+        back.par_chunks_mut(width * BAND_ROWS)
+            .enumerate()
+            .for_each(|(band, cells)| {
+                for (i, cell) in cells.iter_mut().enumerate() {
+                    let x = (i % width) as u32;
+                    let y = (band * BAND_ROWS + i / width) as u32;
+                    *cell = f(this, x, y);
+                }
+            });
+        self.back = std::mem::replace(&mut self.cells, back);
+    }
+}
+
+impl<T: Clone + Eq + Hash> Grid<T> {
+    /// Number of cells of each value, e.g. of each state of a multi-state
+    /// automaton.
+    pub fn histogram(&self) -> HashMap<T, usize> {
+        let mut histogram = HashMap::new();
+        for cell in &self.cells {
+            *histogram.entry(cell.clone()).or_default() += 1;
+        }
+        histogram
+    }
+}
+
+impl<T: PartialEq> Grid<T> {
+    /// Cells that changed in the last [`Grid::step_with`], e.g. to update only
+    /// those pixels of the image.
+    pub fn changes(&self) -> impl Iterator<Item = (u32, u32, &T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .zip(&self.back)
+            .enumerate()
+            .filter(|(_, (cell, prev))| cell != prev)
+            .map(move |(i, (cell, _))| (i as u32 % width, i as u32 / width, cell))
+    }
+}
+
+impl<T> Grid<T> {
+    #[inline]
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self { boundary, ..self }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[inline]
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    #[inline]
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    #[inline]
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> Option<&T> {
+        self.contains(x, y).then(|| &self.cells[self.index(x, y)])
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
+        let i = self.index(x, y);
+        self.contains(x, y).then(|| &mut self.cells[i])
+    }
+
+    /// Does nothing outside the grid.
+    #[inline]
+    pub fn set(&mut self, x: u32, y: u32, value: T) {
+        if let Some(cell) = self.get_mut(x, y) {
+            *cell = value;
+        }
+    }
+
+    /// Resolves possibly out-of-range coordinates according to the boundary.
+    pub fn resolve(&self, x: i64, y: i64) -> Option<(u32, u32)> {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let (x, y) = match self.boundary {
+            Boundary::Wrap => (x.rem_euclid(w), y.rem_euclid(h)),
+            Boundary::Clamp => (x.clamp(0, w - 1), y.clamp(0, h - 1)),
+            Boundary::Dead if (0..w).contains(&x) && (0..h).contains(&y) => (x, y),
+            Boundary::Dead => return None,
+        };
+        Some((x as u32, y as u32))
+    }
+
+    /// Like [`Grid::get`], but resolving coordinates with the boundary.
+    #[inline]
+    pub fn get_resolved(&self, x: i64, y: i64) -> Option<&T> {
+        let (x, y) = self.resolve(x, y)?;
+        self.get(x, y)
+    }
+
+    /// Iterates over all cells with their coordinates, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, &T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (i as u32 % width, i as u32 / width, cell))
+    }
+
+    /// Iterates over the cells of the rectangle with its top-left corner at
+    /// `(x, y)` with their coordinates, row by row. The rectangle is clipped
+    /// to the grid whatever the boundary.
+    ///
+    /// ```
+    /// # use cells_renderer::grid::Grid;
+    /// let grid = Grid::from_fn(8, 8, |x, y| x + y);
+    /// let sum: u32 = grid.region(2, 2, 3, 3).map(|(_, _, &v)| v).sum();
+    /// assert_eq!(sum, 54);
+    /// ```
+    pub fn region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> impl Iterator<Item = (u32, u32, &T)> + '_ {
+        let x1 = x.saturating_add(width).min(self.width);
+        let y1 = y.saturating_add(height).min(self.height);
+        (y..y1).flat_map(move |y| (x..x1).map(move |x| (x, y, &self.cells[self.index(x, y)])))
+    }
+
+    /// Number of cells for which `predicate` holds, e.g. the population.
+    /// Count a rectangle with [`Grid::region`].
+    pub fn count(&self, mut predicate: impl FnMut(&T) -> bool) -> usize {
+        self.cells.iter().filter(|&cell| predicate(cell)).count()
+    }
+
+    /// The eight cells surrounding `(x, y)`.
+    pub fn neighbors_moore(&self, x: u32, y: u32) -> impl Iterator<Item = &T> + '_ {
+        const OFFSETS: [(i64, i64); 8] = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ];
+        self.offset_cells(x, y, OFFSETS)
+    }
+
+    /// The four cells orthogonally adjacent to `(x, y)`.
+    pub fn neighbors_von_neumann(&self, x: u32, y: u32) -> impl Iterator<Item = &T> + '_ {
+        const OFFSETS: [(i64, i64); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+        self.offset_cells(x, y, OFFSETS)
+    }
+
+    /// Cells within `radius` of `(x, y)`, excluding the cell itself, e.g. for
+    /// Larger than Life rules.
+    pub fn neighbors_radius(
+        &self,
+        x: u32,
+        y: u32,
+        radius: u32,
+        neighborhood: Neighborhood,
+    ) -> impl Iterator<Item = &T> + '_ {
+        let r = radius as i64;
+        let offsets = (-r..=r)
+            .flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(move |&(dx, dy)| match neighborhood {
+                Neighborhood::Moore => true,
+                Neighborhood::VonNeumann => dx.abs() + dy.abs() <= r,
+            })
+            .filter(|&offset| offset != (0, 0));
+        self.offset_cells(x, y, offsets)
+    }
+
+    fn offset_cells<I>(&self, x: u32, y: u32, offsets: I) -> impl Iterator<Item = &T> + '_
+    where
+        I: IntoIterator<Item = (i64, i64)>,
+        I::IntoIter: 'static,
+    {
+        offsets
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.get_resolved(x as i64 + dx, y as i64 + dy))
+    }
+
+    #[inline]
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        x as usize + y as usize * self.width as usize
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{UpdateCtx, World, WorldImage, checkpoint::Checkpoints};
+use anyhow::Context as _;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::Write as _,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Configuration of an unattended headless run.
+#[derive(Debug, Clone)]
+pub struct SoakConfigs {
+    pub dir: PathBuf,
+    pub dt: Duration,
+    pub checkpoint_interval: u64,
+    pub max_checkpoints: usize,
+    pub stats_interval: Duration,
+    pub max_ticks: Option<u64>,
+    pub max_duration: Option<Duration>,
+    pub resume: bool,
+}
+
+impl Default for SoakConfigs {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("soak"),
+            dt: Duration::from_secs(1) / 60,
+            checkpoint_interval: 10_000,
+            max_checkpoints: 5,
+            stats_interval: Duration::from_secs(60),
+            max_ticks: None,
+            max_duration: None,
+            resume: true,
+        }
+    }
+}
+
+impl SoakConfigs {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn dir(self, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ..self
+        }
+    }
+
+    /// Simulated time per tick reported in [`UpdateCtx::dt`].
+    #[inline]
+    pub fn dt(self, dt: Duration) -> Self {
+        Self { dt, ..self }
+    }
+
+    /// Number of ticks between checkpoints. Zero disables periodic checkpoints.
+    #[inline]
+    pub fn checkpoint_interval(self, checkpoint_interval: u64) -> Self {
+        Self {
+            checkpoint_interval,
+            ..self
+        }
+    }
+
+    /// Number of checkpoints kept on disk. Zero keeps every checkpoint.
+    #[inline]
+    pub fn max_checkpoints(self, max_checkpoints: usize) -> Self {
+        Self {
+            max_checkpoints,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn stats_interval(self, stats_interval: Duration) -> Self {
+        Self {
+            stats_interval,
+            ..self
+        }
+    }
+
+    /// Total number of ticks to reach, counting ticks restored from a checkpoint.
+    #[inline]
+    pub fn max_ticks(self, max_ticks: Option<u64>) -> Self {
+        Self { max_ticks, ..self }
+    }
+
+    /// Wall-clock limit of this run.
+    #[inline]
+    pub fn max_duration(self, max_duration: Option<Duration>) -> Self {
+        Self {
+            max_duration,
+            ..self
+        }
+    }
+
+    /// Whether to continue from the latest checkpoint in `dir`.
+    #[inline]
+    pub fn resume(self, resume: bool) -> Self {
+        Self { resume, ..self }
+    }
+}
+
+/// Summary of a finished headless run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakReport {
+    pub resumed_from: Option<u64>,
+    pub ticks: u64,
+    pub elapsed: Duration,
+}
+
+/// Runs a world without a window as fast as possible, writing periodic
+/// checkpoints and `stats.csv` to a directory.
+///
+/// [`Soak::run`] fails right away if [`World::save_state`] returns `None`.
+#[derive(Debug)]
+pub struct Soak<W> {
+    configs: SoakConfigs,
+    world: W,
+}
+
+impl<W: World> Soak<W> {
+    #[inline]
+    pub fn new(configs: SoakConfigs, world: W) -> Self {
+        Self { configs, world }
+    }
+
+    pub fn run(mut self) -> anyhow::Result<SoakReport> {
+        let checkpoints = Checkpoints::new(&self.configs.dir, self.configs.max_checkpoints)?;
+        let mut image = self.world.init_image();
+
+        let mut tick = 0;
+        let mut resumed_from = None;
+        if self.configs.resume
+            && let Some((_, path)) = checkpoints.latest()?
+        {
+            tick = Checkpoints::load(&path, &mut self.world, &mut image)?;
+            resumed_from = Some(tick);
+        }
+        // Fail now rather than at the first checkpoint, possibly hours in.
+        self.world
+            .save_state()
+            .context("this world does not support saving states")?;
+
+        let mut stats = StatsWriter::open(self.configs.dir.join("stats.csv"))?;
+
+        let start = Instant::now();
+        let mut last_checkpoint = tick;
+        let mut last_stats = (start, tick);
+
+        while !self.is_finished(tick, start.elapsed()) {
+            self.step(tick, &mut image);
+            tick += 1;
+
+            if self.configs.checkpoint_interval > 0 && tick % self.configs.checkpoint_interval == 0
+            {
+                checkpoints.save(tick, &self.world)?;
+                last_checkpoint = tick;
+            }
+
+            let now = Instant::now();
+            if now - last_stats.0 >= self.configs.stats_interval {
+                stats.write(tick, now - start, &last_stats)?;
+                last_stats = (now, tick);
+            }
+        }
+
+        if tick != last_checkpoint {
+            checkpoints.save(tick, &self.world)?;
+        }
+        let elapsed = start.elapsed();
+        stats.write(tick, elapsed, &last_stats)?;
+
+        Ok(SoakReport {
+            resumed_from,
+            ticks: tick,
+            elapsed,
+        })
+    }
+
+    fn step(&mut self, tick: u64, image: &mut WorldImage) {
+        step(&mut self.world, tick, self.configs.dt, image);
+    }
+
+    fn is_finished(&self, tick: u64, elapsed: Duration) -> bool {
+        self.configs.max_ticks.is_some_and(|max| tick >= max)
+            || self.configs.max_duration.is_some_and(|max| elapsed >= max)
+    }
+}
+
+/// Runs update `tick` with a fixed time step.
+fn step<W: World>(world: &mut W, tick: u64, dt: Duration, image: &mut WorldImage) {
+    span!("update", tick);
+    let ctx = UpdateCtx {
+        tick,
+        elapsed: Duration::from_secs_f64(dt.as_secs_f64() * tick as f64),
+        dt,
+        ..Default::default()
+    };
+    world.update_with_ctx(&ctx, image);
+}
+
+struct StatsWriter {
+    file: File,
+}
+
+impl StatsWriter {
+    fn open(path: PathBuf) -> anyhow::Result<Self> {
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        if is_new {
+            writeln!(file, "tick,elapsed_secs,ups")?;
+        }
+        Ok(Self { file })
+    }
+
+    fn write(
+        &mut self,
+        tick: u64,
+        elapsed: Duration,
+        (last_time, last_tick): &(Instant, u64),
+    ) -> anyhow::Result<()> {
+        let window = last_time.elapsed().as_secs_f64();
+        let ups = if window > 0.0 {
+            (tick - last_tick) as f64 / window
+        } else {
+            0.0
+        };
+        writeln!(self.file, "{tick},{:.3},{ups:.2}", elapsed.as_secs_f64())?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Distribution of the durations of one kind of step in a [`Bench`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StepStats {
+    pub mean: Duration,
+    pub min: Duration,
+    pub median: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl StepStats {
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Self {
+            mean: samples.iter().sum::<Duration>() / samples.len() as u32,
+            min: samples[0],
+            median: percentile(50),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+impl fmt::Display for StepStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean {:?}, min {:?}, median {:?}, p99 {:?}, max {:?}",
+            self.mean, self.min, self.median, self.p99, self.max
+        )
+    }
+}
+
+/// Results of a [`Bench`], printable as a short summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// Generations measured, not counting the warm-up.
+    pub generations: u64,
+    /// Wall-clock time of the measured generations.
+    pub elapsed: Duration,
+    pub update: StepStats,
+    /// Time spent copying the image out, if simulated.
+    pub upload: Option<StepStats>,
+}
+
+impl BenchReport {
+    #[inline]
+    pub fn generations_per_second(&self) -> f64 {
+        self.generations as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} generations in {:?} ({:.1} generations/s)",
+            self.generations,
+            self.elapsed,
+            self.generations_per_second()
+        )?;
+        write!(f, "update: {}", self.update)?;
+        if let Some(upload) = &self.upload {
+            write!(f, "\nupload: {upload}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures how fast a world updates without a window, for comparing rule
+/// implementations.
+///
+/// Every generation is timed on its own, so memory grows by 16 bytes per
+/// generation, or 32 with [`Bench::with_upload`].
+#[derive(Debug)]
+pub struct Bench<W> {
+    world: W,
+    generations: u64,
+    warmup: u64,
+    upload: bool,
+    dt: Duration,
+}
+
+impl<W: World> Bench<W> {
+    #[inline]
+    pub fn new(world: W, generations: u64) -> Self {
+        Self {
+            world,
+            generations,
+            warmup: 0,
+            upload: false,
+            dt: Duration::from_secs(1) / 60,
+        }
+    }
+
+    /// Generations run before measuring, e.g. to fill caches. Defaults to 0.
+    #[inline]
+    pub fn with_warmup(self, warmup: u64) -> Self {
+        Self { warmup, ..self }
+    }
+
+    /// Copies the image bytes after every generation, approximating the
+    /// CPU side of the texture upload the app would do. Defaults to `false`.
+    #[inline]
+    pub fn with_upload(self, upload: bool) -> Self {
+        Self { upload, ..self }
+    }
+
+    /// Simulated time advanced by each update. Defaults to 1/60 s.
+    #[inline]
+    pub fn with_dt(self, dt: Duration) -> Self {
+        Self { dt, ..self }
+    }
+
+    pub fn run(mut self) -> BenchReport {
+        let mut image = self.world.init_image();
+        let mut staging = Vec::new();
+        let mut updates = Vec::with_capacity(self.generations as usize);
+        let mut uploads = Vec::with_capacity(if self.upload {
+            self.generations as usize
+        } else {
+            0
+        });
+
+        for tick in 0..self.warmup {
+            self.step(tick, &mut image);
+        }
+
+        let start = Instant::now();
+        for tick in self.warmup..self.warmup + self.generations {
+            let step_start = Instant::now();
+            self.step(tick, &mut image);
+            updates.push(step_start.elapsed());
+
+            if self.upload {
+                let upload_start = Instant::now();
+                staging.clear();
+                staging.extend_from_slice(image.buf());
+                std::hint::black_box(&staging);
+                uploads.push(upload_start.elapsed());
+            }
+        }
+        let elapsed = start.elapsed();
+
+        let report = BenchReport {
+            generations: self.generations,
+            elapsed,
+            update: StepStats::from_samples(&mut updates),
+            upload: self.upload.then(|| StepStats::from_samples(&mut uploads)),
+        };
+        log::info!("{report}");
+        report
+    }
+
+    fn step(&mut self, tick: u64, image: &mut WorldImage) {
+        step(&mut self.world, tick, self.dt, image);
+    }
+}
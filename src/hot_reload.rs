@@ -0,0 +1,377 @@
+//! Reloading a [`World`] implementation from a dynamic library while the app
+//! is running.
+//!
+//! Build the world as a `cdylib` that calls
+//! [`export_world!`](crate::export_world), then run it through a
+//! [`HotReloadWorld`] pointing at the built library. Rebuilding the library
+//! swaps the new code in, carrying the state over through
+//! [`World::save_state`] and [`World::load_state`].
+//!
+//! This is a development tool: the library must be built by the same
+//! compiler and against the same version of this crate as the app.
+
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, InputFocus, Lod, MouseEvent, Overlay, UpdateCtx,
+    VectorField, World, WorldImage, WorldTransform,
+    winit::{KeyEvent, MouseButton},
+};
+use anyhow::Context as _;
+use libloading::Library;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+#[doc(hidden)]
+pub const CREATE_SYMBOL: &[u8] = b"cells_renderer_create_world";
+
+/// Exports a world constructor from a `cdylib` for [`HotReloadWorld`].
+///
+/// ```ignore
+/// cells_renderer::export_world!(|| MyWorld::new(64, 64));
+/// ```
+#[macro_export]
+macro_rules! export_world {
+    ($create:expr) => {
+        #[unsafe(no_mangle)]
+        pub fn cells_renderer_create_world() -> ::std::boxed::Box<dyn $crate::World> {
+            ::std::boxed::Box::new(($create)())
+        }
+    };
+}
+
+type CreateWorld = fn() -> Box<dyn World>;
+
+/// A world loaded from a dynamic library, reloaded whenever the library
+/// file changes.
+pub struct HotReloadWorld {
+    // Declared before `library` so that it is dropped first.
+    world: Box<dyn World>,
+    library: LibraryCopy,
+
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    generation: u32,
+    poll_interval: Duration,
+    last_poll: Instant,
+
+    handle: Option<AppHandle>,
+    transform: Option<WorldTransform>,
+    lod: Option<Lod>,
+}
+
+impl HotReloadWorld {
+    pub fn new(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let modified = modified(&path);
+        let (library, world) = load(&path, 0)?;
+        Ok(Self {
+            world,
+            library,
+            path,
+            modified,
+            generation: 0,
+            poll_interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+            handle: None,
+            transform: None,
+            lod: None,
+        })
+    }
+
+    /// How often the library file is checked for changes. Defaults to 500 ms.
+    #[inline]
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of successful reloads so far.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    fn poll(&mut self, image: &mut WorldImage) {
+        if self.last_poll.elapsed() < self.poll_interval {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        match self.reload(image) {
+            Ok(()) => log::info!("reloaded {}", self.path.display()),
+            Err(err) => log::error!("failed to reload {}: {err:#}", self.path.display()),
+        }
+    }
+
+    fn reload(&mut self, image: &mut WorldImage) -> anyhow::Result<()> {
+        let state = self.world.save_state();
+        let (library, mut world) = load(&self.path, self.generation + 1)?;
+
+        let mut new_image = world.init_image();
+        if let Some(handle) = &self.handle {
+            world.attach(handle.clone());
+        }
+        if let Some(state) = &state
+            && let Err(err) = world.load_state(state, &mut new_image)
+        {
+            log::warn!("state was not carried over: {err:#}");
+        }
+        world.on_start(&mut new_image);
+        if let Some(transform) = &self.transform {
+            world.transform_changed(transform);
+        }
+        if let Some(lod) = &self.lod {
+            world.lod_changed(lod, &mut new_image);
+        }
+
+        // The old world must go before the library holding its code, whose
+        // copy is deleted with it.
+        self.world = world;
+        self.library = library;
+        self.generation += 1;
+        *image = new_image;
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|meta| meta.modified()).ok()
+}
+
+/// A temporary copy of the library, deleted once it is unloaded.
+struct LibraryCopy {
+    library: Option<Library>,
+    path: PathBuf,
+}
+
+impl Drop for LibraryCopy {
+    fn drop(&mut self) {
+        // Windows refuses to delete a library that is still loaded.
+        drop(self.library.take());
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            log::warn!("failed to delete {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// Loads a copy of the library, so that the original can be rebuilt while the
+/// copy is in use.
+fn load(path: &Path, generation: u32) -> anyhow::Result<(LibraryCopy, Box<dyn World>)> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file", path.display()))?;
+    let copy_path = std::env::temp_dir().join(format!(
+        "{}-{}-{generation}",
+        std::process::id(),
+        file_name.to_string_lossy()
+    ));
+    std::fs::copy(path, &copy_path)
+        .with_context(|| format!("failed to copy {}", path.display()))?;
+    // Deletes the copy again if loading fails.
+    let mut copy = LibraryCopy {
+        library: None,
+        path: copy_path,
+    };
+
+    // SAFETY: The library is trusted development code exporting
+    // `CREATE_SYMBOL` through `export_world!`.
+    unsafe {
+        let library = Library::new(&copy.path)
+            .with_context(|| format!("failed to load {}", path.display()))?;
+        let create = *library
+            .get::<CreateWorld>(CREATE_SYMBOL)
+            .with_context(|| format!("{} does not use export_world!", path.display()))?;
+        let world = create();
+        copy.library = Some(library);
+        Ok((copy, world))
+    }
+}
+
+impl World for HotReloadWorld {
+    #[inline]
+    fn init_image(&mut self) -> WorldImage {
+        self.world.init_image()
+    }
+
+    #[inline]
+    fn update(&mut self, image: &mut WorldImage) {
+        self.poll(image);
+        self.world.update(image);
+    }
+
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        self.poll(image);
+        self.world.update_with_ctx(ctx, image);
+    }
+
+    #[inline]
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        self.poll(image);
+        self.world.keyboard_input(event, image);
+    }
+
+    #[inline]
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        self.world.mouse_input(event, image);
+    }
+
+    #[inline]
+    fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        self.world.cursor_moved(pos, image);
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        self.world.cursor_moved_precise(pos, image);
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        self.world.cursor_drag(from, to, button, image);
+    }
+
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        self.handle = Some(handle.clone());
+        self.world.attach(handle);
+    }
+
+    #[inline]
+    fn on_start(&mut self, image: &mut WorldImage) {
+        self.world.on_start(image);
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        self.world.on_pause();
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        self.world.on_resume();
+    }
+
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        self.world.on_close_requested()
+    }
+
+    #[inline]
+    fn on_exit(&mut self) {
+        self.world.on_exit();
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        self.world.text_input(text, image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        self.world.gamepad_input(event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        self.world.focus_changed(focus);
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        self.transform = Some(*transform);
+        self.world.transform_changed(transform);
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        self.lod = Some(lod.clone());
+        self.world.lod_changed(lod, image);
+    }
+
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        self.world.cell_actions(x, y)
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        self.world.cell_action(x, y, action, image);
+    }
+
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world.command(command, image)
+    }
+
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        self.world.hud_lines()
+    }
+
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        self.world.inspect(x, y)
+    }
+
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        self.world.hover_cells(x, y)
+    }
+
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        self.world.draw_overlay(overlay);
+    }
+
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        self.world.draw_agents(agents);
+    }
+
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        self.world.draw_vector_field(field);
+    }
+
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        self.world.poll_image(image)
+    }
+
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        self.world.requested_resize()
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        self.world.save_state()
+    }
+
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world.load_state(data, image)
+    }
+}
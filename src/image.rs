@@ -1,31 +1,225 @@
-/// RGBA framebuffer.
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::{Color, Colormap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod heatmap;
+mod sprite;
+mod stats;
+mod text;
+mod view;
+pub use heatmap::Heatmap;
+pub use sprite::{BlendMode, Orientation, Sprite};
+pub use stats::ValueStats;
+#[cfg(feature = "ttf")]
+pub use text::TtfFont;
+pub use view::{ImageView, ImageViewMut};
+
+/// Layout of the pixels of a [`WorldImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// Four bytes per pixel, see [`Color`].
+    #[default]
+    Rgba8,
+    /// One byte per pixel, indexing the image's 256-color palette. Colors are
+    /// looked up on the GPU, so only a quarter of the bytes are uploaded and
+    /// changing the palette recolors the image instantly.
+    Indexed8,
+    /// One `f32` per pixel, e.g. a concentration or temperature. Values are
+    /// mapped from the image's value range onto the palette on the GPU, so
+    /// continuous simulations can upload their state without quantizing it.
+    R32Float,
+}
+
+impl ImageFormat {
+    #[inline]
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba8 => 4,
+            Self::Indexed8 => 1,
+            Self::R32Float => 4,
+        }
+    }
+
+    pub(crate) fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            Self::Rgba8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+            Self::Indexed8 => wgpu::TextureFormat::R8Unorm,
+            Self::R32Float => wgpu::TextureFormat::R32Float,
+        }
+    }
+}
+
+/// How the bytes of an RGBA [`WorldImage`] map to displayed colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    /// sRGB-encoded colors, as in image files and color pickers, shown as
+    /// they are. Filtering blends them in linear light.
+    #[default]
+    Srgb,
+    /// Values proportional to light intensity, e.g. raw simulation
+    /// quantities, encoded to sRGB for display, which brightens dark values.
+    /// Shown as they are on displays that can only be drawn to without sRGB
+    /// encoding.
+    Linear,
+}
+
+/// Framebuffer of the world, RGBA unless created with another
+/// [`ImageFormat`].
+///
+/// RGBA pixels can be accessed as bytes, as [`Color`]s or as packed `u32`s.
+/// The color methods panic on images of other formats.
+///
+/// An image can carry RGBA layers, drawn over it in order with alpha
+/// blending, so that e.g. agents or annotations need not be baked into the
+/// terrain below them, and a [`Heatmap`] drawn over them.
+///
+/// With the `serde` feature on, images serialize their size, format, raw
+/// bytes as returned by [`WorldImage::buf`], palette, value range, color
+/// space, layers and heatmap.
+///
+/// Every image tracks an [`ImageVersion`] that changes with each mutable
+/// access, so the app only uploads images that may have changed. Images
+/// changed only through [`WorldImage::write_rect`] are uploaded in part.
+#[derive(Debug)]
 pub struct WorldImage {
     width: u32,
     height: u32,
-    buf: Vec<u8>,
+    format: ImageFormat,
+    // Stored as words for aligned `u32` and `f32` views.
+    pixels: Vec<u32>,
+    palette: Vec<Color>,
+    value_range: (f32, f32),
+    color_space: ColorSpace,
+    layers: Vec<WorldImage>,
+    heatmap: Option<Box<Heatmap>>,
+    version: ImageVersion,
+    written: Option<WrittenRects>,
+}
+
+/// Identifies one state of a [`WorldImage`], see [`WorldImage::version`].
+///
+/// Versions are unique per image: clones and new images never share a
+/// version with another image, even when their pixels are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageVersion {
+    image: u64,
+    count: u64,
+}
+
+impl ImageVersion {
+    fn new() -> Self {
+        static NEXT_IMAGE: AtomicU64 = AtomicU64::new(0);
+        Self {
+            image: NEXT_IMAGE.fetch_add(1, Ordering::Relaxed),
+            count: 0,
+        }
+    }
+}
+
+/// Rectangle of pixels of a [`WorldImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The rectangles written with [`WorldImage::write_rect`] while the image
+/// went from version `since` to `until`, with no other change in between.
+#[derive(Debug)]
+struct WrittenRects {
+    since: ImageVersion,
+    until: ImageVersion,
+    rects: Vec<PixelRect>,
+    area: u64,
+}
+
+impl Clone for WorldImage {
+    fn clone(&self) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            pixels: self.pixels.clone(),
+            palette: self.palette.clone(),
+            value_range: self.value_range,
+            color_space: self.color_space,
+            layers: self.layers.clone(),
+            heatmap: self.heatmap.clone(),
+            version: ImageVersion::new(),
+            written: None,
+        }
+    }
+}
+
+/// Compares the contents, ignoring the [`ImageVersion`].
+impl PartialEq for WorldImage {
+    fn eq(&self, other: &Self) -> bool {
+        (self.width, self.height, self.format) == (other.width, other.height, other.format)
+            && self.pixels == other.pixels
+            && self.palette == other.palette
+            && self.value_range == other.value_range
+            && self.color_space == other.color_space
+            && self.layers == other.layers
+            && self.heatmap == other.heatmap
+    }
 }
 
 impl WorldImage {
-    const CHANNELS: usize = 4;
+    pub const PALETTE_LEN: usize = 256;
 
     #[inline]
     pub fn new(width: u32, height: u32) -> Self {
+        Self::with_format(width, height, ImageFormat::Rgba8)
+    }
+
+    /// An image of `format` with every byte zero. Indexed and float images
+    /// start with a grayscale palette, and float images with a value range
+    /// of `0.0..=1.0`.
+    pub fn with_format(width: u32, height: u32, format: ImageFormat) -> Self {
         assert!(width > 0 && height > 0);
 
+        let len = width as usize * height as usize * format.bytes_per_pixel();
+        let palette = match format {
+            ImageFormat::Indexed8 | ImageFormat::R32Float => (0..Self::PALETTE_LEN)
+                .map(|i| Color::rgb(i as u8, i as u8, i as u8))
+                .collect(),
+            _ => Vec::new(),
+        };
         Self {
             width,
             height,
-            buf: vec![0; width as usize * height as usize * Self::CHANNELS],
+            format,
+            pixels: vec![0; len.div_ceil(4)],
+            palette,
+            value_range: (0.0, 1.0),
+            color_space: ColorSpace::default(),
+            layers: Vec::new(),
+            heatmap: None,
+            version: ImageVersion::new(),
+            written: None,
         }
     }
 
+    /// An [`ImageFormat::Indexed8`] image.
+    #[inline]
+    pub fn new_indexed(width: u32, height: u32) -> Self {
+        Self::with_format(width, height, ImageFormat::Indexed8)
+    }
+
+    /// An [`ImageFormat::R32Float`] image.
     #[inline]
-    pub fn filled(width: u32, height: u32, color: [u8; 4]) -> Self {
+    pub fn new_float(width: u32, height: u32) -> Self {
+        Self::with_format(width, height, ImageFormat::R32Float)
+    }
+
+    #[inline]
+    pub fn filled(width: u32, height: u32, color: impl Into<Color>) -> Self {
         let mut this = Self::new(width, height);
-        for pixel in this.buf.chunks_exact_mut(4) {
-            pixel.copy_from_slice(&color);
-        }
+        this.fill(color);
         this
     }
 
@@ -39,31 +233,557 @@ impl WorldImage {
         self.height
     }
 
+    #[inline]
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Changes whenever the image is borrowed mutably, whether or not the
+    /// borrow actually changes anything, so an unchanged version means
+    /// unchanged contents.
+    #[inline]
+    pub fn version(&self) -> ImageVersion {
+        self.version
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        self.version.count = self.version.count.wrapping_add(1);
+    }
+
+    /// Raw bytes of all pixels, row by row.
     #[inline]
     pub fn buf(&self) -> &[u8] {
-        &self.buf
+        let len = self.byte_len();
+        &bytemuck::cast_slice(&self.pixels)[..len]
     }
 
     #[inline]
     pub fn buf_mut(&mut self) -> &mut [u8] {
-        &mut self.buf
+        self.touch();
+        let len = self.byte_len();
+        &mut bytemuck::cast_slice_mut(&mut self.pixels)[..len]
+    }
+
+    /// Row-major pixels.
+    #[inline]
+    pub fn pixels(&self) -> &[Color] {
+        bytemuck::cast_slice(self.packed())
+    }
+
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        bytemuck::cast_slice_mut(self.packed_mut())
+    }
+
+    /// Row-major pixels packed into native-endian `u32`s, see
+    /// [`Color::to_u32`].
+    #[inline]
+    pub fn packed(&self) -> &[u32] {
+        self.assert_format(ImageFormat::Rgba8);
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn packed_mut(&mut self) -> &mut [u32] {
+        self.assert_format(ImageFormat::Rgba8);
+        self.touch();
+        &mut self.pixels
     }
 
+    /// Bytes of one pixel, in any format.
     #[inline]
     pub fn get(&self, x: u32, y: u32) -> Option<&[u8]> {
-        self.calc_offset(x, y)
-            .map(|i| &self.buf[i..i + Self::CHANNELS])
+        let bpp = self.format.bytes_per_pixel();
+        self.calc_index(x, y)
+            .map(|i| &self.buf()[i * bpp..(i + 1) * bpp])
     }
 
     #[inline]
     pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
-        self.calc_offset(x, y)
-            .map(|i| &mut self.buf[i..i + Self::CHANNELS])
+        let bpp = self.format.bytes_per_pixel();
+        self.calc_index(x, y)
+            .map(|i| &mut self.buf_mut()[i * bpp..(i + 1) * bpp])
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<&Color> {
+        self.calc_index(x, y).map(|i| &self.pixels()[i])
+    }
+
+    #[inline]
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut Color> {
+        self.calc_index(x, y).map(|i| &mut self.pixels_mut()[i])
+    }
+
+    /// Does nothing outside the image.
+    #[inline]
+    pub fn set(&mut self, x: u32, y: u32, color: impl Into<Color>) {
+        if let Some(i) = self.calc_index(x, y) {
+            self.packed_mut()[i] = color.into().to_u32();
+        }
+    }
+
+    #[inline]
+    pub fn fill(&mut self, color: impl Into<Color>) {
+        self.packed_mut().fill(color.into().to_u32());
+    }
+
+    /// Fills the rectangle with its top-left corner at `(x, y)`, clipped to
+    /// the image.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: impl Into<Color>) {
+        let Some((x0, x1)) = clip(x, width, self.width) else {
+            return;
+        };
+        let Some((y0, y1)) = clip(y, height, self.height) else {
+            return;
+        };
+        let packed = color.into().to_u32();
+        for y in y0..y1 {
+            let start = self.calc_index(x0, y).unwrap();
+            self.packed_mut()[start..start + (x1 - x0) as usize].fill(packed);
+        }
+    }
+
+    /// Draws a one-pixel line including both ends, clipped to the image.
+    pub fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), color: impl Into<Color>) {
+        let color = color.into();
+        for (x, y) in line_drawing::Bresenham::new(from, to) {
+            self.set_clipped(x, y, color);
+        }
+    }
+
+    /// Draws the one-pixel outline of a circle, clipped to the image.
+    pub fn draw_circle(&mut self, center: (i32, i32), radius: u32, color: impl Into<Color>) {
+        let color = color.into();
+        let radius = radius.min(i32::MAX as u32) as i32;
+        for (x, y) in line_drawing::BresenhamCircle::new(center.0, center.1, radius) {
+            self.set_clipped(x, y, color);
+        }
+    }
+
+    /// Fills a disc, clipped to the image.
+    pub fn fill_circle(&mut self, center: (i32, i32), radius: u32, color: impl Into<Color>) {
+        let color = color.into();
+        let r = radius.min(i32::MAX as u32) as i64;
+        for dy in -r..=r {
+            let half = ((r * r - dy * dy) as f64).sqrt() as i64;
+            let (x, y) = (center.0 as i64 - half, center.1 as i64 + dy);
+            if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+                self.fill_rect(x, y, (half * 2 + 1) as u32, 1, color);
+            }
+        }
+    }
+
+    /// Copies `src` with its top-left corner at `(x, y)`, clipped to the
+    /// image. Pixels are replaced, not blended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the images have different formats.
+    pub fn blit(&mut self, src: &WorldImage, x: i32, y: i32) {
+        src.assert_format(self.format);
+        let Some((x0, x1)) = clip(x, src.width, self.width) else {
+            return;
+        };
+        let Some((y0, y1)) = clip(y, src.height, self.height) else {
+            return;
+        };
+        let bpp = self.format.bytes_per_pixel();
+        let len = (x1 - x0) as usize * bpp;
+        for dst_y in y0..y1 {
+            let src_x = (x0 as i64 - x as i64) as u32;
+            let src_y = (dst_y as i64 - y as i64) as u32;
+            let from = src.calc_index(src_x, src_y).unwrap() * bpp;
+            let to = self.calc_index(x0, dst_y).unwrap() * bpp;
+            self.buf_mut()[to..to + len].copy_from_slice(&src.buf()[from..from + len]);
+        }
+    }
+
+    /// Copies rows of `width` pixels, as raw bytes in the format of the
+    /// image, to the rectangle whose top-left pixel is `(x, y)`.
+    ///
+    /// Other mutable accesses make the app upload the whole image. While an
+    /// image is only written this way, the app uploads just the rectangles
+    /// written since its last upload, e.g. the tiles of a large image that
+    /// changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is not whole rows of `width` pixels or the rectangle
+    /// does not fit in the image.
+    pub fn write_rect(&mut self, x: u32, y: u32, width: u32, bytes: &[u8]) {
+        let bpp = self.format.bytes_per_pixel();
+        let len = width as usize * bpp;
+        assert!(
+            len > 0 && bytes.len().is_multiple_of(len),
+            "expected whole rows of {width} pixels"
+        );
+        let height = (bytes.len() / len) as u32;
+        assert!(
+            x.checked_add(width).is_some_and(|end| end <= self.width)
+                && y.checked_add(height).is_some_and(|end| end <= self.height),
+            "{width}x{height} rectangle at ({x}, {y}) outside the {}x{} image",
+            self.width,
+            self.height
+        );
+
+        let version = self.version;
+        let written = self
+            .written
+            .take()
+            .filter(|written| written.until == version);
+        let stride = self.width as usize * bpp;
+        let buf = self.buf_mut();
+        for (row, src) in bytes.chunks_exact(len).enumerate() {
+            let start = (y as usize + row) * stride + x as usize * bpp;
+            buf[start..start + len].copy_from_slice(src);
+        }
+
+        let mut written = written.unwrap_or(WrittenRects {
+            since: version,
+            until: version,
+            rects: Vec::new(),
+            area: 0,
+        });
+        written.until = self.version;
+        written.rects.push(PixelRect {
+            x,
+            y,
+            width,
+            height,
+        });
+        written.area += u64::from(width) * u64::from(height);
+        // Past the size of the image, uploading it whole is cheaper.
+        if written.area < u64::from(self.width) * u64::from(self.height) {
+            self.written = Some(written);
+        }
+    }
+
+    /// The rectangles written since `version`, if nothing else changed.
+    pub(crate) fn take_written_rects(&mut self, version: ImageVersion) -> Option<Vec<PixelRect>> {
+        let written = self.written.take()?;
+        (written.until == self.version
+            && written.since.image == version.image
+            && written.since.count <= version.count)
+            .then_some(written.rects)
+    }
+
+    /// Colors every pixel from a row-major field of values in `0.0..=1.0`,
+    /// one per pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `field` does not have one value per pixel.
+    pub fn paint_scalar_field(&mut self, field: &[f32], colormap: &Colormap) {
+        let pixels = self.packed_mut();
+        assert_eq!(
+            field.len(),
+            pixels.len(),
+            "the field must have one value per pixel"
+        );
+        for (pixel, &value) in pixels.iter_mut().zip(field) {
+            *pixel = colormap.color(value).to_u32();
+        }
+    }
+
+    fn set_clipped(&mut self, x: i32, y: i32, color: Color) {
+        if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+            self.set(x, y, color);
+        }
+    }
+
+    /// Palette indices of an [`ImageFormat::Indexed8`] image, row by row.
+    #[inline]
+    pub fn indices(&self) -> &[u8] {
+        self.assert_format(ImageFormat::Indexed8);
+        self.buf()
+    }
+
+    #[inline]
+    pub fn indices_mut(&mut self) -> &mut [u8] {
+        self.assert_format(ImageFormat::Indexed8);
+        self.buf_mut()
+    }
+
+    #[inline]
+    pub fn get_index(&self, x: u32, y: u32) -> Option<u8> {
+        self.calc_index(x, y).map(|i| self.indices()[i])
+    }
+
+    /// Does nothing outside the image.
+    #[inline]
+    pub fn set_index(&mut self, x: u32, y: u32, index: u8) {
+        if let Some(i) = self.calc_index(x, y) {
+            self.indices_mut()[i] = index;
+        }
+    }
+
+    /// Values of an [`ImageFormat::R32Float`] image, row by row.
+    #[inline]
+    pub fn values(&self) -> &[f32] {
+        self.assert_format(ImageFormat::R32Float);
+        bytemuck::cast_slice(&self.pixels)
+    }
+
+    #[inline]
+    pub fn values_mut(&mut self) -> &mut [f32] {
+        self.assert_format(ImageFormat::R32Float);
+        self.touch();
+        bytemuck::cast_slice_mut(&mut self.pixels)
+    }
+
+    #[inline]
+    pub fn get_value(&self, x: u32, y: u32) -> Option<f32> {
+        self.calc_index(x, y).map(|i| self.values()[i])
+    }
+
+    /// Does nothing outside the image.
+    #[inline]
+    pub fn set_value(&mut self, x: u32, y: u32, value: f32) {
+        if let Some(i) = self.calc_index(x, y) {
+            self.values_mut()[i] = value;
+        }
+    }
+
+    /// Values mapped to the first and last palette entries of a float image.
+    #[inline]
+    pub fn value_range(&self) -> (f32, f32) {
+        self.value_range
+    }
+
+    /// Values outside the range get the color of the nearest end.
+    pub fn set_value_range(&mut self, min: f32, max: f32) {
+        self.assert_format(ImageFormat::R32Float);
+        assert!(min < max, "empty value range {min}..{max}");
+        self.touch();
+        self.value_range = (min, max);
+    }
+
+    /// Defaults to [`ColorSpace::Srgb`].
+    #[inline]
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Only RGBA images and layers are drawn in their color space. Palettes
+    /// are always sRGB.
+    #[inline]
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.touch();
+        self.color_space = color_space;
+    }
+
+    /// The 256 colors of an indexed or float image. Empty for RGBA images.
+    #[inline]
+    pub fn palette(&self) -> &[Color] {
+        &self.palette
+    }
+
+    /// Replaces the palette from its first entry. Entries beyond the given
+    /// colors are kept.
+    pub fn set_palette(&mut self, colors: impl IntoIterator<Item = Color>) {
+        self.assert_palette();
+        self.touch();
+        for (entry, color) in self.palette.iter_mut().zip(colors) {
+            *entry = color;
+        }
+    }
+
+    #[inline]
+    pub fn set_palette_entry(&mut self, index: u8, color: Color) {
+        self.assert_palette();
+        self.touch();
+        self.palette[index as usize] = color;
+    }
+
+    /// Fills the palette with evenly spaced samples of `colormap`.
+    pub fn set_colormap(&mut self, colormap: &Colormap) {
+        let last = (Self::PALETTE_LEN - 1) as f32;
+        self.set_palette((0..Self::PALETTE_LEN).map(|i| colormap.color(i as f32 / last)));
+    }
+
+    /// Converts to an RGBA image, looking up palette colors.
+    pub fn to_rgba(&self) -> WorldImage {
+        let mut rgba = Self::new(self.width, self.height);
+        match self.format {
+            ImageFormat::Rgba8 => return self.clone(),
+            ImageFormat::Indexed8 => {
+                for (pixel, &index) in rgba.pixels_mut().iter_mut().zip(self.indices()) {
+                    *pixel = self.palette[index as usize];
+                }
+            }
+            ImageFormat::R32Float => {
+                let (min, max) = self.value_range;
+                let last = (Self::PALETTE_LEN - 1) as f32;
+                for (pixel, &value) in rgba.pixels_mut().iter_mut().zip(self.values()) {
+                    let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+                    *pixel = self.palette[(t * last).round() as usize];
+                }
+            }
+        }
+        rgba
+    }
+
+    /// Encodes the image as an RGBA PNG, looking up palette colors. Layers
+    /// are not included.
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self) -> anyhow::Result<Vec<u8>> {
+        let rgba;
+        let image = match self.format {
+            ImageFormat::Rgba8 => self,
+            _ => {
+                rgba = self.to_rgba();
+                &rgba
+            }
+        };
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, image.width, image.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // Cell images compress well even at the fastest setting.
+        encoder.set_compression(png::Compression::Fast);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(image.buf())?;
+        writer.finish()?;
+        Ok(data)
+    }
+
+    /// Writes [`WorldImage::encode_png`] to a file.
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let path = path.as_ref();
+        std::fs::write(path, self.encode_png()?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Replaces the contents with `src`, reusing the allocation when the
+    /// images have the same dimensions and format.
+    pub(crate) fn copy_from(&mut self, src: &WorldImage) {
+        if (self.width, self.height, self.format) != (src.width, src.height, src.format) {
+            *self = src.clone();
+            return;
+        }
+        self.touch();
+        self.pixels.copy_from_slice(&src.pixels);
+        self.palette.clone_from(&src.palette);
+        self.value_range = src.value_range;
+        self.color_space = src.color_space;
+        self.layers.clone_from(&src.layers);
+        self.heatmap.clone_from(&src.heatmap);
+    }
+
+    /// Adds a layer drawn over the image and the layers added before it,
+    /// stretched to cover the whole world, and returns its index. Layers may
+    /// have a different resolution than the image, e.g. a finer one for
+    /// annotations.
+    ///
+    /// Layers of layers are not drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `layer` is not an RGBA image.
+    pub fn add_layer(&mut self, layer: WorldImage) -> usize {
+        layer.assert_format(ImageFormat::Rgba8);
+        self.touch();
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    #[inline]
+    pub fn layers(&self) -> &[WorldImage] {
+        &self.layers
+    }
+
+    /// Layers replaced with images of other formats are not drawn.
+    #[inline]
+    pub fn layers_mut(&mut self) -> &mut [WorldImage] {
+        self.touch();
+        &mut self.layers
+    }
+
+    /// Removes and returns a layer, shifting the ones above it down.
+    #[inline]
+    pub fn remove_layer(&mut self, index: usize) -> WorldImage {
+        self.touch();
+        self.layers.remove(index)
+    }
+
+    /// Moves every row up by `rows`, filling the rows uncovered at the bottom
+    /// with `fill`.
+    pub fn scroll_up(&mut self, rows: u32, fill: impl Into<Color>) {
+        let rows = rows.min(self.height);
+        let offset = rows as usize * self.width as usize;
+        let pixels = self.packed_mut();
+        pixels.copy_within(offset.., 0);
+        let len = pixels.len();
+        pixels[len - offset..].fill(fill.into().to_u32());
+    }
+
+    /// Changes the dimensions of the image, keeping the pixels of the
+    /// overlapping top-left region. New pixels are zero, i.e. transparent
+    /// black in RGBA images. Layers and the heatmap keep their own size.
+    ///
+    /// The app picks up the new size on the next frame.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        assert!(width > 0 && height > 0);
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        let mut resized = Self::with_format(width, height, self.format);
+        resized.blit(self, 0, 0);
+        resized.palette = std::mem::take(&mut self.palette);
+        resized.value_range = self.value_range;
+        resized.color_space = self.color_space;
+        resized.layers = std::mem::take(&mut self.layers);
+        resized.heatmap = self.heatmap.take();
+        *self = resized;
+    }
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.width as usize * self.height as usize * self.format.bytes_per_pixel()
+    }
+
+    #[inline]
+    fn calc_index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| x as usize + y as usize * self.width as usize)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn assert_format(&self, format: ImageFormat) {
+        assert!(
+            self.format == format,
+            "expected a {format:?} image, found {:?}",
+            self.format
+        );
     }
 
-    fn calc_offset(&self, x: u32, y: u32) -> Option<usize> {
-        (x < self.width && y < self.height)
-            .then(|| (x as usize + y as usize * self.width as usize) * 4)
+    #[inline]
+    #[track_caller]
+    fn assert_palette(&self) {
+        assert!(
+            !self.palette.is_empty(),
+            "{:?} images have no palette",
+            self.format
+        );
+    }
+
+    /// The format of the image's texture when drawn to an sRGB target, which
+    /// encodes colors from linear light, or to one that stores them as they
+    /// are.
+    pub(crate) fn texture_format(&self, srgb_target: bool) -> wgpu::TextureFormat {
+        match (self.format, self.color_space) {
+            (ImageFormat::Rgba8, ColorSpace::Srgb) if srgb_target => {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            }
+            (ImageFormat::Rgba8, _) => wgpu::TextureFormat::Rgba8Unorm,
+            (format, _) => format.texture_format(),
+        }
     }
 
     pub(crate) fn create_texture(
@@ -71,54 +791,151 @@ impl WorldImage {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         label: Option<&str>,
+        srgb_target: bool,
     ) -> anyhow::Result<(wgpu::Texture, wgpu::TextureView, wgpu::Sampler)> {
+        let (texture, view) =
+            self.create_texture_with_mips(device, queue, label, srgb_target, 1)?;
+        let sampler = Self::create_sampler(device, wgpu::FilterMode::Nearest, 1);
+        Ok((texture, view, sampler))
+    }
+
+    /// Levels below the first are left for the caller to render.
+    pub(crate) fn create_texture_with_mips(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: Option<&str>,
+        srgb_target: bool,
+        mip_level_count: u32,
+    ) -> anyhow::Result<(wgpu::Texture, wgpu::TextureView)> {
         let texture_size = self.texture_size();
+        let usage = if mip_level_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::empty()
+        };
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size: texture_size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            format: self.texture_format(srgb_target),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | usage,
             view_formats: &[],
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        self.update_wgpu_texture(&texture, queue);
+
+        Ok((texture, view))
+    }
+
+    /// Magnifies with `filter`. Minifies with it too, unless the texture has
+    /// mip levels to blend between.
+    pub(crate) fn create_sampler(
+        device: &wgpu::Device,
+        filter: wgpu::FilterMode,
+        mip_level_count: u32,
+    ) -> wgpu::Sampler {
+        let min_filter = if mip_level_count > 1 {
+            wgpu::FilterMode::Linear
+        } else {
+            filter
+        };
+        device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mag_filter: filter,
+            min_filter,
+            mipmap_filter: min_filter,
             ..Default::default()
-        });
+        })
+    }
 
-        self.update_wgpu_texture(&texture, queue);
+    pub(crate) fn update_wgpu_texture(&self, texture: &wgpu::Texture, queue: &wgpu::Queue) {
+        self.update_wgpu_texture_rows(texture, queue, 0..self.height());
+    }
 
-        Ok((texture, view, sampler))
+    /// Uploads only the given rows of the image.
+    pub(crate) fn update_wgpu_texture_rows(
+        &self,
+        texture: &wgpu::Texture,
+        queue: &wgpu::Queue,
+        rows: std::ops::Range<u32>,
+    ) {
+        let rows = rows.start.min(self.height())..rows.end.min(self.height());
+        if rows.is_empty() {
+            return;
+        }
+        let bytes_per_row = self.format.bytes_per_pixel() as u32 * self.width();
+        let start = rows.start as usize * bytes_per_row as usize;
+        let end = rows.end as usize * bytes_per_row as usize;
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: rows.start,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.buf()[start..end],
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows.len() as u32),
+            },
+            wgpu::Extent3d {
+                width: self.width(),
+                height: rows.len() as u32,
+                depth_or_array_layers: 1,
+            },
+        );
     }
 
-    pub(crate) fn update_wgpu_texture(&self, texture: &wgpu::Texture, queue: &wgpu::Queue) {
+    /// Uploads only the pixels in `rect`.
+    pub(crate) fn update_wgpu_texture_rect(
+        &self,
+        texture: &wgpu::Texture,
+        queue: &wgpu::Queue,
+        rect: PixelRect,
+    ) {
+        let bpp = self.format.bytes_per_pixel();
+        let bytes_per_row = self.width() as usize * bpp;
+        let start = rect.y as usize * bytes_per_row + rect.x as usize * bpp;
+        let end = (rect.y + rect.height - 1) as usize * bytes_per_row
+            + (rect.x + rect.width) as usize * bpp;
+
         queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture,
                 mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
+                origin: wgpu::Origin3d {
+                    x: rect.x,
+                    y: rect.y,
+                    z: 0,
+                },
                 aspect: wgpu::TextureAspect::All,
             },
-            &self.buf,
+            &self.buf()[start..end],
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * self.width()),
-                rows_per_image: Some(self.height()),
+                bytes_per_row: Some(bytes_per_row as u32),
+                rows_per_image: Some(rect.height),
+            },
+            wgpu::Extent3d {
+                width: rect.width,
+                height: rect.height,
+                depth_or_array_layers: 1,
             },
-            self.texture_size(),
         );
-        queue.submit([]);
     }
 
     fn texture_size(&self) -> wgpu::Extent3d {
@@ -129,3 +946,99 @@ impl WorldImage {
         }
     }
 }
+
+/// Clips the span of `len` pixels starting at `start` to `0..limit`.
+fn clip(start: i32, len: u32, limit: u32) -> Option<(u32, u32)> {
+    let end = (start as i64 + len as i64).min(limit as i64);
+    let start = (start as i64).max(0);
+    (start < end).then_some((start as u32, end as u32))
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct ImageRef<'a> {
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    pixels: &'a [u8],
+    palette: &'a [Color],
+    value_range: (f32, f32),
+    color_space: ColorSpace,
+    layers: &'a [WorldImage],
+    heatmap: Option<&'a Heatmap>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ImageData {
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    pixels: Vec<u8>,
+    #[serde(default)]
+    palette: Vec<Color>,
+    value_range: (f32, f32),
+    #[serde(default)]
+    color_space: ColorSpace,
+    #[serde(default)]
+    layers: Vec<WorldImage>,
+    #[serde(default)]
+    heatmap: Option<Heatmap>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for WorldImage {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ImageRef {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            pixels: self.buf(),
+            palette: &self.palette,
+            value_range: self.value_range,
+            color_space: self.color_space,
+            layers: &self.layers,
+            heatmap: self.heatmap(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WorldImage {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        let data = ImageData::deserialize(deserializer)?;
+        if data.width == 0 || data.height == 0 {
+            return Err(D::Error::custom("empty image"));
+        }
+        let mut image = Self::with_format(data.width, data.height, data.format);
+        if data.pixels.len() != image.byte_len() {
+            return Err(D::Error::custom(format_args!(
+                "expected {} bytes for a {}x{} {:?} image, found {}",
+                image.byte_len(),
+                data.width,
+                data.height,
+                data.format,
+                data.pixels.len()
+            )));
+        }
+        if data
+            .layers
+            .iter()
+            .any(|layer| layer.format != ImageFormat::Rgba8)
+        {
+            return Err(D::Error::custom("layers must be RGBA images"));
+        }
+        image.buf_mut().copy_from_slice(&data.pixels);
+        for (entry, color) in image.palette.iter_mut().zip(data.palette) {
+            *entry = color;
+        }
+        image.value_range = data.value_range;
+        image.color_space = data.color_space;
+        image.layers = data.layers;
+        image.heatmap = data.heatmap.map(Box::new);
+        Ok(image)
+    }
+}
@@ -0,0 +1,135 @@
+use super::{ImageFormat, WorldImage};
+use crate::Colormap;
+
+/// A scalar field drawn over an image and its layers as a translucent
+/// colormapped layer, e.g. the pheromone concentration over an ant colony.
+/// See [`WorldImage::set_heatmap`].
+///
+/// The field is an [`ImageFormat::R32Float`] image whose values are mapped
+/// onto its palette through its value range on the GPU, like the world
+/// image itself. It is stretched to cover the whole world, so it may be
+/// coarser than the image below it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "HeatmapData"))]
+pub struct Heatmap {
+    field: WorldImage,
+    /// Opacity of the whole layer, from `0.0` to `1.0`. Multiplied with the
+    /// alpha of the palette colors. Defaults to `0.5`.
+    pub opacity: f32,
+    /// Interpolates between the values of neighboring texels instead of
+    /// drawing them as blocks. Defaults to `false`.
+    pub smooth: bool,
+}
+
+impl Heatmap {
+    /// A field of zeros mapped through `colormap`, values from `0.0` to
+    /// `1.0`.
+    pub fn new(width: u32, height: u32, colormap: &Colormap) -> Self {
+        let mut field = WorldImage::new_float(width, height);
+        field.set_colormap(colormap);
+        Self::from_field(field)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `field` is not an [`ImageFormat::R32Float`] image.
+    pub fn from_field(field: WorldImage) -> Self {
+        field.assert_format(ImageFormat::R32Float);
+        Self {
+            field,
+            opacity: 0.5,
+            smooth: false,
+        }
+    }
+
+    #[inline]
+    pub fn opacity(self, opacity: f32) -> Self {
+        Self { opacity, ..self }
+    }
+
+    #[inline]
+    pub fn smooth(self, smooth: bool) -> Self {
+        Self { smooth, ..self }
+    }
+
+    #[inline]
+    pub fn field(&self) -> &WorldImage {
+        &self.field
+    }
+
+    /// The field, to change its values, value range or palette. Changing it
+    /// into an image of another format hides the heatmap.
+    #[inline]
+    pub fn field_mut(&mut self) -> &mut WorldImage {
+        &mut self.field
+    }
+
+    /// Values of the field, row by row.
+    #[inline]
+    pub fn values(&self) -> &[f32] {
+        self.field.values()
+    }
+
+    #[inline]
+    pub fn values_mut(&mut self) -> &mut [f32] {
+        self.field.values_mut()
+    }
+}
+
+impl WorldImage {
+    /// Sets the heatmap drawn over the image and its layers, or removes it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field of `heatmap` is not an
+    /// [`ImageFormat::R32Float`] image.
+    pub fn set_heatmap(&mut self, heatmap: Option<Heatmap>) {
+        if let Some(heatmap) = &heatmap {
+            heatmap.field.assert_format(ImageFormat::R32Float);
+        }
+        self.touch();
+        self.heatmap = heatmap.map(Box::new);
+    }
+
+    #[inline]
+    pub fn heatmap(&self) -> Option<&Heatmap> {
+        self.heatmap.as_deref()
+    }
+
+    /// The field keeps a version of its own, so changing it does not change
+    /// the [`WorldImage::version`] of this image, and uploads only the
+    /// heatmap.
+    #[inline]
+    pub fn heatmap_mut(&mut self) -> Option<&mut Heatmap> {
+        self.heatmap.as_deref_mut()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct HeatmapData {
+    field: WorldImage,
+    opacity: f32,
+    #[serde(default)]
+    smooth: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HeatmapData> for Heatmap {
+    type Error = String;
+
+    fn try_from(data: HeatmapData) -> Result<Self, Self::Error> {
+        if data.field.format() != ImageFormat::R32Float {
+            return Err(format!(
+                "heatmap fields must be R32Float images, found {:?}",
+                data.field.format()
+            ));
+        }
+        Ok(Self {
+            field: data.field,
+            opacity: data.opacity,
+            smooth: data.smooth,
+        })
+    }
+}
@@ -0,0 +1,280 @@
+use super::{ImageFormat, WorldImage, clip};
+use crate::Color;
+
+/// A small RGBA raster drawn into images with [`WorldImage::blit_sprite`],
+/// e.g. a creature of an agent-based simulation spanning several cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// How the pixels of a [`Sprite`] are combined with those below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    /// Pixels are copied as they are, alpha included.
+    Replace,
+    /// Pixels are drawn over the image by their alpha, and fully
+    /// transparent ones are skipped.
+    #[default]
+    Alpha,
+    /// Pixels of the key color are skipped and the others copied, for
+    /// sprites drawn without an alpha channel.
+    ColorKey(Color),
+}
+
+/// One of the eight ways to turn and flip a [`Sprite`] onto the grid,
+/// see [`WorldImage::blit_sprite_oriented`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientation {
+    #[default]
+    Identity,
+    /// A quarter turn clockwise.
+    Rotate90,
+    Rotate180,
+    /// A quarter turn counterclockwise.
+    Rotate270,
+    /// Mirrored left to right.
+    FlipHorizontal,
+    /// Mirrored top to bottom.
+    FlipVertical,
+    /// Mirrored along the diagonal from the top-left corner.
+    Transpose,
+    /// Mirrored along the diagonal from the top-right corner.
+    Transverse,
+}
+
+impl Orientation {
+    /// Whether the width and height of the sprite are swapped.
+    #[inline]
+    pub fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Self::Rotate90 | Self::Rotate270 | Self::Transpose | Self::Transverse
+        )
+    }
+
+    /// The pixel of a `width` by `height` sprite drawn at `(x, y)` of the
+    /// oriented sprite.
+    fn source(self, (width, height): (u32, u32), x: u32, y: u32) -> (u32, u32) {
+        let (right, bottom) = (width - 1, height - 1);
+        match self {
+            Self::Identity => (x, y),
+            Self::Rotate90 => (y, bottom - x),
+            Self::Rotate180 => (right - x, bottom - y),
+            Self::Rotate270 => (right - y, x),
+            Self::FlipHorizontal => (right - x, y),
+            Self::FlipVertical => (x, bottom - y),
+            Self::Transpose => (y, x),
+            Self::Transverse => (right - y, bottom - x),
+        }
+    }
+}
+
+impl Sprite {
+    /// A fully transparent sprite.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either side is zero.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(
+            width,
+            height,
+            vec![Color::TRANSPARENT; width as usize * height as usize],
+        )
+    }
+
+    /// # Panics
+    ///
+    /// Panics if either side is zero or `pixels` does not have
+    /// `width * height` row-major pixels.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "the sprite must have width * height pixels"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// A sprite with the pixel at `(x, y)` set to `f(x, y)`.
+    pub fn from_fn(width: u32, height: u32, mut f: impl FnMut(u32, u32) -> Color) -> Self {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Self::from_pixels(width, height, pixels)
+    }
+
+    /// A sprite drawn as rows of characters, each looked up with `color`,
+    /// with shorter rows padded with transparent pixels.
+    ///
+    /// ```
+    /// # use cells_renderer::{Color, image::Sprite};
+    /// let glider = Sprite::from_rows(&[".#.", "..#", "###"], |c| match c {
+    ///     '#' => Color::WHITE,
+    ///     _ => Color::TRANSPARENT,
+    /// });
+    /// assert_eq!(glider.size(), (3, 3));
+    /// ```
+    pub fn from_rows(rows: &[&str], mut color: impl FnMut(char) -> Color) -> Self {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut pixels = Vec::with_capacity(width * rows.len());
+        for row in rows {
+            let start = pixels.len();
+            pixels.extend(row.chars().map(&mut color));
+            pixels.resize(start + width, Color::TRANSPARENT);
+        }
+        Self::from_pixels(width as u32, rows.len() as u32, pixels)
+    }
+
+    /// Copies an [`ImageFormat::Rgba8`] image.
+    pub fn from_image(image: &WorldImage) -> Self {
+        Self::from_pixels(image.width(), image.height(), image.pixels().to_vec())
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Row-major pixels.
+    #[inline]
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<&Color> {
+        self.index(x, y).map(|i| &self.pixels[i])
+    }
+
+    #[inline]
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut Color> {
+        self.index(x, y).map(|i| &mut self.pixels[i])
+    }
+
+    /// Does nothing outside the sprite.
+    #[inline]
+    pub fn set(&mut self, x: u32, y: u32, color: impl Into<Color>) {
+        if let Some(pixel) = self.pixel_mut(x, y) {
+            *pixel = color.into();
+        }
+    }
+
+    /// A copy turned and flipped by `orientation`, for sprites drawn the same
+    /// way many times.
+    pub fn oriented(&self, orientation: Orientation) -> Self {
+        let (width, height) = if orientation.swaps_axes() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        Self::from_fn(width, height, |x, y| {
+            let (sx, sy) = orientation.source(self.size(), x, y);
+            self.pixels[self.index(sx, sy).unwrap()]
+        })
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl BlendMode {
+    /// `src` drawn over `dst`, or `None` to leave `dst` as it is.
+    fn blend(self, src: Color, dst: Color) -> Option<Color> {
+        match self {
+            Self::Replace => Some(src),
+            Self::Alpha => match src.a() {
+                0 => None,
+                u8::MAX => Some(src),
+                a => {
+                    let t = a as f32 / 255.0;
+                    let alpha = a as f32 + dst.a() as f32 * (1.0 - t);
+                    Some(dst.lerp(src, t).with_alpha(alpha.round() as u8))
+                }
+            },
+            Self::ColorKey(key) => (src != key).then_some(src),
+        }
+    }
+}
+
+impl WorldImage {
+    /// Draws `sprite` with its top-left corner at `(x, y)`, clipped to the
+    /// image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is not [`ImageFormat::Rgba8`].
+    pub fn blit_sprite(&mut self, x: i32, y: i32, sprite: &Sprite, mode: BlendMode) {
+        self.blit_sprite_oriented(x, y, sprite, Orientation::Identity, mode);
+    }
+
+    /// Like [`WorldImage::blit_sprite`], turned and flipped by
+    /// `orientation` without copying the sprite. The top-left corner of the
+    /// oriented sprite is at `(x, y)`.
+    pub fn blit_sprite_oriented(
+        &mut self,
+        x: i32,
+        y: i32,
+        sprite: &Sprite,
+        orientation: Orientation,
+        mode: BlendMode,
+    ) {
+        self.assert_format(ImageFormat::Rgba8);
+        let (width, height) = if orientation.swaps_axes() {
+            (sprite.height, sprite.width)
+        } else {
+            (sprite.width, sprite.height)
+        };
+        let Some((x0, x1)) = clip(x, width, self.width) else {
+            return;
+        };
+        let Some((y0, y1)) = clip(y, height, self.height) else {
+            return;
+        };
+        let image_width = self.width as usize;
+        let pixels = self.pixels_mut();
+        for dst_y in y0..y1 {
+            let sprite_y = (dst_y as i64 - y as i64) as u32;
+            for dst_x in x0..x1 {
+                let sprite_x = (dst_x as i64 - x as i64) as u32;
+                let (sx, sy) = orientation.source(sprite.size(), sprite_x, sprite_y);
+                let src = sprite.pixels[sprite.index(sx, sy).unwrap()];
+                let dst = &mut pixels[dst_y as usize * image_width + dst_x as usize];
+                if let Some(color) = mode.blend(src, *dst) {
+                    *dst = color;
+                }
+            }
+        }
+    }
+}
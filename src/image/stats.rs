@@ -0,0 +1,160 @@
+use super::{ImageFormat, ImageView, WorldImage};
+use crate::Color;
+use std::collections::HashMap;
+
+/// Summary of the values of an [`ImageFormat::R32Float`] image or view,
+/// see [`ImageView::value_stats`]. NaN values are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValueStats {
+    /// Number of values that are not NaN.
+    pub count: usize,
+    pub sum: f64,
+    /// Smallest and largest value, `None` when there are none.
+    pub range: Option<(f32, f32)>,
+}
+
+impl ValueStats {
+    /// `None` when there are no values.
+    #[inline]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    fn add(&mut self, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.sum += value as f64;
+        self.range = Some(match self.range {
+            Some((min, max)) => (min.min(value), max.max(value)),
+            None => (value, value),
+        });
+    }
+}
+
+impl ImageView<'_> {
+    /// Number of pixels of exactly `color`, e.g. the population of a species.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is not [`ImageFormat::Rgba8`].
+    #[track_caller]
+    pub fn count_color(&self, color: impl Into<Color>) -> usize {
+        let color = color.into();
+        self.assert_format(ImageFormat::Rgba8);
+        self.bytes_rows()
+            .flat_map(|row| row.chunks_exact(4))
+            .filter(|&pixel| pixel == color.0)
+            .count()
+    }
+
+    /// Number of pixels of each color in the view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is not [`ImageFormat::Rgba8`].
+    #[track_caller]
+    pub fn color_histogram(&self) -> HashMap<Color, usize> {
+        self.assert_format(ImageFormat::Rgba8);
+        let mut histogram = HashMap::new();
+        for pixel in self.bytes_rows().flat_map(|row| row.chunks_exact(4)) {
+            *histogram
+                .entry(Color(pixel.try_into().unwrap()))
+                .or_default() += 1;
+        }
+        histogram
+    }
+
+    /// Number of pixels of `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is not [`ImageFormat::Indexed8`].
+    #[track_caller]
+    pub fn count_index(&self, index: u8) -> usize {
+        self.index_histogram()[index as usize]
+    }
+
+    /// Number of pixels of each palette index, e.g. of each cell state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is not [`ImageFormat::Indexed8`].
+    #[track_caller]
+    pub fn index_histogram(&self) -> [usize; 256] {
+        self.assert_format(ImageFormat::Indexed8);
+        let mut histogram = [0; 256];
+        for &index in self.bytes_rows().flatten() {
+            histogram[index as usize] += 1;
+        }
+        histogram
+    }
+
+    /// Count, sum and range of the values in the view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is not [`ImageFormat::R32Float`].
+    #[track_caller]
+    pub fn value_stats(&self) -> ValueStats {
+        self.assert_format(ImageFormat::R32Float);
+        let mut stats = ValueStats::default();
+        for bytes in self.bytes_rows().flat_map(|row| row.chunks_exact(4)) {
+            stats.add(f32::from_ne_bytes(bytes.try_into().unwrap()));
+        }
+        stats
+    }
+
+    fn bytes_rows(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        (0..self.height()).filter_map(|y| self.row(y))
+    }
+
+    #[track_caller]
+    fn assert_format(&self, format: ImageFormat) {
+        assert!(
+            self.format() == format,
+            "expected a {format:?} image, found {:?}",
+            self.format()
+        );
+    }
+}
+
+/// Statistics over the whole image. Those over a rectangle of it are on
+/// [`ImageView`], e.g. `image.view(x, y, width, height).count_color(color)`.
+impl WorldImage {
+    /// See [`ImageView::count_color`].
+    #[track_caller]
+    pub fn count_color(&self, color: impl Into<Color>) -> usize {
+        self.whole().count_color(color)
+    }
+
+    /// See [`ImageView::color_histogram`].
+    #[track_caller]
+    pub fn color_histogram(&self) -> HashMap<Color, usize> {
+        self.whole().color_histogram()
+    }
+
+    /// See [`ImageView::count_index`].
+    #[track_caller]
+    pub fn count_index(&self, index: u8) -> usize {
+        self.whole().count_index(index)
+    }
+
+    /// See [`ImageView::index_histogram`].
+    #[track_caller]
+    pub fn index_histogram(&self) -> [usize; 256] {
+        self.whole().index_histogram()
+    }
+
+    /// See [`ImageView::value_stats`].
+    #[track_caller]
+    pub fn value_stats(&self) -> ValueStats {
+        self.whole().value_stats()
+    }
+
+    fn whole(&self) -> ImageView<'_> {
+        self.view(0, 0, self.width, self.height)
+    }
+}
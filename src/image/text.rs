@@ -0,0 +1,177 @@
+use super::{ImageViewMut, WorldImage};
+use crate::{Color, font};
+
+impl WorldImage {
+    /// Draws `text` in the built-in 5x7 bitmap font with its top-left corner
+    /// at `(x, y)`, clipped to the image. `'\n'` starts a new line, and
+    /// characters outside printable ASCII are drawn as `'?'`.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: impl Into<Color>) {
+        self.draw_text_scaled(x, y, text, 1, color);
+    }
+
+    /// Like [`WorldImage::draw_text`], each font pixel a square of `scale`
+    /// pixels along each axis.
+    pub fn draw_text_scaled(
+        &mut self,
+        x: i32,
+        y: i32,
+        text: &str,
+        scale: u32,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        for (dx, dy) in font::pixels(text) {
+            let (dx, dy) = (dx as i64 * scale as i64, dy as i64 * scale as i64);
+            if let (Ok(x), Ok(y)) = (i32::try_from(x as i64 + dx), i32::try_from(y as i64 + dy)) {
+                self.fill_rect(x, y, scale, scale, color);
+            }
+        }
+    }
+
+    /// Size of `text` in pixels as drawn by [`WorldImage::draw_text`], e.g.
+    /// for centering or right-aligning labels.
+    pub fn text_size(text: &str) -> (u32, u32) {
+        font::size(text)
+    }
+
+    /// Draws `text` in a TrueType or OpenType font with its top-left corner at
+    /// `(x, y)`, clipped to the image. Edges are blended into the image by
+    /// their coverage and the alpha of `color`.
+    #[cfg(feature = "ttf")]
+    pub fn draw_text_ttf(
+        &mut self,
+        x: i32,
+        y: i32,
+        text: &str,
+        font: &TtfFont,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        for (glyph, coverage) in font.rasterize(x, y, text) {
+            for (i, &coverage) in coverage.iter().enumerate() {
+                let (gx, gy) = ((i % glyph.width) as i32, (i / glyph.width) as i32);
+                let (Ok(px), Ok(py)) = (u32::try_from(glyph.x + gx), u32::try_from(glyph.y + gy))
+                else {
+                    continue;
+                };
+                if let Some(pixel) = self.pixel_mut(px, py) {
+                    let t = coverage as f32 / 255.0 * color.a() as f32 / 255.0;
+                    *pixel = pixel.lerp(color.with_alpha(u8::MAX), t);
+                }
+            }
+        }
+    }
+}
+
+impl ImageViewMut<'_> {
+    /// Like [`WorldImage::draw_text`], relative to the view and clipped to
+    /// it.
+    #[track_caller]
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: impl Into<Color>) {
+        let color = color.into();
+        for (dx, dy) in font::pixels(text) {
+            let (x, y) = (x as i64 + dx as i64, y as i64 + dy as i64);
+            if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                self.set(x, y, color);
+            }
+        }
+    }
+}
+
+/// A TrueType or OpenType font at one size, for
+/// [`WorldImage::draw_text_ttf`].
+#[cfg(feature = "ttf")]
+pub struct TtfFont {
+    font: fontdue::Font,
+    px: f32,
+}
+
+/// Placement of a rasterized glyph in the image.
+#[cfg(feature = "ttf")]
+struct PlacedGlyph {
+    x: i32,
+    y: i32,
+    width: usize,
+}
+
+#[cfg(feature = "ttf")]
+impl TtfFont {
+    /// Parses a `.ttf` or `.otf` file, drawn `px` pixels high.
+    pub fn from_bytes(bytes: &[u8], px: f32) -> anyhow::Result<Self> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(|err| anyhow::anyhow!("failed to parse font: {err}"))?;
+        Ok(Self { font, px })
+    }
+
+    /// Reads a `.ttf` or `.otf` file, drawn `px` pixels high.
+    pub fn from_path(path: impl AsRef<std::path::Path>, px: f32) -> anyhow::Result<Self> {
+        use anyhow::Context as _;
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read font {}", path.display()))?;
+        Self::from_bytes(&bytes, px)
+    }
+
+    /// The same font drawn `px` pixels high.
+    #[inline]
+    pub fn with_size(self, px: f32) -> Self {
+        Self { px, ..self }
+    }
+
+    #[inline]
+    pub fn size(&self) -> f32 {
+        self.px
+    }
+
+    /// Size of `text` in pixels as drawn by [`WorldImage::draw_text_ttf`].
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
+        let layout = self.layout(0, 0, text);
+        let width = layout
+            .glyphs()
+            .iter()
+            .map(|glyph| glyph.x + glyph.width as f32)
+            .fold(0.0f32, f32::max);
+        (width.ceil() as u32, layout.height().ceil() as u32)
+    }
+
+    fn layout(&self, x: i32, y: i32, text: &str) -> fontdue::layout::Layout {
+        use fontdue::layout::{CoordinateSystem, Layout, LayoutSettings, TextStyle};
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        layout.reset(&LayoutSettings {
+            x: x as f32,
+            y: y as f32,
+            ..LayoutSettings::default()
+        });
+        layout.append(&[&self.font], &TextStyle::new(text, self.px, 0));
+        layout
+    }
+
+    /// Coverage of each glyph of `text`, row by row.
+    fn rasterize(&self, x: i32, y: i32, text: &str) -> Vec<(PlacedGlyph, Vec<u8>)> {
+        let layout = self.layout(x, y, text);
+        layout
+            .glyphs()
+            .iter()
+            .filter(|glyph| glyph.width > 0 && glyph.height > 0)
+            .map(|glyph| {
+                let (_, coverage) = self.font.rasterize_config(glyph.key);
+                let placed = PlacedGlyph {
+                    x: glyph.x.round() as i32,
+                    y: glyph.y.round() as i32,
+                    width: glyph.width,
+                };
+                (placed, coverage)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "ttf")]
+impl std::fmt::Debug for TtfFont {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TtfFont")
+            .field("name", &self.font.name())
+            .field("px", &self.px)
+            .finish()
+    }
+}
@@ -0,0 +1,397 @@
+use super::{ImageFormat, WorldImage, clip};
+use crate::Color;
+
+/// Placement of a view in the bytes of its image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    /// Bytes per row of the image.
+    stride: usize,
+}
+
+impl Region {
+    /// The rectangle clipped to `image`, empty when they don't overlap.
+    fn clipped(image: &WorldImage, x: i32, y: i32, width: u32, height: u32) -> Self {
+        let (x0, x1) = clip(x, width, image.width).unwrap_or((0, 0));
+        let (y0, y1) = clip(y, height, image.height).unwrap_or((0, 0));
+        let (width, height) = if x0 < x1 && y0 < y1 {
+            (x1 - x0, y1 - y0)
+        } else {
+            (0, 0)
+        };
+        Self {
+            x: x0,
+            y: y0,
+            width,
+            height,
+            format: image.format,
+            stride: image.width as usize * image.format.bytes_per_pixel(),
+        }
+    }
+
+    fn bpp(&self) -> usize {
+        self.format.bytes_per_pixel()
+    }
+
+    /// Bytes of the image from the first pixel of the view to its last.
+    fn byte_range(&self) -> std::ops::Range<usize> {
+        let start = self.y as usize * self.stride + self.x as usize * self.bpp();
+        if self.height == 0 {
+            return start..start;
+        }
+        start..start + self.row_offset(self.height - 1) + self.width as usize * self.bpp()
+    }
+
+    fn row_offset(&self, y: u32) -> usize {
+        y as usize * self.stride
+    }
+
+    /// Offset of a pixel from the first byte of the view.
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| self.row_offset(y) + x as usize * self.bpp())
+    }
+
+    fn row(&self, y: u32) -> Option<std::ops::Range<usize>> {
+        let start = self.index(0, y)?;
+        Some(start..start + self.width as usize * self.bpp())
+    }
+
+    #[track_caller]
+    fn assert_format(&self, format: ImageFormat) {
+        assert!(
+            self.format == format,
+            "expected a {format:?} image, found {:?}",
+            self.format
+        );
+    }
+}
+
+/// A rectangle of a [`WorldImage`], see [`WorldImage::view`].
+///
+/// Coordinates are relative to the top-left corner of the rectangle, and
+/// pixels outside it are out of reach like those outside an image.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+    buf: &'a [u8],
+    region: Region,
+}
+
+/// A mutable rectangle of a [`WorldImage`], see [`WorldImage::view_mut`].
+///
+/// Has the pixel accessors of an image, relative to the top-left corner of
+/// the rectangle and clipped to it, so that tools and regions of a world
+/// can be handed a part of the image to draw in. Views split into bands of
+/// rows with [`ImageViewMut::split_at_row`], e.g. one per thread.
+#[derive(Debug)]
+pub struct ImageViewMut<'a> {
+    buf: &'a mut [u8],
+    region: Region,
+}
+
+impl WorldImage {
+    /// The rectangle with its top-left corner at `(x, y)`, clipped to the
+    /// image. Empty when they don't overlap.
+    pub fn view(&self, x: i32, y: i32, width: u32, height: u32) -> ImageView<'_> {
+        let region = Region::clipped(self, x, y, width, height);
+        ImageView {
+            buf: &self.buf()[region.byte_range()],
+            region,
+        }
+    }
+
+    /// Like [`WorldImage::view`], for drawing.
+    pub fn view_mut(&mut self, x: i32, y: i32, width: u32, height: u32) -> ImageViewMut<'_> {
+        let region = Region::clipped(self, x, y, width, height);
+        ImageViewMut {
+            buf: &mut self.buf_mut()[region.byte_range()],
+            region,
+        }
+    }
+}
+
+impl<'a> ImageView<'a> {
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.region.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.region.height
+    }
+
+    /// Position of the top-left corner in the image.
+    #[inline]
+    pub fn origin(&self) -> (u32, u32) {
+        (self.region.x, self.region.y)
+    }
+
+    #[inline]
+    pub fn format(&self) -> ImageFormat {
+        self.region.format
+    }
+
+    /// Bytes of one row of the view.
+    #[inline]
+    pub fn row(&self, y: u32) -> Option<&'a [u8]> {
+        self.region.row(y).map(|range| &self.buf[range])
+    }
+
+    /// Bytes of one pixel, in any format.
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> Option<&'a [u8]> {
+        let bpp = self.region.bpp();
+        self.region.index(x, y).map(|i| &self.buf[i..i + bpp])
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<&'a Color> {
+        self.region.assert_format(ImageFormat::Rgba8);
+        self.get(x, y).map(bytemuck::from_bytes)
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn get_index(&self, x: u32, y: u32) -> Option<u8> {
+        self.region.assert_format(ImageFormat::Indexed8);
+        self.get(x, y).map(|bytes| bytes[0])
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn get_value(&self, x: u32, y: u32) -> Option<f32> {
+        self.region.assert_format(ImageFormat::R32Float);
+        self.get(x, y)
+            .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Copies the view into an image of its own, without palette or layers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the view is empty.
+    pub fn to_image(&self) -> WorldImage {
+        let mut image = WorldImage::with_format(self.width(), self.height(), self.format());
+        let row_len = self.width() as usize * self.region.bpp();
+        for (y, row) in image.buf_mut().chunks_exact_mut(row_len).enumerate() {
+            row.copy_from_slice(self.row(y as u32).unwrap());
+        }
+        image
+    }
+}
+
+impl ImageViewMut<'_> {
+    /// A read-only view of the same rectangle.
+    #[inline]
+    pub fn as_view(&self) -> ImageView<'_> {
+        ImageView {
+            buf: self.buf,
+            region: self.region,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.region.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.region.height
+    }
+
+    /// Position of the top-left corner in the image.
+    #[inline]
+    pub fn origin(&self) -> (u32, u32) {
+        (self.region.x, self.region.y)
+    }
+
+    #[inline]
+    pub fn format(&self) -> ImageFormat {
+        self.region.format
+    }
+
+    /// A smaller view of this one, with its top-left corner at `(x, y)` of
+    /// this view, clipped to it.
+    pub fn view_mut(&mut self, x: i32, y: i32, width: u32, height: u32) -> ImageViewMut<'_> {
+        let (x0, x1) = clip(x, width, self.width()).unwrap_or((0, 0));
+        let (y0, y1) = clip(y, height, self.height()).unwrap_or((0, 0));
+        let mut region = Region {
+            x: self.region.x + x0,
+            y: self.region.y + y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            ..self.region
+        };
+        if region.width == 0 || region.height == 0 {
+            (region.width, region.height) = (0, 0);
+        }
+        let start = self.region.row_offset(y0) + x0 as usize * region.bpp();
+        let len = region.byte_range().len();
+        ImageViewMut {
+            buf: &mut self.buf[start..start + len],
+            region,
+        }
+    }
+
+    /// Splits the view into the rows above `y` and the rest, which can be
+    /// drawn in at the same time, e.g. on different threads.
+    pub fn split_at_row(self, y: u32) -> (Self, Self) {
+        let y = y.min(self.region.height);
+        let top = Region {
+            height: y,
+            ..self.region
+        };
+        let bottom = Region {
+            y: self.region.y + y,
+            height: self.region.height - y,
+            ..self.region
+        };
+        let split = if bottom.height == 0 {
+            self.buf.len()
+        } else {
+            self.region.row_offset(y)
+        };
+        let (top_buf, bottom_buf) = self.buf.split_at_mut(split);
+        (
+            Self {
+                buf: &mut top_buf[..top.byte_range().len()],
+                region: top,
+            },
+            Self {
+                buf: bottom_buf,
+                region: bottom,
+            },
+        )
+    }
+
+    /// Splits the view into bands of `rows` rows, the last one possibly
+    /// shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` is zero.
+    pub fn into_bands(self, rows: u32) -> Vec<Self> {
+        assert!(rows > 0, "bands must have at least one row");
+        let mut bands = Vec::new();
+        let mut rest = self;
+        while rest.height() > rows {
+            let (band, below) = rest.split_at_row(rows);
+            bands.push(band);
+            rest = below;
+        }
+        bands.push(rest);
+        bands
+    }
+
+    #[inline]
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        self.as_view().row(y)
+    }
+
+    #[inline]
+    pub fn row_mut(&mut self, y: u32) -> Option<&mut [u8]> {
+        self.region.row(y).map(|range| &mut self.buf[range])
+    }
+
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> Option<&[u8]> {
+        self.as_view().get(x, y)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
+        let bpp = self.region.bpp();
+        self.region.index(x, y).map(|i| &mut self.buf[i..i + bpp])
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<&Color> {
+        self.as_view().pixel(x, y)
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut Color> {
+        self.region.assert_format(ImageFormat::Rgba8);
+        self.get_mut(x, y).map(bytemuck::from_bytes_mut)
+    }
+
+    /// Does nothing outside the view.
+    #[inline]
+    #[track_caller]
+    pub fn set(&mut self, x: u32, y: u32, color: impl Into<Color>) {
+        if let Some(pixel) = self.pixel_mut(x, y) {
+            *pixel = color.into();
+        }
+    }
+
+    #[track_caller]
+    pub fn fill(&mut self, color: impl Into<Color>) {
+        self.fill_rect(0, 0, self.width(), self.height(), color);
+    }
+
+    /// Fills the rectangle with its top-left corner at `(x, y)`, clipped to
+    /// the view.
+    #[track_caller]
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: impl Into<Color>) {
+        self.region.assert_format(ImageFormat::Rgba8);
+        let color = color.into();
+        let mut rect = self.view_mut(x, y, width, height);
+        for y in 0..rect.height() {
+            let row = rect.row_mut(y).unwrap();
+            for pixel in bytemuck::cast_slice_mut::<u8, Color>(row) {
+                *pixel = color;
+            }
+        }
+    }
+
+    /// Draws a one-pixel line including both ends, clipped to the view.
+    #[track_caller]
+    pub fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), color: impl Into<Color>) {
+        let color = color.into();
+        for (x, y) in line_drawing::Bresenham::new(from, to) {
+            if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn get_index(&self, x: u32, y: u32) -> Option<u8> {
+        self.as_view().get_index(x, y)
+    }
+
+    /// Does nothing outside the view.
+    #[inline]
+    #[track_caller]
+    pub fn set_index(&mut self, x: u32, y: u32, index: u8) {
+        self.region.assert_format(ImageFormat::Indexed8);
+        if let Some(bytes) = self.get_mut(x, y) {
+            bytes[0] = index;
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn get_value(&self, x: u32, y: u32) -> Option<f32> {
+        self.as_view().get_value(x, y)
+    }
+
+    /// Does nothing outside the view.
+    #[inline]
+    #[track_caller]
+    pub fn set_value(&mut self, x: u32, y: u32, value: f32) {
+        self.region.assert_format(ImageFormat::R32Float);
+        if let Some(bytes) = self.get_mut(x, y) {
+            bytes.copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Key bindings for the app's actions, rebindable while the app runs.
+
+use crate::winit::{KeyCode, KeyEvent};
+use std::collections::HashSet;
+use winit::keyboard::{ModifiersState, PhysicalKey};
+
+/// A key pressed while exactly the given modifiers are held, e.g. `Ctrl+S`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyChord {
+    /// The key without any modifiers.
+    #[inline]
+    pub const fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    #[inline]
+    pub const fn with_modifiers(self, modifiers: ModifiersState) -> Self {
+        Self { modifiers, ..self }
+    }
+
+    #[inline]
+    pub fn ctrl(self) -> Self {
+        self.with_modifiers(self.modifiers | ModifiersState::CONTROL)
+    }
+
+    #[inline]
+    pub fn shift(self) -> Self {
+        self.with_modifiers(self.modifiers | ModifiersState::SHIFT)
+    }
+
+    #[inline]
+    pub fn alt(self) -> Self {
+        self.with_modifiers(self.modifiers | ModifiersState::ALT)
+    }
+
+    /// Whether `event` is about this chord's key while `modifiers` are held,
+    /// whether it presses or releases the key.
+    #[inline]
+    pub fn matches(&self, event: &KeyEvent, modifiers: ModifiersState) -> bool {
+        event.physical_key == PhysicalKey::Code(self.key) && modifiers == self.modifiers
+    }
+}
+
+/// When the action of a binding fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub enum Trigger {
+    /// Once when the chord is pressed.
+    #[default]
+    Press,
+    /// When the chord is pressed, and again on every auto-repeat of the
+    /// operating system while it is held.
+    Repeat,
+    /// Once when the chord's key is released.
+    Release,
+    /// Once every frame while the chord is held.
+    Held,
+}
+
+impl Trigger {
+    /// Whether `event` fires an action with this trigger. [`Trigger::Held`]
+    /// is polled through [`KeyboardState`] instead.
+    #[inline]
+    pub fn fires_on(self, event: &KeyEvent) -> bool {
+        match self {
+            Self::Press => event.state.is_pressed() && !event.repeat,
+            Self::Repeat => event.state.is_pressed(),
+            Self::Release => !event.state.is_pressed(),
+            Self::Held => false,
+        }
+    }
+}
+
+/// Keys and modifiers currently held down, for polling input in updates
+/// instead of reacting to events. See
+/// [`AppHandle::keyboard`](crate::AppHandle::keyboard).
+///
+/// Keys are only tracked while the world has keyboard focus and the window
+/// is focused.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyboardState {
+    down: HashSet<KeyCode>,
+    modifiers: ModifiersState,
+}
+
+impl KeyboardState {
+    #[inline]
+    pub fn is_down(&self, key: KeyCode) -> bool {
+        self.down.contains(&key)
+    }
+
+    /// Whether the chord's key is down with exactly its modifiers.
+    #[inline]
+    pub fn is_chord_down(&self, chord: KeyChord) -> bool {
+        self.is_down(chord.key) && self.modifiers == chord.modifiers
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Keys held down, in no particular order.
+    #[inline]
+    pub fn down(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.down.iter().copied()
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &KeyEvent) {
+        if let PhysicalKey::Code(key) = event.physical_key {
+            self.set_down(key, event.state.is_pressed());
+        }
+    }
+
+    pub(crate) fn set_down(&mut self, key: KeyCode, down: bool) {
+        if down {
+            self.down.insert(key);
+        } else {
+            self.down.remove(&key);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    /// Forgets held keys, whose releases will not be seen, e.g. when the
+    /// window loses focus.
+    #[inline]
+    pub(crate) fn release_all(&mut self) {
+        self.down.clear();
+    }
+}
+
+impl From<KeyCode> for KeyChord {
+    #[inline]
+    fn from(key: KeyCode) -> Self {
+        Self::new(key)
+    }
+}
+
+/// Parses chords like `"Ctrl+Shift+KeyS"` or `"F3"`: modifiers `Ctrl`,
+/// `Shift`, `Alt` or `Super` joined by `+` to the name of a [`KeyCode`].
+/// Single letters and digits also stand for their keys, as in `"Ctrl+S"`.
+#[cfg(feature = "serde")]
+impl std::str::FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use serde::{Deserialize as _, de::IntoDeserializer as _};
+
+        let mut parts: Vec<_> = s.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" => ModifiersState::ALT,
+                "super" | "cmd" | "meta" => ModifiersState::SUPER,
+                _ => anyhow::bail!("unknown modifier `{part}` in `{s}`"),
+            };
+        }
+
+        let name = match key.as_bytes() {
+            [c] if c.is_ascii_alphabetic() => format!("Key{}", key.to_ascii_uppercase()),
+            [c] if c.is_ascii_digit() => format!("Digit{key}"),
+            _ => key.to_owned(),
+        };
+        let deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            name.as_str().into_deserializer();
+        let key = KeyCode::deserialize(deserializer)
+            .map_err(|_| anyhow::anyhow!("unknown key `{key}` in `{s}`"))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KeyChord {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Bindings from key chords to actions of type `A`.
+///
+/// An action can have any number of chords, and a chord can trigger several
+/// actions. Bindings are kept in the order they were added. Each action
+/// fires on a [`Trigger`], [`Trigger::Press`] unless set otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMap<A> {
+    bindings: Vec<(KeyChord, A)>,
+    triggers: Vec<(A, Trigger)>,
+}
+
+impl<A> Default for InputMap<A> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            triggers: Vec::new(),
+        }
+    }
+}
+
+impl<A: Copy + PartialEq> InputMap<A> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding, keeping the others.
+    #[inline]
+    pub fn with(mut self, chord: impl Into<KeyChord>, action: A) -> Self {
+        self.bind(chord, action);
+        self
+    }
+
+    /// Adds a binding, keeping the others.
+    pub fn bind(&mut self, chord: impl Into<KeyChord>, action: A) {
+        let binding = (chord.into(), action);
+        if !self.bindings.contains(&binding) {
+            self.bindings.push(binding);
+        }
+    }
+
+    /// Replaces all bindings of `action` with `chord`, or removes them for
+    /// `None`.
+    pub fn rebind(&mut self, action: A, chord: Option<KeyChord>) {
+        self.unbind_action(action);
+        if let Some(chord) = chord {
+            self.bind(chord, action);
+        }
+    }
+
+    /// Sets when `action` fires.
+    #[inline]
+    pub fn with_trigger(mut self, action: A, trigger: Trigger) -> Self {
+        self.set_trigger(action, trigger);
+        self
+    }
+
+    /// Sets when `action` fires.
+    pub fn set_trigger(&mut self, action: A, trigger: Trigger) {
+        self.triggers.retain(|(a, _)| *a != action);
+        if trigger != Trigger::default() {
+            self.triggers.push((action, trigger));
+        }
+    }
+
+    pub fn trigger(&self, action: A) -> Trigger {
+        self.triggers
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or_else(Trigger::default, |(_, trigger)| *trigger)
+    }
+
+    /// Removes every binding of `chord`.
+    pub fn unbind(&mut self, chord: impl Into<KeyChord>) {
+        let chord = chord.into();
+        self.bindings.retain(|(c, _)| *c != chord);
+    }
+
+    /// Removes every binding of `action`.
+    pub fn unbind_action(&mut self, action: A) {
+        self.bindings.retain(|(_, a)| *a != action);
+    }
+
+    /// Chords bound to `action`.
+    pub fn chords(&self, action: A) -> impl Iterator<Item = KeyChord> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(chord, _)| *chord)
+    }
+
+    #[inline]
+    pub fn bindings(&self) -> &[(KeyChord, A)] {
+        &self.bindings
+    }
+
+    /// Actions fired by `event` while `modifiers` are held, according to
+    /// their triggers.
+    pub fn fired<'a>(
+        &'a self,
+        event: &'a KeyEvent,
+        modifiers: ModifiersState,
+    ) -> impl Iterator<Item = A> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |(chord, action)| {
+                chord.matches(event, modifiers) && self.trigger(*action).fires_on(event)
+            })
+            .map(|(_, action)| *action)
+    }
+
+    /// Actions with [`Trigger::Held`] whose chords are down in `keyboard`.
+    pub fn held<'a>(&'a self, keyboard: &'a KeyboardState) -> impl Iterator<Item = A> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |(chord, action)| {
+                self.trigger(*action) == Trigger::Held && keyboard.is_chord_down(*chord)
+            })
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Actions of the app itself, bound through
+/// [`AppConfigs::input_map`](crate::AppConfigs::input_map).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[non_exhaustive]
+pub enum AppAction {
+    /// Pauses or resumes the updates.
+    Play,
+    /// Runs a single update while paused.
+    UpdateOnce,
+    /// Toggles the grid lines.
+    Grid,
+    /// Toggles the frame time graph.
+    FrameGraph,
+    /// Toggles the heads-up display.
+    Hud,
+    /// Toggles the cell inspector.
+    Inspector,
+    /// Toggles showing only the cells changed by the last update.
+    DiffView,
+    /// Switches between nearest and linear
+    /// [`TextureFilter`](crate::TextureFilter)s.
+    TextureFilter,
+    /// Raises the exposure of the
+    /// [`DisplayAdjustments`](crate::DisplayAdjustments) by half a stop.
+    ExposureUp,
+    /// Lowers the exposure by half a stop.
+    ExposureDown,
+    /// Restores the configured display adjustments. Not bound by default.
+    ResetDisplay,
+    /// Moves keyboard focus to text input.
+    TextFocus,
+    /// Opens the command palette, if
+    /// [`AppConfigs::command_palette`](crate::AppConfigs::command_palette)
+    /// is on.
+    CommandPalette,
+    /// Undoes zooming and panning.
+    ResetView,
+    /// Closes the window, asking [`World::on_close_requested`] first like
+    /// the close button. Not bound by default.
+    ///
+    /// [`World::on_close_requested`]: crate::World::on_close_requested
+    Quit,
+    /// Captures the next frame with an attached graphics debugger.
+    #[cfg(feature = "frame-capture")]
+    Capture,
+    /// Starts or stops recording a video, see
+    /// [`AppConfigs::video`](crate::AppConfigs::video).
+    #[cfg(feature = "video")]
+    Record,
+}
+
+impl InputMap<AppAction> {
+    /// The bindings of a default [`AppConfigs`](crate::AppConfigs). All
+    /// actions fire on press, except that holding the key of
+    /// [`AppAction::UpdateOnce`] keeps stepping with the key repeat.
+    pub fn app_defaults() -> Self {
+        let map = Self::new()
+            .with(KeyCode::Space, AppAction::Play)
+            .with(KeyCode::Enter, AppAction::UpdateOnce)
+            .with(KeyCode::KeyG, AppAction::Grid)
+            .with(KeyCode::F3, AppAction::FrameGraph)
+            .with(KeyCode::F2, AppAction::Hud)
+            .with(KeyCode::F4, AppAction::Inspector)
+            .with(KeyCode::F5, AppAction::DiffView)
+            .with(KeyCode::F6, AppAction::TextureFilter)
+            .with(KeyCode::F7, AppAction::ExposureDown)
+            .with(KeyCode::F8, AppAction::ExposureUp)
+            .with(
+                KeyChord::new(KeyCode::KeyP).ctrl(),
+                AppAction::CommandPalette,
+            )
+            // `:` on US layouts.
+            .with(
+                KeyChord::new(KeyCode::Semicolon).shift(),
+                AppAction::CommandPalette,
+            )
+            .with(KeyCode::Home, AppAction::ResetView)
+            .with_trigger(AppAction::UpdateOnce, Trigger::Repeat);
+        #[cfg(feature = "frame-capture")]
+        let map = map.with(KeyCode::F12, AppAction::Capture);
+        #[cfg(feature = "video")]
+        let map = map.with(KeyCode::F9, AppAction::Record);
+        map
+    }
+}
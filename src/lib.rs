@@ -2,28 +2,138 @@ pub mod winit {
     pub use winit::{
         event::KeyEvent,
         event::{ElementState, MouseButton},
-        keyboard::KeyCode,
+        keyboard::{KeyCode, ModifiersState},
         window::WindowAttributes,
     };
+
+    #[cfg(all(feature = "android", target_os = "android"))]
+    pub use winit::platform::android::activity::AndroidApp;
+}
+
+pub mod wgpu {
+    pub use wgpu::{Backends, PowerPreference};
 }
 
+/// Enters a `tracing` span until the end of the enclosing block, with the
+/// `tracing` feature on.
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+
+pub mod color;
+pub use color::Color;
+
+pub mod colormap;
+pub use colormap::Colormap;
+
 pub mod image;
-pub use image::WorldImage;
+#[cfg(feature = "ttf")]
+pub use image::TtfFont;
+pub use image::{
+    BlendMode, ColorSpace, Heatmap, ImageFormat, ImageVersion, ImageView, ImageViewMut, Sprite,
+    ValueStats, WorldImage,
+};
 
 pub mod configs;
-pub use configs::AppConfigs;
+pub use configs::{
+    AppConfigs, DiffStyle, DisplayAdjustments, ErrorHandler, PaletteCycle, TextureFilter,
+    WatchdogAction,
+};
+
+pub mod input;
+pub use input::{AppAction, InputMap, KeyChord, KeyboardState, Trigger};
 
 pub mod mouse_event;
-pub use mouse_event::MouseEvent;
+pub use mouse_event::{Gestures, MouseEvent, MouseEventKind};
+
+pub mod focus;
+pub use focus::InputFocus;
 
 pub mod world;
-pub use world::World;
+pub use world::{CloseAction, FrameStats, FrameTiming, RateStats, UpdateCtx, World};
+
+pub mod transform;
+pub use transform::{CellPosition, CursorRounding, WorldTransform};
+
+pub mod lod;
+pub use lod::Lod;
+
+pub mod grid;
+pub use grid::{Boundary, Grid, Neighborhood};
+
+pub mod pattern;
+pub use pattern::Pattern;
+
+pub mod rules;
+pub use rules::{ElementaryRule, ElementaryWorld, LifeLikeWorld, Rule};
 
 pub mod app;
 pub use app::App;
 
+pub mod commands;
+#[allow(deprecated)]
+pub use commands::AppCommands;
+pub use commands::AppHandle;
+
+pub mod checkpoint;
+
+pub mod timelapse;
+
+#[cfg(feature = "config-file")]
+pub mod config_file;
+
+#[cfg(feature = "scripting")]
+pub mod scripting;
+
+#[cfg(feature = "remote")]
+pub mod remote;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+
+#[cfg(feature = "video")]
+pub mod video;
+
+#[cfg(feature = "terminal")]
+pub mod terminal;
+#[cfg(feature = "terminal")]
+pub use terminal::TerminalApp;
+
+pub mod headless;
+
+pub mod testing;
+
+pub mod overlay;
+pub use overlay::{Agent, AgentShape, HoverHighlight, Overlay, VectorField};
+
+pub mod metrics;
+pub use metrics::Metrics;
+
+pub mod plot;
+pub use plot::Plot;
+
+pub mod rng;
+pub use rng::Rng;
+
+pub mod tutorial;
+pub use tutorial::Tutorial;
+
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+
 pub mod util;
 
+mod font;
+
 pub mod prelude {
-    pub use crate::{App, AppConfigs, MouseEvent, World as WorldTrait, WorldImage, winit::*};
+    pub use crate::{
+        App, AppConfigs, Color, MouseEvent, UpdateCtx, World as WorldTrait, WorldImage,
+        WorldTransform, winit::*,
+    };
 }
@@ -0,0 +1,59 @@
+use crate::WorldTransform;
+use std::ops::Range;
+
+/// Visible part of the world and the detail it is shown at, passed to
+/// [`World::lod_changed`](crate::World::lod_changed).
+///
+/// Worlds with more cells than they can afford to draw every frame can use
+/// it to draw a coarse summary when zoomed far out, and full-resolution
+/// tiles of only the visible region when zoomed in.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Lod {
+    /// Window pixels covered by one cell.
+    pub pixels_per_cell: f64,
+    /// `0` while a cell covers at least one window pixel, `n` when about
+    /// `2^n` cells share a window pixel along each axis.
+    pub level: u32,
+    /// Columns of cells visible in the window, clamped to the world.
+    pub visible_x: Range<u32>,
+    /// Rows of cells visible in the window, clamped to the world.
+    pub visible_y: Range<u32>,
+}
+
+impl Lod {
+    pub(crate) fn new(transform: &WorldTransform, window_size: (u32, u32)) -> Self {
+        let pixels_per_cell = transform.cell_size().0.min(transform.cell_size().1);
+        let level = if pixels_per_cell >= 1.0 {
+            0
+        } else {
+            (1.0 / pixels_per_cell).log2().floor() as u32
+        };
+
+        let (width, height) = transform.world_size();
+        let (x0, y0) = transform.window_to_world((0.0, 0.0));
+        let (x1, y1) = transform.window_to_world((window_size.0 as f64, window_size.1 as f64));
+        let clamp = |val: f64, len: u32| val.clamp(0.0, len as f64) as u32;
+
+        Self {
+            pixels_per_cell,
+            level,
+            visible_x: clamp(x0.floor(), width)..clamp(x1.ceil(), width),
+            visible_y: clamp(y0.floor(), height)..clamp(y1.ceil(), height),
+        }
+    }
+
+    /// Number of cells sharing a window pixel along each axis, `2^level`.
+    #[inline]
+    pub fn cells_per_pixel(&self) -> u32 {
+        1 << self.level.min(31)
+    }
+
+    /// Coordinates of the `tile_size` x `tile_size` tiles overlapping the
+    /// visible region, row by row.
+    pub fn visible_tiles(&self, tile_size: u32) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let tiles = |range: &Range<u32>| range.start / tile_size..range.end.div_ceil(tile_size);
+        let xs = tiles(&self.visible_x);
+        tiles(&self.visible_y).flat_map(move |y| xs.clone().map(move |x| (x, y)))
+    }
+}
@@ -0,0 +1,303 @@
+//! Named values recorded by a world each tick and written to a table with a
+//! row per tick, for analyzing simulation runs offline.
+//!
+//! Worlds record into the [`Metrics`] of [`AppHandle::metrics`], and the
+//! app writes them as configured with
+//! [`AppConfigs::metrics`](crate::AppConfigs::metrics):
+//!
+//! ```no_run
+//! # use cells_renderer::AppHandle;
+//! # fn update(handle: &AppHandle, population: usize, births: usize) {
+//! let metrics = handle.metrics();
+//! metrics.record("population", population as f64);
+//! metrics.record("births", births as f64);
+//! # }
+//! ```
+//!
+//! [`AppHandle::metrics`]: crate::AppHandle::metrics
+
+use anyhow::Context as _;
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// File format of a [`MetricsExport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MetricsFormat {
+    /// Comma-separated values with a header row, leaving values that were not
+    /// recorded in a tick empty.
+    #[default]
+    Csv,
+    /// An Apache Parquet file with a non-null `INT64` tick column and a
+    /// nullable `DOUBLE` column per metric.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+/// Where and how often a running [`App`](crate::App) writes its
+/// [`Metrics`], configured with
+/// [`AppConfigs::metrics`](crate::AppConfigs::metrics).
+///
+/// The whole table is written again each time, also when the app exits.
+/// Failing to write it is reported to the error handler without stopping
+/// the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsExport {
+    pub path: PathBuf,
+    pub format: MetricsFormat,
+    /// Wall-clock time between writes while the app runs, or `None` to
+    /// write only on exit.
+    pub interval: Option<Duration>,
+}
+
+impl MetricsExport {
+    /// Writes CSV to `path` on exit.
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: MetricsFormat::default(),
+            interval: None,
+        }
+    }
+
+    #[inline]
+    pub fn format(self, format: MetricsFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    #[inline]
+    pub fn interval(self, interval: Option<Duration>) -> Self {
+        Self { interval, ..self }
+    }
+}
+
+/// Cloneable handle to a table of named values with a row per tick, shared
+/// by the app and its world, possibly on other threads.
+///
+/// Columns are added in the order their names are first recorded. Values
+/// recorded in the same tick land in the same row, and recording a name
+/// twice in a tick keeps the last value.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    table: Arc<Mutex<Table>>,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    tick: u64,
+    columns: Vec<String>,
+    rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    tick: u64,
+    /// One per column, NaN for values not recorded in the tick.
+    values: Vec<f64>,
+}
+
+impl Metrics {
+    /// An empty table, e.g. for recording a headless run, starting at tick
+    /// zero.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `name` to `value` in the row of the current tick.
+    pub fn record(&self, name: &str, value: f64) {
+        let mut table = self.lock();
+        let column = match table.columns.iter().position(|column| column == name) {
+            Some(column) => column,
+            None => {
+                table.columns.push(name.to_owned());
+                table.columns.len() - 1
+            }
+        };
+        let tick = table.tick;
+        if table.rows.last().is_none_or(|row| row.tick != tick) {
+            table.rows.push(Row {
+                tick,
+                values: Vec::new(),
+            });
+        }
+        let values = &mut table.rows.last_mut().unwrap().values;
+        if values.len() <= column {
+            values.resize(column + 1, f64::NAN);
+        }
+        values[column] = value;
+    }
+
+    /// The tick that values are recorded in, set by the app before each
+    /// update.
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.lock().tick
+    }
+
+    /// Moves on to `tick`, e.g. from a headless loop.
+    #[inline]
+    pub fn set_tick(&self, tick: u64) {
+        self.lock().tick = tick;
+    }
+
+    /// Names of the columns in the order they were first recorded, without
+    /// the tick.
+    #[inline]
+    pub fn columns(&self) -> Vec<String> {
+        self.lock().columns.clone()
+    }
+
+    /// Number of rows, i.e. of ticks with recorded values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().rows.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().rows.is_empty()
+    }
+
+    /// Values of `name` with their ticks, oldest first, skipping ticks it
+    /// was not recorded in.
+    pub fn values(&self, name: &str) -> Vec<(u64, f64)> {
+        let table = self.lock();
+        let Some(column) = table.columns.iter().position(|column| column == name) else {
+            return Vec::new();
+        };
+        table
+            .rows
+            .iter()
+            .filter_map(|row| Some((row.tick, *row.values.get(column)?)))
+            .filter(|(_, value)| !value.is_nan())
+            .collect()
+    }
+
+    /// Removes all rows and columns, keeping the tick.
+    #[inline]
+    pub fn clear(&self) {
+        let mut table = self.lock();
+        table.columns.clear();
+        table.rows.clear();
+    }
+
+    /// The table as CSV with a `tick` column first.
+    pub fn to_csv(&self) -> String {
+        let table = self.lock();
+        let mut csv = String::from("tick");
+        for column in &table.columns {
+            csv.push(',');
+            csv.push_str(&csv_field(column));
+        }
+        csv.push('\n');
+        for row in &table.rows {
+            write!(csv, "{}", row.tick).unwrap();
+            for column in 0..table.columns.len() {
+                csv.push(',');
+                match row.values.get(column) {
+                    Some(value) if !value.is_nan() => write!(csv, "{value}").unwrap(),
+                    _ => (),
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Writes the table to `path` in `format`, replacing the file.
+    pub fn write(&self, path: impl AsRef<Path>, format: MetricsFormat) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let result: anyhow::Result<()> = match format {
+            MetricsFormat::Csv => fs::write(path, self.to_csv()).map_err(Into::into),
+            #[cfg(feature = "parquet")]
+            MetricsFormat::Parquet => self.write_parquet(path),
+        };
+        result.with_context(|| format!("failed to write metrics to {}", path.display()))
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_parquet(&self, path: &Path) -> anyhow::Result<()> {
+        use parquet::{
+            basic::{Repetition, Type as PhysicalType},
+            data_type::{DoubleType, Int64Type},
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+            schema::types::Type,
+        };
+
+        let table = self.lock();
+        let column_type = |name: &str, physical_type, repetition| {
+            Type::primitive_type_builder(name, physical_type)
+                .with_repetition(repetition)
+                .build()
+                .map(Arc::new)
+        };
+        let mut fields = vec![column_type(
+            "tick",
+            PhysicalType::INT64,
+            Repetition::REQUIRED,
+        )?];
+        for column in &table.columns {
+            fields.push(column_type(
+                column,
+                PhysicalType::DOUBLE,
+                Repetition::OPTIONAL,
+            )?);
+        }
+        let schema = Type::group_type_builder("metrics")
+            .with_fields(fields)
+            .build()?;
+
+        let file = fs::File::create(path)?;
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), properties)?;
+        let mut row_group = writer.next_row_group()?;
+
+        let ticks: Vec<i64> = table.rows.iter().map(|row| row.tick as i64).collect();
+        let mut tick_column = row_group.next_column()?.context("missing tick column")?;
+        tick_column
+            .typed::<Int64Type>()
+            .write_batch(&ticks, None, None)?;
+        tick_column.close()?;
+
+        for column in 0..table.columns.len() {
+            let mut values = Vec::new();
+            let mut definition_levels = Vec::with_capacity(table.rows.len());
+            for row in &table.rows {
+                match row.values.get(column) {
+                    Some(&value) if !value.is_nan() => {
+                        values.push(value);
+                        definition_levels.push(1);
+                    }
+                    _ => definition_levels.push(0),
+                }
+            }
+            let mut writer = row_group.next_column()?.context("missing metric column")?;
+            writer
+                .typed::<DoubleType>()
+                .write_batch(&values, Some(&definition_levels), None)?;
+            writer.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Table> {
+        self.table.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// `field` quoted if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
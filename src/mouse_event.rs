@@ -1,8 +1,137 @@
 use crate::winit::{ElementState, MouseButton};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseEvent {
     pub state: ElementState,
     pub button: MouseButton,
     pub pos: Option<(u32, u32)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: MouseEventKind,
+}
+
+/// What a [`MouseEvent`] reports. Everything but [`MouseEventKind::Button`]
+/// is only sent with [`AppConfigs::gestures`](crate::AppConfigs::gestures)
+/// set, in addition to the plain presses and releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MouseEventKind {
+    /// The button was pressed or released, as told by `state`.
+    #[default]
+    Button,
+    /// The button was released without dragging. Sent after the release.
+    Click,
+    /// A second click close enough to the last one. Sent after its
+    /// [`MouseEventKind::Click`].
+    DoubleClick,
+    /// The cursor moved far enough from where the button was pressed.
+    /// `pos` is the cell under the cursor now, not where the drag started.
+    DragStart,
+    /// The button was released after a drag. Sent after the release.
+    DragEnd,
+}
+
+/// Thresholds of the gestures reported through [`MouseEventKind`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gestures {
+    /// Window pixels the cursor moves with a button held before a click
+    /// turns into a drag. Defaults to 4.
+    pub drag_distance: f64,
+    /// Longest time between two clicks of a double click. Defaults to 500
+    /// ms.
+    pub double_click_interval: Duration,
+}
+
+impl Default for Gestures {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            drag_distance: 4.0,
+            double_click_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    button: MouseButton,
+    origin: (f64, f64),
+    dragging: bool,
+}
+
+/// Turns presses, releases and cursor moves in window pixels into gestures.
+/// Only the first of several buttons held together is tracked.
+#[derive(Debug, Default)]
+pub(crate) struct GestureTracker {
+    press: Option<Press>,
+    last_click: Option<(MouseButton, Instant, (f64, f64))>,
+}
+
+impl GestureTracker {
+    pub(crate) fn press(&mut self, button: MouseButton, position: (f64, f64)) {
+        if self.press.is_none() {
+            self.press = Some(Press {
+                button,
+                origin: position,
+                dragging: false,
+            });
+        }
+    }
+
+    /// The button that started dragging with this move, if any.
+    pub(crate) fn moved(
+        &mut self,
+        position: (f64, f64),
+        gestures: &Gestures,
+    ) -> Option<MouseButton> {
+        let press = self.press.as_mut().filter(|press| !press.dragging)?;
+        if distance(press.origin, position) < gestures.drag_distance {
+            return None;
+        }
+        press.dragging = true;
+        Some(press.button)
+    }
+
+    /// The gestures completed by releasing `button`, in the order they
+    /// should be sent.
+    pub(crate) fn release(
+        &mut self,
+        button: MouseButton,
+        position: (f64, f64),
+        gestures: &Gestures,
+    ) -> Vec<MouseEventKind> {
+        let Some(press) = self.press.filter(|press| press.button == button) else {
+            return Vec::new();
+        };
+        self.press = None;
+        if press.dragging {
+            self.last_click = None;
+            return vec![MouseEventKind::DragEnd];
+        }
+
+        let now = Instant::now();
+        let double = self.last_click.is_some_and(|(last, time, origin)| {
+            last == button
+                && now - time <= gestures.double_click_interval
+                && distance(origin, position) < gestures.drag_distance
+        });
+        if double {
+            // A third click starts over rather than making another double.
+            self.last_click = None;
+            vec![MouseEventKind::Click, MouseEventKind::DoubleClick]
+        } else {
+            self.last_click = Some((button, now, position));
+            vec![MouseEventKind::Click]
+        }
+    }
+
+    /// Forgets the held button, whose release will not be seen.
+    pub(crate) fn cancel(&mut self) {
+        self.press = None;
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
 }
@@ -0,0 +1,498 @@
+use crate::{Color, WorldTransform, app::overlay::Shapes};
+use std::{collections::HashSet, ops::Range};
+
+/// Coordinates used by the drawing methods of an [`Overlay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Space {
+    /// Window pixels with the origin at the top-left corner of the window.
+    #[default]
+    Screen,
+    /// Fractional cells with the origin at the top-left corner of the world,
+    /// following the view as it is zoomed and panned. Cell `(x, y)` covers
+    /// `(x, y)` to `(x + 1, y + 1)`.
+    World,
+}
+
+/// How the cells under the cursor are marked with
+/// [`AppConfigs::hover_highlight`](crate::AppConfigs::hover_highlight).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HoverHighlight {
+    /// Blends the color over the cells, usually with a low alpha.
+    Tint(Color),
+    /// Draws a line of the given width in window pixels around the cells.
+    Outline(Color, f32),
+}
+
+impl HoverHighlight {
+    pub(crate) fn draw(self, cells: &[(u32, u32)], overlay: &mut Overlay) {
+        match self {
+            Self::Tint(color) => overlay.highlight_cells(cells.iter().copied(), color),
+            Self::Outline(color, width) => {
+                overlay.outline_cells(cells.iter().copied(), width, color);
+            }
+        }
+    }
+}
+
+/// A creature, particle or other mover drawn above the world at a position
+/// between cells, collected with [`World::draw_agents`](crate::World::draw_agents).
+///
+/// Agents are drawn in world space as one instanced quad each, so tens of
+/// thousands of them are cheap, above the grid and below the [`Overlay`].
+/// Like circles of the overlay, they are stretched along with cells that are
+/// not square. Agents smaller than two window pixels are drawn two pixels
+/// wide so that they stay visible when zoomed out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Agent {
+    /// Center in fractional cells, see [`Space::World`].
+    pub pos: (f32, f32),
+    /// Width and height in cells.
+    pub size: f32,
+    pub color: Color,
+    /// Clockwise angle in radians, from facing right.
+    pub rotation: f32,
+    pub shape: AgentShape,
+}
+
+impl Agent {
+    /// A disc one cell wide at `pos`.
+    #[inline]
+    pub fn new(pos: (f32, f32), color: impl Into<Color>) -> Self {
+        Self {
+            pos,
+            size: 1.0,
+            color: color.into(),
+            rotation: 0.0,
+            shape: AgentShape::default(),
+        }
+    }
+
+    #[inline]
+    pub fn size(self, size: f32) -> Self {
+        Self { size, ..self }
+    }
+
+    #[inline]
+    pub fn rotation(self, rotation: f32) -> Self {
+        Self { rotation, ..self }
+    }
+
+    #[inline]
+    pub fn shape(self, shape: AgentShape) -> Self {
+        Self { shape, ..self }
+    }
+}
+
+/// Outline of an [`Agent`] within its square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AgentShape {
+    #[default]
+    Circle,
+    Square,
+    /// A triangle pointing in the direction of the rotation, e.g. for boids.
+    Arrow,
+}
+
+/// Arrows drawn above the world, one per block of cells, collected with
+/// [`World::draw_vector_field`](crate::World::draw_vector_field), e.g. the
+/// velocity of a fluid or the gradient of a potential.
+///
+/// To keep arrows readable when zoomed out, the world is divided into blocks
+/// of [`VectorField::step`] cells along each axis, chosen so that arrows are
+/// at least [`AppConfigs::vector_spacing`](crate::AppConfigs::vector_spacing)
+/// window pixels apart, and only the blocks in view are sampled. Each arrow
+/// is centered on its block.
+///
+/// Changes to the style fields apply to the arrows added afterwards.
+#[derive(Debug, Clone)]
+pub struct VectorField {
+    step: u32,
+    visible_x: Range<u32>,
+    visible_y: Range<u32>,
+    /// Color of arrows added without one. Defaults to white.
+    pub color: Color,
+    /// Length of the arrow of a vector of length 1, relative to the distance
+    /// between arrows. Defaults to `0.9`.
+    pub scale: f32,
+    /// Width of the shafts in window pixels. Defaults to `1.5`.
+    pub width: f32,
+    pub(crate) arrows: Vec<Arrow>,
+}
+
+/// An arrow in world space, from `center - vector / 2` to
+/// `center + vector / 2`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Arrow {
+    pub center: (f32, f32),
+    pub vector: (f32, f32),
+    pub color: Color,
+    pub width: f32,
+}
+
+impl VectorField {
+    pub(crate) fn new(step: u32, visible_x: Range<u32>, visible_y: Range<u32>) -> Self {
+        Self {
+            step: step.max(1),
+            visible_x,
+            visible_y,
+            color: Color::WHITE,
+            scale: 0.9,
+            width: 1.5,
+            arrows: Vec::new(),
+        }
+    }
+
+    /// Cells per block along each axis, a power of two.
+    #[inline]
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// The top-left cells of the visible blocks, row by row. Sampling the
+    /// field there, or averaging it over each block, gives one vector per
+    /// arrow.
+    pub fn points(&self) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let step = self.step;
+        let blocks = |range: &Range<u32>| range.start / step..range.end.div_ceil(step);
+        let xs = blocks(&self.visible_x);
+        blocks(&self.visible_y).flat_map(move |y| xs.clone().map(move |x| (x * step, y * step)))
+    }
+
+    /// Adds the arrow of the block containing the cell `(x, y)`, in cells
+    /// with `y` pointing down. Zero vectors are skipped.
+    pub fn set(&mut self, x: u32, y: u32, vector: (f32, f32)) {
+        self.set_colored(x, y, vector, self.color);
+    }
+
+    pub fn set_colored(&mut self, x: u32, y: u32, vector: (f32, f32), color: impl Into<Color>) {
+        if vector == (0.0, 0.0) {
+            return;
+        }
+        let step = self.step;
+        let center = |v: u32| (v / step * step) as f32 + step as f32 / 2.0;
+        let length = self.scale * step as f32;
+        self.arrows.push(Arrow {
+            center: (center(x), center(y)),
+            vector: (vector.0 * length, vector.1 * length),
+            color: color.into(),
+            width: self.width,
+        });
+    }
+
+    /// Adds an arrow for each of [`VectorField::points`], skipping those
+    /// `vector` returns `None` for.
+    pub fn fill(&mut self, mut vector: impl FnMut(u32, u32) -> Option<(f32, f32)>) {
+        for (x, y) in self.points() {
+            if let Some(v) = vector(x, y) {
+                self.set(x, y, v);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty()
+    }
+
+    /// An empty field sampling the same blocks.
+    pub(crate) fn empty_like(&self) -> Self {
+        Self::new(self.step, self.visible_x.clone(), self.visible_y.clone())
+    }
+
+    /// An empty field for the part of the world from column `offset`
+    /// that is `width` cells wide, in its own coordinates.
+    pub(crate) fn pane(&self, offset: u32, width: u32) -> Self {
+        let clip = |v: u32| v.saturating_sub(offset).min(width);
+        Self::new(
+            self.step,
+            clip(self.visible_x.start)..clip(self.visible_x.end),
+            self.visible_y.clone(),
+        )
+    }
+
+    /// Appends the arrows of `other`, moved right by `offset` columns.
+    pub(crate) fn extend(&mut self, other: &Self, offset: u32) {
+        self.arrows.extend(other.arrows.iter().map(|arrow| Arrow {
+            center: (arrow.center.0 + offset as f32, arrow.center.1),
+            ..*arrow
+        }));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Shape {
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+        width: f32,
+    },
+    Rect {
+        min: (f64, f64),
+        max: (f64, f64),
+    },
+    RectOutline {
+        min: (f64, f64),
+        max: (f64, f64),
+        width: f32,
+    },
+    Circle {
+        center: (f64, f64),
+        radius: f64,
+    },
+    CircleOutline {
+        center: (f64, f64),
+        radius: f64,
+        width: f32,
+    },
+    Text {
+        pos: (f64, f64),
+        scale: f32,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Command {
+    space: Space,
+    color: Color,
+    shape: Shape,
+}
+
+/// Shapes drawn above the world and the grid for a single frame, passed to
+/// [`World::draw_overlay`](crate::World::draw_overlay).
+///
+/// Positions and radii are in the current [`Space`], screen space unless
+/// changed with [`Overlay::set_space`]. Line widths and text scales are
+/// always in window pixels. Shapes are drawn in the order they were added.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    window_size: (u32, u32),
+    transform: WorldTransform,
+    space: Space,
+    commands: Vec<Command>,
+}
+
+impl Overlay {
+    pub(crate) fn new(window_size: (u32, u32), transform: WorldTransform) -> Self {
+        Self {
+            window_size,
+            transform,
+            space: Space::default(),
+            commands: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn window_size(&self) -> (u32, u32) {
+        self.window_size
+    }
+
+    /// Mapping between window and world coordinates in this frame.
+    #[inline]
+    pub fn transform(&self) -> &WorldTransform {
+        &self.transform
+    }
+
+    #[inline]
+    pub fn space(&self) -> Space {
+        self.space
+    }
+
+    /// Sets the coordinates used by the shapes added from now on.
+    #[inline]
+    pub fn set_space(&mut self, space: Space) {
+        self.space = space;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), width: f32, color: impl Into<Color>) {
+        self.push(color, Shape::Line { from, to, width });
+    }
+
+    /// Fills the rectangle between the corners `min` and `max`.
+    pub fn rect(&mut self, min: (f64, f64), max: (f64, f64), color: impl Into<Color>) {
+        self.push(color, Shape::Rect { min, max });
+    }
+
+    /// Draws the outline of a rectangle, e.g. a bounding box, `width` pixels
+    /// thick on its inside.
+    pub fn rect_outline(
+        &mut self,
+        min: (f64, f64),
+        max: (f64, f64),
+        width: f32,
+        color: impl Into<Color>,
+    ) {
+        self.push(color, Shape::RectOutline { min, max, width });
+    }
+
+    /// Fills a circle. In world space it is stretched along with the cells.
+    pub fn circle(&mut self, center: (f64, f64), radius: f64, color: impl Into<Color>) {
+        self.push(color, Shape::Circle { center, radius });
+    }
+
+    pub fn circle_outline(
+        &mut self,
+        center: (f64, f64),
+        radius: f64,
+        width: f32,
+        color: impl Into<Color>,
+    ) {
+        self.push(
+            color,
+            Shape::CircleOutline {
+                center,
+                radius,
+                width,
+            },
+        );
+    }
+
+    /// Draws `text` in the built-in 5x7 bitmap font with its top-left corner
+    /// at `pos`, each font pixel `scale` window pixels wide. `'\n'` starts a
+    /// new line and characters outside printable ASCII are drawn as `?`.
+    pub fn text(&mut self, pos: (f64, f64), scale: f32, text: &str, color: impl Into<Color>) {
+        self.push(
+            color,
+            Shape::Text {
+                pos,
+                scale,
+                text: text.to_owned(),
+            },
+        );
+    }
+
+    /// Fills the cell `(x, y)`, whatever the current space.
+    pub fn cell(&mut self, x: u32, y: u32, color: impl Into<Color>) {
+        self.commands.push(Command {
+            space: Space::World,
+            color: color.into(),
+            shape: Shape::Rect {
+                min: (x as f64, y as f64),
+                max: (x as f64 + 1.0, y as f64 + 1.0),
+            },
+        });
+    }
+
+    /// Fills each of `cells` like [`Overlay::cell`], e.g. the results of a
+    /// search. Use a translucent color to tint them.
+    pub fn highlight_cells(
+        &mut self,
+        cells: impl IntoIterator<Item = (u32, u32)>,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        for (x, y) in cells {
+            self.cell(x, y, color);
+        }
+    }
+
+    /// Draws a line `width` window pixels wide around `cells` as a whole,
+    /// e.g. around a selection, skipping the edges between two of them.
+    /// Like [`Overlay::cell`], whatever the current space.
+    pub fn outline_cells(
+        &mut self,
+        cells: impl IntoIterator<Item = (u32, u32)>,
+        width: f32,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        let set: HashSet<(u32, u32)> = cells.into_iter().collect();
+        let has = |x: u32, y: u32, dx: i64, dy: i64| {
+            let (x, y) = (x as i64 + dx, y as i64 + dy);
+            x >= 0 && y >= 0 && set.contains(&(x as u32, y as u32))
+        };
+        let space = self.space;
+        self.space = Space::World;
+        for &(x, y) in &set {
+            let (x0, y0) = (x as f64, y as f64);
+            let (x1, y1) = (x0 + 1.0, y0 + 1.0);
+            let edges = [
+                (0, -1, (x0, y0), (x1, y0)),
+                (0, 1, (x0, y1), (x1, y1)),
+                (-1, 0, (x0, y0), (x0, y1)),
+                (1, 0, (x1, y0), (x1, y1)),
+            ];
+            for (dx, dy, from, to) in edges {
+                if !has(x, y, dx, dy) {
+                    self.line(from, to, width, color);
+                }
+            }
+        }
+        self.space = space;
+    }
+
+    fn push(&mut self, color: impl Into<Color>, shape: Shape) {
+        self.commands.push(Command {
+            space: self.space,
+            color: color.into(),
+            shape,
+        });
+    }
+
+    /// Appends the shapes of `other`.
+    pub(crate) fn extend(&mut self, other: &Self) {
+        self.commands.extend_from_slice(&other.commands);
+    }
+
+    pub(crate) fn tessellate(&self, shapes: &mut Shapes) {
+        for command in &self.commands {
+            let point = |(x, y): (f64, f64)| -> [f32; 2] {
+                match command.space {
+                    Space::Screen => [x as f32, y as f32],
+                    Space::World => {
+                        let (min, scale) = (self.transform.min(), self.transform.cell_size());
+                        [(min.0 + x * scale.0) as f32, (min.1 + y * scale.1) as f32]
+                    }
+                }
+            };
+            let radii = |radius: f64| -> [f32; 2] {
+                match command.space {
+                    Space::Screen => [radius as f32; 2],
+                    Space::World => {
+                        let scale = self.transform.cell_size();
+                        [(radius * scale.0) as f32, (radius * scale.1) as f32]
+                    }
+                }
+            };
+            let color = linear(command.color);
+
+            match &command.shape {
+                &Shape::Line { from, to, width } => {
+                    shapes.line(point(from), point(to), width, color);
+                }
+                &Shape::Rect { min, max } => shapes.rect(point(min), point(max), color),
+                &Shape::RectOutline { min, max, width } => {
+                    shapes.rect_outline(point(min), point(max), width, color);
+                }
+                &Shape::Circle { center, radius } => {
+                    shapes.ellipse(point(center), radii(radius), color);
+                }
+                &Shape::CircleOutline {
+                    center,
+                    radius,
+                    width,
+                } => shapes.ellipse_outline(point(center), radii(radius), width, color),
+                Shape::Text { pos, scale, text } => shapes.text(point(*pos), *scale, text, color),
+            }
+        }
+    }
+}
+
+/// Converts an sRGB color to the linear values written to the sRGB surface.
+pub(crate) fn linear(color: Color) -> [f32; 4] {
+    let [r, g, b, a] = color.0;
+    let channel = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [channel(r), channel(g), channel(b), a as f32 / 255.0]
+}
@@ -0,0 +1,109 @@
+use crate::Grid;
+use anyhow::{Context as _, bail, ensure};
+use std::{fs, path::Path};
+
+/// A cell pattern in the run length encoded format of Golly and LifeWiki.
+///
+/// `b` and `.` are dead cells, `o` is an alive cell and `A` to `X` are the
+/// states 1 to 24 of multistate rules. `$` ends a row and `!` the pattern.
+/// Lines starting with `#` are comments. The header gives the size and
+/// optionally the rule:
+///
+/// ```
+/// use cells_renderer::pattern::Pattern;
+///
+/// let glider = Pattern::parse_rle("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+/// assert_eq!(glider.cells().dims(), (3, 3));
+/// assert_eq!(glider.cells().get(1, 0), Some(&1));
+/// assert_eq!(glider.rule(), Some("B3/S23"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    cells: Grid<u8>,
+    rule: Option<String>,
+}
+
+impl Pattern {
+    /// Reads an RLE file, see [`parse_rle`](Self::parse_rle).
+    pub fn load_rle(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse_rle(&source).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn parse_rle(source: &str) -> anyhow::Result<Self> {
+        let mut lines = source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let header = lines.next().context("missing header")?;
+
+        let (mut width, mut height, mut rule) = (None, None, None);
+        for field in header.split(',') {
+            let (key, value) = field
+                .split_once('=')
+                .with_context(|| format!("invalid header field {:?}", field.trim()))?;
+            let value = value.trim();
+            match key.trim() {
+                "x" => width = Some(value.parse::<u32>().context("invalid width")?),
+                "y" => height = Some(value.parse::<u32>().context("invalid height")?),
+                "rule" => rule = Some(value.to_owned()),
+                key => bail!("unknown header field {key:?}"),
+            }
+        }
+        let (width, height) = width.zip(height).context("the header lacks x or y")?;
+
+        let mut cells = Grid::new(width, height);
+        let (mut x, mut y) = (0u32, 0u32);
+        let mut run = 0u32;
+        'body: for line in lines {
+            for c in line.chars() {
+                let state = match c {
+                    '0'..='9' => {
+                        run = run
+                            .checked_mul(10)
+                            .and_then(|run| run.checked_add(c as u32 - '0' as u32))
+                            .context("run count too large")?;
+                        continue;
+                    }
+                    '!' => break 'body,
+                    '$' => {
+                        y = y.saturating_add(run.max(1));
+                        x = 0;
+                        run = 0;
+                        continue;
+                    }
+                    'b' | '.' => 0,
+                    'o' => 1,
+                    'A'..='X' => c as u8 - b'A' + 1,
+                    c if c.is_whitespace() => continue,
+                    c => bail!("unexpected {c:?} in the pattern"),
+                };
+                let run = std::mem::take(&mut run).max(1);
+                ensure!(
+                    y < height && x.checked_add(run).is_some_and(|end| end <= width),
+                    "the pattern overflows its {width}x{height} header"
+                );
+                for _ in 0..run {
+                    cells.set(x, y, state);
+                    x += 1;
+                }
+            }
+        }
+
+        Ok(Self { cells, rule })
+    }
+
+    /// The states of the cells, `0` where they are dead.
+    #[inline]
+    pub fn cells(&self) -> &Grid<u8> {
+        &self.cells
+    }
+
+    /// The rule of the header, e.g. to pass to [`Rule::parse`](crate::Rule::parse).
+    #[inline]
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+}
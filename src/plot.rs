@@ -0,0 +1,258 @@
+//! Live line charts of values pushed by a world each tick, e.g. the
+//! population of each species, drawn in the bottom-left corner of the
+//! window.
+//!
+//! ```no_run
+//! # use cells_renderer::{AppHandle, Color};
+//! # fn update(handle: &AppHandle, prey: usize, predators: usize) {
+//! handle.plot_color("predators", Color::RED);
+//! handle.plot("prey", prey as f64);
+//! handle.plot("predators", predators as f64);
+//! # }
+//! ```
+
+use crate::{Color, app::overlay::Shapes, overlay::linear};
+use std::collections::VecDeque;
+use winit::dpi::PhysicalSize;
+
+/// Colors of series without one of their own, in order of creation.
+const COLORS: [Color; 8] = [
+    Color::hex(0x4e79a7),
+    Color::hex(0xf28e2b),
+    Color::hex(0x59a14f),
+    Color::hex(0xe15759),
+    Color::hex(0x76b7b2),
+    Color::hex(0xedc948),
+    Color::hex(0xb07aa1),
+    Color::hex(0xff9da7),
+];
+
+/// Named series of the most recent samples pushed with
+/// [`AppHandle::plot`](crate::AppHandle::plot), from
+/// [`AppHandle::plot_data`](crate::AppHandle::plot_data).
+///
+/// Each series keeps up to [`AppConfigs::plot_samples`] samples, dropping
+/// the oldest ones. The chart is shown while any series has samples, scaled
+/// to fit the smallest and largest of them, with the latest samples of all
+/// series at its right edge.
+///
+/// [`AppConfigs::plot_samples`]: crate::AppConfigs::plot_samples
+#[derive(Debug, Clone)]
+pub struct Plot {
+    capacity: usize,
+    series: Vec<Series>,
+}
+
+/// Samples of one named line of a [`Plot`], oldest first.
+#[derive(Debug, Clone)]
+pub struct Series {
+    name: String,
+    color: Color,
+    samples: VecDeque<f64>,
+}
+
+impl Series {
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = f64> + '_ {
+        self.samples.iter().copied()
+    }
+
+    #[inline]
+    pub fn latest(&self) -> Option<f64> {
+        self.samples.back().copied()
+    }
+}
+
+impl Plot {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(2),
+            series: Vec::new(),
+        }
+    }
+
+    /// Series in order of creation.
+    #[inline]
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Series> {
+        self.series.iter().find(|series| series.name == name)
+    }
+
+    /// Whether no series has samples, hiding the chart.
+    pub fn is_empty(&self) -> bool {
+        self.series.iter().all(|series| series.samples.is_empty())
+    }
+
+    /// Number of samples kept per series.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(2);
+        for series in &mut self.series {
+            let excess = series.samples.len().saturating_sub(self.capacity);
+            series.samples.drain(..excess);
+        }
+    }
+
+    pub(crate) fn push(&mut self, name: &str, value: f64) {
+        let capacity = self.capacity;
+        let samples = &mut self.series_mut(name).samples;
+        if samples.len() >= capacity {
+            samples.pop_front();
+        }
+        samples.push_back(value);
+    }
+
+    pub(crate) fn set_color(&mut self, name: &str, color: Color) {
+        self.series_mut(name).color = color;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.series.clear();
+    }
+
+    fn series_mut(&mut self, name: &str) -> &mut Series {
+        let i = match self.series.iter().position(|series| series.name == name) {
+            Some(i) => i,
+            None => {
+                self.series.push(Series {
+                    name: name.to_owned(),
+                    color: COLORS[self.series.len() % COLORS.len()],
+                    samples: VecDeque::new(),
+                });
+                self.series.len() - 1
+            }
+        };
+        &mut self.series[i]
+    }
+
+    /// Smallest and largest finite sample of all series.
+    fn range(&self) -> Option<(f64, f64)> {
+        self.series
+            .iter()
+            .flat_map(|series| series.samples())
+            .filter(|value| value.is_finite())
+            .fold(None, |range, value| match range {
+                Some((min, max)) => Some((value.min(min), value.max(max))),
+                None => Some((value, value)),
+            })
+    }
+
+    /// Draws the chart with a legend of the latest values in the
+    /// bottom-left corner.
+    pub(crate) fn draw(&self, shapes: &mut Shapes, window_size: PhysicalSize<u32>) {
+        const SCALE: f32 = 2.0;
+        const MARGIN: f32 = 8.0;
+        const PADDING: f32 = 6.0;
+        const CHART_SIZE: [f32; 2] = [300.0, 100.0];
+
+        let Some((mut min, mut max)) = self.range() else {
+            return;
+        };
+        if min == max {
+            min -= 1.0;
+            max += 1.0;
+        }
+
+        let legend: Vec<_> = self
+            .series
+            .iter()
+            .filter_map(|series| Some((series, series.latest()?)))
+            .map(|(series, value)| (series, format!("{} {}", series.name, format_value(value))))
+            .collect();
+        let line_height = Shapes::text_size("0", SCALE)[1] + SCALE * 2.0;
+        let (max_label, min_label) = (format_value(max), format_value(min));
+        let label_width =
+            Shapes::text_size(&max_label, SCALE)[0].max(Shapes::text_size(&min_label, SCALE)[0]);
+        let legend_width = legend
+            .iter()
+            .map(|(_, text)| Shapes::text_size(text, SCALE)[0])
+            .fold(0.0, f32::max);
+
+        let width = (CHART_SIZE[0] + PADDING + label_width).max(legend_width);
+        let height = legend.len() as f32 * line_height + PADDING + CHART_SIZE[1];
+        let bottom = window_size.height as f32 - MARGIN;
+        let panel_min = [MARGIN, bottom - height - PADDING * 2.0];
+        shapes.rect(
+            panel_min,
+            [MARGIN + width + PADDING * 2.0, bottom],
+            [0.0, 0.0, 0.0, 0.6],
+        );
+
+        let mut y = panel_min[1] + PADDING;
+        for (series, text) in &legend {
+            shapes.text(
+                [panel_min[0] + PADDING, y],
+                SCALE,
+                text,
+                linear(series.color),
+            );
+            y += line_height;
+        }
+
+        let chart_min = [panel_min[0] + PADDING, y + PADDING];
+        let chart_max = [chart_min[0] + CHART_SIZE[0], chart_min[1] + CHART_SIZE[1]];
+        let label_color = [0.7, 0.7, 0.7, 0.9];
+        shapes.rect_outline(chart_min, chart_max, 1.0, [1.0, 1.0, 1.0, 0.2]);
+        shapes.text(
+            [chart_max[0] + PADDING, chart_min[1]],
+            SCALE,
+            &max_label,
+            label_color,
+        );
+        let min_label_y = chart_max[1] - Shapes::text_size(&min_label, SCALE)[1];
+        shapes.text(
+            [chart_max[0] + PADDING, min_label_y],
+            SCALE,
+            &min_label,
+            label_color,
+        );
+
+        let step = CHART_SIZE[0] / (self.capacity - 1) as f32;
+        for series in &self.series {
+            let color = linear(series.color);
+            let n = series.samples.len();
+            let point = |i: usize, value: f64| {
+                let t = ((value - min) / (max - min)) as f32;
+                [
+                    chart_max[0] - (n - 1 - i) as f32 * step,
+                    chart_max[1] - t * CHART_SIZE[1],
+                ]
+            };
+            let mut previous = None;
+            for (i, value) in series.samples().enumerate() {
+                let current = value.is_finite().then(|| point(i, value));
+                if let (Some(from), Some(to)) = (previous, current) {
+                    shapes.line(from, to, 1.5, color);
+                }
+                previous = current;
+            }
+        }
+    }
+}
+
+/// Up to three decimals, without trailing zeros.
+fn format_value(value: f64) -> String {
+    let text = format!("{value:.3}");
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_owned()
+    } else {
+        text
+    }
+}
@@ -0,0 +1,210 @@
+//! Controlling a running app from other programs over TCP, with the `remote`
+//! feature on, e.g. from a notebook or a test script.
+//!
+//! A [`RemoteServer`] accepts connections on a socket and reads one JSON
+//! [`RemoteCommand`] per line, such as `{"cmd": "step", "n": 10}`, routing
+//! it through an [`AppHandle`]. Each command is answered with one line,
+//! `{"ok": true}` or `{"ok": false, "error": "..."}`, and for
+//! [`RemoteCommand::Screenshot`] with the image as `{"ok": true, "image":
+//! {...}}` in the serde format of [`WorldImage`].
+//!
+//! ```text
+//! $ nc 127.0.0.1 7878
+//! {"cmd": "pause"}
+//! {"ok":true}
+//! {"cmd": "write_pixels", "pixels": [[3, 4, [255, 0, 0, 255]]]}
+//! {"ok":true}
+//! ```
+//!
+//! There is no authentication: listen on a loopback address such as
+//! `127.0.0.1` unless the network is trusted.
+
+use crate::{AppHandle, Color, WorldImage};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead as _, BufReader, Read as _, Write as _},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+/// A request to the app, one JSON object per line with the name of the
+/// command in `"cmd"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RemoteCommand {
+    /// See [`AppHandle::pause`].
+    Pause,
+    /// See [`AppHandle::resume`].
+    Resume,
+    /// Runs `n` updates, one by default, with [`AppHandle::step`] or
+    /// [`AppHandle::fast_forward`].
+    Step {
+        #[serde(default = "one")]
+        n: u64,
+    },
+    /// See [`AppHandle::set_ups`].
+    SetUps { ups: u32 },
+    /// `[x, y, [r, g, b, a]]` triples. See [`AppHandle::write_pixels`].
+    WritePixels { pixels: Vec<(u32, u32, Color)> },
+    /// Replies with the world image. See [`AppHandle::request_snapshot`].
+    Screenshot,
+    /// See [`AppHandle::exit`].
+    Exit,
+}
+
+fn one() -> u64 {
+    1
+}
+
+#[derive(Serialize)]
+struct Reply<'a> {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<&'a WorldImage>,
+}
+
+/// Accepts remote commands for an app on a background thread. See the
+/// [module](self) docs.
+///
+/// The server keeps listening until the process exits, also when this
+/// value is dropped.
+#[derive(Debug)]
+pub struct RemoteServer {
+    local_addr: SocketAddr,
+}
+
+impl RemoteServer {
+    /// How long a screenshot may take before the request fails, e.g. while
+    /// the window is minimized.
+    pub const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Longest accepted command line in bytes, newline included. Longer
+    /// lines close the connection.
+    pub const MAX_LINE: u64 = 16 << 20;
+
+    /// Connections served at once. Further ones are closed right away until
+    /// one of them ends.
+    pub const MAX_CONNECTIONS: usize = 16;
+
+    /// Listens on `addr`, e.g. `"127.0.0.1:7878"`, or port `0` for any free
+    /// port, and serves each connection on its own thread.
+    pub fn spawn(addr: impl ToSocketAddrs, handle: AppHandle) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).context("failed to bind the remote server")?;
+        let local_addr = listener.local_addr()?;
+        let connections = Arc::new(AtomicUsize::new(0));
+        thread::Builder::new()
+            .name("remote-server".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            log::warn!("remote connection failed: {err}");
+                            continue;
+                        }
+                    };
+                    if connections.fetch_add(1, Ordering::Relaxed) >= Self::MAX_CONNECTIONS {
+                        connections.fetch_sub(1, Ordering::Relaxed);
+                        log::warn!(
+                            "refused a remote connection, {} are open",
+                            Self::MAX_CONNECTIONS
+                        );
+                        continue;
+                    }
+                    let handle = handle.clone();
+                    let served = Arc::clone(&connections);
+                    let spawned = thread::Builder::new()
+                        .name("remote-client".to_owned())
+                        .spawn(move || {
+                            if let Err(err) = serve(stream, &handle) {
+                                log::warn!("remote connection closed: {err:#}");
+                            }
+                            served.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    if let Err(err) = spawned {
+                        connections.fetch_sub(1, Ordering::Relaxed);
+                        log::warn!("failed to serve a remote connection: {err}");
+                    }
+                }
+            })?;
+        log::info!("remote server listening on {local_addr}");
+        Ok(Self { local_addr })
+    }
+
+    /// The address the server listens on, e.g. to find the port picked for
+    /// port `0`.
+    #[inline]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+fn serve(stream: TcpStream, handle: &AppHandle) -> anyhow::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(RemoteServer::MAX_LINE)
+            .read_line(&mut line)?;
+        if read == 0 {
+            break;
+        }
+        anyhow::ensure!(
+            line.ends_with('\n') || (read as u64) < RemoteServer::MAX_LINE,
+            "command longer than {} bytes",
+            RemoteServer::MAX_LINE
+        );
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = serde_json::from_str(&line)
+            .context("invalid command")
+            .and_then(|command| execute(command, handle));
+        let reply = match &result {
+            Ok(image) => Reply {
+                ok: true,
+                error: None,
+                image: image.as_ref(),
+            },
+            Err(err) => Reply {
+                ok: false,
+                error: Some(format!("{err:#}")),
+                image: None,
+            },
+        };
+        serde_json::to_writer(&mut writer, &reply)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn execute(command: RemoteCommand, handle: &AppHandle) -> anyhow::Result<Option<WorldImage>> {
+    match command {
+        RemoteCommand::Pause => handle.pause(),
+        RemoteCommand::Resume => handle.resume(),
+        RemoteCommand::Step { n: 1 } => handle.step(),
+        RemoteCommand::Step { n } => handle.fast_forward(n),
+        RemoteCommand::SetUps { ups } => handle.set_ups(ups),
+        RemoteCommand::WritePixels { pixels } => handle.write_pixels(pixels),
+        RemoteCommand::Screenshot => {
+            let image = handle
+                .request_snapshot()
+                .recv_timeout(RemoteServer::SCREENSHOT_TIMEOUT)
+                .context("the app did not respond")?;
+            return Ok(Some(image));
+        }
+        RemoteCommand::Exit => handle.exit(),
+    }
+    Ok(None)
+}
@@ -0,0 +1,436 @@
+use crate::{Boundary, Grid, Pattern, World, WorldImage, util::WorldGrid2d};
+use anyhow::{Context as _, bail, ensure};
+use std::{fmt, str::FromStr};
+
+/// An outer-totalistic rule on the Moore neighborhood, optionally with
+/// Generations-style decay states.
+///
+/// Parsed from `B3/S23` style rulestrings, `S/B` strings such as `23/3`,
+/// and Generations rules such as `B2/S/C3` or `345/2/4` (survival, birth,
+/// number of states).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+    states: u8,
+}
+
+impl Rule {
+    /// Conway's Game of Life, `B3/S23`.
+    pub const LIFE: Self = Self {
+        birth: digits(&[3]),
+        survival: digits(&[2, 3]),
+        states: 2,
+    };
+
+    #[inline]
+    pub fn parse(rulestring: &str) -> anyhow::Result<Self> {
+        rulestring.parse()
+    }
+
+    /// Number of states including dead and alive. `2` for Life-like rules.
+    #[inline]
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// State after one step, counting only fully alive neighbors.
+    ///
+    /// State `0` is dead, `1` is alive, and `2..states` are decaying cells
+    /// that can neither survive nor be born into.
+    #[inline]
+    pub fn next(&self, state: u8, alive_neighbors: usize) -> u8 {
+        match state {
+            0 => self.birth[alive_neighbors] as u8,
+            1 if self.survival[alive_neighbors] => 1,
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parts: Vec<&str> = s.split('/').collect();
+        ensure!(
+            (2..=3).contains(&parts.len()),
+            "invalid rulestring {s:?}: expected 2 or 3 parts separated by '/'"
+        );
+
+        let (birth, survival, states) = if parts[0].starts_with(['B', 'b']) {
+            let survival = parts[1]
+                .strip_prefix(['S', 's'])
+                .with_context(|| format!("invalid rulestring {s:?}: expected 'S' part"))?;
+            let states = match parts.get(2) {
+                Some(part) => Some(
+                    part.strip_prefix(['C', 'c', 'G', 'g'])
+                        .with_context(|| format!("invalid rulestring {s:?}: expected 'C' part"))?,
+                ),
+                None => None,
+            };
+            (&parts[0][1..], survival, states)
+        } else {
+            (parts[1], parts[0], parts.get(2).copied())
+        };
+
+        let states = match states {
+            Some(states) => states
+                .parse()
+                .with_context(|| format!("invalid number of states in {s:?}"))?,
+            None => 2,
+        };
+        ensure!(states >= 2, "rule {s:?} must have at least 2 states");
+
+        Ok(Self {
+            birth: parse_digits(birth).with_context(|| format!("invalid rulestring {s:?}"))?,
+            survival: parse_digits(survival)
+                .with_context(|| format!("invalid rulestring {s:?}"))?,
+            states,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |set: &[bool; 9]| {
+            (0..9)
+                .filter(|&n| set[n])
+                .map(|n| char::from(b'0' + n as u8))
+                .collect::<String>()
+        };
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as its rulestring.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+const fn digits(ns: &[usize]) -> [bool; 9] {
+    let mut set = [false; 9];
+    let mut i = 0;
+    while i < ns.len() {
+        set[ns[i]] = true;
+        i += 1;
+    }
+    set
+}
+
+fn parse_digits(s: &str) -> anyhow::Result<[bool; 9]> {
+    let mut set = [false; 9];
+    for c in s.chars() {
+        match c.to_digit(10) {
+            Some(n) if n <= 8 => set[n as usize] = true,
+            _ => bail!("unexpected {c:?} in neighbor counts"),
+        }
+    }
+    Ok(set)
+}
+
+/// A ready-made [`World`] running any [`Rule`] on a wrapping grid.
+///
+/// Cell states are `u8` as described in [`Rule::next`]. Alive cells are
+/// white and decaying cells fade from yellow to red.
+#[derive(Debug, Clone)]
+pub struct LifeLikeWorld {
+    rule: Rule,
+    cells: Grid<u8>,
+}
+
+impl LifeLikeWorld {
+    #[inline]
+    pub fn new(width: u32, height: u32, rule: Rule) -> Self {
+        Self {
+            rule,
+            cells: Grid::new(width, height),
+        }
+    }
+
+    #[inline]
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self {
+            cells: self.cells.with_boundary(boundary),
+            ..self
+        }
+    }
+
+    /// Sets each cell alive where `alive` returns `true`.
+    pub fn with_cells<F>(mut self, mut alive: F) -> Self
+    where
+        F: FnMut(u32, u32) -> bool,
+    {
+        let (width, height) = self.cells.dims();
+        for y in 0..height {
+            for x in 0..width {
+                self.cells.set(x, y, alive(x, y) as u8);
+            }
+        }
+        self
+    }
+
+    /// Places `pattern` centered in the world, replacing the cells it covers
+    /// and leaving out what does not fit. States above those of the rule are
+    /// clamped to alive.
+    pub fn with_pattern(mut self, pattern: &Pattern) -> Self {
+        let (width, height) = self.cells.dims();
+        let (pattern_width, pattern_height) = pattern.cells().dims();
+        let x0 = (i64::from(width) - i64::from(pattern_width)) / 2;
+        let y0 = (i64::from(height) - i64::from(pattern_height)) / 2;
+        let states = self.rule.states();
+        for (x, y, &state) in pattern.cells().iter() {
+            let (x, y) = (x0 + i64::from(x), y0 + i64::from(y));
+            if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                let state = if state < states { state } else { 1 };
+                self.cells.set(x, y, state);
+            }
+        }
+        self
+    }
+
+    #[inline]
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    #[inline]
+    pub fn cells(&self) -> &Grid<u8> {
+        &self.cells
+    }
+
+    #[inline]
+    pub fn cells_mut(&mut self) -> &mut Grid<u8> {
+        &mut self.cells
+    }
+}
+
+impl WorldGrid2d for LifeLikeWorld {
+    type Cell = u8;
+
+    #[inline]
+    fn dims(&self) -> (u32, u32) {
+        self.cells.dims()
+    }
+
+    #[inline]
+    fn get_cell(&self, x: u32, y: u32) -> Option<&u8> {
+        self.cells.get(x, y)
+    }
+
+    #[inline]
+    fn set_cell(&mut self, x: u32, y: u32, cell: u8) {
+        self.cells.set(x, y, cell.min(self.rule.states - 1));
+    }
+
+    fn cell_color(&self, cell: &u8) -> [u8; 4] {
+        match *cell {
+            0 => [0, 0, 0, 255],
+            1 => [255, 255, 255, 255],
+            state => {
+                let decay = (state - 1) as f32 / (self.rule.states - 1) as f32;
+                let fade = 1.0 - decay;
+                [(255.0 * fade) as u8, (200.0 * fade * fade) as u8, 0, 255]
+            }
+        }
+    }
+}
+
+#[cfg(feature = "scripting")]
+impl crate::scripting::ScriptCells for LifeLikeWorld {
+    #[inline]
+    fn script_size(&self) -> (u32, u32) {
+        self.cells.dims()
+    }
+
+    #[inline]
+    fn script_cell(&self, x: u32, y: u32) -> Option<i64> {
+        self.cells.get(x, y).map(|&state| state as i64)
+    }
+
+    /// Clamps `value` to the states of the rule.
+    #[inline]
+    fn set_script_cell(&mut self, x: u32, y: u32, value: i64) {
+        self.set_cell(x, y, value.clamp(0, u8::MAX as i64) as u8);
+    }
+
+    #[inline]
+    fn redraw(&mut self, image: &mut WorldImage) {
+        self.refresh_image(image);
+    }
+}
+
+impl World for LifeLikeWorld {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new(width, height);
+        self.refresh_image(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        let rule = self.rule;
+        self.cells.step_with(|cells, x, y| {
+            let alive = cells.neighbors_moore(x, y).filter(|&&s| s == 1).count();
+            rule.next(*cells.get(x, y).unwrap(), alive)
+        });
+        for (x, y, cell) in self.cells.changes() {
+            image.set(x, y, self.cell_color(cell));
+        }
+    }
+}
+
+/// One of Wolfram's 256 elementary cellular automaton rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementaryRule(pub u8);
+
+impl ElementaryRule {
+    pub const RULE_30: Self = Self(30);
+    pub const RULE_90: Self = Self(90);
+    pub const RULE_110: Self = Self(110);
+
+    #[inline]
+    pub fn next(self, left: bool, center: bool, right: bool) -> bool {
+        let pattern = (left as u8) << 2 | (center as u8) << 1 | right as u8;
+        self.0 >> pattern & 1 == 1
+    }
+}
+
+/// A one-dimensional automaton drawn as a scrolling history.
+///
+/// Each update computes one new generation, scrolls the image up by a row
+/// and draws the generation at the bottom.
+#[derive(Debug, Clone)]
+pub struct ElementaryWorld {
+    rule: ElementaryRule,
+    cells: Vec<bool>,
+    next: Vec<bool>,
+    height: u32,
+    boundary: Boundary,
+}
+
+impl ElementaryWorld {
+    /// Starts with a single live cell in the middle.
+    #[inline]
+    pub fn new(width: u32, height: u32, rule: ElementaryRule) -> Self {
+        assert!(width > 0 && height > 0);
+        let mut cells = vec![false; width as usize];
+        cells[width as usize / 2] = true;
+        Self {
+            rule,
+            next: cells.clone(),
+            cells,
+            height,
+            boundary: Boundary::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self { boundary, ..self }
+    }
+
+    /// Replaces the first generation, setting each cell alive where `alive`
+    /// returns `true`.
+    pub fn with_seed<F>(mut self, alive: F) -> Self
+    where
+        F: FnMut(u32) -> bool,
+    {
+        let width = self.cells.len() as u32;
+        self.cells = (0..width).map(alive).collect();
+        self
+    }
+
+    /// Replaces the first generation with `pattern` centered in the row.
+    pub fn with_pattern(self, pattern: &[bool]) -> Self {
+        let width = self.cells.len();
+        let start = width.saturating_sub(pattern.len()) / 2;
+        self.with_seed(|x| {
+            (x as usize)
+                .checked_sub(start)
+                .and_then(|i| pattern.get(i))
+                .is_some_and(|&alive| alive)
+        })
+    }
+
+    #[inline]
+    pub fn rule(&self) -> ElementaryRule {
+        self.rule
+    }
+
+    #[inline]
+    pub fn set_rule(&mut self, rule: ElementaryRule) {
+        self.rule = rule;
+    }
+
+    /// The current generation.
+    #[inline]
+    pub fn cells(&self) -> &[bool] {
+        &self.cells
+    }
+
+    fn cell(&self, x: i64) -> bool {
+        let width = self.cells.len() as i64;
+        let x = match self.boundary {
+            Boundary::Wrap => x.rem_euclid(width),
+            Boundary::Clamp => x.clamp(0, width - 1),
+            Boundary::Dead if (0..width).contains(&x) => x,
+            Boundary::Dead => return false,
+        };
+        self.cells[x as usize]
+    }
+
+    fn draw_last_row(&self, image: &mut WorldImage) {
+        let y = image.height() - 1;
+        for (x, &alive) in self.cells.iter().enumerate() {
+            image.set(x as u32, y, color(alive));
+        }
+    }
+}
+
+fn color(alive: bool) -> [u8; 4] {
+    if alive {
+        [255, 255, 255, 255]
+    } else {
+        [0, 0, 0, 255]
+    }
+}
+
+impl World for ElementaryWorld {
+    fn init_image(&mut self) -> WorldImage {
+        let mut image = WorldImage::filled(self.cells.len() as u32, self.height, color(false));
+        self.draw_last_row(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        for x in 0..self.cells.len() {
+            let i = x as i64;
+            self.next[x] = self
+                .rule
+                .next(self.cell(i - 1), self.cell(i), self.cell(i + 1));
+        }
+        std::mem::swap(&mut self.cells, &mut self.next);
+
+        image.scroll_up(1, color(false));
+        self.draw_last_row(image);
+    }
+}
@@ -0,0 +1,435 @@
+//! Driving a world from [rhai](https://rhai.rs) scripts, with the
+//! `scripting` feature on, e.g. to seed patterns or take measurements
+//! without writing Rust.
+//!
+//! A [`ScriptedWorld`] wraps a world implementing [`ScriptCells`] and calls
+//! these functions of its script when they are defined:
+//!
+//! - `on_start()`, when the app starts,
+//! - `on_tick(tick)`, after every update, with the number of updates run,
+//! - `on_key(key)`, when a key is pressed, with the name of its
+//!   [`KeyCode`](crate::winit::KeyCode) such as `"KeyR"` or `"Space"`.
+//!
+//! Scripts can call:
+//!
+//! - `width()` and `height()`, the size of the world in cells,
+//! - `get_cell(x, y)`, the cell as an integer, or `()` outside the world,
+//! - `set_cell(x, y, value)`,
+//! - `tick()`, the number of updates run,
+//! - `pause()`, `resume()`, `step()`, `fast_forward(n)` and `set_ups(n)`,
+//!   like the methods of [`AppHandle`], doing nothing when running headless,
+//! - `hud(line)`, adding a line to the heads-up display until the next tick,
+//! - `plot(name, value)`, like [`AppHandle::plot`],
+//! - `random(n)`, an integer from `0` to `n - 1`, seeded with
+//!   [`AppHandle::seed`] from `on_start` on,
+//! - `print(value)`, logged at the info level.
+//!
+//! ```rhai
+//! fn on_start() {
+//!     for i in 0..width() * height() / 4 {
+//!         set_cell(random(width()), random(height()), 1);
+//!     }
+//! }
+//!
+//! fn on_tick(tick) {
+//!     let alive = 0;
+//!     for y in 0..height() {
+//!         for x in 0..width() {
+//!             alive += get_cell(x, y);
+//!         }
+//!     }
+//!     hud(`Alive ${alive}`);
+//!     if alive == 0 {
+//!         pause();
+//!     }
+//! }
+//! ```
+//!
+//! Errors raised by scripts are logged without stopping the app.
+
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, InputFocus, Lod, MouseEvent, Overlay, Rng,
+    UpdateCtx, VectorField, World, WorldImage, WorldTransform,
+    winit::{KeyEvent, MouseButton},
+};
+use anyhow::Context as _;
+use rhai::{AST, Dynamic, Engine, FuncArgs, Scope};
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use winit::keyboard::PhysicalKey;
+
+/// Worlds whose cells scripts can read and write, as integers.
+pub trait ScriptCells: World {
+    /// The size of the world in cells, `(width, height)`.
+    fn script_size(&self) -> (u32, u32);
+
+    /// The cell at `(x, y)`, or `None` outside the world.
+    fn script_cell(&self, x: u32, y: u32) -> Option<i64>;
+
+    /// Sets the cell at `(x, y)`. The image is redrawn through
+    /// [`ScriptCells::redraw`] once the script returns.
+    fn set_script_cell(&mut self, x: u32, y: u32, value: i64);
+
+    /// Redraws `image` after scripts set cells.
+    fn redraw(&mut self, image: &mut WorldImage);
+}
+
+/// What scripts share with the [`ScriptedWorld`] running them.
+#[derive(Debug)]
+struct Host {
+    handle: Option<AppHandle>,
+    tick: u64,
+    hud: Vec<String>,
+    dirty: bool,
+    rng: Rng,
+}
+
+/// A world driven by a rhai script. See the [module](self) docs.
+pub struct ScriptedWorld<W> {
+    world: Arc<Mutex<W>>,
+    host: Arc<Mutex<Host>>,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    callbacks: HashSet<String>,
+}
+
+impl<W: ScriptCells + Send + 'static> ScriptedWorld<W> {
+    /// Compiles `source` and runs its top-level statements.
+    pub fn new(world: W, source: &str) -> anyhow::Result<Self> {
+        let world = Arc::new(Mutex::new(world));
+        // Reseeded with the seed of the run once attached.
+        let host = Arc::new(Mutex::new(Host {
+            handle: None,
+            tick: 0,
+            hud: Vec::new(),
+            dirty: false,
+            rng: Rng::new(Rng::time_seed()),
+        }));
+        let engine = engine(&world, &host);
+
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow::anyhow!("{err}"))
+            .context("failed to compile the script")?;
+        let callbacks = ast.iter_functions().map(|f| f.name.to_owned()).collect();
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|err| anyhow::anyhow!("{err}"))
+            .context("failed to run the script")?;
+
+        Ok(Self {
+            world,
+            host,
+            engine,
+            ast,
+            scope,
+            callbacks,
+        })
+    }
+
+    pub fn from_path(world: W, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::new(world, &source).with_context(|| format!("in {}", path.display()))
+    }
+}
+
+impl<W> ScriptedWorld<W> {
+    /// Locks the wrapped world. Scripts cannot run while it is held.
+    #[inline]
+    pub fn world(&self) -> MutexGuard<'_, W> {
+        lock(&self.world)
+    }
+
+    /// Calls the script function `name` if it is defined, then redraws the
+    /// image if the script set cells.
+    fn call(&mut self, name: &str, args: impl FuncArgs, image: &mut WorldImage)
+    where
+        W: ScriptCells,
+    {
+        if !self.callbacks.contains(name) {
+            return;
+        }
+        if let Err(err) = self
+            .engine
+            .call_fn::<Dynamic>(&mut self.scope, &self.ast, name, args)
+        {
+            log::error!("script error in {name}: {err}");
+        }
+        if std::mem::take(&mut lock(&self.host).dirty) {
+            lock(&self.world).redraw(image);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn engine<W: ScriptCells + Send + 'static>(
+    world: &Arc<Mutex<W>>,
+    host: &Arc<Mutex<Host>>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|text| log::info!("{text}"));
+    engine.on_debug(|text, _, pos| log::debug!("{pos:?}: {text}"));
+
+    let cell = |x: i64, y: i64| Some((u32::try_from(x).ok()?, u32::try_from(y).ok()?));
+
+    let w = world.clone();
+    engine.register_fn("width", move || lock(&w).script_size().0 as i64);
+    let w = world.clone();
+    engine.register_fn("height", move || lock(&w).script_size().1 as i64);
+    let w = world.clone();
+    engine.register_fn("get_cell", move |x: i64, y: i64| {
+        cell(x, y)
+            .and_then(|(x, y)| lock(&w).script_cell(x, y))
+            .map_or(Dynamic::UNIT, Dynamic::from_int)
+    });
+    let (w, h) = (world.clone(), host.clone());
+    engine.register_fn("set_cell", move |x: i64, y: i64, value: i64| {
+        if let Some((x, y)) = cell(x, y) {
+            lock(&w).set_script_cell(x, y, value);
+            lock(&h).dirty = true;
+        }
+    });
+
+    let h = host.clone();
+    engine.register_fn("tick", move || lock(&h).tick as i64);
+    let h = host.clone();
+    engine.register_fn("hud", move |line: &str| lock(&h).hud.push(line.to_owned()));
+    let h = host.clone();
+    engine.register_fn("random", move |n: i64| {
+        lock(&h).rng.below(n.max(1) as u64) as i64
+    });
+
+    let handle = |host: &Arc<Mutex<Host>>, f: fn(&AppHandle)| {
+        let host = host.clone();
+        move || {
+            if let Some(handle) = &lock(&host).handle {
+                f(handle);
+            }
+        }
+    };
+    engine.register_fn("pause", handle(host, AppHandle::pause));
+    engine.register_fn("resume", handle(host, AppHandle::resume));
+    engine.register_fn("step", handle(host, AppHandle::step));
+    let h = host.clone();
+    engine.register_fn("fast_forward", move |n: i64| {
+        if let Some(handle) = &lock(&h).handle {
+            handle.fast_forward(n.max(0) as u64);
+        }
+    });
+    let h = host.clone();
+    engine.register_fn("set_ups", move |ups: i64| {
+        if let Some(handle) = &lock(&h).handle {
+            handle.set_ups(ups.clamp(1, u32::MAX as i64) as u32);
+        }
+    });
+    let h = host.clone();
+    engine.register_fn("plot", move |name: &str, value: f64| {
+        if let Some(handle) = &lock(&h).handle {
+            handle.plot(name, value);
+        }
+    });
+    let h = host.clone();
+    engine.register_fn("plot", move |name: &str, value: i64| {
+        if let Some(handle) = &lock(&h).handle {
+            handle.plot(name, value as f64);
+        }
+    });
+    engine
+}
+
+impl<W: ScriptCells> World for ScriptedWorld<W> {
+    #[inline]
+    fn init_image(&mut self) -> WorldImage {
+        lock(&self.world).init_image()
+    }
+
+    #[inline]
+    fn update(&mut self, image: &mut WorldImage) {
+        lock(&self.world).update(image);
+    }
+
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        lock(&self.world).update_with_ctx(ctx, image);
+        let tick = {
+            let mut host = lock(&self.host);
+            host.tick = ctx.tick + 1;
+            host.hud.clear();
+            host.tick
+        };
+        self.call("on_tick", (tick as i64,), image);
+    }
+
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        let pressed = match event.physical_key {
+            PhysicalKey::Code(key) if event.state.is_pressed() && !event.repeat => Some(key),
+            _ => None,
+        };
+        lock(&self.world).keyboard_input(event, image);
+        if let Some(key) = pressed {
+            self.call("on_key", (format!("{key:?}"),), image);
+        }
+    }
+
+    #[inline]
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        lock(&self.world).mouse_input(event, image);
+    }
+
+    #[inline]
+    fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        lock(&self.world).cursor_moved(pos, image);
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        lock(&self.world).cursor_moved_precise(pos, image);
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        lock(&self.world).cursor_drag(from, to, button, image);
+    }
+
+    fn attach(&mut self, handle: AppHandle) {
+        let mut host = lock(&self.host);
+        host.rng = Rng::new(handle.seed());
+        host.handle = Some(handle.clone());
+        drop(host);
+        lock(&self.world).attach(handle);
+    }
+
+    fn on_start(&mut self, image: &mut WorldImage) {
+        lock(&self.world).on_start(image);
+        self.call("on_start", (), image);
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        lock(&self.world).on_pause();
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        lock(&self.world).on_resume();
+    }
+
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        lock(&self.world).on_close_requested()
+    }
+
+    #[inline]
+    fn on_exit(&mut self) {
+        lock(&self.world).on_exit();
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        lock(&self.world).text_input(text, image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        lock(&self.world).gamepad_input(event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        lock(&self.world).focus_changed(focus);
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        lock(&self.world).transform_changed(transform);
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        lock(&self.world).lod_changed(lod, image);
+    }
+
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        lock(&self.world).requested_resize()
+    }
+
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        lock(&self.world).cell_actions(x, y)
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        lock(&self.world).cell_action(x, y, action, image);
+    }
+
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        lock(&self.world).command(command, image)
+    }
+
+    /// The world's lines followed by those added by the script.
+    fn hud_lines(&self) -> Vec<String> {
+        let mut lines = lock(&self.world).hud_lines();
+        lines.extend(lock(&self.host).hud.iter().cloned());
+        lines
+    }
+
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        lock(&self.world).inspect(x, y)
+    }
+
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        lock(&self.world).hover_cells(x, y)
+    }
+
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        lock(&self.world).draw_overlay(overlay);
+    }
+
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        lock(&self.world).draw_agents(agents);
+    }
+
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        lock(&self.world).draw_vector_field(field);
+    }
+
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        lock(&self.world).poll_image(image)
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        lock(&self.world).save_state()
+    }
+
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        lock(&self.world).load_state(data, image)
+    }
+}
@@ -0,0 +1,230 @@
+//! Mirroring the world image to other programs over WebSocket, with the
+//! `stream` feature on, e.g. for a browser dashboard next to the window.
+//!
+//! A [`FrameStreamer`] accepts WebSocket connections on a socket and, while
+//! any client is connected, sends each of them the world image at a fixed
+//! rate as one binary message per frame, encoded as set in
+//! [`StreamOptions::encoding`]. It asks the app for the image through an
+//! [`AppHandle`], so it works next to a `remote::RemoteServer` on the same
+//! app.
+//!
+//! ```js
+//! const socket = new WebSocket("ws://127.0.0.1:7879");
+//! socket.onmessage = async (event) => {
+//!     const bitmap = await createImageBitmap(event.data);
+//!     canvas.getContext("2d").drawImage(bitmap, 0, 0);
+//! };
+//! ```
+//!
+//! Messages from clients are not read, and there is no authentication:
+//! listen on a loopback address such as `127.0.0.1` unless the network is
+//! trusted.
+
+use crate::{AppHandle, ImageFormat, WorldImage};
+use anyhow::Context as _;
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, mpsc::RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+use tungstenite::{Message, WebSocket};
+
+/// How frames are encoded into messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FrameEncoding {
+    /// A PNG file, which browsers decode with `createImageBitmap`.
+    #[default]
+    Png,
+    /// The width and height as little-endian `u32`s, followed by the pixels
+    /// as RGBA bytes, row by row from the top. Larger, but cheaper to encode.
+    Raw,
+}
+
+/// Settings for [`FrameStreamer::spawn`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamOptions {
+    /// Frames sent per second. Defaults to `10.0`. [`FrameStreamer::spawn`]
+    /// fails unless the time between frames is positive and fits in a
+    /// [`Duration`].
+    pub fps: f64,
+    /// Defaults to [`FrameEncoding::Png`].
+    pub encoding: FrameEncoding,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            fps: 10.0,
+            encoding: FrameEncoding::default(),
+        }
+    }
+}
+
+impl StreamOptions {
+    #[inline]
+    pub fn fps(self, fps: f64) -> Self {
+        Self { fps, ..self }
+    }
+
+    #[inline]
+    pub fn encoding(self, encoding: FrameEncoding) -> Self {
+        Self { encoding, ..self }
+    }
+}
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+/// Sends the world image to WebSocket clients on background threads. See
+/// the [module](self) docs.
+///
+/// The streamer keeps running until the app exits, also when this value is
+/// dropped.
+#[derive(Debug)]
+pub struct FrameStreamer {
+    local_addr: SocketAddr,
+}
+
+impl FrameStreamer {
+    /// How long a client may take to receive a frame before it is
+    /// disconnected, so that one slow client does not stall the others.
+    pub const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// How long a client may take to send its WebSocket handshake, so that
+    /// one silent connection does not hold up those after it.
+    pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Listens on `addr`, e.g. `"127.0.0.1:7879"`, or port `0` for any free
+    /// port, and starts sending frames once a client connects.
+    pub fn spawn(
+        addr: impl ToSocketAddrs,
+        handle: AppHandle,
+        options: StreamOptions,
+    ) -> anyhow::Result<Self> {
+        let interval = Duration::try_from_secs_f64(1.0 / options.fps)
+            .ok()
+            .filter(|interval| !interval.is_zero())
+            .with_context(|| format!("invalid stream rate {}", options.fps))?;
+        let listener = TcpListener::bind(addr).context("failed to bind the frame streamer")?;
+        let local_addr = listener.local_addr()?;
+        let clients = Clients::default();
+
+        let accepted = Arc::clone(&clients);
+        thread::Builder::new()
+            .name("stream-listener".to_owned())
+            .spawn(move || accept(listener, &accepted))?;
+        thread::Builder::new()
+            .name("stream-sender".to_owned())
+            .spawn(move || broadcast(&handle, &clients, interval, options.encoding))?;
+
+        log::info!("streaming frames on ws://{local_addr}");
+        Ok(Self { local_addr })
+    }
+
+    /// The address the streamer listens on, e.g. to find the port picked for
+    /// port `0`.
+    #[inline]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+fn accept(listener: TcpListener, clients: &Clients) {
+    for stream in listener.incoming() {
+        let socket = stream
+            .and_then(|stream| {
+                stream.set_write_timeout(Some(FrameStreamer::WRITE_TIMEOUT))?;
+                stream.set_read_timeout(Some(FrameStreamer::HANDSHAKE_TIMEOUT))?;
+                Ok(stream)
+            })
+            .map_err(anyhow::Error::from)
+            .and_then(|stream| {
+                let socket = tungstenite::accept(stream)
+                    .map_err(|err| anyhow::anyhow!("handshake: {err}"))?;
+                socket.get_ref().set_read_timeout(None)?;
+                Ok(socket)
+            });
+        match socket {
+            Ok(socket) => clients
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(socket),
+            Err(err) => log::warn!("stream connection failed: {err:#}"),
+        }
+    }
+}
+
+fn broadcast(handle: &AppHandle, clients: &Clients, interval: Duration, encoding: FrameEncoding) {
+    let mut next = Instant::now();
+    loop {
+        next += interval;
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+        // Don't catch up on frames missed while the app was busy.
+        next = next.max(Instant::now());
+
+        if clients
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .is_empty()
+        {
+            continue;
+        }
+        let image = match handle.request_snapshot().recv_timeout(interval.max(
+            // Leave the app a few frames to respond at low rates.
+            Duration::from_millis(100),
+        )) {
+            Ok(image) => image,
+            Err(RecvTimeoutError::Timeout) => continue,
+            // The app has exited.
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let message = match encode(&image, encoding) {
+            Ok(data) => Message::binary(data),
+            Err(err) => {
+                log::warn!("failed to encode a frame: {err:#}");
+                continue;
+            }
+        };
+        clients
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain_mut(|socket| match socket.send(message.clone()) {
+                Ok(()) => true,
+                Err(err) => {
+                    log::info!("stream client disconnected: {err}");
+                    false
+                }
+            });
+    }
+
+    for mut socket in clients
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .drain(..)
+    {
+        let _ = socket.close(None);
+        let _ = socket.flush();
+    }
+}
+
+fn encode(image: &WorldImage, encoding: FrameEncoding) -> anyhow::Result<Vec<u8>> {
+    let rgba;
+    let image = match image.format() {
+        ImageFormat::Rgba8 => image,
+        _ => {
+            rgba = image.to_rgba();
+            &rgba
+        }
+    };
+    let (width, height) = (image.width(), image.height());
+    match encoding {
+        FrameEncoding::Raw => {
+            let mut data = Vec::with_capacity(8 + image.buf().len());
+            data.extend_from_slice(&width.to_le_bytes());
+            data.extend_from_slice(&height.to_le_bytes());
+            data.extend_from_slice(image.buf());
+            Ok(data)
+        }
+        FrameEncoding::Png => image.encode_png(),
+    }
+}
@@ -0,0 +1,636 @@
+//! Running worlds in a terminal instead of a window, with the `terminal`
+//! feature on, e.g. over SSH or for a quick look while debugging.
+//!
+//! A [`TerminalApp`] drives the same [`World`] as [`App`](crate::App) and
+//! draws its image with `▀` characters in truecolor, two pixels per
+//! character, scaled down to fit the terminal. The last line shows the tick
+//! and the update rate, and the world's HUD lines with
+//! [`AppConfigs::hud`].
+//!
+//! Differences from the window:
+//!
+//! - winit's [`KeyEvent`](crate::winit::KeyEvent) cannot be constructed
+//!   here, so [`World::keyboard_input`] is never called. Keys are reported
+//!   through [`AppHandle::keyboard`], and typed characters through
+//!   [`World::text_input`] while the focus is [`InputFocus::Text`]. Most
+//!   terminals don't report releases, so keys count as held until the next
+//!   update.
+//! - Of the app's actions, only [`AppAction::Play`],
+//!   [`AppAction::UpdateOnce`] and [`AppAction::Quit`] are bound, from
+//!   [`AppConfigs::input_map`]. `Esc` or `Ctrl+C` also quits.
+//! - Mouse buttons and drags are reported at the cell in the top half of the
+//!   character under the cursor.
+
+use crate::{
+    AppAction, AppConfigs, AppHandle, CloseAction, Color, ImageFormat, InputFocus, KeyChord,
+    MouseEvent, MouseEventKind, UpdateCtx, World, WorldImage,
+    commands::AppCommand,
+    winit::{ElementState, KeyCode, ModifiersState, MouseButton},
+};
+use crossterm::{
+    QueueableCommand as _, cursor, event,
+    event::{Event, KeyEventKind, KeyModifiers},
+    style::{self, Print},
+    terminal,
+};
+use std::{
+    io::{self, Write as _},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// Runs a world in the terminal. See the [module](self) docs.
+pub struct TerminalApp<W> {
+    configs: AppConfigs,
+    world: W,
+    commands: AppHandle,
+    receiver: mpsc::Receiver<AppCommand>,
+}
+
+impl<W: World> TerminalApp<W> {
+    /// Frame rate used when [`AppConfigs::max_fps`] is not set, since
+    /// terminals redraw slowly.
+    pub const DEFAULT_FPS: u32 = 30;
+
+    #[inline]
+    pub fn new(configs: AppConfigs, world: W) -> Self {
+        let (commands, receiver) = AppHandle::channel(configs.seed);
+        Self {
+            configs,
+            world,
+            commands,
+            receiver,
+        }
+    }
+
+    /// Returns a handle for controlling the app once it runs.
+    #[inline]
+    pub fn commands(&self) -> AppHandle {
+        self.commands.clone()
+    }
+
+    /// Takes over the terminal until the app is quit, then restores it.
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut terminal = TerminalGuard::enter()?;
+        terminal
+            .out
+            .queue(terminal::SetTitle(&self.configs.window_attributes.title))?;
+        let report_releases = terminal.report_releases;
+        Runner::new(self, report_releases).run(&mut terminal.out)
+    }
+}
+
+/// Puts the terminal into raw mode on the alternate screen, and restores it
+/// when dropped, also on errors and panics.
+struct TerminalGuard {
+    out: io::BufWriter<io::Stdout>,
+    report_releases: bool,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::BufWriter::new(io::stdout());
+        out.queue(terminal::EnterAlternateScreen)?
+            .queue(cursor::Hide)?
+            .queue(event::EnableMouseCapture)?;
+        let report_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if report_releases {
+            out.queue(event::PushKeyboardEnhancementFlags(
+                event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES,
+            ))?;
+        }
+        out.flush()?;
+        Ok(Self {
+            out,
+            report_releases,
+        })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.report_releases {
+            let _ = self.out.queue(event::PopKeyboardEnhancementFlags);
+        }
+        let _ = self
+            .out
+            .queue(event::DisableMouseCapture)
+            .and_then(|out| out.queue(style::ResetColor))
+            .and_then(|out| out.queue(cursor::Show))
+            .and_then(|out| out.queue(terminal::LeaveAlternateScreen))
+            .and_then(|out| out.flush());
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Runner<W> {
+    configs: AppConfigs,
+    world: W,
+    handle: AppHandle,
+    receiver: mpsc::Receiver<AppCommand>,
+    image: WorldImage,
+    tick: u64,
+    elapsed: Duration,
+    update_interval: Duration,
+    frame_interval: Duration,
+    paused: bool,
+    focus: InputFocus,
+    exit: bool,
+    dirty: bool,
+    report_releases: bool,
+    /// Keys pressed without a release event, released after the next update.
+    pressed: Vec<KeyCode>,
+    /// Pixels per character column, and per half character row.
+    scale: u32,
+    cursor: Option<(u32, u32)>,
+    dragging: Option<MouseButton>,
+}
+
+impl<W: World> Runner<W> {
+    fn new(app: TerminalApp<W>, report_releases: bool) -> Self {
+        let TerminalApp {
+            configs,
+            mut world,
+            commands,
+            receiver,
+        } = app;
+        let mut image = world.init_image();
+        world.attach(commands.clone());
+        world.on_start(&mut image);
+        let max_fps = configs.max_fps.unwrap_or(TerminalApp::<W>::DEFAULT_FPS);
+        Self {
+            update_interval: Duration::from_secs(1) / configs.updates_per_second.max(1),
+            frame_interval: Duration::from_secs(1) / max_fps.max(1),
+            paused: configs.start_paused,
+            configs,
+            world,
+            handle: commands,
+            receiver,
+            image,
+            tick: 0,
+            elapsed: Duration::ZERO,
+            focus: InputFocus::World,
+            exit: false,
+            dirty: true,
+            report_releases,
+            pressed: Vec::new(),
+            scale: 1,
+            cursor: None,
+            dragging: None,
+        }
+    }
+
+    fn run(mut self, out: &mut impl io::Write) -> anyhow::Result<()> {
+        // The world is told on errors too.
+        let result = self.run_loop(out);
+        self.world.on_exit();
+        result
+    }
+
+    fn run_loop(&mut self, out: &mut impl io::Write) -> anyhow::Result<()> {
+        let mut next_update = Instant::now() + self.update_interval;
+        let mut next_frame = Instant::now();
+        while !self.exit {
+            let now = Instant::now();
+            // Wake up at the frame rate also when idle, for commands sent from
+            // other threads.
+            let mut deadline = now + self.frame_interval;
+            if !self.paused {
+                deadline = deadline.min(next_update);
+            }
+            if self.dirty {
+                deadline = deadline.min(next_frame);
+            }
+            if event::poll(deadline.saturating_duration_since(now))? {
+                self.handle_event(event::read()?);
+                while event::poll(Duration::ZERO)? {
+                    self.handle_event(event::read()?);
+                }
+            }
+            self.process_commands();
+
+            let now = Instant::now();
+            if self.paused {
+                next_update = now + self.update_interval;
+            } else {
+                let mut steps = 0;
+                while now >= next_update {
+                    if self
+                        .configs
+                        .max_updates_per_frame
+                        .is_some_and(|max| steps >= max)
+                    {
+                        next_update = now + self.update_interval;
+                        break;
+                    }
+                    self.update();
+                    next_update += self.update_interval;
+                    steps += 1;
+                }
+            }
+
+            if self.dirty && now >= next_frame {
+                self.draw(out)?;
+                self.dirty = false;
+                next_frame = now + self.frame_interval;
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self) {
+        span!("update", tick = self.tick);
+        let ctx = UpdateCtx {
+            tick: self.tick,
+            elapsed: self.elapsed,
+            dt: self.update_interval,
+            ..Default::default()
+        };
+        self.world.update_with_ctx(&ctx, &mut self.image);
+        self.tick += 1;
+        self.elapsed += self.update_interval;
+        self.sync();
+
+        let mut keyboard = self.handle.lock_keyboard();
+        for key in self.pressed.drain(..) {
+            keyboard.set_down(key, false);
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if paused == self.paused {
+            return;
+        }
+        self.paused = paused;
+        if paused {
+            self.world.on_pause();
+        } else {
+            self.world.on_resume();
+        }
+        self.dirty = true;
+    }
+
+    /// Picks up images changed outside of updates and requested resizes.
+    fn sync(&mut self) {
+        self.world.poll_image(&mut self.image);
+        if let Some((width, height)) = self.world.requested_resize() {
+            self.image.resize(width, height);
+        }
+        self.dirty = true;
+    }
+
+    fn process_commands(&mut self) {
+        while let Ok(command) = self.receiver.try_recv() {
+            match command {
+                AppCommand::FastForward(n) => {
+                    for _ in 0..n {
+                        self.update();
+                    }
+                }
+                AppCommand::SetFocus(focus) => {
+                    if focus == InputFocus::Text {
+                        self.handle.lock_keyboard().release_all();
+                        self.pressed.clear();
+                    }
+                    self.focus = focus;
+                    self.world.focus_changed(focus);
+                }
+                AppCommand::Pause => self.set_paused(true),
+                AppCommand::Resume => self.set_paused(false),
+                AppCommand::Step => self.update(),
+                AppCommand::SetUps(ups) => {
+                    self.configs.updates_per_second = ups.max(1);
+                    self.update_interval = Duration::from_secs(1) / self.configs.updates_per_second;
+                }
+                AppCommand::Exit => self.exit = true,
+                AppCommand::Redraw => self.sync(),
+                AppCommand::Bind(chord, action) => self.configs.input_map.bind(chord, action),
+                AppCommand::Rebind(action, chord) => self.configs.input_map.rebind(action, chord),
+                AppCommand::WritePixels(pixels) => {
+                    if self.image.format() != ImageFormat::Rgba8 {
+                        log::warn!("ignored pixels written to a non-RGBA world image");
+                        continue;
+                    }
+                    for (x, y, color) in pixels {
+                        if let Some(pixel) = self.image.pixel_mut(x, y) {
+                            *pixel = color;
+                        }
+                    }
+                    self.dirty = true;
+                }
+                AppCommand::Snapshot(reply) => {
+                    let _ = reply.send(self.image.clone());
+                }
+                AppCommand::Screenshot(_, target) => {
+                    let err = anyhow::anyhow!("screenshots are not supported in the terminal");
+                    if let Some(err) = target.finish(Err(err)) {
+                        log::error!("{err:#}");
+                    }
+                }
+                // There is no tutorial panel in the terminal, and colors are
+                // drawn as they are.
+                AppCommand::AdvanceTutorial
+                | AppCommand::SetDisplayAdjustments(_)
+                | AppCommand::SetPaletteCycle(_) => {}
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(event) => self.key_input(event),
+            Event::Mouse(event) => self.mouse_input(event),
+            Event::Resize(..) => self.dirty = true,
+            _ => {}
+        }
+    }
+
+    fn key_input(&mut self, event: event::KeyEvent) {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        if event.kind != KeyEventKind::Release
+            && (event.code == event::KeyCode::Esc
+                || (ctrl && event.code == event::KeyCode::Char('c')))
+        {
+            self.exit = self.world.on_close_requested() == CloseAction::Exit;
+            return;
+        }
+        let Some(key) = key_code(event.code) else {
+            return;
+        };
+        let mut modifiers = ModifiersState::empty();
+        modifiers.set(
+            ModifiersState::SHIFT,
+            event.modifiers.contains(KeyModifiers::SHIFT)
+                || matches!(event.code, event::KeyCode::Char(c) if c.is_uppercase()),
+        );
+        modifiers.set(ModifiersState::CONTROL, ctrl);
+        modifiers.set(
+            ModifiersState::ALT,
+            event.modifiers.contains(KeyModifiers::ALT),
+        );
+
+        if event.kind == KeyEventKind::Release {
+            self.handle.lock_keyboard().set_down(key, false);
+            return;
+        }
+        if self.focus == InputFocus::Text {
+            if let event::KeyCode::Char(c) = event.code
+                && !ctrl
+            {
+                self.world
+                    .text_input(c.encode_utf8(&mut [0; 4]), &mut self.image);
+                self.sync();
+            }
+            return;
+        }
+
+        let chord = KeyChord::new(key).with_modifiers(modifiers);
+        let action = self
+            .configs
+            .input_map
+            .bindings()
+            .iter()
+            .find(|(bound, _)| *bound == chord)
+            .map(|&(_, action)| action);
+        match action {
+            Some(AppAction::Play) if event.kind == KeyEventKind::Press => {
+                self.set_paused(!self.paused);
+            }
+            Some(AppAction::UpdateOnce) if self.paused => self.update(),
+            Some(AppAction::Quit) => {
+                self.exit = self.world.on_close_requested() == CloseAction::Exit;
+            }
+            _ => {
+                let mut keyboard = self.handle.lock_keyboard();
+                keyboard.set_modifiers(modifiers);
+                keyboard.set_down(key, true);
+                if !self.report_releases {
+                    self.pressed.push(key);
+                }
+            }
+        }
+    }
+
+    fn mouse_input(&mut self, event: event::MouseEvent) {
+        let (x, y) = (
+            event.column as u32 * self.scale,
+            event.row as u32 * 2 * self.scale,
+        );
+        let pos = (x < self.image.width() && y < self.image.height()).then_some((x, y));
+        if pos != self.cursor {
+            self.cursor = pos;
+            self.world.cursor_moved(pos, &mut self.image);
+        }
+
+        match event.kind {
+            event::MouseEventKind::Down(button) => {
+                let button = mouse_button(button);
+                self.dragging = Some(button);
+                self.send_mouse(ElementState::Pressed, button);
+                if let Some(cell) = pos {
+                    self.world.cursor_drag(cell, cell, button, &mut self.image);
+                }
+            }
+            event::MouseEventKind::Up(button) => {
+                self.dragging = None;
+                self.send_mouse(ElementState::Released, mouse_button(button));
+            }
+            event::MouseEventKind::Drag(_) => {
+                if let (Some(button), Some(cell)) = (self.dragging, pos) {
+                    self.world.cursor_drag(cell, cell, button, &mut self.image);
+                }
+            }
+            _ => {}
+        }
+        self.sync();
+    }
+
+    fn send_mouse(&mut self, state: ElementState, button: MouseButton) {
+        let event = MouseEvent {
+            state,
+            button,
+            pos: self.cursor,
+            kind: MouseEventKind::Button,
+        };
+        self.world.mouse_input(event, &mut self.image);
+    }
+
+    fn draw(&mut self, out: &mut impl io::Write) -> io::Result<()> {
+        span!("render");
+        let (columns, rows) = terminal::size()?;
+        let (columns, image_rows) = (columns as u32, rows.saturating_sub(1) as u32);
+
+        let rgba;
+        let image = match self.image.format() {
+            ImageFormat::Rgba8 => &self.image,
+            _ => {
+                rgba = self.image.to_rgba();
+                &rgba
+            }
+        };
+        let (width, height) = (image.width(), image.height());
+        self.scale = width
+            .div_ceil(columns.max(1))
+            .max(height.div_ceil(image_rows.max(1) * 2))
+            .max(1);
+        let scale = self.scale;
+        let pixel = |x: u32, y: u32| image.pixel(x * scale, y * scale).copied();
+
+        let mut last = None;
+        for row in 0..height.div_ceil(scale * 2).min(image_rows) {
+            out.queue(cursor::MoveTo(0, row as u16))?;
+            for column in 0..width.div_ceil(scale).min(columns) {
+                let top = pixel(column, row * 2).unwrap_or(Color::BLACK);
+                let bottom = pixel(column, row * 2 + 1);
+                if last != Some((top, bottom)) {
+                    last = Some((top, bottom));
+                    out.queue(style::SetColors(style::Colors::new(
+                        term_color(Some(top)),
+                        term_color(bottom),
+                    )))?;
+                }
+                out.queue(Print('▀'))?;
+            }
+            last = None;
+            out.queue(style::ResetColor)?
+                .queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+        }
+
+        let mut status = format!(
+            "tick {}  {} ups",
+            self.tick, self.configs.updates_per_second
+        );
+        if self.paused {
+            status.push_str("  paused");
+        }
+        if scale > 1 {
+            status.push_str(&format!("  1:{scale}"));
+        }
+        if self.configs.hud {
+            for line in self.world.hud_lines() {
+                status.push_str("  ");
+                status.push_str(&line);
+            }
+        }
+        let status: String = status.chars().take(columns as usize).collect();
+        out.queue(cursor::MoveTo(
+            0,
+            height.div_ceil(scale * 2).min(image_rows) as u16,
+        ))?
+        .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?
+        .queue(cursor::MoveTo(0, rows.saturating_sub(1)))?
+        .queue(Print(status))?;
+        out.flush()
+    }
+}
+
+fn term_color(color: Option<Color>) -> style::Color {
+    match color {
+        Some(Color([r, g, b, _])) => style::Color::Rgb { r, g, b },
+        None => style::Color::Reset,
+    }
+}
+
+fn mouse_button(button: event::MouseButton) -> MouseButton {
+    match button {
+        event::MouseButton::Left => MouseButton::Left,
+        event::MouseButton::Right => MouseButton::Right,
+        event::MouseButton::Middle => MouseButton::Middle,
+    }
+}
+
+/// The physical key most likely pressed for `code` on a US layout.
+fn key_code(code: event::KeyCode) -> Option<KeyCode> {
+    use event::KeyCode as Code;
+
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA,
+        KeyCode::KeyB,
+        KeyCode::KeyC,
+        KeyCode::KeyD,
+        KeyCode::KeyE,
+        KeyCode::KeyF,
+        KeyCode::KeyG,
+        KeyCode::KeyH,
+        KeyCode::KeyI,
+        KeyCode::KeyJ,
+        KeyCode::KeyK,
+        KeyCode::KeyL,
+        KeyCode::KeyM,
+        KeyCode::KeyN,
+        KeyCode::KeyO,
+        KeyCode::KeyP,
+        KeyCode::KeyQ,
+        KeyCode::KeyR,
+        KeyCode::KeyS,
+        KeyCode::KeyT,
+        KeyCode::KeyU,
+        KeyCode::KeyV,
+        KeyCode::KeyW,
+        KeyCode::KeyX,
+        KeyCode::KeyY,
+        KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    const FUNCTION_KEYS: [KeyCode; 12] = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+    ];
+
+    Some(match code {
+        Code::Char(c) => match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => LETTERS[(c as u8 - b'a') as usize],
+            c @ '0'..='9' => DIGITS[(c as u8 - b'0') as usize],
+            ' ' => KeyCode::Space,
+            '-' | '_' => KeyCode::Minus,
+            '=' | '+' => KeyCode::Equal,
+            '[' | '{' => KeyCode::BracketLeft,
+            ']' | '}' => KeyCode::BracketRight,
+            ';' | ':' => KeyCode::Semicolon,
+            '\'' | '"' => KeyCode::Quote,
+            ',' | '<' => KeyCode::Comma,
+            '.' | '>' => KeyCode::Period,
+            '/' | '?' => KeyCode::Slash,
+            '\\' | '|' => KeyCode::Backslash,
+            '`' | '~' => KeyCode::Backquote,
+            _ => return None,
+        },
+        Code::F(n @ 1..=12) => FUNCTION_KEYS[n as usize - 1],
+        Code::Enter => KeyCode::Enter,
+        Code::Backspace => KeyCode::Backspace,
+        Code::Tab | Code::BackTab => KeyCode::Tab,
+        Code::Left => KeyCode::ArrowLeft,
+        Code::Right => KeyCode::ArrowRight,
+        Code::Up => KeyCode::ArrowUp,
+        Code::Down => KeyCode::ArrowDown,
+        Code::Home => KeyCode::Home,
+        Code::End => KeyCode::End,
+        Code::PageUp => KeyCode::PageUp,
+        Code::PageDown => KeyCode::PageDown,
+        Code::Delete => KeyCode::Delete,
+        Code::Insert => KeyCode::Insert,
+        _ => return None,
+    })
+}
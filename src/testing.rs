@@ -0,0 +1,320 @@
+//! Driving a [`World`] in unit tests, without a window or a GPU.
+//!
+//! A [`TestHarness`] calls the world's hooks the way the app does: it
+//! creates the image, starts the world, runs updates with a simulation
+//! clock, and translates mouse input into [`MouseEvent`]s at the current
+//! cursor cell.
+//!
+//! ```
+//! use cells_renderer::{Color, LifeLikeWorld, Rule, testing::TestHarness};
+//!
+//! // A blinker: three cells in a row turn into three cells in a column.
+//! let world = LifeLikeWorld::new(5, 5, Rule::LIFE).with_cells(|x, y| y == 2 && (1..4).contains(&x));
+//! let mut harness = TestHarness::new(world);
+//! harness.assert_region((1, 2), (4, 3), Color::WHITE);
+//!
+//! harness.step();
+//! harness.assert_region((2, 1), (3, 4), Color::WHITE);
+//! harness.assert_pixel(1, 2, Color::BLACK);
+//! assert_eq!(harness.count(Color::WHITE), 3);
+//! ```
+
+use crate::{
+    AppHandle, CellPosition, Color, ImageFormat, InputFocus, MouseEvent, MouseEventKind, UpdateCtx,
+    World, WorldImage,
+    commands::AppCommand,
+    winit::{ElementState, KeyCode, KeyEvent, MouseButton},
+};
+use std::{sync::mpsc, time::Duration};
+
+/// Owns a world and its image, and feeds them updates and input.
+///
+/// Commands sent through the [`AppHandle`] passed to
+/// [`World::attach`] are ignored, and its [`seed`](crate::AppHandle::seed)
+/// is always zero, so that randomly seeded worlds are reproducible.
+///
+/// winit's [`KeyEvent`] cannot be constructed outside of winit, so keyboard
+/// input can only be forwarded from real events with
+/// [`TestHarness::key`]. Worlds that should be testable can handle keys in
+/// a method of their own, called from [`World::keyboard_input`], or poll
+/// [`AppHandle::keyboard`](crate::AppHandle::keyboard), which follows
+/// [`TestHarness::hold_key`] and [`TestHarness::release_key`].
+pub struct TestHarness<W> {
+    world: W,
+    image: WorldImage,
+    tick: u64,
+    elapsed: Duration,
+    dt: Duration,
+    cursor: Option<CellPosition>,
+    dragging: Vec<MouseButton>,
+    handle: AppHandle,
+    // Kept so that sending commands does not fail.
+    _commands: mpsc::Receiver<AppCommand>,
+}
+
+impl<W: World> TestHarness<W> {
+    /// Creates the image and starts the world, as the app does on launch.
+    pub fn new(mut world: W) -> Self {
+        let mut image = world.init_image();
+        let (handle, commands) = AppHandle::channel(Some(0));
+        world.attach(handle.clone());
+        world.on_start(&mut image);
+        let mut harness = Self {
+            world,
+            image,
+            tick: 0,
+            elapsed: Duration::ZERO,
+            dt: Duration::from_secs(1) / 60,
+            cursor: None,
+            dragging: Vec::new(),
+            handle,
+            _commands: commands,
+        };
+        harness.sync();
+        harness
+    }
+
+    /// Simulated time advanced by each update. Defaults to 1/60 s.
+    #[inline]
+    pub fn with_dt(mut self, dt: Duration) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    #[inline]
+    pub fn world(&self) -> &W {
+        &self.world
+    }
+
+    #[inline]
+    pub fn world_mut(&mut self) -> &mut W {
+        &mut self.world
+    }
+
+    #[inline]
+    pub fn image(&self) -> &WorldImage {
+        &self.image
+    }
+
+    /// Number of updates run so far.
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The cell under the simulated cursor.
+    #[inline]
+    pub fn cursor(&self) -> Option<(u32, u32)> {
+        self.cursor.map(|pos| pos.cell)
+    }
+
+    /// Calls [`World::on_exit`] as the app does when closed, and returns the
+    /// world.
+    pub fn exit(mut self) -> W {
+        self.world.on_exit();
+        self.world
+    }
+
+    /// Runs one update.
+    pub fn step(&mut self) {
+        let ctx = UpdateCtx {
+            tick: self.tick,
+            elapsed: self.elapsed,
+            dt: self.dt,
+            ..Default::default()
+        };
+        self.world.update_with_ctx(&ctx, &mut self.image);
+        self.tick += 1;
+        self.elapsed += self.dt;
+        self.sync();
+    }
+
+    /// Runs `n` updates.
+    pub fn steps(&mut self, n: u64) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Moves the cursor to the center of the cell `(x, y)`.
+    pub fn move_cursor(&mut self, x: u32, y: u32) {
+        self.move_cursor_precise(Some(CellPosition {
+            cell: (x, y),
+            fract: (0.5, 0.5),
+        }));
+    }
+
+    /// Moves the cursor off the world.
+    pub fn leave_cursor(&mut self) {
+        self.move_cursor_precise(None);
+    }
+
+    pub fn move_cursor_precise(&mut self, pos: Option<CellPosition>) {
+        let last = std::mem::replace(&mut self.cursor, pos).map(|pos| pos.cell);
+        self.world.cursor_moved_precise(pos, &mut self.image);
+        if let (Some(from), Some(to)) = (last, self.cursor())
+            && from != to
+        {
+            for &button in &self.dragging {
+                self.world.cursor_drag(from, to, button, &mut self.image);
+            }
+        }
+        self.sync();
+    }
+
+    pub fn press(&mut self, button: MouseButton) {
+        self.mouse(ElementState::Pressed, button);
+    }
+
+    pub fn release(&mut self, button: MouseButton) {
+        self.mouse(ElementState::Released, button);
+    }
+
+    /// Presses and releases `button` at the cursor.
+    pub fn click(&mut self, button: MouseButton) {
+        self.press(button);
+        self.release(button);
+    }
+
+    /// Moves the cursor to the cell `(x, y)` and clicks `button` there.
+    pub fn click_at(&mut self, x: u32, y: u32, button: MouseButton) {
+        self.move_cursor(x, y);
+        self.click(button);
+    }
+
+    /// Presses `button`, moves the cursor through `path` and releases it
+    /// at the last cell, calling [`World::cursor_drag`] along the way.
+    pub fn drag(&mut self, button: MouseButton, path: impl IntoIterator<Item = (u32, u32)>) {
+        let mut path = path.into_iter();
+        if let Some((x, y)) = path.next() {
+            self.move_cursor(x, y);
+        }
+        self.press(button);
+        for (x, y) in path {
+            self.move_cursor(x, y);
+        }
+        self.release(button);
+    }
+
+    /// Forwards a real keyboard event, e.g. one recorded from a window.
+    pub fn key(&mut self, event: KeyEvent) {
+        self.world.keyboard_input(event, &mut self.image);
+        self.sync();
+    }
+
+    /// Holds `key` down in the keyboard state polled by the world, without
+    /// sending a key event.
+    pub fn hold_key(&mut self, key: KeyCode) {
+        self.handle.lock_keyboard().set_down(key, true);
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.handle.lock_keyboard().set_down(key, false);
+    }
+
+    /// Types `text` with [`InputFocus::Text`], then gives the focus back to
+    /// the world.
+    pub fn text(&mut self, text: &str) {
+        self.world.focus_changed(InputFocus::Text);
+        self.world.text_input(text, &mut self.image);
+        self.world.focus_changed(InputFocus::World);
+        self.sync();
+    }
+
+    /// Runs an action from the context menu of the cell `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// If [`World::cell_actions`] does not offer `action` there.
+    #[track_caller]
+    pub fn cell_action(&mut self, x: u32, y: u32, action: &str) {
+        let actions = self.world.cell_actions(x, y);
+        assert!(
+            actions.iter().any(|a| a == action),
+            "{action:?} is not one of the actions at ({x}, {y}): {actions:?}"
+        );
+        self.world.cell_action(x, y, action, &mut self.image);
+        self.sync();
+    }
+
+    /// The color the cell `(x, y)` is drawn with, looked up in the palette
+    /// for indexed and float images.
+    ///
+    /// # Panics
+    ///
+    /// If `(x, y)` is outside the image.
+    #[track_caller]
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        let (width, height) = (self.image.width(), self.image.height());
+        assert!(
+            x < width && y < height,
+            "({x}, {y}) is outside the {width}x{height} image"
+        );
+        match self.image.format() {
+            ImageFormat::Rgba8 => *self.image.pixel(x, y).unwrap(),
+            _ => *self.image.to_rgba().pixel(x, y).unwrap(),
+        }
+    }
+
+    /// Number of cells drawn with `color`.
+    pub fn count(&self, color: impl Into<Color>) -> usize {
+        let color = color.into();
+        let count = |image: &WorldImage| image.pixels().iter().filter(|&&c| c == color).count();
+        match self.image.format() {
+            ImageFormat::Rgba8 => count(&self.image),
+            _ => count(&self.image.to_rgba()),
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_pixel(&self, x: u32, y: u32, color: impl Into<Color>) {
+        let (expected, actual) = (color.into(), self.color(x, y));
+        assert_eq!(
+            actual, expected,
+            "pixel ({x}, {y}) is {actual:?}, expected {expected:?}"
+        );
+    }
+
+    /// Asserts that every cell from `min` up to but excluding `max` is drawn
+    /// with `color`.
+    #[track_caller]
+    pub fn assert_region(&self, min: (u32, u32), max: (u32, u32), color: impl Into<Color>) {
+        let expected = color.into();
+        for y in min.1..max.1 {
+            for x in min.0..max.0 {
+                let actual = self.color(x, y);
+                assert_eq!(
+                    actual, expected,
+                    "pixel ({x}, {y}) in {min:?}..{max:?} is {actual:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+
+    /// Picks up images produced outside of the callbacks and requested
+    /// resizes, as the app does every frame.
+    fn sync(&mut self) {
+        self.world.poll_image(&mut self.image);
+        if let Some((width, height)) = self.world.requested_resize() {
+            self.image.resize(width, height);
+        }
+    }
+
+    fn mouse(&mut self, state: ElementState, button: MouseButton) {
+        let event = MouseEvent {
+            state,
+            button,
+            pos: self.cursor(),
+            kind: MouseEventKind::Button,
+        };
+        self.world.mouse_input(event, &mut self.image);
+        self.dragging.retain(|&b| b != button);
+        if state.is_pressed() {
+            self.dragging.push(button);
+            if let Some(cell) = self.cursor() {
+                self.world.cursor_drag(cell, cell, button, &mut self.image);
+            }
+        }
+        self.sync();
+    }
+}
@@ -0,0 +1,59 @@
+//! Capturing one frame every `n` updates rather than every rendered frame,
+//! so that runs of millions of generations make timelapses of a manageable
+//! length. See [`AppConfigs::timelapse`](crate::AppConfigs::timelapse).
+
+use crate::WorldImage;
+#[cfg(feature = "png")]
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+/// Captures configured with
+/// [`AppConfigs::timelapse`](crate::AppConfigs::timelapse).
+///
+/// A frame is the world drawn like
+/// [`AppHandle::request_screenshot_scaled`](crate::AppHandle::request_screenshot_scaled),
+/// as of the first rendered frame after every `every` updates, and once
+/// when the app starts. When more than `every` updates run per rendered
+/// frame, one frame is captured per rendered frame. Nothing is captured
+/// while paused.
+///
+/// The app stops capturing after the first failure, which is reported to the
+/// error handler.
+#[derive(Debug, Clone)]
+pub struct Timelapse {
+    pub every: u64,
+    pub output: TimelapseOutput,
+}
+
+impl Timelapse {
+    /// Captures every `every` updates, at least 1.
+    #[inline]
+    pub fn new(every: u64, output: TimelapseOutput) -> Self {
+        Self {
+            every: every.max(1),
+            output,
+        }
+    }
+}
+
+/// Where the frames of a [`Timelapse`] go.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum TimelapseOutput {
+    /// PNG files named `frame-<tick>.png` in `dir`, with the tick padded to
+    /// sort in order, `scale` pixels per cell along each axis.
+    #[cfg(feature = "png")]
+    Png { dir: PathBuf, scale: u32 },
+    /// One video, started with the first frame and finished when the app
+    /// exits. Its frames are played back at
+    /// [`VideoExport::fps`](crate::video::VideoExport::fps).
+    #[cfg(feature = "video")]
+    Video(crate::video::VideoExport),
+    /// The tick and RGBA image of each frame, `scale` pixels per cell, e.g.
+    /// for an encoder of the world's own. Capturing stops once the receiver
+    /// is dropped.
+    Channel {
+        sender: mpsc::Sender<(u64, WorldImage)>,
+        scale: u32,
+    },
+}
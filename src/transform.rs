@@ -0,0 +1,126 @@
+/// Mapping between window pixel coordinates and world cell coordinates.
+///
+/// Window coordinates are physical pixels with the origin at the top-left
+/// corner of the window, as reported by winit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldTransform {
+    min: (f64, f64),
+    max: (f64, f64),
+    cell_scale: (f64, f64),
+    world_size: (u32, u32),
+}
+
+impl WorldTransform {
+    pub(crate) fn new(min: (f64, f64), max: (f64, f64), world_size: (u32, u32)) -> Self {
+        let cell_scale = (
+            (max.0 - min.0) / world_size.0 as f64,
+            (max.1 - min.1) / world_size.1 as f64,
+        );
+        Self {
+            min,
+            max,
+            cell_scale,
+            world_size,
+        }
+    }
+
+    /// Top-left corner of the world quad in window coordinates.
+    #[inline]
+    pub fn min(&self) -> (f64, f64) {
+        self.min
+    }
+
+    /// Bottom-right corner of the world quad in window coordinates.
+    #[inline]
+    pub fn max(&self) -> (f64, f64) {
+        self.max
+    }
+
+    /// Size of a single cell in window pixels.
+    #[inline]
+    pub fn cell_size(&self) -> (f64, f64) {
+        self.cell_scale
+    }
+
+    #[inline]
+    pub fn world_size(&self) -> (u32, u32) {
+        self.world_size
+    }
+
+    /// Returns the cell under a window position, or `None` outside the world.
+    #[inline]
+    pub fn window_to_cell(&self, pos: (f64, f64)) -> Option<(u32, u32)> {
+        self.window_to_cell_precise(pos, CursorRounding::Floor)
+            .map(|pos| pos.cell)
+    }
+
+    /// Returns the cell under a window position using the given rounding,
+    /// together with the fractional offset from that cell's top-left corner.
+    ///
+    /// Positions up to half a window pixel outside the world quad are
+    /// clamped onto the edge cells, so the last row and column stay
+    /// reachable regardless of how the quad was rasterized.
+    pub fn window_to_cell_precise(
+        &self,
+        pos: (f64, f64),
+        rounding: CursorRounding,
+    ) -> Option<CellPosition> {
+        fn calc_pos(val: f64, len: u32, scale: f64, rounding: CursorRounding) -> Option<u32> {
+            let tolerance = 0.5 / scale;
+            if val < -tolerance || val >= len as f64 + tolerance {
+                return None;
+            }
+            let cell = match rounding {
+                CursorRounding::Floor => val.floor(),
+                CursorRounding::Nearest => val.round(),
+            };
+            Some((cell.max(0.0) as u32).min(len - 1))
+        }
+
+        let (fx, fy) = self.window_to_world(pos);
+        let x = calc_pos(fx, self.world_size.0, self.cell_scale.0, rounding)?;
+        let y = calc_pos(fy, self.world_size.1, self.cell_scale.1, rounding)?;
+        Some(CellPosition {
+            cell: (x, y),
+            fract: (fx - x as f64, fy - y as f64),
+        })
+    }
+
+    /// Converts a window position to fractional cell coordinates without any
+    /// rounding or bounds check.
+    #[inline]
+    pub fn window_to_world(&self, pos: (f64, f64)) -> (f64, f64) {
+        (
+            (pos.0 - self.min.0) / self.cell_scale.0,
+            (pos.1 - self.min.1) / self.cell_scale.1,
+        )
+    }
+
+    /// Returns the window position of the top-left corner of a cell.
+    #[inline]
+    pub fn cell_to_window(&self, cell: (u32, u32)) -> (f64, f64) {
+        (
+            self.min.0 + cell.0 as f64 * self.cell_scale.0,
+            self.min.1 + cell.1 as f64 * self.cell_scale.1,
+        )
+    }
+}
+
+/// A cell together with the sub-cell position of the cursor within it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellPosition {
+    pub cell: (u32, u32),
+    /// Offset from the top-left corner of `cell`, in cells.
+    pub fract: (f64, f64),
+}
+
+/// How window positions are rounded to cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorRounding {
+    /// The cell containing the position.
+    #[default]
+    Floor,
+    /// The cell whose top-left corner is nearest to the position, which is
+    /// useful for picking grid intersections.
+    Nearest,
+}
@@ -0,0 +1,139 @@
+//! Guided onboarding: a list of prompts, each completed by an action.
+//!
+//! While a [`Tutorial`] runs, its progress is shown as a bar at the top of
+//! the window, with the current prompt below it. Steps advance as the
+//! user performs the expected actions.
+
+use crate::winit::{KeyCode, MouseButton};
+
+/// What the user has to do to complete a [`TutorialStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TutorialAction {
+    /// Press the key.
+    Key(KeyCode),
+    /// Press the mouse button over the window.
+    MouseButton(MouseButton),
+    /// Pause the simulation, by any means.
+    Pause,
+    /// Resume the simulation, by any means.
+    Resume,
+    /// Zoom the view.
+    Zoom,
+    /// Pan the view.
+    Pan,
+    /// Completed by the world calling
+    /// [`AppHandle::advance_tutorial`](crate::AppHandle::advance_tutorial),
+    /// e.g. once the user has painted a cell.
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TutorialStep {
+    pub prompt: String,
+    pub action: TutorialAction,
+}
+
+/// A sequence of [`TutorialStep`]s. See [`AppConfigs::tutorial`](crate::AppConfigs::tutorial).
+///
+/// ```
+/// use cells_renderer::{
+///     tutorial::{Tutorial, TutorialAction},
+///     winit::KeyCode,
+/// };
+///
+/// let tutorial = Tutorial::new()
+///     .step("Press Space to pause", TutorialAction::Pause)
+///     .step("Press Enter to advance one step", TutorialAction::Key(KeyCode::Enter))
+///     .step("Scroll to zoom in", TutorialAction::Zoom);
+/// assert_eq!(tutorial.len(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Tutorial {
+    steps: Vec<TutorialStep>,
+    current: usize,
+}
+
+impl Tutorial {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step.
+    #[inline]
+    pub fn step(mut self, prompt: impl Into<String>, action: TutorialAction) -> Self {
+        self.steps.push(TutorialStep {
+            prompt: prompt.into(),
+            action,
+        });
+        self
+    }
+
+    #[inline]
+    pub fn steps(&self) -> &[TutorialStep] {
+        &self.steps
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Index of the step awaiting its action, or `len()` once finished.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    #[inline]
+    pub fn current(&self) -> Option<&TutorialStep> {
+        self.steps.get(self.current)
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    /// Fraction of completed steps, from 0.0 to 1.0.
+    #[inline]
+    pub fn progress(&self) -> f32 {
+        if self.steps.is_empty() {
+            1.0
+        } else {
+            self.current as f32 / self.steps.len() as f32
+        }
+    }
+
+    /// Completes the current step if it expects `action`. Returns whether the
+    /// tutorial advanced.
+    pub fn perform(&mut self, action: TutorialAction) -> bool {
+        let matches = self.current().is_some_and(|step| step.action == action);
+        if matches {
+            self.current += 1;
+        }
+        matches
+    }
+
+    /// Completes the current step whatever its action. Returns whether there
+    /// was a step to complete.
+    #[inline]
+    pub fn advance(&mut self) -> bool {
+        let advanced = !self.is_finished();
+        if advanced {
+            self.current += 1;
+        }
+        advanced
+    }
+
+    /// Starts over from the first step.
+    #[inline]
+    pub fn restart(&mut self) {
+        self.current = 0;
+    }
+}
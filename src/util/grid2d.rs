@@ -0,0 +1,37 @@
+use crate::WorldImage;
+
+/// A world stored as a 2D grid of typed cells, one image pixel per cell.
+///
+/// Implementing it enables [`with_painter_grid`](super::WithPainterExtGrid::with_painter_grid).
+pub trait WorldGrid2d {
+    type Cell;
+
+    /// Width and height in cells.
+    fn dims(&self) -> (u32, u32);
+
+    /// Returns `None` outside the grid.
+    fn get_cell(&self, x: u32, y: u32) -> Option<&Self::Cell>;
+
+    /// Does nothing outside the grid.
+    fn set_cell(&mut self, x: u32, y: u32, cell: Self::Cell);
+
+    fn cell_color(&self, cell: &Self::Cell) -> [u8; 4];
+
+    /// Writes the color of a single cell to `image`.
+    #[inline]
+    fn refresh_cell(&self, x: u32, y: u32, image: &mut WorldImage) {
+        if let Some(cell) = self.get_cell(x, y) {
+            image.set(x, y, self.cell_color(cell));
+        }
+    }
+
+    /// Writes the colors of all cells to `image`.
+    fn refresh_image(&self, image: &mut WorldImage) {
+        let (width, height) = self.dims();
+        for y in 0..height {
+            for x in 0..width {
+                self.refresh_cell(x, y, image);
+            }
+        }
+    }
+}
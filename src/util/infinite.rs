@@ -0,0 +1,372 @@
+//! Unbounded grids stored in chunks, shown through a movable window.
+//!
+//! The world image of an [`InfiniteWorld`] is an atlas with a slot for each
+//! chunk of the window. Only chunks that are visible through the camera are
+//! rasterized into their slots, and only when they changed or came into
+//! view, with [`WorldImage::write_rect`] so that the app uploads just those
+//! slots.
+
+use crate::{Lod, UpdateCtx, World, WorldImage, util::is_pressed};
+use std::collections::{HashMap, HashSet};
+use winit::{event::KeyEvent, keyboard::KeyCode};
+
+/// Width and height of a chunk in cells.
+pub const CHUNK_SIZE: u32 = 64;
+
+const CHUNK_LEN: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Unbounded 2D cell storage, allocated in [`CHUNK_SIZE`] square chunks on
+/// first write. Cells that were never written read as `C::default()`.
+#[derive(Debug, Clone)]
+pub struct Chunks<C> {
+    chunks: HashMap<(i64, i64), Chunk<C>>,
+}
+
+#[derive(Debug, Clone)]
+struct Chunk<C> {
+    cells: Box<[C]>,
+    // Whether a cell changed since `Chunks::take_changed`.
+    changed: bool,
+}
+
+/// Compares the allocated chunks and their cells.
+impl<C: PartialEq> PartialEq for Chunks<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.chunks.len() == other.chunks.len()
+            && self.chunks.iter().all(|(key, chunk)| {
+                other
+                    .chunks
+                    .get(key)
+                    .is_some_and(|other| chunk.cells == other.cells)
+            })
+    }
+}
+
+impl<C: Eq> Eq for Chunks<C> {}
+
+impl<C> Default for Chunks<C> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+}
+
+impl<C: Clone + Default + PartialEq> Chunks<C> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn get(&self, x: i64, y: i64) -> C {
+        let (key, i) = locate(x, y);
+        self.chunks
+            .get(&key)
+            .map_or_else(C::default, |chunk| chunk.cells[i].clone())
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, cell: C) {
+        let (key, i) = locate(x, y);
+        match self.chunks.get_mut(&key) {
+            Some(chunk) => {
+                if chunk.cells[i] != cell {
+                    chunk.cells[i] = cell;
+                    chunk.changed = true;
+                }
+            }
+            None if cell == C::default() => (),
+            None => {
+                let mut cells = vec![C::default(); CHUNK_LEN].into_boxed_slice();
+                cells[i] = cell;
+                let chunk = Chunk {
+                    cells,
+                    changed: true,
+                };
+                self.chunks.insert(key, chunk);
+            }
+        }
+    }
+
+    /// Number of allocated chunks.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Coordinates of the allocated chunks, in chunk units.
+    #[inline]
+    pub fn chunk_keys(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Cells of one chunk in row-major order, if it is allocated.
+    #[inline]
+    pub fn chunk(&self, chunk_x: i64, chunk_y: i64) -> Option<&[C]> {
+        self.chunks
+            .get(&(chunk_x, chunk_y))
+            .map(|chunk| &chunk.cells[..])
+    }
+
+    /// Iterates over every cell that differs from `C::default()`.
+    pub fn iter(&self) -> impl Iterator<Item = ((i64, i64), &C)> + '_ {
+        let empty = C::default();
+        self.chunks.iter().flat_map(move |(&(cx, cy), chunk)| {
+            let empty = empty.clone();
+            chunk
+                .cells
+                .iter()
+                .enumerate()
+                .filter(move |(_, cell)| **cell != empty)
+                .map(move |(i, cell)| {
+                    let x = cx * CHUNK_SIZE as i64 + (i as u32 % CHUNK_SIZE) as i64;
+                    let y = cy * CHUNK_SIZE as i64 + (i as u32 / CHUNK_SIZE) as i64;
+                    ((x, y), cell)
+                })
+        })
+    }
+
+    /// Frees chunks that only contain `C::default()`.
+    pub fn prune(&mut self) {
+        let empty = C::default();
+        self.chunks
+            .retain(|_, chunk| chunk.cells.iter().any(|cell| *cell != empty));
+    }
+
+    /// Keys of the chunks with cells changed since the last call.
+    fn take_changed(&mut self) -> HashSet<(i64, i64)> {
+        self.chunks
+            .iter_mut()
+            .filter_map(|(&key, chunk)| std::mem::take(&mut chunk.changed).then_some(key))
+            .collect()
+    }
+}
+
+fn locate(x: i64, y: i64) -> ((i64, i64), usize) {
+    let size = CHUNK_SIZE as i64;
+    let key = (x.div_euclid(size), y.div_euclid(size));
+    let i = (x.rem_euclid(size) + y.rem_euclid(size) * size) as usize;
+    (key, i)
+}
+
+/// Simulation rules of an [`InfiniteWorld`].
+pub trait InfiniteRules {
+    type Cell: Clone + Default + PartialEq;
+
+    fn step(&mut self, cells: &mut Chunks<Self::Cell>, ctx: &UpdateCtx);
+
+    fn color(&self, cell: &Self::Cell) -> [u8; 4];
+}
+
+/// Hosts an unbounded [`Chunks`] grid in the app.
+///
+/// The world image is a window of `view_chunks` chunks starting at
+/// `origin`, moved a chunk at a time with the arrow keys; patterns outside
+/// it keep running but are not shown. Each chunk of the window has its
+/// slot in the image. Allocated chunks visible through the camera are drawn
+/// into their slots when they change or come into view; the slots of the
+/// others show the default cell.
+pub struct InfiniteWorld<R: InfiniteRules> {
+    rules: R,
+    cells: Chunks<R::Cell>,
+    origin: (i64, i64),
+    view_chunks: (u32, u32),
+    visible: Option<Lod>,
+    // Chunks drawn into their slots, which otherwise show the default cell.
+    drawn: HashSet<(i64, i64)>,
+}
+
+impl<R: InfiniteRules> InfiniteWorld<R> {
+    #[inline]
+    pub fn new(rules: R) -> Self {
+        Self {
+            rules,
+            cells: Chunks::new(),
+            origin: (0, 0),
+            view_chunks: (4, 4),
+            visible: None,
+            drawn: HashSet::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_cells(self, cells: Chunks<R::Cell>) -> Self {
+        Self { cells, ..self }
+    }
+
+    /// Size of the world image in chunks. Defaults to 4 x 4.
+    #[inline]
+    pub fn with_view_chunks(self, width: u32, height: u32) -> Self {
+        assert!(width > 0 && height > 0);
+        Self {
+            view_chunks: (width, height),
+            ..self
+        }
+    }
+
+    /// World coordinates of the top-left cell of the image.
+    #[inline]
+    pub fn with_origin(self, x: i64, y: i64) -> Self {
+        Self {
+            origin: (x, y),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn rules(&self) -> &R {
+        &self.rules
+    }
+
+    #[inline]
+    pub fn cells(&self) -> &Chunks<R::Cell> {
+        &self.cells
+    }
+
+    /// Cells can be changed freely; call [`InfiniteWorld::redraw`] afterwards.
+    #[inline]
+    pub fn cells_mut(&mut self) -> &mut Chunks<R::Cell> {
+        &mut self.cells
+    }
+
+    #[inline]
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    /// Converts an image position, e.g. from [`World::cursor_moved`], to
+    /// world coordinates.
+    #[inline]
+    pub fn image_to_world(&self, pos: (u32, u32)) -> (i64, i64) {
+        (self.origin.0 + pos.0 as i64, self.origin.1 + pos.1 as i64)
+    }
+
+    /// Draws the visible chunks that changed or came into view since the
+    /// last call into their slots, and clears the slots of drawn chunks that
+    /// were freed or went out of view.
+    pub fn redraw(&mut self, image: &mut WorldImage) {
+        let visible = self.visible_chunks(image);
+        let changed = self.cells.take_changed();
+
+        let hidden: Vec<_> = self.drawn.difference(&visible).copied().collect();
+        for key in hidden {
+            self.draw_slot(key, None, image);
+            self.drawn.remove(&key);
+        }
+        for key in visible {
+            let cells = self.cells.chunk(key.0, key.1);
+            let stale = if cells.is_some() {
+                self.drawn.insert(key) || changed.contains(&key)
+            } else {
+                self.drawn.remove(&key)
+            };
+            if stale {
+                self.draw_slot(key, cells, image);
+            }
+        }
+    }
+
+    /// Clears the image and draws every visible chunk, e.g. after the
+    /// window moved.
+    fn redraw_all(&mut self, image: &mut WorldImage) {
+        image.fill(self.rules.color(&R::Cell::default()));
+        self.drawn.clear();
+        self.redraw(image);
+    }
+
+    /// Chunks with cells in the visible part of the image.
+    fn visible_chunks(&self, image: &WorldImage) -> HashSet<(i64, i64)> {
+        let (x_range, y_range) = match &self.visible {
+            Some(lod) => (lod.visible_x.clone(), lod.visible_y.clone()),
+            None => (0..image.width(), 0..image.height()),
+        };
+        if x_range.is_empty() || y_range.is_empty() {
+            return HashSet::new();
+        }
+
+        // The origin need not be chunk aligned, so a visible range of image
+        // cells may touch one more chunk than it spans.
+        let first = self.image_to_world((x_range.start, y_range.start));
+        let last = self.image_to_world((x_range.end - 1, y_range.end - 1));
+        let size = CHUNK_SIZE as i64;
+        let xs = first.0.div_euclid(size)..=last.0.div_euclid(size);
+        let ys = first.1.div_euclid(size)..=last.1.div_euclid(size);
+        ys.flat_map(|cy| xs.clone().map(move |cx| (cx, cy)))
+            .collect()
+    }
+
+    /// Rasterizes the cells of a chunk, or the default cell without them,
+    /// into the slot of the chunk, clipped to the image.
+    fn draw_slot(&self, (cx, cy): (i64, i64), cells: Option<&[R::Cell]>, image: &mut WorldImage) {
+        let size = CHUNK_SIZE as i64;
+        let left = cx * size - self.origin.0;
+        let top = cy * size - self.origin.1;
+        let (x0, y0) = (left.max(0), top.max(0));
+        let x1 = (left + size).min(image.width() as i64);
+        let y1 = (top + size).min(image.height() as i64);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let empty = self.rules.color(&R::Cell::default());
+        let mut bytes = Vec::with_capacity(((x1 - x0) * (y1 - y0) * 4) as usize);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let color = match cells {
+                    Some(cells) => {
+                        let i = (x - left + (y - top) * size) as usize;
+                        self.rules.color(&cells[i])
+                    }
+                    None => empty,
+                };
+                bytes.extend_from_slice(&color);
+            }
+        }
+        image.write_rect(x0 as u32, y0 as u32, (x1 - x0) as u32, &bytes);
+    }
+}
+
+impl<R: InfiniteRules> World for InfiniteWorld<R> {
+    fn init_image(&mut self) -> WorldImage {
+        let mut image = WorldImage::new(
+            self.view_chunks.0 * CHUNK_SIZE,
+            self.view_chunks.1 * CHUNK_SIZE,
+        );
+        self.redraw_all(&mut image);
+        image
+    }
+
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        self.rules.step(&mut self.cells, ctx);
+        self.redraw(image);
+    }
+
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        let size = CHUNK_SIZE as i64;
+        let moves = [
+            (KeyCode::ArrowLeft, (-size, 0)),
+            (KeyCode::ArrowRight, (size, 0)),
+            (KeyCode::ArrowUp, (0, -size)),
+            (KeyCode::ArrowDown, (0, size)),
+        ];
+        for (key, (dx, dy)) in moves {
+            if is_pressed(&event, key) {
+                self.origin = (self.origin.0 + dx, self.origin.1 + dy);
+                self.redraw_all(image);
+            }
+        }
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        self.visible = Some(lod.clone());
+        self.redraw(image);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{AppHandle, World, WorldImage};
+use std::sync::mpsc;
+
+/// Shows the world of another window, e.g. on a second monitor, by copying
+/// its image once per frame. See [`App::add_window`](crate::App::add_window).
+///
+/// Input to the mirror is ignored, and the last image stays shown after the
+/// other window is closed.
+#[derive(Debug)]
+pub struct Mirror {
+    source: AppHandle,
+    pending: Option<mpsc::Receiver<WorldImage>>,
+}
+
+impl Mirror {
+    /// Mirrors the window controlled by `source`.
+    #[inline]
+    pub fn new(source: AppHandle) -> Self {
+        Self {
+            source,
+            pending: None,
+        }
+    }
+}
+
+impl World for Mirror {
+    fn init_image(&mut self) -> WorldImage {
+        WorldImage::new(1, 1)
+    }
+
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        let receiver = self
+            .pending
+            .get_or_insert_with(|| self.source.request_snapshot());
+        match receiver.try_recv() {
+            Ok(snapshot) => {
+                *image = snapshot;
+                self.pending = Some(self.source.request_snapshot());
+                true
+            }
+            Err(mpsc::TryRecvError::Empty) => false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.pending = None;
+                false
+            }
+        }
+    }
+}
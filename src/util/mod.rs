@@ -3,8 +3,37 @@ use winit::{
     keyboard::{KeyCode, PhysicalKey},
 };
 
+pub mod grid2d;
+pub use grid2d::WorldGrid2d;
+
 pub mod painter;
-pub use painter::{WithPainter, WithPainterExt};
+pub use painter::{GridPainter, WithPainter, WithPainterExt, WithPainterExtGrid};
+
+pub mod infinite;
+pub use infinite::{Chunks, InfiniteRules, InfiniteWorld};
+
+pub mod mirror;
+pub use mirror::Mirror;
+
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "rayon")]
+pub use parallel::{CellRule, ParallelWorld};
+
+pub mod scenes;
+pub use scenes::SceneManager;
+
+pub mod split;
+pub use split::{SplitInput, SplitView};
+
+pub mod threaded;
+pub use threaded::ThreadedWorld;
+
+/// Cells on the straight line from `from` to `to`, both included.
+pub fn line_cells(from: (u32, u32), to: (u32, u32)) -> impl Iterator<Item = (u32, u32)> {
+    line_drawing::Bresenham::new((from.0 as i32, from.1 as i32), (to.0 as i32, to.1 as i32))
+        .map(|(x, y)| (x as u32, y as u32))
+}
 
 pub(crate) fn is_pressed(event: &KeyEvent, key: KeyCode) -> bool {
     event.state.is_pressed() && event.physical_key == PhysicalKey::Code(key)
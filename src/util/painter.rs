@@ -1,5 +1,9 @@
-use crate::{MouseEvent, World, WorldImage, util::is_pressed};
-use std::collections::BTreeMap;
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, InputFocus, Lod, MouseEvent, Overlay, Rng,
+    UpdateCtx, VectorField, World, WorldImage, WorldTransform,
+    util::{WorldGrid2d, is_pressed, line_cells},
+};
+use std::{cell::Cell, collections::BTreeMap};
 use winit::{
     event::{KeyEvent, MouseButton},
     keyboard::KeyCode,
@@ -12,13 +16,24 @@ pub struct WithPainter<W, Ink, F> {
     palette: BTreeMap<KeyCode, Ink>,
     paint_fn: F,
 
+    paint_button: MouseButton,
+    erase: Option<(MouseButton, Ink)>,
+    random_fill: Option<(KeyCode, f64)>,
+    confirm_close: bool,
+
     // Painter state
     selected: Option<Ink>,
     mouse_pos_prev: Option<(u32, u32)>,
     mouse_pos: Option<(u32, u32)>,
-    is_painting: bool,
+    painting: Option<Ink>,
+    /// Whether anything was painted since the state was last saved.
+    unsaved: Cell<bool>,
+    close_warned: bool,
+    rng: Rng,
 }
 
+const UNSAVED_WARNING: &str = "Unsaved drawing: close again to discard it";
+
 impl<W: World, Ink, F> WithPainter<W, Ink, F>
 where
     F: Fn(&mut W, u32, u32, Ink, &mut WorldImage),
@@ -32,12 +47,78 @@ where
             world,
             palette: palette.into_iter().collect(),
             paint_fn,
+            paint_button: MouseButton::Left,
+            erase: None,
+            random_fill: None,
+            confirm_close: false,
             selected,
             mouse_pos_prev: None,
             mouse_pos: None,
-            is_painting: false,
+            painting: None,
+            unsaved: Cell::new(false),
+            close_warned: false,
+            rng: Rng::new(0),
+        }
+    }
+
+    /// Button that paints with the selected ink. Defaults to the left button.
+    #[inline]
+    pub fn with_paint_button(self, paint_button: MouseButton) -> Self {
+        Self {
+            paint_button,
+            ..self
+        }
+    }
+
+    /// Button that paints with `ink` regardless of the selection, e.g. the
+    /// right button with the empty cell.
+    #[inline]
+    pub fn with_erase_button(self, erase_button: MouseButton, ink: Ink) -> Self {
+        Self {
+            erase: Some((erase_button, ink)),
+            ..self
+        }
+    }
+
+    /// Key that paints each cell of the image with the selected ink with
+    /// probability `density`, e.g. to seed a board. Cells are picked by an
+    /// [`Rng`] seeded with [`AppHandle::seed`], so runs with the same
+    /// [`AppConfigs::seed`](crate::AppConfigs::seed) fill the same cells.
+    #[inline]
+    pub fn with_random_fill(self, key: KeyCode, density: f64) -> Self {
+        Self {
+            random_fill: Some((key, density)),
+            ..self
         }
     }
+
+    /// Keeps the window open the first time it is closed after painting,
+    /// with a warning in the log and the HUD, so that a hand-drawn board is
+    /// not lost by accident. Closing it again exits. Defaults to `false`.
+    ///
+    /// Saving the state, e.g. in a checkpoint, counts as saving the drawing.
+    #[inline]
+    pub fn with_confirm_close(self, confirm_close: bool) -> Self {
+        Self {
+            confirm_close,
+            ..self
+        }
+    }
+
+    /// Whether anything was painted since the state was last saved or
+    /// loaded, or since [`WithPainter::mark_saved`].
+    #[inline]
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved.get()
+    }
+
+    /// Forgets about unsaved changes, e.g. after exporting the drawing in a
+    /// format of the world's own.
+    #[inline]
+    pub fn mark_saved(&mut self) {
+        self.unsaved.set(false);
+        self.close_warned = false;
+    }
 }
 
 impl<W, Ink, F> WithPainter<W, Ink, F>
@@ -46,27 +127,41 @@ where
     Ink: Clone,
     F: Fn(&mut W, u32, u32, Ink, &mut WorldImage),
 {
-    fn draw(&mut self, image: &mut WorldImage) {
-        if self.is_painting {
-            if let Some(ref ink) = self.selected {
-                if let Some((x0, y0)) = self.mouse_pos_prev {
-                    if let Some((x1, y1)) = self.mouse_pos {
-                        for (x, y) in line_drawing::Bresenham::new(
-                            (x0 as i32, y0 as i32),
-                            (x1 as i32, y1 as i32),
-                        ) {
-                            (self.paint_fn)(
-                                &mut self.world,
-                                x as u32,
-                                y as u32,
-                                ink.clone(),
-                                image,
-                            );
-                        }
-                    }
+    fn track_cursor(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        self.mouse_pos_prev = self.mouse_pos;
+        self.mouse_pos = pos;
+        if self.mouse_pos_prev.is_none() {
+            self.mouse_pos_prev = self.mouse_pos;
+        }
+        self.draw(image);
+    }
+
+    fn fill_randomly(&mut self, density: f64, image: &mut WorldImage) {
+        let Some(ink) = self.selected.clone() else {
+            return;
+        };
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                if self.rng.chance(density) {
+                    (self.paint_fn)(&mut self.world, x, y, ink.clone(), image);
                 }
             }
         }
+        self.unsaved.set(true);
+        self.close_warned = false;
+    }
+
+    fn draw(&mut self, image: &mut WorldImage) {
+        if let Some(ref ink) = self.painting
+            && let Some((x0, y0)) = self.mouse_pos_prev
+            && let Some((x1, y1)) = self.mouse_pos
+        {
+            for (x, y) in line_cells((x0, y0), (x1, y1)) {
+                (self.paint_fn)(&mut self.world, x, y, ink.clone(), image);
+            }
+            self.unsaved.set(true);
+            self.close_warned = false;
+        }
     }
 }
 
@@ -86,6 +181,11 @@ where
         self.world.update(image);
     }
 
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        self.world.update_with_ctx(ctx, image);
+    }
+
     #[inline]
     fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
         for (key, ink) in &self.palette {
@@ -93,6 +193,11 @@ where
                 self.selected = Some(ink.clone());
             }
         }
+        if let Some((key, density)) = self.random_fill
+            && is_pressed(&event, key)
+        {
+            self.fill_randomly(density, image);
+        }
         self.world.keyboard_input(event, image);
     }
 
@@ -100,8 +205,12 @@ where
     fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
         let MouseEvent { state, button, .. } = event;
 
-        if button == MouseButton::Left {
-            self.is_painting = state.is_pressed();
+        if button == self.paint_button {
+            self.painting = state.is_pressed().then(|| self.selected.clone()).flatten();
+        } else if let Some((erase_button, ink)) = &self.erase
+            && button == *erase_button
+        {
+            self.painting = state.is_pressed().then(|| ink.clone());
         }
         self.draw(image);
 
@@ -109,20 +218,176 @@ where
     }
 
     fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
-        self.mouse_pos_prev = self.mouse_pos;
-        self.mouse_pos = pos;
-        if self.mouse_pos_prev.is_none() {
-            self.mouse_pos_prev = self.mouse_pos;
+        self.track_cursor(pos, image);
+        self.world.cursor_moved(pos, image);
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        self.track_cursor(pos.map(|pos| pos.cell), image);
+        self.world.cursor_moved_precise(pos, image);
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        self.world.cursor_drag(from, to, button, image);
+    }
+
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        self.rng = Rng::new(handle.seed());
+        self.world.attach(handle);
+    }
+
+    #[inline]
+    fn on_start(&mut self, image: &mut WorldImage) {
+        self.world.on_start(image);
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        self.world.on_pause();
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        self.world.on_resume();
+    }
+
+    fn on_close_requested(&mut self) -> CloseAction {
+        if self.world.on_close_requested() == CloseAction::Cancel {
+            return CloseAction::Cancel;
         }
-        self.draw(image);
+        if self.confirm_close && self.unsaved.get() && !self.close_warned {
+            self.close_warned = true;
+            log::warn!("{UNSAVED_WARNING}");
+            return CloseAction::Cancel;
+        }
+        CloseAction::Exit
+    }
 
-        self.world.cursor_moved(pos, image);
+    #[inline]
+    fn on_exit(&mut self) {
+        self.world.on_exit();
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        self.world.text_input(text, image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        self.world.gamepad_input(event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        self.painting = None;
+        self.world.focus_changed(focus);
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        self.world.transform_changed(transform);
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        self.world.lod_changed(lod, image);
+    }
+
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        self.world.cell_actions(x, y)
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        self.world.cell_action(x, y, action, image);
+    }
+
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world.command(command, image)
+    }
+
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        let mut lines = self.world.hud_lines();
+        if self.close_warned {
+            lines.push(UNSAVED_WARNING.to_owned());
+        }
+        lines
+    }
+
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        self.world.inspect(x, y)
+    }
+
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        self.world.hover_cells(x, y)
+    }
+
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        self.world.draw_overlay(overlay);
+    }
+
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        self.world.draw_agents(agents);
+    }
+
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        self.world.draw_vector_field(field);
+    }
+
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        self.world.poll_image(image)
+    }
+
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        self.world.requested_resize()
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        let state = self.world.save_state();
+        if state.is_some() {
+            self.unsaved.set(false);
+        }
+        state
+    }
+
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world.load_state(data, image)?;
+        self.mark_saved();
+        Ok(())
     }
 }
 
 pub trait WithPainterExt: World {
     #[inline]
-    fn with_painter<P, F, Ink>(self, palette: P, paint_fn: F, selected: Option<Ink>) -> impl World
+    fn with_painter<P, F, Ink>(
+        self,
+        palette: P,
+        paint_fn: F,
+        selected: Option<Ink>,
+    ) -> WithPainter<Self, Ink, F>
     where
         P: IntoIterator<Item = (KeyCode, Ink)>,
         Ink: Clone,
@@ -134,21 +399,31 @@ pub trait WithPainterExt: World {
 }
 impl<W: World> WithPainterExt for W {}
 
-// pub trait WithPainterExtGrid: World + WorldGrid2d<Cell: Clone> {
-//     #[inline]
-//     fn with_painter_grid<P>(
-//         self,
-//         palette: P,
-//         selected: Option<Self::Cell>,
-//     ) -> impl World
-//     where
-//         P: IntoIterator<Item = (KeyCode, Self::Cell)>,
-//         Self: Sized,
-//     {
-//         WithPainter::new(self, palette, |world, x, y, cell, image| {
-//             if let Some(dst) = world.get_cell_mut(x, y) {
-
-//             }
-//         })
-//     }
-// }
+/// Painter returned by [`WithPainterExtGrid::with_painter_grid`].
+pub type GridPainter<W> = WithPainter<
+    W,
+    <W as WorldGrid2d>::Cell,
+    fn(&mut W, u32, u32, <W as WorldGrid2d>::Cell, &mut WorldImage),
+>;
+
+pub trait WithPainterExtGrid: World + WorldGrid2d<Cell: Clone> {
+    /// Like [`WithPainterExt::with_painter`], painting with
+    /// [`WorldGrid2d::set_cell`] and refreshing the painted pixels.
+    #[inline]
+    fn with_painter_grid<P>(self, palette: P, selected: Option<Self::Cell>) -> GridPainter<Self>
+    where
+        P: IntoIterator<Item = (KeyCode, Self::Cell)>,
+        Self: Sized,
+    {
+        WithPainter::new(
+            self,
+            palette,
+            |world, x, y, cell, image| {
+                world.set_cell(x, y, cell);
+                world.refresh_cell(x, y, image);
+            },
+            selected,
+        )
+    }
+}
+impl<W: World + WorldGrid2d<Cell: Clone>> WithPainterExtGrid for W {}
@@ -0,0 +1,94 @@
+use crate::{Grid, World, WorldImage, util::WorldGrid2d};
+use rayon::prelude::*;
+
+/// Per-cell rule of a [`ParallelWorld`].
+pub trait CellRule: Sync {
+    type Cell: Clone + PartialEq + Send + Sync;
+
+    /// Next state of the cell at `(x, y)`, given the current generation.
+    fn next(&self, cells: &Grid<Self::Cell>, x: u32, y: u32) -> Self::Cell;
+
+    fn color(&self, cell: &Self::Cell) -> [u8; 4];
+}
+
+/// Runs a [`CellRule`] over a [`Grid`] with [`Grid::par_step`], and redraws
+/// the image in parallel as well.
+#[derive(Debug, Clone)]
+pub struct ParallelWorld<R: CellRule> {
+    rule: R,
+    cells: Grid<R::Cell>,
+}
+
+impl<R: CellRule> ParallelWorld<R> {
+    #[inline]
+    pub fn new(rule: R, cells: Grid<R::Cell>) -> Self {
+        Self { rule, cells }
+    }
+
+    #[inline]
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    #[inline]
+    pub fn cells(&self) -> &Grid<R::Cell> {
+        &self.cells
+    }
+
+    #[inline]
+    pub fn cells_mut(&mut self) -> &mut Grid<R::Cell> {
+        &mut self.cells
+    }
+
+    fn draw(&self, image: &mut WorldImage) {
+        let width = self.cells.width() as usize;
+        image
+            .buf_mut()
+            .par_chunks_mut(width * 4)
+            .zip(self.cells.cells().par_chunks(width))
+            .for_each(|(pixels, cells)| {
+                for (pixel, cell) in pixels.chunks_exact_mut(4).zip(cells) {
+                    pixel.copy_from_slice(&self.rule.color(cell));
+                }
+            });
+    }
+}
+
+impl<R: CellRule> WorldGrid2d for ParallelWorld<R> {
+    type Cell = R::Cell;
+
+    #[inline]
+    fn dims(&self) -> (u32, u32) {
+        self.cells.dims()
+    }
+
+    #[inline]
+    fn get_cell(&self, x: u32, y: u32) -> Option<&R::Cell> {
+        self.cells.get(x, y)
+    }
+
+    #[inline]
+    fn set_cell(&mut self, x: u32, y: u32, cell: R::Cell) {
+        self.cells.set(x, y, cell);
+    }
+
+    #[inline]
+    fn cell_color(&self, cell: &R::Cell) -> [u8; 4] {
+        self.rule.color(cell)
+    }
+}
+
+impl<R: CellRule> World for ParallelWorld<R> {
+    fn init_image(&mut self) -> WorldImage {
+        let (width, height) = self.cells.dims();
+        let mut image = WorldImage::new(width, height);
+        self.draw(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        let rule = &self.rule;
+        self.cells.par_step(|cells, x, y| rule.next(cells, x, y));
+        self.draw(image);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, InputFocus, Lod, MouseEvent, Overlay, UpdateCtx,
+    VectorField, World, WorldImage, WorldTransform, util::is_pressed,
+};
+use winit::{
+    event::{KeyEvent, MouseButton},
+    keyboard::KeyCode,
+};
+
+const DEFAULT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+struct Scene {
+    world: Box<dyn World>,
+    /// The scene's image while it is inactive. The active scene's image is
+    /// owned by the app, so this slot only holds a placeholder then.
+    image: WorldImage,
+}
+
+/// Holds several worlds and shows one of them at a time.
+///
+/// Number keys `1` to `9` switch to the corresponding scene by default.
+/// Inactive scenes keep their image and state but are not updated. Scenes
+/// may have different dimensions; the app recreates its texture on switch.
+pub struct SceneManager {
+    scenes: Vec<Scene>,
+    current: usize,
+    keys: Vec<KeyCode>,
+    transform: Option<WorldTransform>,
+}
+
+impl Default for SceneManager {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneManager {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            scenes: Vec::new(),
+            current: 0,
+            keys: DEFAULT_KEYS.to_vec(),
+            transform: None,
+        }
+    }
+
+    /// Adds a scene. Scenes are numbered in the order they are added.
+    #[inline]
+    pub fn with_scene(mut self, world: impl World + 'static) -> Self {
+        self.push(world);
+        self
+    }
+
+    /// Replaces the keys that switch scenes. The `n`-th key selects the
+    /// `n`-th scene.
+    #[inline]
+    pub fn with_keys<K>(self, keys: K) -> Self
+    where
+        K: IntoIterator<Item = KeyCode>,
+    {
+        Self {
+            keys: keys.into_iter().collect(),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, world: impl World + 'static) {
+        self.scenes.push(Scene {
+            world: Box::new(world),
+            image: placeholder(),
+        });
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Index of the active scene.
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Makes `index` the active scene, swapping its image into `image`.
+    ///
+    /// Returns `false` if there is no such scene.
+    pub fn switch_to(&mut self, index: usize, image: &mut WorldImage) -> bool {
+        if index >= self.scenes.len() {
+            return false;
+        }
+        if index == self.current {
+            return true;
+        }
+
+        std::mem::swap(image, &mut self.scenes[self.current].image);
+        self.current = index;
+        std::mem::swap(image, &mut self.scenes[self.current].image);
+
+        // With different dimensions the app sends a new transform itself.
+        if let Some(transform) = &self.transform
+            && transform.world_size() == (image.width(), image.height())
+        {
+            self.scenes[self.current].world.transform_changed(transform);
+        }
+        true
+    }
+
+    fn world(&mut self) -> &mut dyn World {
+        self.scenes[self.current].world.as_mut()
+    }
+}
+
+impl World for SceneManager {
+    /// Initializes the image of every scene.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no scene has been added.
+    fn init_image(&mut self) -> WorldImage {
+        assert!(!self.scenes.is_empty(), "SceneManager has no scenes");
+        for scene in &mut self.scenes {
+            scene.image = scene.world.init_image();
+        }
+        std::mem::replace(&mut self.scenes[self.current].image, placeholder())
+    }
+
+    #[inline]
+    fn update(&mut self, image: &mut WorldImage) {
+        self.world().update(image);
+    }
+
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        self.world().update_with_ctx(ctx, image);
+    }
+
+    #[inline]
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        let selected = self.keys.iter().position(|key| is_pressed(&event, *key));
+        if let Some(index) = selected
+            && self.switch_to(index, image)
+        {
+            return;
+        }
+        self.world().keyboard_input(event, image);
+    }
+
+    #[inline]
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        self.world().mouse_input(event, image);
+    }
+
+    #[inline]
+    fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        self.world().cursor_moved(pos, image);
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        self.world().cursor_moved_precise(pos, image);
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        self.world().cursor_drag(from, to, button, image);
+    }
+
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        for scene in &mut self.scenes {
+            scene.world.attach(handle.clone());
+        }
+    }
+
+    fn on_start(&mut self, image: &mut WorldImage) {
+        let current = self.current;
+        for (i, scene) in self.scenes.iter_mut().enumerate() {
+            if i == current {
+                scene.world.on_start(image);
+            } else {
+                scene.world.on_start(&mut scene.image);
+            }
+        }
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        for scene in &mut self.scenes {
+            scene.world.on_pause();
+        }
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        for scene in &mut self.scenes {
+            scene.world.on_resume();
+        }
+    }
+
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        self.world().on_close_requested()
+    }
+
+    #[inline]
+    fn on_exit(&mut self) {
+        for scene in &mut self.scenes {
+            scene.world.on_exit();
+        }
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        self.world().text_input(text, image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        self.world().gamepad_input(event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        for scene in &mut self.scenes {
+            scene.world.focus_changed(focus);
+        }
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        self.transform = Some(*transform);
+        self.world().transform_changed(transform);
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        self.world().lod_changed(lod, image);
+    }
+
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        self.scenes
+            .get(self.current)
+            .map_or_else(Vec::new, |scene| scene.world.cell_actions(x, y))
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        self.world().cell_action(x, y, action, image);
+    }
+
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world().command(command, image)
+    }
+
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        self.scenes
+            .get(self.current)
+            .map_or_else(Vec::new, |scene| scene.world.hud_lines())
+    }
+
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        self.scenes.get(self.current)?.world.inspect(x, y)
+    }
+
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        self.scenes
+            .get(self.current)
+            .map_or_else(|| vec![(x, y)], |scene| scene.world.hover_cells(x, y))
+    }
+
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        if let Some(scene) = self.scenes.get(self.current) {
+            scene.world.draw_overlay(overlay);
+        }
+    }
+
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        if let Some(scene) = self.scenes.get(self.current) {
+            scene.world.draw_agents(agents);
+        }
+    }
+
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        if let Some(scene) = self.scenes.get(self.current) {
+            scene.world.draw_vector_field(field);
+        }
+    }
+
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        self.world().poll_image(image)
+    }
+
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        self.world().requested_resize()
+    }
+
+    /// Saves the state of the active scene.
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        self.scenes.get(self.current)?.world.save_state()
+    }
+
+    /// Loads a state into the active scene.
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        self.world().load_state(data, image)
+    }
+}
+
+fn placeholder() -> WorldImage {
+    WorldImage::new(1, 1)
+}
@@ -0,0 +1,584 @@
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, Color, ImageFormat, InputFocus, Lod, MouseEvent,
+    Overlay, UpdateCtx, VectorField, World, WorldImage, WorldTransform,
+};
+use anyhow::Context as _;
+use winit::event::{KeyEvent, MouseButton};
+
+/// Which of the worlds of a [`SplitView`] receives input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SplitInput {
+    /// The world under the cursor, or the one last under it for keys.
+    #[default]
+    Hovered,
+    /// Both worlds, with the cursor mirrored to the same cell of each, so
+    /// drawing on one side draws the same on the other.
+    Both,
+    /// Only the left world, with the cursor mirrored from the right side.
+    Left,
+    /// Only the right world, with the cursor mirrored from the left side.
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+const SIDES: [Side; 2] = [Side::Left, Side::Right];
+
+/// Runs two worlds side by side, e.g. two rule variants or two seeds of the
+/// same simulation, to compare them.
+///
+/// Both worlds receive the same updates, so their ticks stay in sync.
+/// Their images are copied into one RGBA image after every callback,
+/// separated by a gap; layers of their images are not shown. Each world sees
+/// its own cell coordinates, transform and overlay space.
+pub struct SplitView<A, B> {
+    left: A,
+    right: B,
+    left_image: WorldImage,
+    right_image: WorldImage,
+    labels: [String; 2],
+    gap: u32,
+    gap_color: Color,
+    input: SplitInput,
+    /// The side the cursor was last over.
+    hovered: Side,
+    /// The cells last passed to each world, to tell it when the cursor
+    /// leaves it.
+    cursor: [Option<(u32, u32)>; 2],
+    transform: Option<WorldTransform>,
+    dirty: bool,
+}
+
+impl<A: World, B: World> SplitView<A, B> {
+    #[inline]
+    pub fn new(left: A, right: B) -> Self {
+        Self {
+            left,
+            right,
+            left_image: WorldImage::new(1, 1),
+            right_image: WorldImage::new(1, 1),
+            labels: ["Left".to_owned(), "Right".to_owned()],
+            gap: 1,
+            gap_color: Color::rgb(64, 64, 64),
+            input: SplitInput::default(),
+            hovered: Side::Left,
+            cursor: [None; 2],
+            transform: None,
+            dirty: false,
+        }
+    }
+
+    /// Names prefixed to the lines of [`World::hud_lines`] of each world.
+    /// Defaults to `Left` and `Right`.
+    #[inline]
+    pub fn with_labels(mut self, left: impl Into<String>, right: impl Into<String>) -> Self {
+        self.labels = [left.into(), right.into()];
+        self
+    }
+
+    /// Columns of cells between the worlds, drawn with `color`. Defaults to
+    /// one dark gray column.
+    #[inline]
+    pub fn with_gap(self, gap: u32, color: impl Into<Color>) -> Self {
+        Self {
+            gap,
+            gap_color: color.into(),
+            ..self
+        }
+    }
+
+    /// Defaults to [`SplitInput::Hovered`].
+    #[inline]
+    pub fn with_input(self, input: SplitInput) -> Self {
+        Self { input, ..self }
+    }
+
+    #[inline]
+    pub fn set_input(&mut self, input: SplitInput) {
+        self.input = input;
+    }
+
+    #[inline]
+    pub fn left(&self) -> &A {
+        &self.left
+    }
+
+    #[inline]
+    pub fn left_mut(&mut self) -> &mut A {
+        &mut self.left
+    }
+
+    #[inline]
+    pub fn right(&self) -> &B {
+        &self.right
+    }
+
+    #[inline]
+    pub fn right_mut(&mut self) -> &mut B {
+        &mut self.right
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> (A, B) {
+        (self.left, self.right)
+    }
+
+    fn pane(&mut self, side: Side) -> (&mut dyn World, &mut WorldImage) {
+        match side {
+            Side::Left => (&mut self.left, &mut self.left_image),
+            Side::Right => (&mut self.right, &mut self.right_image),
+        }
+    }
+
+    fn world(&self, side: Side) -> &dyn World {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    fn size(&self, side: Side) -> (u32, u32) {
+        let image = match side {
+            Side::Left => &self.left_image,
+            Side::Right => &self.right_image,
+        };
+        (image.width(), image.height())
+    }
+
+    /// Column of the combined image where `side` starts.
+    fn offset(&self, side: Side) -> u32 {
+        match side {
+            Side::Left => 0,
+            Side::Right => self.size(Side::Left).0 + self.gap,
+        }
+    }
+
+    fn combined_size(&self) -> (u32, u32) {
+        let (left, right) = (self.size(Side::Left), self.size(Side::Right));
+        (left.0 + self.gap + right.0, left.1.max(right.1))
+    }
+
+    /// The side and local cell of a cell of the combined image, or `None`
+    /// in the gap and below the shorter world.
+    fn locate(&self, (x, y): (u32, u32)) -> Option<(Side, (u32, u32))> {
+        SIDES.into_iter().find_map(|side| {
+            let (width, height) = self.size(side);
+            let x = x.checked_sub(self.offset(side))?;
+            (x < width && y < height).then_some((side, (x, y)))
+        })
+    }
+
+    fn targets(&self) -> &'static [Side] {
+        match (self.input, self.hovered) {
+            (SplitInput::Hovered, Side::Left) | (SplitInput::Left, _) => &[Side::Left],
+            (SplitInput::Hovered, Side::Right) | (SplitInput::Right, _) => &[Side::Right],
+            (SplitInput::Both, _) => &SIDES,
+        }
+    }
+
+    /// `cell` of one side moved to the same cell of `side`, if it is there.
+    fn mirror(&self, side: Side, cell: (u32, u32)) -> Option<(u32, u32)> {
+        let (width, height) = self.size(side);
+        (cell.0 < width && cell.1 < height).then_some(cell)
+    }
+
+    fn pane_transform(&self, side: Side, transform: &WorldTransform) -> WorldTransform {
+        let (cell_width, cell_height) = transform.cell_size();
+        let (width, height) = self.size(side);
+        let min = (
+            transform.min().0 + self.offset(side) as f64 * cell_width,
+            transform.min().1,
+        );
+        let max = (
+            min.0 + width as f64 * cell_width,
+            min.1 + height as f64 * cell_height,
+        );
+        WorldTransform::new(min, max, (width, height))
+    }
+
+    fn pane_lod(&self, side: Side, lod: &Lod) -> Lod {
+        let (width, height) = self.size(side);
+        let offset = self.offset(side);
+        let clip = |start: u32, end: u32, len: u32| start.min(len)..end.min(len);
+        Lod {
+            visible_x: clip(
+                lod.visible_x.start.saturating_sub(offset),
+                lod.visible_x.end.saturating_sub(offset),
+                width,
+            ),
+            visible_y: clip(lod.visible_y.start, lod.visible_y.end, height),
+            ..lod.clone()
+        }
+    }
+
+    /// Tells both worlds their transforms, after the layout may have
+    /// changed.
+    fn send_transforms(&mut self) {
+        if let Some(transform) = self.transform {
+            for side in SIDES {
+                let pane = self.pane_transform(side, &transform);
+                self.pane(side).0.transform_changed(&pane);
+            }
+        }
+    }
+
+    fn compose(&mut self, image: &mut WorldImage) {
+        let (width, height) = self.combined_size();
+        if (image.width(), image.height(), image.format()) != (width, height, ImageFormat::Rgba8) {
+            *image = WorldImage::new(width, height);
+        }
+        image.fill(self.gap_color);
+        for side in SIDES {
+            let pane = match side {
+                Side::Left => &self.left_image,
+                Side::Right => &self.right_image,
+            };
+            let x = self.offset(side) as i32;
+            match pane.format() {
+                ImageFormat::Rgba8 => image.blit(pane, x, 0),
+                _ => image.blit(&pane.to_rgba(), x, 0),
+            }
+        }
+        self.dirty = false;
+    }
+
+    /// Runs `f` with each target world and its image, then redraws.
+    fn each_target(
+        &mut self,
+        image: &mut WorldImage,
+        mut f: impl FnMut(Side, &mut dyn World, &mut WorldImage),
+    ) {
+        for &side in self.targets() {
+            let (world, pane) = self.pane(side);
+            f(side, world, pane);
+        }
+        self.compose(image);
+    }
+}
+
+impl<A: World, B: World> World for SplitView<A, B> {
+    fn init_image(&mut self) -> WorldImage {
+        self.left_image = self.left.init_image();
+        self.right_image = self.right.init_image();
+        let mut image = WorldImage::new(1, 1);
+        self.compose(&mut image);
+        image
+    }
+
+    fn update(&mut self, image: &mut WorldImage) {
+        self.left.update(&mut self.left_image);
+        self.right.update(&mut self.right_image);
+        self.compose(image);
+    }
+
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        self.left.update_with_ctx(ctx, &mut self.left_image);
+        self.right.update_with_ctx(ctx, &mut self.right_image);
+        self.compose(image);
+    }
+
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        self.each_target(image, |_, world, pane| {
+            world.keyboard_input(event.clone(), pane);
+        });
+    }
+
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        let local = event.pos.and_then(|pos| self.locate(pos));
+        if let Some((side, _)) = local {
+            self.hovered = side;
+        }
+        let sizes = SIDES.map(|side| self.size(side));
+        self.each_target(image, |side, world, pane| {
+            let (width, height) = sizes[side as usize];
+            let pos = local
+                .map(|(_, cell)| cell)
+                .filter(|&(x, y)| x < width && y < height);
+            world.mouse_input(MouseEvent { pos, ..event }, pane);
+        });
+    }
+
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        let local = pos.and_then(|pos| Some((self.locate(pos.cell)?, pos.fract)));
+        if let Some(((side, _), _)) = local {
+            self.hovered = side;
+        }
+        let targets = self.targets();
+        for side in SIDES {
+            let pos = local
+                .filter(|_| targets.contains(&side))
+                .and_then(|((_, cell), fract)| {
+                    Some(CellPosition {
+                        cell: self.mirror(side, cell)?,
+                        fract,
+                    })
+                });
+            let cell = pos.map(|pos| pos.cell);
+            // Other worlds only hear once that the cursor left them.
+            if cell.is_some() || self.cursor[side as usize].is_some() {
+                self.cursor[side as usize] = cell;
+                let (world, pane) = self.pane(side);
+                world.cursor_moved_precise(pos, pane);
+            }
+        }
+        self.compose(image);
+    }
+
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        let Some((to_side, to)) = self.locate(to) else {
+            return;
+        };
+        // A drag across the gap starts over on the other side.
+        let from = match self.locate(from) {
+            Some((side, from)) if side == to_side => from,
+            _ => to,
+        };
+        let sizes = SIDES.map(|side| self.size(side));
+        self.each_target(image, |side, world, pane| {
+            let (width, height) = sizes[side as usize];
+            let inside = |(x, y): (u32, u32)| x < width && y < height;
+            if inside(from) && inside(to) {
+                world.cursor_drag(from, to, button, pane);
+            }
+        });
+    }
+
+    fn attach(&mut self, handle: AppHandle) {
+        self.left.attach(handle.clone());
+        self.right.attach(handle);
+    }
+
+    fn on_start(&mut self, image: &mut WorldImage) {
+        self.left.on_start(&mut self.left_image);
+        self.right.on_start(&mut self.right_image);
+        self.compose(image);
+    }
+
+    fn on_pause(&mut self) {
+        self.left.on_pause();
+        self.right.on_pause();
+    }
+
+    fn on_resume(&mut self) {
+        self.left.on_resume();
+        self.right.on_resume();
+    }
+
+    /// Asks both worlds, and cancels if either does.
+    fn on_close_requested(&mut self) -> CloseAction {
+        let left = self.left.on_close_requested();
+        let right = self.right.on_close_requested();
+        if left == CloseAction::Cancel || right == CloseAction::Cancel {
+            CloseAction::Cancel
+        } else {
+            CloseAction::Exit
+        }
+    }
+
+    fn on_exit(&mut self) {
+        self.left.on_exit();
+        self.right.on_exit();
+    }
+
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        self.each_target(image, |_, world, pane| world.text_input(text, pane));
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        self.each_target(image, |_, world, pane| world.gamepad_input(event, pane));
+    }
+
+    fn focus_changed(&mut self, focus: InputFocus) {
+        self.left.focus_changed(focus);
+        self.right.focus_changed(focus);
+    }
+
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        self.transform = Some(*transform);
+        self.send_transforms();
+    }
+
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        for side in SIDES {
+            let lod = self.pane_lod(side, lod);
+            let (world, pane) = self.pane(side);
+            world.lod_changed(&lod, pane);
+        }
+        self.compose(image);
+    }
+
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        self.locate((x, y)).map_or_else(Vec::new, |(side, (x, y))| {
+            self.world(side).cell_actions(x, y)
+        })
+    }
+
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        if let Some((side, (x, y))) = self.locate((x, y)) {
+            let (world, pane) = self.pane(side);
+            world.cell_action(x, y, action, pane);
+            self.compose(image);
+        }
+    }
+
+    /// Runs commands starting with a label and a space in that world only,
+    /// e.g. `"Left seed 1"`, and other commands in both worlds.
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        let routed = SIDES.into_iter().find_map(|side| {
+            let rest = command.strip_prefix(self.labels[side as usize].as_str())?;
+            rest.starts_with(' ')
+                .then(|| (side, rest.trim_start().to_owned()))
+        });
+        let result = match routed {
+            Some((side, command)) => {
+                let (world, pane) = self.pane(side);
+                world.command(&command, pane)
+            }
+            None => SIDES.into_iter().try_for_each(|side| {
+                let label = self.labels[side as usize].clone();
+                let (world, pane) = self.pane(side);
+                world
+                    .command(command, pane)
+                    .with_context(|| format!("{label} world"))
+            }),
+        };
+        self.compose(image);
+        result
+    }
+
+    /// The lines of both worlds, prefixed with their labels.
+    fn hud_lines(&self) -> Vec<String> {
+        SIDES
+            .into_iter()
+            .flat_map(|side| {
+                let label = &self.labels[side as usize];
+                self.world(side)
+                    .hud_lines()
+                    .into_iter()
+                    .map(move |line| format!("{label}: {line}"))
+            })
+            .collect()
+    }
+
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        let (side, (x, y)) = self.locate((x, y))?;
+        let label = &self.labels[side as usize];
+        Some(match self.world(side).inspect(x, y) {
+            Some(text) => format!("{label} ({x}, {y})\n{text}"),
+            None => format!("{label} ({x}, {y})"),
+        })
+    }
+
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        let Some((side, (x, y))) = self.locate((x, y)) else {
+            return Vec::new();
+        };
+        let offset = self.offset(side);
+        self.world(side)
+            .hover_cells(x, y)
+            .into_iter()
+            .map(|(x, y)| (x + offset, y))
+            .collect()
+    }
+
+    /// Draws the overlay of each world in its own world space.
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        for side in SIDES {
+            let transform = self.pane_transform(side, overlay.transform());
+            let mut pane = Overlay::new(overlay.window_size(), transform);
+            self.world(side).draw_overlay(&mut pane);
+            overlay.extend(&pane);
+        }
+    }
+
+    /// Draws the agents of each world moved into its pane.
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        for side in SIDES {
+            let start = agents.len();
+            self.world(side).draw_agents(agents);
+            let offset = self.offset(side) as f32;
+            for agent in &mut agents[start..] {
+                agent.pos.0 += offset;
+            }
+        }
+    }
+
+    /// Draws the vector field of each world in its pane.
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        for side in SIDES {
+            let offset = self.offset(side);
+            let mut pane = field.pane(offset, self.size(side).0);
+            self.world(side).draw_vector_field(&mut pane);
+            field.extend(&pane, offset);
+        }
+    }
+
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        let left = self.left.poll_image(&mut self.left_image);
+        let right = self.right.poll_image(&mut self.right_image);
+        if left || right || self.dirty {
+            self.compose(image);
+            return true;
+        }
+        false
+    }
+
+    /// Resizes the images of the worlds that ask for it, and the combined
+    /// image to fit them.
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        let before = self.combined_size();
+        if let Some((width, height)) = self.left.requested_resize() {
+            self.left_image.resize(width, height);
+        }
+        if let Some((width, height)) = self.right.requested_resize() {
+            self.right_image.resize(width, height);
+        }
+        let after = self.combined_size();
+        if after == before {
+            return None;
+        }
+        self.dirty = true;
+        self.send_transforms();
+        Some(after)
+    }
+
+    /// Saves both worlds, if both support it, as the length of the left
+    /// state in 8 little-endian bytes followed by both states.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        let left = self.left.save_state()?;
+        let right = self.right.save_state()?;
+        let mut data = Vec::with_capacity(8 + left.len() + right.len());
+        data.extend_from_slice(&(left.len() as u64).to_le_bytes());
+        data.extend_from_slice(&left);
+        data.extend_from_slice(&right);
+        Some(data)
+    }
+
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        let (len, rest) = data
+            .split_first_chunk::<8>()
+            .context("split view state is truncated")?;
+        let len = usize::try_from(u64::from_le_bytes(*len))?;
+        anyhow::ensure!(len <= rest.len(), "split view state is truncated");
+        let (left, right) = rest.split_at(len);
+        self.left
+            .load_state(left, &mut self.left_image)
+            .context("failed to load the left world")?;
+        self.right
+            .load_state(right, &mut self.right_image)
+            .context("failed to load the right world")?;
+        self.compose(image);
+        Ok(())
+    }
+}
@@ -0,0 +1,508 @@
+use crate::{
+    Agent, AppHandle, CellPosition, CloseAction, InputFocus, Lod, MouseEvent, Overlay, UpdateCtx,
+    VectorField, World, WorldImage, WorldTransform,
+    winit::{KeyEvent, MouseButton},
+};
+use std::{
+    mem,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread::{self, JoinHandle},
+};
+
+enum Message {
+    Update(UpdateCtx),
+    Keyboard(KeyEvent),
+    Mouse(MouseEvent),
+    Cursor(Option<(u32, u32)>),
+    Drag((u32, u32), (u32, u32), MouseButton),
+    CursorPrecise(Option<CellPosition>),
+    Attach(AppHandle),
+    Start,
+    Pause,
+    Resume,
+    Text(String),
+    #[cfg(feature = "gamepad")]
+    Gamepad(crate::gamepad::GamepadEvent),
+    Focus(InputFocus),
+    Transform(WorldTransform),
+    Lod(Lod),
+    CellActions(u32, u32, mpsc::Sender<Vec<String>>),
+    CellAction(u32, u32, String),
+    Command(String, mpsc::Sender<anyhow::Result<()>>),
+    CloseRequested(mpsc::Sender<CloseAction>),
+    SaveState(mpsc::Sender<Option<Vec<u8>>>),
+    LoadState(Vec<u8>, mpsc::Sender<anyhow::Result<()>>),
+    Exit,
+}
+
+/// The most recently completed frame, handed from the worker to the app.
+#[derive(Debug)]
+struct Frame {
+    image: WorldImage,
+    hud_lines: Vec<String>,
+    /// The cell under the cursor as seen by the worker, with its
+    /// description and hover cells.
+    cursor: Option<(u32, u32)>,
+    inspected: Option<String>,
+    hover_cells: Vec<(u32, u32)>,
+    overlay: Option<Overlay>,
+    agents: Vec<Agent>,
+    vector_field: Option<VectorField>,
+    /// Set by the app, for the worker to record overlays with.
+    window_size: (u32, u32),
+    /// Set by the app, for the worker to sample the vector field with.
+    vector_blocks: Option<VectorField>,
+    fresh: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    frame: Mutex<Frame>,
+    queued_updates: AtomicUsize,
+}
+
+impl Shared {
+    fn frame(&self) -> MutexGuard<'_, Frame> {
+        self.frame.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Runs a world on a dedicated thread, so that slow updates never hold up
+/// window events or rendering.
+///
+/// Updates and input are forwarded to the thread in order. The worker draws
+/// into its own image and publishes a copy after each message; the app picks
+/// up the latest one through [`World::poll_image`], so the worker's image, the
+/// published frame and the app's image form a triple buffer.
+///
+/// When the worker falls behind by more than `max_queued_updates`, further
+/// updates are skipped rather than queued. Input is never dropped.
+pub struct ThreadedWorld<W> {
+    pending: Option<W>,
+    max_queued_updates: usize,
+    sender: Option<mpsc::Sender<Message>>,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<W: World + Send + 'static> ThreadedWorld<W> {
+    /// The thread starts when the app calls [`World::init_image`].
+    #[inline]
+    pub fn new(world: W) -> Self {
+        Self {
+            pending: Some(world),
+            max_queued_updates: 2,
+            sender: None,
+            shared: Arc::new(Shared {
+                frame: Mutex::new(Frame {
+                    image: WorldImage::new(1, 1),
+                    hud_lines: Vec::new(),
+                    cursor: None,
+                    inspected: None,
+                    hover_cells: Vec::new(),
+                    overlay: None,
+                    agents: Vec::new(),
+                    vector_field: None,
+                    window_size: (0, 0),
+                    vector_blocks: None,
+                    fresh: false,
+                }),
+                queued_updates: AtomicUsize::new(0),
+            }),
+            worker: None,
+        }
+    }
+
+    /// Number of updates that may wait for the worker before new ones are
+    /// skipped. Defaults to 2.
+    #[inline]
+    pub fn with_max_queued_updates(mut self, max_queued_updates: usize) -> Self {
+        self.max_queued_updates = max_queued_updates.max(1);
+        self
+    }
+
+    fn request<T>(&self, message: impl FnOnce(mpsc::Sender<T>) -> Message) -> Option<T> {
+        let (reply, response) = mpsc::channel();
+        self.send(message(reply));
+        response.recv().ok()
+    }
+}
+
+impl<W> ThreadedWorld<W> {
+    fn send(&self, message: Message) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(message);
+        }
+    }
+
+    fn stop(&mut self) {
+        self.send(Message::Exit);
+        self.sender = None;
+        if let Some(worker) = self.worker.take()
+            && worker.join().is_err()
+        {
+            log::error!("world thread panicked");
+        }
+    }
+}
+
+impl<W> Drop for ThreadedWorld<W> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run<W: World>(
+    mut world: W,
+    mut image: WorldImage,
+    messages: mpsc::Receiver<Message>,
+    shared: Arc<Shared>,
+) {
+    let mut handle = None;
+    let mut transform = None;
+    let mut cursor = None;
+    while let Ok(message) = messages.recv() {
+        let is_update = matches!(message, Message::Update(_));
+        match message {
+            Message::Update(ctx) => {
+                span!("update", tick = ctx.tick);
+                world.update_with_ctx(&ctx, &mut image);
+                shared.queued_updates.fetch_sub(1, Ordering::AcqRel);
+            }
+            Message::Keyboard(event) => world.keyboard_input(event, &mut image),
+            Message::Mouse(event) => world.mouse_input(event, &mut image),
+            Message::Cursor(pos) => {
+                cursor = pos;
+                world.cursor_moved(pos, &mut image);
+            }
+            Message::Drag(from, to, button) => world.cursor_drag(from, to, button, &mut image),
+            Message::CursorPrecise(pos) => {
+                cursor = pos.map(|pos| pos.cell);
+                world.cursor_moved_precise(pos, &mut image);
+            }
+            Message::Attach(app) => {
+                handle = Some(app.clone());
+                world.attach(app);
+            }
+            Message::Start => world.on_start(&mut image),
+            Message::Pause => world.on_pause(),
+            Message::Resume => world.on_resume(),
+            Message::Text(text) => world.text_input(&text, &mut image),
+            #[cfg(feature = "gamepad")]
+            Message::Gamepad(event) => world.gamepad_input(event, &mut image),
+            Message::Focus(focus) => world.focus_changed(focus),
+            Message::Transform(new_transform) => {
+                world.transform_changed(&new_transform);
+                transform = Some(new_transform);
+            }
+            Message::Lod(lod) => world.lod_changed(&lod, &mut image),
+            Message::CellActions(x, y, reply) => {
+                let _ = reply.send(world.cell_actions(x, y));
+                continue;
+            }
+            Message::CellAction(x, y, action) => world.cell_action(x, y, &action, &mut image),
+            Message::Command(command, reply) => {
+                let _ = reply.send(world.command(&command, &mut image));
+            }
+            Message::CloseRequested(reply) => {
+                let _ = reply.send(world.on_close_requested());
+                continue;
+            }
+            Message::SaveState(reply) => {
+                let _ = reply.send(world.save_state());
+                continue;
+            }
+            Message::LoadState(data, reply) => {
+                let _ = reply.send(world.load_state(&data, &mut image));
+            }
+            Message::Exit => {
+                world.on_exit();
+                return;
+            }
+        }
+
+        if let Some((width, height)) = world.requested_resize() {
+            image.resize(width, height);
+        }
+        publish(&shared, &image, &world, transform, cursor);
+        // The app redraws after updates anyway; other changes may arrive
+        // while it is idle.
+        if !is_update && let Some(handle) = &handle {
+            handle.request_redraw();
+        }
+    }
+}
+
+fn publish<W: World>(
+    shared: &Shared,
+    image: &WorldImage,
+    world: &W,
+    transform: Option<WorldTransform>,
+    cursor: Option<(u32, u32)>,
+) {
+    let hud_lines = world.hud_lines();
+    let (inspected, hover_cells) = match cursor {
+        Some((x, y)) => (world.inspect(x, y), world.hover_cells(x, y)),
+        None => (None, Vec::new()),
+    };
+    let overlay = transform.map(|transform| {
+        let window_size = shared.frame().window_size;
+        let mut overlay = Overlay::new(window_size, transform);
+        world.draw_overlay(&mut overlay);
+        overlay
+    });
+    let mut agents = Vec::new();
+    world.draw_agents(&mut agents);
+    let vector_blocks = shared.frame().vector_blocks.clone();
+    let vector_field = vector_blocks.map(|mut field| {
+        world.draw_vector_field(&mut field);
+        field
+    });
+
+    let mut frame = shared.frame();
+    frame.image.copy_from(image);
+    frame.hud_lines = hud_lines;
+    frame.cursor = cursor;
+    frame.inspected = inspected;
+    frame.hover_cells = hover_cells;
+    frame.overlay = overlay;
+    frame.agents = agents;
+    frame.vector_field = vector_field;
+    frame.fresh = true;
+}
+
+impl<W: World + Send + 'static> World for ThreadedWorld<W> {
+    fn init_image(&mut self) -> WorldImage {
+        let Some(mut world) = self.pending.take() else {
+            let frame = self.shared.frame();
+            return frame.image.clone();
+        };
+        let image = world.init_image();
+        self.shared.frame().image = image.clone();
+
+        let (sender, messages) = mpsc::channel();
+        let shared = Arc::clone(&self.shared);
+        let worker_image = image.clone();
+        self.worker = Some(
+            thread::Builder::new()
+                .name("cells-renderer-world".into())
+                .spawn(move || run(world, worker_image, messages, shared))
+                .expect("failed to spawn the world thread"),
+        );
+        self.sender = Some(sender);
+        image
+    }
+
+    #[inline]
+    fn update(&mut self, image: &mut WorldImage) {
+        self.update_with_ctx(&UpdateCtx::default(), image);
+    }
+
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        let queued = &self.shared.queued_updates;
+        if queued.load(Ordering::Acquire) < self.max_queued_updates {
+            queued.fetch_add(1, Ordering::AcqRel);
+            self.send(Message::Update(*ctx));
+        }
+        self.poll_image(image);
+    }
+
+    #[inline]
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Keyboard(event));
+    }
+
+    #[inline]
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Mouse(event));
+    }
+
+    #[inline]
+    fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Cursor(pos));
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::CursorPrecise(pos));
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        let _ = image;
+        self.send(Message::Drag(from, to, button));
+    }
+
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        self.send(Message::Attach(handle));
+    }
+
+    #[inline]
+    fn on_start(&mut self, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Start);
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        self.send(Message::Pause);
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        self.send(Message::Resume);
+    }
+
+    /// Waits for the worker to handle everything sent before.
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        self.request(Message::CloseRequested).unwrap_or_default()
+    }
+
+    /// Stops the thread after the world has handled everything sent before.
+    #[inline]
+    fn on_exit(&mut self) {
+        self.stop();
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Text(text.to_owned()));
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Gamepad(event));
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        self.send(Message::Focus(focus));
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        self.send(Message::Transform(*transform));
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::Lod(lod.clone()));
+    }
+
+    /// Waits for the worker to list the actions.
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        self.request(|reply| Message::CellActions(x, y, reply))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        let _ = image;
+        self.send(Message::CellAction(x, y, action.to_owned()));
+    }
+
+    /// Waits for the worker to run the command. `image` is updated through
+    /// [`World::poll_image`].
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        let _ = image;
+        self.request(|reply| Message::Command(command.to_owned(), reply))
+            .unwrap_or_else(|| Err(anyhow::anyhow!("the world thread has stopped")))
+    }
+
+    /// The lines the worker listed with its latest frame.
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        self.shared.frame().hud_lines.clone()
+    }
+
+    /// The worker's description of the cell under the cursor with its
+    /// latest frame, or `None` for other cells.
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        let frame = self.shared.frame();
+        frame
+            .inspected
+            .clone()
+            .filter(|_| frame.cursor == Some((x, y)))
+    }
+
+    /// The worker's cells for the cell under the cursor with its latest
+    /// frame, or the cell itself until the worker has seen the cursor there.
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        let frame = self.shared.frame();
+        if frame.cursor == Some((x, y)) {
+            frame.hover_cells.clone()
+        } else {
+            vec![(x, y)]
+        }
+    }
+
+    /// Draws the overlay the worker recorded with its latest frame. Nothing
+    /// is drawn until the worker has been told the world transform.
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        let mut frame = self.shared.frame();
+        frame.window_size = overlay.window_size();
+        if let Some(recorded) = &frame.overlay {
+            overlay.extend(recorded);
+        }
+    }
+
+    /// Draws the agents the worker recorded with its latest frame.
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        agents.extend_from_slice(&self.shared.frame().agents);
+    }
+
+    /// Draws the vector field the worker recorded with its latest frame,
+    /// sampled at the blocks of the frame before.
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        let mut frame = self.shared.frame();
+        frame.vector_blocks = Some(field.empty_like());
+        if let Some(recorded) = &frame.vector_field {
+            field.extend(recorded, 0);
+        }
+    }
+
+    /// Swaps in the latest frame completed by the worker, if any.
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        let mut frame = self.shared.frame();
+        let fresh = mem::take(&mut frame.fresh);
+        if fresh {
+            mem::swap(image, &mut frame.image);
+        }
+        fresh
+    }
+
+    /// Waits for the worker to finish the messages sent before.
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        self.request(Message::SaveState).flatten()
+    }
+
+    /// Waits for the worker to load the state. `image` is updated through
+    /// [`World::poll_image`].
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        let _ = image;
+        self.request(|reply| Message::LoadState(data.to_vec(), reply))
+            .unwrap_or_else(|| Err(anyhow::anyhow!("the world thread has stopped")))
+    }
+}
@@ -0,0 +1,262 @@
+//! Recording the app to video files with the `video` feature on, by piping
+//! frames to an `ffmpeg` executable, for timelapses of long runs at
+//! resolutions GIFs can't handle.
+//!
+//! [`AppAction::Record`](crate::AppAction::Record), `F9` by default, starts
+//! a recording and stops it, finishing the file. Each recording goes to a new
+//! file in [`VideoExport::dir`], named after the time it started.
+
+use crate::WorldImage;
+use anyhow::Context as _;
+use std::{
+    fs,
+    io::Write as _,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Container and codec of recordings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VideoFormat {
+    /// H.264 in an MP4 file, playable almost everywhere.
+    #[default]
+    Mp4,
+    /// VP9 in a WebM file, for browsers.
+    WebM,
+}
+
+impl VideoFormat {
+    #[inline]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::WebM => "webm",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            Self::Mp4 => "libx264",
+            Self::WebM => "libvpx-vp9",
+        }
+    }
+}
+
+/// Settings of the recordings, see
+/// [`AppConfigs::video`](crate::AppConfigs::video).
+///
+/// Frames are the world drawn `scale` times the size of its image, like
+/// [`AppHandle::request_screenshot_scaled`](crate::AppHandle::request_screenshot_scaled),
+/// taken at most `fps` times per second so that videos play back in real
+/// time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoExport {
+    pub dir: PathBuf,
+    pub format: VideoFormat,
+    pub fps: u32,
+    /// Target bitrate in kilobits per second, or `None` for the codec's
+    /// default quality.
+    pub bitrate: Option<u32>,
+    pub scale: u32,
+    /// The `ffmpeg` executable, looked up in `PATH` unless it is a path.
+    pub ffmpeg: PathBuf,
+}
+
+impl Default for VideoExport {
+    #[inline]
+    fn default() -> Self {
+        Self::new("videos")
+    }
+}
+
+impl VideoExport {
+    /// Records MP4 files to `dir` at 30 frames per second, one pixel per
+    /// cell.
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            format: VideoFormat::default(),
+            fps: 30,
+            bitrate: None,
+            scale: 1,
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+
+    #[inline]
+    pub fn format(self, format: VideoFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Zero is treated as one.
+    #[inline]
+    pub fn fps(self, fps: u32) -> Self {
+        Self {
+            fps: fps.max(1),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn bitrate(self, bitrate: Option<u32>) -> Self {
+        Self { bitrate, ..self }
+    }
+
+    /// Pixels per cell along each axis. Both codecs store colors at half the
+    /// resolution, so single-pixel cells lose some of their color; a scale
+    /// of 2 or more keeps them crisp. Zero is treated as one.
+    #[inline]
+    pub fn scale(self, scale: u32) -> Self {
+        Self {
+            scale: scale.max(1),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn ffmpeg(self, ffmpeg: impl Into<PathBuf>) -> Self {
+        Self {
+            ffmpeg: ffmpeg.into(),
+            ..self
+        }
+    }
+}
+
+/// A running `ffmpeg` process encoding frames of one size, fed from a
+/// thread so that slow encodes hold up the app only once a few frames are
+/// queued.
+#[derive(Debug)]
+pub(crate) struct VideoEncoder {
+    path: PathBuf,
+    size: (u32, u32),
+    interval: Duration,
+    last_frame: Option<Instant>,
+    frames: Option<mpsc::SyncSender<WorldImage>>,
+    writer: Option<thread::JoinHandle<anyhow::Result<()>>>,
+}
+
+impl VideoEncoder {
+    const QUEUED_FRAMES: usize = 4;
+
+    /// Starts encoding frames of `size` pixels to a new file.
+    pub fn start(export: &VideoExport, size: (u32, u32)) -> anyhow::Result<Self> {
+        fs::create_dir_all(&export.dir).with_context(|| {
+            format!("failed to create video directory {}", export.dir.display())
+        })?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = export
+            .dir
+            .join(format!("recording-{started}.{}", export.format.extension()));
+
+        let fps = export.fps.max(1);
+        let mut command = Command::new(&export.ffmpeg);
+        command
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo"])
+            .args(["-pixel_format", "rgba", "-video_size"])
+            .arg(format!("{}x{}", size.0, size.1))
+            .arg("-framerate")
+            .arg(fps.to_string())
+            .args(["-i", "-", "-c:v", export.format.codec()])
+            // Chroma subsampling needs even sizes.
+            .args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-pix_fmt",
+                "yuv420p",
+            ]);
+        if let Some(bitrate) = export.bitrate {
+            command.arg("-b:v").arg(format!("{bitrate}k"));
+        }
+        let mut child = command
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to run {}", export.ffmpeg.display()))?;
+        let mut stdin = child.stdin.take().context("ffmpeg has no stdin")?;
+
+        let (frames, receiver) = mpsc::sync_channel::<WorldImage>(Self::QUEUED_FRAMES);
+        let writer = thread::Builder::new()
+            .name("cells-renderer video".to_owned())
+            .spawn(move || {
+                let written = receiver
+                    .iter()
+                    .try_for_each(|frame| stdin.write_all(frame.buf()));
+                drop(stdin);
+                let status = child.wait()?;
+                written.context("ffmpeg stopped reading frames")?;
+                anyhow::ensure!(status.success(), "ffmpeg failed with {status}");
+                Ok(())
+            })?;
+
+        log::info!("recording {}", path.display());
+        Ok(Self {
+            path,
+            size,
+            interval: Duration::from_secs(1) / fps,
+            last_frame: None,
+            frames: Some(frames),
+            writer: Some(writer),
+        })
+    }
+
+    /// Whether the next frame is due, keeping the video in real time.
+    pub fn is_due(&self) -> bool {
+        self.last_frame
+            .is_none_or(|last_frame| last_frame.elapsed() >= self.interval)
+    }
+
+    /// Queues an RGBA frame of the size the encoder was started with.
+    pub fn push(&mut self, frame: WorldImage) -> anyhow::Result<()> {
+        let size = (frame.width(), frame.height());
+        anyhow::ensure!(
+            size == self.size,
+            "the recording is {}x{} pixels, but a frame is {}x{}",
+            self.size.0,
+            self.size.1,
+            size.0,
+            size.1
+        );
+        self.last_frame = Some(Instant::now());
+        let sent = self.frames.as_ref().map(|frames| frames.send(frame));
+        match sent {
+            Some(Ok(())) => Ok(()),
+            // The writer only hangs up when it failed.
+            _ => self.join(),
+        }
+    }
+
+    /// Waits for `ffmpeg` to encode the queued frames and finish the file.
+    pub fn finish(mut self) -> anyhow::Result<PathBuf> {
+        self.join()?;
+        Ok(std::mem::take(&mut self.path))
+    }
+
+    fn join(&mut self) -> anyhow::Result<()> {
+        self.frames = None;
+        let Some(writer) = self.writer.take() else {
+            anyhow::bail!("the recording has already stopped");
+        };
+        writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("the video thread panicked"))?
+            .with_context(|| format!("failed to record {}", self.path.display()))
+    }
+}
+
+impl Drop for VideoEncoder {
+    fn drop(&mut self) {
+        if self.writer.is_some()
+            && let Err(err) = self.join()
+        {
+            log::error!("{err:#}");
+        }
+    }
+}
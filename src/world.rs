@@ -1,4 +1,9 @@
-use crate::{MouseEvent, WorldImage, winit::KeyEvent};
+use crate::{
+    Agent, AppHandle, CellPosition, InputFocus, Lod, MouseEvent, Overlay, VectorField, WorldImage,
+    WorldTransform,
+    winit::{KeyEvent, MouseButton},
+};
+use std::{collections::VecDeque, time::Duration};
 
 pub trait World {
     fn init_image(&mut self) -> WorldImage;
@@ -8,6 +13,14 @@ pub trait World {
         let _ = image;
     }
 
+    /// Like [`World::update`], but also receives the simulation clock.
+    /// Defaults to calling `update`.
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        let _ = ctx;
+        self.update(image);
+    }
+
     #[inline]
     fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
         let _ = (event, image);
@@ -22,4 +35,518 @@ pub trait World {
     fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
         let _ = (pos, image);
     }
+
+    /// Like [`World::cursor_moved`], but also receives the position of the
+    /// cursor within the cell. Defaults to calling `cursor_moved`.
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        self.cursor_moved(pos.map(|pos| pos.cell), image);
+    }
+
+    /// Called while `button` is held down over the world: once with
+    /// `from == to` when it is pressed over a cell, then every time the
+    /// cursor moves from the cell `from` to the cell `to`. Called after
+    /// [`World::mouse_input`] and [`World::cursor_moved`].
+    ///
+    /// A fast cursor skips cells, so worlds that draw along the drag should
+    /// visit the cells between, e.g. with
+    /// [`line_cells`](crate::util::line_cells).
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        let _ = (from, to, button, image);
+    }
+
+    /// Receives a handle for controlling the app, before
+    /// [`World::on_start`]. Worlds that want to pause, step or exit the app
+    /// themselves can keep it.
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        let _ = handle;
+    }
+
+    /// Called once after the window and renderer are ready, before the first
+    /// update.
+    #[inline]
+    fn on_start(&mut self, image: &mut WorldImage) {
+        let _ = image;
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {}
+
+    #[inline]
+    fn on_resume(&mut self) {}
+
+    /// Called when the user asks to close the window, e.g. with its close
+    /// button, before [`World::on_exit`]. Returning [`CloseAction::Cancel`]
+    /// keeps the app running, e.g. to warn about unsaved changes first.
+    ///
+    /// Not called for [`AppHandle::exit`].
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        CloseAction::Exit
+    }
+
+    /// Called when the window is closing, e.g. to flush logs or stop
+    /// background threads. Also called when the app stops on a fatal error,
+    /// unless it failed before the world started.
+    #[inline]
+    fn on_exit(&mut self) {}
+
+    /// Receives typed text while the app has [`InputFocus::Text`].
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        let _ = (text, image);
+    }
+
+    /// Receives gamepads connecting and buttons being pressed, once per
+    /// frame. Sticks and held buttons can be polled with
+    /// [`AppHandle::gamepads`].
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        let _ = (event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        let _ = focus;
+    }
+
+    /// Called with the current mapping between window and cell coordinates
+    /// when the app starts and whenever the window or the world image is
+    /// resized, or the view is zoomed or panned.
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        let _ = transform;
+    }
+
+    /// Called when the zoom or the visible region changes, and once after
+    /// [`World::on_start`].
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        let _ = (lod, image);
+    }
+
+    /// Polled once per frame. Returning new dimensions resizes the image with
+    /// [`WorldImage::resize`] before it is uploaded.
+    ///
+    /// Worlds may also resize or replace the image directly in any callback.
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        None
+    }
+
+    /// Names of the actions offered when the cell at `(x, y)` is
+    /// right-clicked. Returning an empty list, the default, passes the click
+    /// on to [`World::mouse_input`] instead of opening a menu.
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        let _ = (x, y);
+        Vec::new()
+    }
+
+    /// Runs the action picked from the menu of [`World::cell_actions`].
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        let _ = (x, y, action, image);
+    }
+
+    /// Runs a line typed into the command palette of
+    /// [`AppConfigs::command_palette`](crate::AppConfigs::command_palette)
+    /// that is not one of the app's own commands, e.g. `"seed 42"`. Errors
+    /// are shown in the palette.
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        let _ = image;
+        anyhow::bail!("unknown command `{command}`")
+    }
+
+    /// Extra lines for the heads-up display enabled with
+    /// [`AppConfigs::hud`](crate::AppConfigs::hud), e.g. a population count.
+    /// Called once per frame while it is shown.
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Describes the cell at `(x, y)` for the inspector enabled with
+    /// [`AppConfigs::inspector`](crate::AppConfigs::inspector), e.g. its
+    /// state and age. Polled every frame while the cursor hovers the cell.
+    /// Several lines can be separated with `'\n'`.
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        let _ = (x, y);
+        None
+    }
+
+    /// Cells marked by [`AppConfigs::hover_highlight`](crate::AppConfigs::hover_highlight)
+    /// while the cursor is over the cell `(x, y)`, e.g. the footprint of a
+    /// brush. Defaults to the cell itself.
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        vec![(x, y)]
+    }
+
+    /// Draws markers, bounding boxes or debug vectors above the world and the
+    /// grid without touching the image. Called once per frame.
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        let _ = overlay;
+    }
+
+    /// Adds the agents to draw this frame to `agents`, which starts empty.
+    /// Called once per frame, before [`World::draw_overlay`].
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        let _ = agents;
+    }
+
+    /// Adds arrows for the visible part of a vector field, e.g. with
+    /// [`VectorField::fill`]. Called once per frame, after
+    /// [`World::draw_agents`].
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        let _ = field;
+    }
+
+    /// Polled once per frame, before [`World::requested_resize`], for worlds
+    /// that produce images outside of their callbacks. Returns whether
+    /// `image` changed and needs uploading.
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        let _ = image;
+        false
+    }
+
+    /// Serializes the world state for checkpoints.
+    ///
+    /// Returns `None` if the world does not support checkpointing.
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restores a state previously produced by [`World::save_state`],
+    /// redrawing `image` to match it.
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        let _ = (data, image);
+        anyhow::bail!("this world does not support loading states")
+    }
+}
+
+/// What to do when the user asks to close the window. See
+/// [`World::on_close_requested`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CloseAction {
+    #[default]
+    Exit,
+    /// Keeps the window open.
+    Cancel,
+}
+
+/// Lets worlds of different types share an [`App`](crate::App) with
+/// [`App::add_window`](crate::App::add_window), as `Box<dyn World>`.
+impl<W: World + ?Sized> World for Box<W> {
+    #[inline]
+    fn init_image(&mut self) -> WorldImage {
+        (**self).init_image()
+    }
+
+    #[inline]
+    fn update(&mut self, image: &mut WorldImage) {
+        (**self).update(image);
+    }
+
+    #[inline]
+    fn update_with_ctx(&mut self, ctx: &UpdateCtx, image: &mut WorldImage) {
+        (**self).update_with_ctx(ctx, image);
+    }
+
+    #[inline]
+    fn keyboard_input(&mut self, event: KeyEvent, image: &mut WorldImage) {
+        (**self).keyboard_input(event, image);
+    }
+
+    #[inline]
+    fn mouse_input(&mut self, event: MouseEvent, image: &mut WorldImage) {
+        (**self).mouse_input(event, image);
+    }
+
+    #[inline]
+    fn cursor_moved(&mut self, pos: Option<(u32, u32)>, image: &mut WorldImage) {
+        (**self).cursor_moved(pos, image);
+    }
+
+    #[inline]
+    fn cursor_moved_precise(&mut self, pos: Option<CellPosition>, image: &mut WorldImage) {
+        (**self).cursor_moved_precise(pos, image);
+    }
+
+    #[inline]
+    fn cursor_drag(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        button: MouseButton,
+        image: &mut WorldImage,
+    ) {
+        (**self).cursor_drag(from, to, button, image);
+    }
+
+    #[inline]
+    fn attach(&mut self, handle: AppHandle) {
+        (**self).attach(handle);
+    }
+
+    #[inline]
+    fn on_start(&mut self, image: &mut WorldImage) {
+        (**self).on_start(image);
+    }
+
+    #[inline]
+    fn on_pause(&mut self) {
+        (**self).on_pause();
+    }
+
+    #[inline]
+    fn on_resume(&mut self) {
+        (**self).on_resume();
+    }
+
+    #[inline]
+    fn on_close_requested(&mut self) -> CloseAction {
+        (**self).on_close_requested()
+    }
+
+    #[inline]
+    fn on_exit(&mut self) {
+        (**self).on_exit();
+    }
+
+    #[inline]
+    fn text_input(&mut self, text: &str, image: &mut WorldImage) {
+        (**self).text_input(text, image);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[inline]
+    fn gamepad_input(&mut self, event: crate::gamepad::GamepadEvent, image: &mut WorldImage) {
+        (**self).gamepad_input(event, image);
+    }
+
+    #[inline]
+    fn focus_changed(&mut self, focus: InputFocus) {
+        (**self).focus_changed(focus);
+    }
+
+    #[inline]
+    fn transform_changed(&mut self, transform: &WorldTransform) {
+        (**self).transform_changed(transform);
+    }
+
+    #[inline]
+    fn lod_changed(&mut self, lod: &Lod, image: &mut WorldImage) {
+        (**self).lod_changed(lod, image);
+    }
+
+    #[inline]
+    fn requested_resize(&mut self) -> Option<(u32, u32)> {
+        (**self).requested_resize()
+    }
+
+    #[inline]
+    fn cell_actions(&self, x: u32, y: u32) -> Vec<String> {
+        (**self).cell_actions(x, y)
+    }
+
+    #[inline]
+    fn cell_action(&mut self, x: u32, y: u32, action: &str, image: &mut WorldImage) {
+        (**self).cell_action(x, y, action, image);
+    }
+
+    #[inline]
+    fn command(&mut self, command: &str, image: &mut WorldImage) -> anyhow::Result<()> {
+        (**self).command(command, image)
+    }
+
+    #[inline]
+    fn hud_lines(&self) -> Vec<String> {
+        (**self).hud_lines()
+    }
+
+    #[inline]
+    fn inspect(&self, x: u32, y: u32) -> Option<String> {
+        (**self).inspect(x, y)
+    }
+
+    #[inline]
+    fn hover_cells(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        (**self).hover_cells(x, y)
+    }
+
+    #[inline]
+    fn draw_overlay(&self, overlay: &mut Overlay) {
+        (**self).draw_overlay(overlay);
+    }
+
+    #[inline]
+    fn draw_agents(&self, agents: &mut Vec<Agent>) {
+        (**self).draw_agents(agents);
+    }
+
+    #[inline]
+    fn draw_vector_field(&self, field: &mut VectorField) {
+        (**self).draw_vector_field(field);
+    }
+
+    #[inline]
+    fn poll_image(&mut self, image: &mut WorldImage) -> bool {
+        (**self).poll_image(image)
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<Vec<u8>> {
+        (**self).save_state()
+    }
+
+    #[inline]
+    fn load_state(&mut self, data: &[u8], image: &mut WorldImage) -> anyhow::Result<()> {
+        (**self).load_state(data, image)
+    }
+}
+
+/// Simulation clock passed to [`World::update_with_ctx`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct UpdateCtx {
+    /// Number of updates that ran before this one.
+    pub tick: u64,
+    /// Simulated time before this update, i.e. the sum of all previous `dt`.
+    pub elapsed: Duration,
+    /// Simulated time advanced by this update.
+    pub dt: Duration,
+    /// Measured timings of the previous rendered frame, for worlds that
+    /// adapt their workload. Zero when nothing has been rendered yet or when
+    /// running headless.
+    pub last_frame: FrameTiming,
+    /// Rates achieved over the last full second. Zero during the first
+    /// second or when running headless.
+    pub rates: RateStats,
+}
+
+/// Wall-clock durations measured over one rendered frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct FrameTiming {
+    /// Total time spent in updates run for the frame.
+    pub update: Duration,
+    /// Time spent uploading the world image to the GPU.
+    pub upload: Duration,
+    /// Time spent encoding, submitting and presenting the frame.
+    pub render: Duration,
+    /// Time since the previous frame was presented.
+    pub interval: Duration,
+}
+
+/// Timings of the most recently rendered frames, oldest first, from
+/// [`AppHandle::frame_stats`](crate::AppHandle::frame_stats).
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    frames: VecDeque<FrameTiming>,
+    capacity: usize,
+}
+
+impl FrameStats {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&mut self, timing: FrameTiming) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(timing);
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &FrameTiming> + '_ {
+        self.frames.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Number of frames kept before the oldest are dropped.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn latest(&self) -> Option<&FrameTiming> {
+        self.frames.back()
+    }
+
+    /// Mean of each timing over the kept frames.
+    pub fn average(&self) -> FrameTiming {
+        let n = self.frames.len().max(1) as u32;
+        let sum = self.fold(|sum, d| sum + d);
+        FrameTiming {
+            update: sum.update / n,
+            upload: sum.upload / n,
+            render: sum.render / n,
+            interval: sum.interval / n,
+        }
+    }
+
+    /// Maximum of each timing over the kept frames, not necessarily from
+    /// the same frame.
+    pub fn max(&self) -> FrameTiming {
+        self.fold(Duration::max)
+    }
+
+    fn fold(&self, f: impl Fn(Duration, Duration) -> Duration) -> FrameTiming {
+        self.frames
+            .iter()
+            .fold(FrameTiming::default(), |acc, timing| FrameTiming {
+                update: f(acc.update, timing.update),
+                upload: f(acc.upload, timing.upload),
+                render: f(acc.render, timing.render),
+                interval: f(acc.interval, timing.interval),
+            })
+    }
+}
+
+/// Event counts over one second of wall-clock time, for checking whether
+/// `updates_per_second` is actually met.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct RateStats {
+    /// Updates that ran.
+    pub updates: u32,
+    /// Updates that were due but dropped, because of
+    /// [`AppConfigs::max_updates_per_frame`](crate::AppConfigs::max_updates_per_frame).
+    pub skipped_updates: u32,
+    /// Frames rendered.
+    pub frames: u32,
+    /// Uploads of the world image, or parts of it, to the GPU.
+    pub uploads: u32,
 }